@@ -16,10 +16,19 @@
 // under the License.
 
 use std::fs::File;
+use std::sync::Arc;
 
+use arrow::datatypes::{DataType, Field, Schema};
+use bytes::Bytes;
 use criterion::{criterion_group, criterion_main, Criterion};
 use datafusion_orc::arrow_reader::ArrowReaderBuilder;
+use datafusion_orc::projection::ProjectionMask;
+use datafusion_orc::proto::{
+    self, column_encoding, r#type, stream, ColumnEncoding, Footer, Metadata, PostScript,
+    StripeFooter, StripeInformation, StripeStatistics,
+};
 use futures_util::TryStreamExt;
+use prost::Message;
 
 fn basic_path(path: &str) -> String {
     let dir = env!("CARGO_MANIFEST_DIR");
@@ -30,11 +39,11 @@ fn basic_path(path: &str) -> String {
 // 1,920,800 total rows
 // Columns:
 //   - Int32
-//   - Dictionary(UInt64, Utf8)
-//   - Dictionary(UInt64, Utf8)
-//   - Dictionary(UInt64, Utf8)
+//   - Utf8 (dictionary encoded)
+//   - Utf8 (dictionary encoded)
+//   - Utf8 (dictionary encoded)
 //   - Int32
-//   - Dictionary(UInt64, Utf8)
+//   - Utf8 (dictionary encoded)
 //   - Int32
 //   - Int32
 //   - Int32
@@ -58,8 +67,140 @@ fn sync_read_all() {
     let _ = reader.collect::<Result<Vec<_>, _>>().unwrap();
 }
 
+fn sync_read_all_threads(decode_threads: usize) {
+    let file_path = basic_path("demo-12-zlib.orc");
+    let f = File::open(file_path).unwrap();
+    let reader = ArrowReaderBuilder::try_new(f)
+        .unwrap()
+        .with_decode_threads(decode_threads)
+        .build();
+    let _ = reader.collect::<Result<Vec<_>, _>>().unwrap();
+}
+
+fn sync_read_dictionary_strings() {
+    let file_path = basic_path("demo-12-zlib.orc");
+    let f = File::open(file_path).unwrap();
+    let builder = ArrowReaderBuilder::try_new(f).unwrap();
+    let projection = ProjectionMask::named_roots(
+        builder.file_metadata().root_data_type(),
+        &["_col1", "_col2", "_col3", "_col5"],
+    );
+    let dictionary = DataType::Dictionary(Box::new(DataType::UInt64), Box::new(DataType::Utf8));
+    let schema = Schema::new(
+        ["_col1", "_col2", "_col3", "_col5"]
+            .map(|name| Field::new(name, dictionary.clone(), true))
+            .to_vec(),
+    );
+    let reader = builder
+        .with_projection(projection)
+        .with_schema(Arc::new(schema))
+        .with_batch_size(1024)
+        .build();
+    let _ = reader.collect::<Result<Vec<_>, _>>().unwrap();
+}
+
+/// Build an uncompressed file with a single stripe of 10 rows and `num_columns`
+/// int columns, each holding a single short repeat run of 1.
+fn wide_file(num_columns: u32) -> Bytes {
+    const NUM_ROWS: u64 = 10;
+    // Short repeat header (width of 1 byte, 10 repeats), then zigzag encoded 1
+    const DATA: [u8; 2] = [NUM_ROWS as u8 - 3, 2];
+
+    let mut file = b"ORC".to_vec();
+    let stripe_offset = file.len() as u64;
+    for _ in 0..num_columns {
+        file.extend_from_slice(&DATA);
+    }
+    let data_length = file.len() as u64 - stripe_offset;
+
+    let stripe_footer = StripeFooter {
+        streams: (1..=num_columns)
+            .map(|column| proto::Stream {
+                kind: Some(stream::Kind::Data as i32),
+                column: Some(column),
+                length: Some(DATA.len() as u64),
+            })
+            .collect(),
+        columns: (0..=num_columns)
+            .map(|column| ColumnEncoding {
+                kind: Some(if column == 0 {
+                    column_encoding::Kind::Direct
+                } else {
+                    column_encoding::Kind::DirectV2
+                } as i32),
+                ..Default::default()
+            })
+            .collect(),
+        ..Default::default()
+    }
+    .encode_to_vec();
+    file.extend_from_slice(&stripe_footer);
+
+    let mut types = vec![proto::Type {
+        kind: Some(r#type::Kind::Struct as i32),
+        subtypes: (1..=num_columns).collect(),
+        field_names: (1..=num_columns).map(|i| format!("c{i}")).collect(),
+        ..Default::default()
+    }];
+    types.extend((0..num_columns).map(|_| proto::Type {
+        kind: Some(r#type::Kind::Int as i32),
+        ..Default::default()
+    }));
+    let metadata = Metadata {
+        stripe_stats: vec![StripeStatistics::default()],
+    }
+    .encode_to_vec();
+    let footer = Footer {
+        header_length: Some(stripe_offset),
+        content_length: Some(file.len() as u64 - stripe_offset),
+        stripes: vec![StripeInformation {
+            offset: Some(stripe_offset),
+            index_length: Some(0),
+            data_length: Some(data_length),
+            footer_length: Some(stripe_footer.len() as u64),
+            number_of_rows: Some(NUM_ROWS),
+            ..Default::default()
+        }],
+        types,
+        number_of_rows: Some(NUM_ROWS),
+        ..Default::default()
+    }
+    .encode_to_vec();
+    file.extend_from_slice(&metadata);
+    file.extend_from_slice(&footer);
+
+    let postscript = PostScript {
+        footer_length: Some(footer.len() as u64),
+        compression: Some(proto::CompressionKind::None as i32),
+        metadata_length: Some(metadata.len() as u64),
+        magic: Some("ORC".to_owned()),
+        ..Default::default()
+    }
+    .encode_to_vec();
+    file.extend_from_slice(&postscript);
+    file.push(postscript.len() as u8);
+    file.into()
+}
+
+fn sync_read_wide(file: &Bytes) {
+    let reader = ArrowReaderBuilder::try_new(file.clone()).unwrap().build();
+    let batches = reader.collect::<Result<Vec<_>, _>>().unwrap();
+    assert_eq!(batches[0].num_columns(), 5000);
+}
+
 fn criterion_benchmark(c: &mut Criterion) {
+    let wide = wide_file(5000);
+    c.bench_function("sync reader 5000 columns", |b| {
+        b.iter(|| sync_read_wide(&wide))
+    });
+
     c.bench_function("sync reader", |b| b.iter(sync_read_all));
+    c.bench_function("sync reader 4 decode threads", |b| {
+        b.iter(|| sync_read_all_threads(4))
+    });
+    c.bench_function("sync reader dictionary strings", |b| {
+        b.iter(sync_read_dictionary_strings)
+    });
     c.bench_function("async reader", |b| {
         b.to_async(tokio::runtime::Runtime::new().unwrap())
             .iter(async_read_all);
@@ -7,6 +7,8 @@ use std::sync::Arc;
 
 use arrow::error::ArrowError;
 use datafusion::arrow::datatypes::SchemaRef;
+use datafusion::common::stats::Precision;
+use datafusion::common::Statistics;
 use datafusion::dataframe::DataFrame;
 use datafusion::datasource::listing::PartitionedFile;
 use datafusion::datasource::physical_plan::{
@@ -20,6 +22,7 @@ use datafusion::physical_plan::{
     DisplayAs, DisplayFormatType, ExecutionPlan, Partitioning, SendableRecordBatchStream,
 };
 use datafusion::prelude::*;
+use datafusion_expr::utils::COUNT_STAR_EXPANSION;
 use datafusion_expr::{Expr, LogicalPlanBuilder};
 use datafusion_orc::ArrowReaderBuilder;
 use datafusion_physical_expr::PhysicalSortExpr;
@@ -43,12 +46,18 @@ async fn main() -> Result<()> {
     )?
     .build()?;
 
-    DataFrame::new(ctx.state(), logical_plan)
+    DataFrame::new(ctx.state(), logical_plan.clone())
         // TODO: support projection pushdown
         // .select_columns(&["int16", "utf8"])?
         .show()
         .await?;
 
+    // Answered from the row count of the file metadata, without scanning it
+    DataFrame::new(ctx.state(), logical_plan)
+        .aggregate(vec![], vec![count(lit(COUNT_STAR_EXPANSION))])?
+        .show()
+        .await?;
+
     Ok(())
 }
 
@@ -56,6 +65,7 @@ async fn main() -> Result<()> {
 struct OrcDataSource {
     path: String,
     schema: SchemaRef,
+    row_count: usize,
 }
 
 impl OrcDataSource {
@@ -63,12 +73,17 @@ impl OrcDataSource {
         let path = path.as_ref().to_owned();
         let f = File::open(path.clone())?;
         let builder = ArrowReaderBuilder::try_new(f).map_err(ArrowError::from)?;
+        let row_count = builder.row_count() as usize;
         let metadata = builder.file_metadata().to_owned();
         let schema = metadata
             .root_data_type()
             .create_arrow_schema(&HashMap::default());
         let schema = Arc::new(schema);
-        Ok(Self { path, schema })
+        Ok(Self {
+            path,
+            schema,
+            row_count,
+        })
     }
 }
 
@@ -143,6 +158,13 @@ impl ExecutionPlan for OrcExec {
         None
     }
 
+    /// The exact row count lets DataFusion answer `count(*)` without a scan.
+    fn statistics(&self) -> Result<Statistics> {
+        let mut statistics = Statistics::new_unknown(&self.schema);
+        statistics.num_rows = Precision::Exact(self.orc.row_count);
+        Ok(statistics)
+    }
+
     fn execute(
         &self,
         partition: usize,
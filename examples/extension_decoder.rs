@@ -0,0 +1,77 @@
+//! Decodes a binary column into an extension type, here hex encoded strings, by
+//! wrapping the default binary decoder and post-processing its arrays.
+
+use std::collections::HashMap;
+use std::error::Error;
+use std::fs::File;
+use std::sync::Arc;
+
+use arrow::array::{ArrayRef, AsArray, StringArray};
+use arrow::datatypes::{DataType, Field, Schema};
+use arrow::util::pretty::pretty_format_batches;
+use datafusion_orc::arrow_reader::decoder::{array_decoder_factory, ArrayBatchDecoder};
+use datafusion_orc::arrow_reader::{DecoderFactory, StringValidation};
+use datafusion_orc::projection::ProjectionMask;
+use datafusion_orc::ArrowReaderBuilder;
+
+const EXTENSION_NAME_KEY: &str = "ARROW:extension:name";
+const HEX_EXTENSION: &str = "example.hex";
+
+struct HexDecoder {
+    inner: Box<dyn ArrayBatchDecoder>,
+}
+
+impl ArrayBatchDecoder for HexDecoder {
+    fn next_batch(
+        &mut self,
+        batch_size: usize,
+        parent_present: Option<&[bool]>,
+    ) -> datafusion_orc::error::Result<ArrayRef> {
+        let array = self.inner.next_batch(batch_size, parent_present)?;
+        let hex = array
+            .as_binary::<i32>()
+            .iter()
+            .map(|value| {
+                value.map(|value| value.iter().map(|b| format!("{b:02x}")).collect::<String>())
+            })
+            .collect::<StringArray>();
+        Ok(Arc::new(hex))
+    }
+}
+
+/// Decodes the fields of the hex extension type, leaving the others to the
+/// default decoders.
+fn hex_decoder_factory() -> DecoderFactory {
+    Arc::new(|column, field, stripe| {
+        if field.metadata().get(EXTENSION_NAME_KEY).map(String::as_str) != Some(HEX_EXTENSION) {
+            return Ok(None);
+        }
+        let binary = Field::new(field.name(), DataType::Binary, field.is_nullable());
+        let inner =
+            array_decoder_factory(column, Arc::new(binary), stripe, StringValidation::Strict)?;
+        Ok(Some(Box::new(HexDecoder { inner })))
+    })
+}
+
+fn main() -> Result<(), Box<dyn Error>> {
+    let file = File::open("tests/basic/data/alltypes.snappy.orc")?;
+    let builder = ArrowReaderBuilder::try_new(file)?;
+    let projection = ProjectionMask::named_roots(
+        builder.file_metadata().root_data_type(),
+        &["int32", "binary"],
+    );
+    let metadata = HashMap::from([(EXTENSION_NAME_KEY.to_owned(), HEX_EXTENSION.to_owned())]);
+    let schema = Schema::new(vec![
+        Field::new("int32", DataType::Int32, true),
+        Field::new("binary", DataType::Utf8, true).with_metadata(metadata),
+    ]);
+
+    let reader = builder
+        .with_projection(projection)
+        .with_schema(Arc::new(schema))
+        .with_decoder_factory(hex_decoder_factory())
+        .build();
+    let batches = reader.collect::<Result<Vec<_>, _>>()?;
+    println!("{}", pretty_format_batches(&batches)?);
+    Ok(())
+}
@@ -0,0 +1,624 @@
+//! Decoding of ORC files into [`arrow2`] arrays, as an alternative to the
+//! arrow-rs [`ArrowReader`](crate::arrow_reader::ArrowReader) for users of the
+//! arrow2 ecosystem (e.g. polars), avoiding a conversion copy through arrow-rs.
+//!
+//! Only the primitive and string types are supported; nested, decimal and union
+//! columns return an error.
+
+use std::io::Read;
+use std::iter::Flatten;
+use std::vec;
+
+use arrow2::array::{Array, BinaryArray, BooleanArray, PrimitiveArray, Utf8Array};
+use arrow2::bitmap::Bitmap;
+use arrow2::chunk::Chunk;
+use arrow2::datatypes::{DataType as Arrow2DataType, Field, Schema, TimeUnit};
+use arrow2::offset::Offsets;
+use arrow2::types::NativeType;
+use snafu::{ensure, OptionExt, ResultExt};
+
+use crate::arrow_reader::column::timestamp::TimestampIterator;
+use crate::arrow_reader::column::Column;
+use crate::arrow_reader::decoder::{total_length, PresentStream, MAX_PREALLOCATION};
+use crate::arrow_reader::{ArrowReaderBuilder, Cursor, RowWindow};
+use crate::error::{
+    Arrow2Snafu, IoSnafu, OutOfBoundSnafu, OutOfSpecSnafu, Result, TruncatedStreamSnafu,
+    UnsupportedArrow2TypeSnafu,
+};
+use crate::proto::column_encoding::Kind as ColumnEncodingKind;
+use crate::proto::stream::Kind;
+use crate::reader::decode::float::FloatIter;
+use crate::reader::decode::{
+    ensure_direct_encoding, get_boolean_reader, get_byte_rle_reader, get_rle_reader,
+    get_unsigned_rle_reader,
+};
+use crate::reader::decompress::Decompressor;
+use crate::reader::ChunkReader;
+use crate::schema::{DataType, RootDataType};
+use crate::stripe::Stripe;
+
+impl DataType {
+    /// The arrow2 type an ORC column is decoded into, if supported.
+    pub fn to_arrow2_data_type(&self) -> Result<Arrow2DataType> {
+        let data_type = match self {
+            DataType::Boolean { .. } => Arrow2DataType::Boolean,
+            DataType::Byte { .. } => Arrow2DataType::Int8,
+            DataType::Short { .. } => Arrow2DataType::Int16,
+            DataType::Int { .. } => Arrow2DataType::Int32,
+            DataType::Long { .. } => Arrow2DataType::Int64,
+            DataType::Float { .. } => Arrow2DataType::Float32,
+            DataType::Double { .. } => Arrow2DataType::Float64,
+            DataType::String { .. } | DataType::Varchar { .. } | DataType::Char { .. } => {
+                Arrow2DataType::Utf8
+            }
+            DataType::Binary { .. } => Arrow2DataType::Binary,
+            DataType::Timestamp { .. } => Arrow2DataType::Timestamp(TimeUnit::Nanosecond, None),
+            DataType::Date { .. } => Arrow2DataType::Date32,
+            orc_type => UnsupportedArrow2TypeSnafu {
+                orc_type: orc_type.clone(),
+            }
+            .fail()?,
+        };
+        Ok(data_type)
+    }
+}
+
+impl RootDataType {
+    /// The arrow2 schema the projected root columns are decoded into.
+    pub fn create_arrow2_schema(&self) -> Result<Schema> {
+        let fields = self
+            .children()
+            .iter()
+            .map(|col| {
+                let dt = col.data_type().to_arrow2_data_type()?;
+                Ok(Field::new(col.name(), dt, true))
+            })
+            .collect::<Result<Vec<_>>>()?;
+        Ok(Schema::from(fields))
+    }
+}
+
+/// Counterpart of [`ArrayBatchDecoder`](crate::arrow_reader::decoder::ArrayBatchDecoder)
+/// emitting arrow2 arrays. Nested types aren't supported, so there is no parent present.
+trait Arrow2BatchDecoder: Send {
+    fn next_batch(&mut self, batch_size: usize) -> Result<Box<dyn Array>>;
+}
+
+fn next_value<T>(iter: &mut impl Iterator<Item = Result<T>>) -> Result<T> {
+    iter.next().transpose()?.context(OutOfSpecSnafu {
+        msg: "array less than expected length",
+    })
+}
+
+/// Decode the values of a batch, filling null slots with the default value.
+fn next_values<T: Default>(
+    iter: &mut impl Iterator<Item = Result<T>>,
+    present: &mut Option<PresentStream>,
+    batch_size: usize,
+) -> Result<(Vec<T>, Option<Bitmap>)> {
+    match present {
+        Some(present) => {
+            let validity = Bitmap::from(present.next_n(batch_size)?);
+            let values = validity
+                .iter()
+                .map(|is_present| {
+                    if is_present {
+                        next_value(iter)
+                    } else {
+                        Ok(T::default())
+                    }
+                })
+                .collect::<Result<Vec<_>>>()?;
+            Ok((values, Some(validity)))
+        }
+        None => {
+            let values = iter.take(batch_size).collect::<Result<Vec<_>>>()?;
+            Ok((values, None))
+        }
+    }
+}
+
+struct PrimitiveDecoder<T: NativeType> {
+    data_type: Arrow2DataType,
+    iter: Box<dyn Iterator<Item = Result<T>> + Send>,
+    present: Option<PresentStream>,
+}
+
+impl<T: NativeType> PrimitiveDecoder<T> {
+    fn next_primitive_batch(&mut self, batch_size: usize) -> Result<PrimitiveArray<T>> {
+        let (values, validity) = next_values(&mut self.iter, &mut self.present, batch_size)?;
+        PrimitiveArray::try_new(self.data_type.clone(), values.into(), validity)
+            .context(Arrow2Snafu)
+    }
+}
+
+impl<T: NativeType> Arrow2BatchDecoder for PrimitiveDecoder<T> {
+    fn next_batch(&mut self, batch_size: usize) -> Result<Box<dyn Array>> {
+        Ok(self.next_primitive_batch(batch_size)?.boxed())
+    }
+}
+
+struct BooleanDecoder {
+    iter: Box<dyn Iterator<Item = Result<bool>> + Send>,
+    present: Option<PresentStream>,
+}
+
+impl Arrow2BatchDecoder for BooleanDecoder {
+    fn next_batch(&mut self, batch_size: usize) -> Result<Box<dyn Array>> {
+        let (values, validity) = next_values(&mut self.iter, &mut self.present, batch_size)?;
+        let array = BooleanArray::try_new(Arrow2DataType::Boolean, values.into(), validity)
+            .context(Arrow2Snafu)?;
+        Ok(array.boxed())
+    }
+}
+
+/// Decodes direct encoded strings and binaries, as the offsets and bytes of a batch.
+struct ByteDecoder {
+    column: String,
+    bytes: Box<Decompressor>,
+    bytes_kind: Kind,
+    lengths: Box<dyn Iterator<Item = Result<u64>> + Send>,
+    present: Option<PresentStream>,
+}
+
+impl ByteDecoder {
+    fn next_byte_batch(
+        &mut self,
+        batch_size: usize,
+    ) -> Result<(Offsets<i32>, Vec<u8>, Option<Bitmap>)> {
+        // Nulls are represented as 0 length
+        let (lengths, validity) = next_values(&mut self.lengths, &mut self.present, batch_size)?;
+        let total_length = total_length(&self.column, &lengths, i32::MAX as usize)?;
+        // The lengths could be corrupt, so only trust them so far for the allocation
+        let mut bytes = Vec::with_capacity(total_length.min(MAX_PREALLOCATION));
+        self.bytes
+            .by_ref()
+            .take(total_length as u64)
+            .read_to_end(&mut bytes)
+            .context(IoSnafu)?;
+        ensure!(
+            bytes.len() == total_length,
+            TruncatedStreamSnafu {
+                column: self.column.clone(),
+                kind: self.bytes_kind,
+            }
+        );
+        let offsets = Offsets::try_from_lengths(lengths.into_iter().map(|l| l as usize))
+            .context(Arrow2Snafu)?;
+        Ok((offsets, bytes, validity))
+    }
+
+    fn next_string_batch(&mut self, batch_size: usize) -> Result<Utf8Array<i32>> {
+        let (offsets, bytes, validity) = self.next_byte_batch(batch_size)?;
+        Utf8Array::try_new(Arrow2DataType::Utf8, offsets.into(), bytes.into(), validity)
+            .context(Arrow2Snafu)
+    }
+}
+
+struct StringDecoder(ByteDecoder);
+
+impl Arrow2BatchDecoder for StringDecoder {
+    fn next_batch(&mut self, batch_size: usize) -> Result<Box<dyn Array>> {
+        Ok(self.0.next_string_batch(batch_size)?.boxed())
+    }
+}
+
+struct BinaryDecoder(ByteDecoder);
+
+impl Arrow2BatchDecoder for BinaryDecoder {
+    fn next_batch(&mut self, batch_size: usize) -> Result<Box<dyn Array>> {
+        let (offsets, bytes, validity) = self.0.next_byte_batch(batch_size)?;
+        let array = BinaryArray::<i32>::try_new(
+            Arrow2DataType::Binary,
+            offsets.into(),
+            bytes.into(),
+            validity,
+        )
+        .context(Arrow2Snafu)?;
+        Ok(array.boxed())
+    }
+}
+
+/// Materializes dictionary encoded strings, as arrow2 has no dictionary array of
+/// `u64` keys equivalent to the arrow-rs decoder's output.
+struct DictionaryStringDecoder {
+    indexes: PrimitiveDecoder<u64>,
+    dictionary: Utf8Array<i32>,
+}
+
+impl Arrow2BatchDecoder for DictionaryStringDecoder {
+    fn next_batch(&mut self, batch_size: usize) -> Result<Box<dyn Array>> {
+        let indexes = self.indexes.next_primitive_batch(batch_size)?;
+        let strings = indexes
+            .iter()
+            .map(|index| {
+                index
+                    .map(|&index| {
+                        let index = index as usize;
+                        if index < self.dictionary.len() {
+                            Ok(self.dictionary.value(index))
+                        } else {
+                            OutOfBoundSnafu { index }.fail()
+                        }
+                    })
+                    .transpose()
+            })
+            .collect::<Result<Vec<_>>>()?;
+        Ok(Utf8Array::<i32>::from(strings).boxed())
+    }
+}
+
+fn new_rle_decoder<T>(column: &Column, stripe: &Stripe) -> Result<Box<dyn Arrow2BatchDecoder>>
+where
+    T: NativeType + crate::reader::decode::NInt,
+{
+    let iter = stripe.stream_map.get(column, Kind::Data)?;
+    Ok(Box::new(PrimitiveDecoder::<T> {
+        data_type: column.data_type().to_arrow2_data_type()?,
+        iter: get_rle_reader(column, iter)?,
+        present: PresentStream::try_new(column, stripe)?,
+    }))
+}
+
+fn new_byte_decoder(column: &Column, stripe: &Stripe) -> Result<ByteDecoder> {
+    let lengths = stripe.stream_map.get(column, Kind::Length)?;
+    Ok(ByteDecoder {
+        column: column.name().to_owned(),
+        bytes: Box::new(stripe.stream_map.get(column, Kind::Data)?),
+        bytes_kind: Kind::Data,
+        lengths: get_unsigned_rle_reader(column, lengths),
+        present: PresentStream::try_new(column, stripe)?,
+    })
+}
+
+fn arrow2_decoder_factory(column: &Column, stripe: &Stripe) -> Result<Box<dyn Arrow2BatchDecoder>> {
+    let decoder: Box<dyn Arrow2BatchDecoder> = match column.data_type() {
+        DataType::Boolean { .. } => {
+            ensure_direct_encoding(column)?;
+            let iter = stripe.stream_map.get(column, Kind::Data)?;
+            Box::new(BooleanDecoder {
+                iter: Box::new(get_boolean_reader(iter)),
+                present: PresentStream::try_new(column, stripe)?,
+            })
+        }
+        DataType::Byte { .. } => {
+            ensure_direct_encoding(column)?;
+            let iter = stripe.stream_map.get(column, Kind::Data)?;
+            Box::new(PrimitiveDecoder::<i8> {
+                data_type: Arrow2DataType::Int8,
+                iter: Box::new(
+                    get_byte_rle_reader(iter).map(|value| value.map(|value| value as i8)),
+                ),
+                present: PresentStream::try_new(column, stripe)?,
+            })
+        }
+        DataType::Short { .. } => new_rle_decoder::<i16>(column, stripe)?,
+        DataType::Int { .. } => new_rle_decoder::<i32>(column, stripe)?,
+        DataType::Long { .. } => new_rle_decoder::<i64>(column, stripe)?,
+        DataType::Date { .. } => new_rle_decoder::<i32>(column, stripe)?,
+        DataType::Float { .. } => {
+            ensure_direct_encoding(column)?;
+            let iter = stripe.stream_map.get(column, Kind::Data)?;
+            Box::new(PrimitiveDecoder::<f32> {
+                data_type: Arrow2DataType::Float32,
+                iter: Box::new(FloatIter::new(iter, stripe.number_of_rows)),
+                present: PresentStream::try_new(column, stripe)?,
+            })
+        }
+        DataType::Double { .. } => {
+            ensure_direct_encoding(column)?;
+            let iter = stripe.stream_map.get(column, Kind::Data)?;
+            Box::new(PrimitiveDecoder::<f64> {
+                data_type: Arrow2DataType::Float64,
+                iter: Box::new(FloatIter::new(iter, stripe.number_of_rows)),
+                present: PresentStream::try_new(column, stripe)?,
+            })
+        }
+        DataType::Timestamp { .. } => {
+            let data = stripe.stream_map.get(column, Kind::Data)?;
+            let secondary = stripe.stream_map.get(column, Kind::Secondary)?;
+            Box::new(PrimitiveDecoder::<i64> {
+                data_type: Arrow2DataType::Timestamp(TimeUnit::Nanosecond, None),
+                iter: Box::new(TimestampIterator::new(
+                    get_rle_reader(column, data)?,
+                    get_rle_reader(column, secondary)?,
+                )),
+                present: PresentStream::try_new(column, stripe)?,
+            })
+        }
+        DataType::String { .. } | DataType::Varchar { .. } | DataType::Char { .. } => {
+            match column.encoding().kind() {
+                ColumnEncodingKind::Direct | ColumnEncodingKind::DirectV2 => {
+                    Box::new(StringDecoder(new_byte_decoder(column, stripe)?))
+                }
+                ColumnEncodingKind::Dictionary | ColumnEncodingKind::DictionaryV2 => {
+                    let lengths = stripe.stream_map.get(column, Kind::Length)?;
+                    let mut dictionary = ByteDecoder {
+                        column: column.name().to_owned(),
+                        bytes: Box::new(stripe.stream_map.get(column, Kind::DictionaryData)?),
+                        bytes_kind: Kind::DictionaryData,
+                        lengths: get_unsigned_rle_reader(column, lengths),
+                        present: None,
+                    };
+                    let dictionary = dictionary.next_string_batch(column.dictionary_size())?;
+
+                    let indexes = stripe.stream_map.get(column, Kind::Data)?;
+                    let indexes = PrimitiveDecoder::<u64> {
+                        data_type: Arrow2DataType::UInt64,
+                        iter: get_unsigned_rle_reader(column, indexes),
+                        present: PresentStream::try_new(column, stripe)?,
+                    };
+                    Box::new(DictionaryStringDecoder {
+                        indexes,
+                        dictionary,
+                    })
+                }
+            }
+        }
+        DataType::Binary { .. } => Box::new(BinaryDecoder(new_byte_decoder(column, stripe)?)),
+        orc_type => UnsupportedArrow2TypeSnafu {
+            orc_type: orc_type.clone(),
+        }
+        .fail()?,
+    };
+    Ok(decoder)
+}
+
+/// Decodes the projected columns of a stripe into arrow2 [`Chunk`]s of at most
+/// `batch_size` rows.
+pub struct Arrow2StripeDecoder {
+    decoders: Vec<Box<dyn Arrow2BatchDecoder>>,
+    index: usize,
+    batch_size: usize,
+    number_of_rows: usize,
+}
+
+impl Arrow2StripeDecoder {
+    pub fn new(stripe: &Stripe, batch_size: usize) -> Result<Self> {
+        let decoders = stripe
+            .columns()
+            .iter()
+            .map(|column| arrow2_decoder_factory(column, stripe))
+            .collect::<Result<Vec<_>>>()?;
+        Ok(Self {
+            decoders,
+            index: 0,
+            batch_size,
+            number_of_rows: stripe.number_of_rows,
+        })
+    }
+
+    fn decode_next_batch(&mut self, remaining: usize) -> Result<Chunk<Box<dyn Array>>> {
+        let chunk_size = remaining.min(self.batch_size);
+        let arrays = self
+            .decoders
+            .iter_mut()
+            .map(|decoder| decoder.next_batch(chunk_size))
+            .collect::<Result<Vec<_>>>()?;
+        Chunk::try_new(arrays).context(Arrow2Snafu)
+    }
+}
+
+impl Iterator for Arrow2StripeDecoder {
+    type Item = Result<Chunk<Box<dyn Array>>>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.index < self.number_of_rows {
+            let chunk = self.decode_next_batch(self.number_of_rows - self.index);
+            self.index += self.batch_size;
+            Some(chunk)
+        } else {
+            None
+        }
+    }
+}
+
+/// Reads an ORC file as arrow2 [`Chunk`]s, honouring the projection, batch size,
+/// offset and limit of the [`ArrowReaderBuilder`] it was built from.
+pub struct Arrow2Reader<R> {
+    cursor: Cursor<R>,
+    schema: Schema,
+    current_stripe: Option<Flatten<vec::IntoIter<Arrow2StripeDecoder>>>,
+    batch_size: usize,
+    row_window: RowWindow,
+}
+
+impl<R> Arrow2Reader<R> {
+    pub fn schema(&self) -> &Schema {
+        &self.schema
+    }
+
+    /// See [`ArrowReader::into_reader`](crate::ArrowReader::into_reader).
+    pub fn into_reader(self) -> R {
+        self.cursor.reader
+    }
+}
+
+impl<R: ChunkReader> ArrowReaderBuilder<R> {
+    /// Build a reader emitting arrow2 arrays instead of arrow-rs record batches.
+    ///
+    /// Any schema set with [`with_schema`](Self::with_schema) is ignored. Errors if
+    /// a projected column has a type which can't be decoded into arrow2.
+    pub fn build_arrow2(self) -> Result<Arrow2Reader<R>> {
+        let batch_size = self.decode_batch_size();
+        let (cursor, _, row_window) = self.build_cursor();
+        let schema = cursor.projected_data_type.create_arrow2_schema()?;
+        Ok(Arrow2Reader {
+            cursor,
+            schema,
+            current_stripe: None,
+            batch_size,
+            row_window,
+        })
+    }
+}
+
+impl<R: ChunkReader> Arrow2Reader<R> {
+    /// Next chunk from the stripes, before applying the offset and limit.
+    fn next_unwindowed(&mut self) -> Result<Option<Chunk<Box<dyn Array>>>> {
+        loop {
+            if let Some(chunk) = self.current_stripe.as_mut().and_then(|s| s.next()) {
+                return chunk.map(Some);
+            }
+            let Some(selection) = self.cursor.next().transpose()? else {
+                return Ok(None);
+            };
+            let selected = selection.parts.iter().map(|(rows, _)| rows.clone());
+            self.row_window.select(selection.number_of_rows, selected);
+            let decoders = selection
+                .parts
+                .iter()
+                .map(|(_, stripe)| Arrow2StripeDecoder::new(stripe, self.batch_size))
+                .collect::<Result<Vec<_>>>()?;
+            self.current_stripe = Some(decoders.into_iter().flatten());
+        }
+    }
+}
+
+impl<R: ChunkReader> Iterator for Arrow2Reader<R> {
+    type Item = Result<Chunk<Box<dyn Array>>>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        while !self.row_window.is_exhausted() {
+            let chunk = match self.next_unwindowed().transpose()? {
+                Ok(chunk) => chunk,
+                Err(err) => return Some(Err(err)),
+            };
+            if let Some((offset, length)) = self.row_window.advance(chunk.len()) {
+                let arrays = chunk
+                    .arrays()
+                    .iter()
+                    .map(|array| array.sliced(offset, length))
+                    .collect();
+                return Some(Ok(Chunk::new(arrays)));
+            }
+        }
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs::File;
+
+    use arrow::array::{Array as _, AsArray};
+    use arrow::compute::cast;
+    use arrow::datatypes::{DataType as ArrowDataType, Int32Type};
+
+    use crate::projection::ProjectionMask;
+
+    use super::*;
+
+    fn read_both(path: &str, column: &str) -> (arrow::array::ArrayRef, Box<dyn Array>) {
+        let path = format!("{}/tests/basic/data/{path}", env!("CARGO_MANIFEST_DIR"));
+        let builder = ArrowReaderBuilder::try_new(File::open(&path).unwrap()).unwrap();
+        let projection =
+            ProjectionMask::named_roots(builder.file_metadata().root_data_type(), &[column]);
+        let mut batches = builder.with_projection(projection.clone()).build();
+        let batch = batches.next().unwrap().unwrap();
+
+        let builder = ArrowReaderBuilder::try_new(File::open(&path).unwrap()).unwrap();
+        let mut chunks = builder.with_projection(projection).build_arrow2().unwrap();
+        let chunk = chunks.next().unwrap().unwrap();
+        assert!(chunks.next().is_none());
+
+        (batch.column(0).clone(), chunk.into_arrays().remove(0))
+    }
+
+    #[test]
+    fn int_column_matches_arrow_rs() {
+        let (expected, actual) = read_both("test.orc", "int_delta");
+        let expected = expected.as_primitive::<Int32Type>();
+        let actual = actual
+            .as_any()
+            .downcast_ref::<PrimitiveArray<i32>>()
+            .unwrap();
+        assert_eq!(
+            expected.iter().collect::<Vec<_>>(),
+            actual.iter().map(|v| v.copied()).collect::<Vec<_>>()
+        );
+    }
+
+    #[test]
+    fn string_column_matches_arrow_rs() {
+        for (path, column) in [("test.orc", "utf8_increase"), ("string_dict.orc", "dict")] {
+            let (expected, actual) = read_both(path, column);
+            // Dictionary encoded strings are materialized by the arrow2 reader
+            let expected = cast(&expected, &ArrowDataType::Utf8).unwrap();
+            let expected = expected.as_string::<i32>();
+            let actual = actual.as_any().downcast_ref::<Utf8Array<i32>>().unwrap();
+            assert_eq!(expected.len(), actual.len());
+            assert_eq!(
+                expected.iter().collect::<Vec<_>>(),
+                actual.iter().collect::<Vec<_>>(),
+                "{column}"
+            );
+        }
+    }
+
+    #[test]
+    fn nullable_columns_in_small_batches() {
+        // Present bits are decoded as batches are read, so each batch must pick up
+        // where the previous one stopped
+        let path = format!(
+            "{}/tests/basic/data/alltypes.none.orc",
+            env!("CARGO_MANIFEST_DIR")
+        );
+        let builder = ArrowReaderBuilder::try_new(File::open(&path).unwrap()).unwrap();
+        let projection = ProjectionMask::named_roots(
+            builder.file_metadata().root_data_type(),
+            &["int32", "utf8"],
+        );
+        let batches = builder
+            .with_projection(projection.clone())
+            .build()
+            .collect::<std::result::Result<Vec<_>, _>>()
+            .unwrap();
+        let expected_ints = batches
+            .iter()
+            .flat_map(|batch| {
+                batch
+                    .column(0)
+                    .as_primitive::<Int32Type>()
+                    .iter()
+                    .collect::<Vec<_>>()
+            })
+            .collect::<Vec<_>>();
+        let expected_strings = batches
+            .iter()
+            .flat_map(|batch| {
+                batch
+                    .column(1)
+                    .as_string::<i32>()
+                    .iter()
+                    .map(|v| v.map(str::to_owned))
+                    .collect::<Vec<_>>()
+            })
+            .collect::<Vec<_>>();
+        assert!(expected_ints.contains(&None));
+
+        let builder = ArrowReaderBuilder::try_new(File::open(&path).unwrap()).unwrap();
+        let chunks = builder
+            .with_projection(projection)
+            .with_batch_size(3)
+            .build_arrow2()
+            .unwrap()
+            .collect::<Result<Vec<_>>>()
+            .unwrap();
+        assert!(chunks.iter().all(|chunk| chunk.len() <= 3));
+        let mut ints = vec![];
+        let mut strings = vec![];
+        for chunk in &chunks {
+            let arrays = chunk.arrays();
+            let int_array = arrays[0]
+                .as_any()
+                .downcast_ref::<PrimitiveArray<i32>>()
+                .unwrap();
+            ints.extend(int_array.iter().map(|v| v.copied()));
+            let string_array = arrays[1].as_any().downcast_ref::<Utf8Array<i32>>().unwrap();
+            strings.extend(string_array.iter().map(|v| v.map(str::to_owned)));
+        }
+        assert_eq!(expected_ints, ints);
+        assert_eq!(expected_strings, strings);
+    }
+}
@@ -0,0 +1,13 @@
+//! Conventions of the Arrow fields ORC columns are decoded into, shared by the
+//! decoders and the checks of schemas against ORC types.
+
+use arrow::datatypes::Fields;
+
+/// Whether the keys are the first of the two `fields` of the entries struct of
+/// a map (or of its key and value lists), going by their names and falling back
+/// to the position when they don't name which one holds the keys.
+pub(crate) fn keys_first(fields: &Fields) -> bool {
+    let is_key = |name: &str| matches!(name, "key" | "keys");
+    let is_value = |name: &str| matches!(name, "value" | "values");
+    !(is_value(fields[0].name()) && is_key(fields[1].name()))
+}
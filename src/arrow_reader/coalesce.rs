@@ -0,0 +1,72 @@
+//! Merging of small record batches, see
+//! [`ArrowReaderBuilder::with_coalesce_batches`](super::ArrowReaderBuilder::with_coalesce_batches).
+
+use arrow::compute::concat_batches;
+use arrow::error::ArrowError;
+use arrow::record_batch::RecordBatch;
+
+/// Buffers record batches until they add up to `target` rows, then concatenates
+/// them into batches of exactly `target` rows.
+#[derive(Debug)]
+pub(crate) struct BatchCoalescer {
+    target: usize,
+    buffered: Vec<RecordBatch>,
+    buffered_rows: usize,
+}
+
+impl BatchCoalescer {
+    /// Coalesce into batches of `target` rows, or of a single row if `target` is 0
+    /// as coalesced batches can't be empty.
+    pub fn new(target: usize) -> Self {
+        Self {
+            target: target.max(1),
+            buffered: vec![],
+            buffered_rows: 0,
+        }
+    }
+
+    pub fn push(&mut self, batch: RecordBatch) {
+        self.buffered_rows += batch.num_rows();
+        self.buffered.push(batch);
+    }
+
+    /// A batch of `target` rows, if that many are buffered.
+    pub fn next_full(&mut self) -> Option<Result<RecordBatch, ArrowError>> {
+        if self.buffered_rows < self.target {
+            return None;
+        }
+        let batch = match self.concat_buffered() {
+            Ok(batch) => batch,
+            Err(err) => return Some(Err(err)),
+        };
+        let rest = batch.slice(self.target, batch.num_rows() - self.target);
+        self.buffered_rows = rest.num_rows();
+        if rest.num_rows() > 0 {
+            self.buffered.push(rest);
+        }
+        Some(Ok(batch.slice(0, self.target)))
+    }
+
+    /// The rows left once the input batches are over, if any.
+    pub fn finish(&mut self) -> Option<Result<RecordBatch, ArrowError>> {
+        if self.buffered_rows == 0 {
+            return None;
+        }
+        self.buffered_rows = 0;
+        Some(self.concat_buffered())
+    }
+
+    /// Drop the buffered rows, e.g. when seeking elsewhere in the file.
+    pub fn clear(&mut self) {
+        self.buffered.clear();
+        self.buffered_rows = 0;
+    }
+
+    fn concat_buffered(&mut self) -> Result<RecordBatch, ArrowError> {
+        let buffered = std::mem::take(&mut self.buffered);
+        match buffered.as_slice() {
+            [batch] => Ok(batch.clone()),
+            _ => concat_batches(&buffered[0].schema(), &buffered),
+        }
+    }
+}
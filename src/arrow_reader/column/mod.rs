@@ -1,15 +1,20 @@
+use std::io::Read;
 use std::sync::Arc;
 
 use arrow::datatypes::Field;
 use bytes::Bytes;
-use snafu::ResultExt;
+use prost::Message;
+use snafu::{OptionExt, ResultExt};
 
-use crate::error::{IoSnafu, Result};
+use crate::bloom_filter::BloomFilter;
+use crate::error::{DecodeProtoSnafu, IoSnafu, OutOfSpecSnafu, Result};
+use crate::proto::column_encoding::Kind as ColumnEncodingKind;
 use crate::proto::stream::Kind;
-use crate::proto::{ColumnEncoding, StripeFooter};
-use crate::reader::decode::boolean_rle::BooleanIter;
+use crate::proto::{BloomFilterIndex, ColumnEncoding, RowIndex, StripeFooter};
+use crate::reader::decode::get_boolean_reader;
 use crate::reader::{AsyncChunkReader, ChunkReader};
 use crate::schema::DataType;
+use crate::statistics::ColumnStatistics;
 use crate::stripe::Stripe;
 
 pub mod timestamp;
@@ -51,6 +56,84 @@ impl Column {
         }
     }
 
+    /// Whether this column has nulls in the given stripe, according to the stripe
+    /// statistics. `None` if the statistics don't record it.
+    pub fn stripe_has_null(&self, stripe: &Stripe) -> Option<bool> {
+        stripe
+            .column_statistics
+            .get(self.column_id() as usize)?
+            .recorded_has_null()
+    }
+
+    /// Bloom filters of the row groups of this column in the given stripe, or
+    /// `None` if the writer didn't store any. Row groups whose filter has no bits
+    /// have `None`, as nothing can be ruled out for them.
+    pub fn stripe_bloom_filters(
+        &self,
+        stripe: &Stripe,
+    ) -> Result<Option<Vec<Option<BloomFilter>>>> {
+        let Some(mut stream) = stripe
+            .stream_map
+            .get_opt(self, Kind::BloomFilterUtf8)
+            .or_else(|| stripe.stream_map.get_opt(self, Kind::BloomFilter))
+        else {
+            return Ok(None);
+        };
+        let mut buffer = vec![];
+        stream.read_to_end(&mut buffer).context(IoSnafu)?;
+        let index = BloomFilterIndex::decode(buffer.as_slice()).context(DecodeProtoSnafu)?;
+        Ok(Some(
+            index
+                .bloom_filter
+                .iter()
+                .map(BloomFilter::from_proto)
+                .collect(),
+        ))
+    }
+
+    /// Statistics of the row groups of this column in the given stripe, from its row
+    /// index, or `None` if the writer didn't store one. These can rule out row
+    /// groups the same way stripe statistics rule out stripes.
+    pub fn stripe_row_group_statistics(
+        &self,
+        stripe: &Stripe,
+    ) -> Result<Option<Vec<ColumnStatistics>>> {
+        let Some(mut stream) = stripe.stream_map.get_opt(self, Kind::RowIndex) else {
+            return Ok(None);
+        };
+        let mut buffer = vec![];
+        stream.read_to_end(&mut buffer).context(IoSnafu)?;
+        let index = RowIndex::decode(buffer.as_slice()).context(DecodeProtoSnafu)?;
+        let statistics = index
+            .entry
+            .iter()
+            .map(|entry| {
+                entry
+                    .statistics
+                    .as_ref()
+                    .context(OutOfSpecSnafu {
+                        msg: "row index entry without statistics",
+                    })?
+                    .try_into()
+            })
+            .collect::<Result<Vec<_>>>()?;
+        Ok(Some(statistics))
+    }
+
+    /// Whether a string or binary value (such as the bytes of a UUID) may be in
+    /// this column in the given stripe, according to its bloom filters. `false`
+    /// means the stripe can be skipped; without bloom filters, it is always `true`.
+    pub fn stripe_may_contain(&self, stripe: &Stripe, value: &[u8]) -> Result<bool> {
+        Ok(match self.stripe_bloom_filters(stripe)? {
+            Some(filters) if !filters.is_empty() => filters.iter().any(|filter| {
+                filter
+                    .as_ref()
+                    .map_or(true, |filter| filter.test_bytes(value))
+            }),
+            _ => true,
+        })
+    }
+
     pub fn dictionary_size(&self) -> usize {
         let column = self.data_type.column_index();
         self.footer.columns[column]
@@ -58,6 +141,15 @@ impl Column {
             .unwrap_or_default() as usize
     }
 
+    /// Whether the column is dictionary encoded in its stripe, in which case
+    /// [`Self::dictionary_size`] is the number of distinct values.
+    pub fn is_dictionary_encoded(&self) -> bool {
+        matches!(
+            self.encoding().kind(),
+            ColumnEncodingKind::Dictionary | ColumnEncodingKind::DictionaryV2
+        )
+    }
+
     pub fn encoding(&self) -> ColumnEncoding {
         let column = self.data_type.column_index();
         self.footer.columns[column].clone()
@@ -161,6 +253,6 @@ pub fn get_present_vec(column: &Column, stripe: &Stripe) -> Result<Option<Vec<bo
     stripe
         .stream_map
         .get_opt(column, Kind::Present)
-        .map(|reader| BooleanIter::new(reader).collect::<Result<Vec<_>>>())
+        .map(|reader| get_boolean_reader(reader).collect::<Result<Vec<_>>>())
         .transpose()
 }
@@ -0,0 +1,68 @@
+//! Threads decoding batches in parallel, see
+//! [`ArrowReaderBuilder::with_decode_threads`](super::ArrowReaderBuilder::with_decode_threads).
+
+use std::sync::mpsc::{self, Receiver, Sender};
+use std::sync::{Arc, Mutex};
+use std::thread;
+
+type Job = Box<dyn FnOnce() + Send>;
+
+/// Long-lived worker threads owned by a reader, shared by the decoders of all its
+/// stripes. The workers stop once the pool is dropped and their jobs are done.
+pub(crate) struct DecodePool {
+    sender: Mutex<Sender<Job>>,
+    decode_threads: usize,
+}
+
+impl DecodePool {
+    /// Start the workers decoding batches on `decode_threads` threads, including
+    /// the thread decoding each batch, plus `spare_workers` to run the jobs calling
+    /// [`NaiveStripeDecoder`](super::NaiveStripeDecoder), e.g. to keep decoding
+    /// out of an async task.
+    pub fn new(decode_threads: usize, spare_workers: usize) -> Self {
+        let (sender, receiver) = mpsc::channel::<Job>();
+        let receiver = Arc::new(Mutex::new(receiver));
+        for _ in 0..decode_threads.saturating_sub(1) + spare_workers {
+            let receiver = receiver.clone();
+            thread::Builder::new()
+                .name("orc-decode".to_owned())
+                .spawn(move || work(&receiver))
+                .expect("failed to spawn decode thread");
+        }
+        Self {
+            sender: Mutex::new(sender),
+            decode_threads,
+        }
+    }
+
+    /// Number of threads decoding the columns of each batch.
+    pub fn decode_threads(&self) -> usize {
+        self.decode_threads
+    }
+
+    /// Run `job` on the first idle worker. Jobs should catch their own panics to
+    /// report them, as a panicking job is otherwise only dropped.
+    pub fn spawn(&self, job: impl FnOnce() + Send + 'static) {
+        self.sender
+            .lock()
+            .expect("decode pool lock poisoned")
+            .send(Box::new(job))
+            .expect("decode threads stopped");
+    }
+}
+
+fn work(receiver: &Mutex<Receiver<Job>>) {
+    loop {
+        // Only hold the lock to wait for the next job, not to run it
+        let job = match receiver.lock() {
+            Ok(receiver) => receiver.recv(),
+            Err(_) => return,
+        };
+        let Ok(job) = job else {
+            // The pool was dropped
+            return;
+        };
+        // Keep the worker alive if the job panics
+        let _ = std::panic::catch_unwind(std::panic::AssertUnwindSafe(job));
+    }
+}
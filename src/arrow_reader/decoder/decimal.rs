@@ -0,0 +1,209 @@
+use std::sync::Arc;
+
+use arrow::array::{ArrayRef, StringBuilder};
+use arrow::datatypes::{DataType as ArrowDataType, Decimal128Type};
+use num::{BigInt, ToPrimitive, Zero};
+use snafu::{ensure, OptionExt, ResultExt};
+
+use crate::arrow_reader::column::Column;
+use crate::arrow_reader::decoder::{
+    derive_present_vec, ArrayBatchDecoder, PresentStream, PrimitiveArrayDecoder,
+};
+use crate::arrow_reader::Stripe;
+use crate::error::{
+    ArrowSnafu, DecimalPrecisionSnafu, OutOfSpecSnafu, Result, TruncatedStreamSnafu,
+};
+use crate::proto::stream::Kind;
+use crate::reader::decode::decimal::{format_decimal, UnboundedVarintIter, MAX_DECIMAL_SCALE};
+use crate::reader::decode::get_rle_reader;
+use crate::reader::decompress::Decompressor;
+use crate::schema::DataType;
+
+/// Decodes decimals into Arrow `Decimal128(precision, scale)`, rescaling the
+/// values to `scale`. Fails up front unless any value of the ORC precision and
+/// scale fits, so neither integer nor fractional digits can be lost.
+pub fn new_decimal128_decoder(
+    column: &Column,
+    stripe: &Stripe,
+    precision: u8,
+    scale: i8,
+) -> Result<Box<dyn ArrayBatchDecoder>> {
+    let arrow_type = ArrowDataType::Decimal128(precision, scale);
+    let DataType::Decimal {
+        precision: orc_precision,
+        scale: orc_scale,
+        ..
+    } = column.data_type()
+    else {
+        unreachable!("decimal decoder of a {} column", column.data_type());
+    };
+    let (orc_precision, orc_scale) = (*orc_precision as i64, *orc_scale as i64);
+    ensure!(
+        scale as i64 >= orc_scale && precision as i64 - scale as i64 >= orc_precision - orc_scale,
+        DecimalPrecisionSnafu {
+            orc: column.data_type().clone(),
+            arrow: arrow_type,
+        }
+    );
+
+    let present = PresentStream::try_new(column, stripe)?;
+    let iter = DecimalValues::try_new(column, stripe)?.map(move |value| {
+        let (value, value_scale) = value?;
+        rescale(value, value_scale, precision, scale)
+    });
+    Ok(Box::new(Decimal128ArrayDecoder {
+        inner: PrimitiveArrayDecoder::new(Box::new(iter), present),
+        precision,
+        scale,
+    }))
+}
+
+/// Unscaled values of a decimal column with their scales, from its data and
+/// secondary streams, failing if either stream holds fewer values than the other.
+struct DecimalValues {
+    column: String,
+    values: UnboundedVarintIter<Decompressor>,
+    scales: Box<dyn Iterator<Item = Result<i32>> + Send>,
+}
+
+impl DecimalValues {
+    fn try_new(column: &Column, stripe: &Stripe) -> Result<Self> {
+        let data = stripe.stream_map.get(column, Kind::Data)?;
+        // Each value carries its own scale
+        let scales = stripe.stream_map.get(column, Kind::Secondary)?;
+        Ok(Self {
+            column: column.name().to_owned(),
+            values: UnboundedVarintIter::new(data),
+            scales: get_rle_reader::<i32>(column, scales)?,
+        })
+    }
+}
+
+impl Iterator for DecimalValues {
+    type Item = Result<(BigInt, i32)>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let kind = match (self.values.next(), self.scales.next()) {
+            (Some(value), Some(scale)) => return Some(value.and_then(|v| Ok((v, scale?)))),
+            (None, None) => return None,
+            (Some(_), None) => Kind::Secondary,
+            (None, Some(_)) => Kind::Data,
+        };
+        let column = self.column.clone();
+        Some(TruncatedStreamSnafu { column, kind }.fail())
+    }
+}
+
+/// Unscaled value of the decimal `value * 10^-value_scale` with the given scale,
+/// failing if it doesn't fit `precision` digits or would be truncated.
+fn rescale(value: BigInt, value_scale: i32, precision: u8, scale: i8) -> Result<i128> {
+    // Bounded so the power of ten below stays small
+    ensure!(
+        (-MAX_DECIMAL_SCALE..=MAX_DECIMAL_SCALE).contains(&value_scale),
+        OutOfSpecSnafu {
+            msg: format!("decimal scale {value_scale} is out of range"),
+        }
+    );
+    let difference = scale as i32 - value_scale;
+    let ten = BigInt::from(10);
+    let rescaled = if difference >= 0 {
+        Some(value * ten.pow(difference as u32))
+    } else {
+        let divisor = ten.pow(difference.unsigned_abs());
+        (&value % &divisor).is_zero().then(|| value / divisor)
+    };
+    rescaled
+        .and_then(|value| value.to_i128())
+        .filter(|value| value.unsigned_abs() < 10u128.pow(precision as u32))
+        .with_context(|| OutOfSpecSnafu {
+            msg: format!(
+                "decimal with {value_scale} digits of scale doesn't fit Decimal128({precision}, {scale})"
+            ),
+        })
+}
+
+/// Sets the precision and scale of the decoded arrays.
+struct Decimal128ArrayDecoder {
+    inner: PrimitiveArrayDecoder<Decimal128Type>,
+    precision: u8,
+    scale: i8,
+}
+
+impl ArrayBatchDecoder for Decimal128ArrayDecoder {
+    fn next_batch(
+        &mut self,
+        batch_size: usize,
+        parent_present: Option<&[bool]>,
+    ) -> Result<ArrayRef> {
+        let array = self
+            .inner
+            .next_primitive_batch(batch_size, parent_present)?;
+        let array = array
+            .with_precision_and_scale(self.precision, self.scale)
+            .context(ArrowSnafu)?;
+        Ok(Arc::new(array))
+    }
+}
+
+pub fn new_decimal_string_decoder(
+    column: &Column,
+    stripe: &Stripe,
+) -> Result<Box<dyn ArrayBatchDecoder>> {
+    let present = PresentStream::try_new(column, stripe)?;
+    let iter = DecimalValues::try_new(column, stripe)?.map(|value| {
+        let (value, scale) = value?;
+        format_decimal(&value, scale)
+    });
+    Ok(Box::new(DecimalStringArrayDecoder {
+        column: column.name().to_owned(),
+        iter: Box::new(iter),
+        present,
+    }))
+}
+
+/// Decodes decimals into their text representation, so values of any precision
+/// can be read.
+pub struct DecimalStringArrayDecoder {
+    column: String,
+    iter: Box<dyn Iterator<Item = Result<String>> + Send>,
+    present: Option<PresentStream>,
+}
+
+impl DecimalStringArrayDecoder {
+    /// Next value, failing if the streams hold no more values.
+    fn next_value(&mut self) -> Result<String> {
+        self.iter.next().transpose()?.context(TruncatedStreamSnafu {
+            column: self.column.clone(),
+            kind: Kind::Data,
+        })
+    }
+}
+
+impl ArrayBatchDecoder for DecimalStringArrayDecoder {
+    fn next_batch(
+        &mut self,
+        batch_size: usize,
+        parent_present: Option<&[bool]>,
+    ) -> Result<ArrayRef> {
+        let present = derive_present_vec(&mut self.present, parent_present, batch_size)?;
+
+        let mut builder = StringBuilder::with_capacity(batch_size, batch_size * 8);
+        match present {
+            Some(present) => {
+                for is_present in present {
+                    if is_present {
+                        builder.append_value(self.next_value()?);
+                    } else {
+                        builder.append_null();
+                    }
+                }
+            }
+            None => {
+                for _ in 0..batch_size {
+                    builder.append_value(self.next_value()?);
+                }
+            }
+        }
+        Ok(Arc::new(builder.finish()))
+    }
+}
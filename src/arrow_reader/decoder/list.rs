@@ -1,41 +1,48 @@
 use std::sync::Arc;
 
-use arrow::array::{ArrayRef, ListArray};
+use arrow::array::{
+    make_array, Array, ArrayRef, AsArray, FixedSizeListArray, ListArray, MutableArrayData,
+};
 use arrow::buffer::{NullBuffer, OffsetBuffer};
-use arrow::datatypes::{Field, FieldRef};
-use snafu::ResultExt;
+use arrow::datatypes::FieldRef;
+use snafu::{ensure, ResultExt};
 
-use crate::arrow_reader::column::{get_present_vec, Column};
+use crate::arrow_reader::column::Column;
 use crate::arrow_reader::decoder::{
-    array_decoder_factory, derive_present_vec, populate_lengths_with_nulls, ArrayBatchDecoder,
+    array_decoder_factory, derive_present_vec, next_child_batch, next_lengths,
+    populate_lengths_with_nulls, total_length, ArrayBatchDecoder, PresentStream, StringValidation,
 };
 use crate::arrow_reader::Stripe;
 use crate::proto::stream::Kind;
 use crate::reader::decode::get_rle_reader;
 
-use crate::error::{ArrowSnafu, Result};
+use crate::error::{ArrowSnafu, FixedSizeListLengthSnafu, Result};
 
 pub struct ListArrayDecoder {
+    column: String,
     inner: Box<dyn ArrayBatchDecoder>,
-    present: Option<Box<dyn Iterator<Item = bool> + Send>>,
+    present: Option<PresentStream>,
     lengths: Box<dyn Iterator<Item = Result<u64>> + Send>,
     field: FieldRef,
 }
 
 impl ListArrayDecoder {
-    pub fn new(column: &Column, stripe: &Stripe) -> Result<Self> {
-        let present = get_present_vec(column, stripe)?
-            .map(|iter| Box::new(iter.into_iter()) as Box<dyn Iterator<Item = bool> + Send>);
+    pub fn new(
+        column: &Column,
+        field: FieldRef,
+        stripe: &Stripe,
+        string_validation: StringValidation,
+    ) -> Result<Self> {
+        let present = PresentStream::try_new(column, stripe)?;
 
         let child = &column.children()[0];
-        let inner = array_decoder_factory(child, stripe)?;
+        let inner = array_decoder_factory(child, field.clone(), stripe, string_validation)?;
 
         let reader = stripe.stream_map.get(column, Kind::Length)?;
         let lengths = get_rle_reader(column, reader)?;
 
-        let field = Arc::new(Field::from(child));
-
         Ok(Self {
+            column: column.name().to_owned(),
             inner,
             present,
             lengths,
@@ -50,7 +57,7 @@ impl ArrayBatchDecoder for ListArrayDecoder {
         batch_size: usize,
         parent_present: Option<&[bool]>,
     ) -> Result<ArrayRef> {
-        let present = derive_present_vec(&mut self.present, parent_present, batch_size);
+        let present = derive_present_vec(&mut self.present, parent_present, batch_size)?;
 
         // How many lengths we need to fetch
         let elements_to_fetch = if let Some(present) = &present {
@@ -58,19 +65,10 @@ impl ArrayBatchDecoder for ListArrayDecoder {
         } else {
             batch_size
         };
-        let lengths = self
-            .lengths
-            .by_ref()
-            .take(elements_to_fetch)
-            .collect::<Result<Vec<_>>>()?;
-        debug_assert_eq!(
-            lengths.len(),
-            elements_to_fetch,
-            "less lengths than expected in ListArray"
-        );
-        let total_length: u64 = lengths.iter().sum();
+        let lengths = next_lengths(&self.column, self.lengths.as_mut(), elements_to_fetch)?;
+        let total_length = total_length(&self.column, &lengths, i32::MAX as usize)?;
         // Fetch child array as one Array with total_length elements
-        let child_array = self.inner.next_batch(total_length as usize, None)?;
+        let child_array = next_child_batch(&self.column, self.inner.as_mut(), total_length, None)?;
         let lengths = populate_lengths_with_nulls(lengths, batch_size, &present);
         let offsets = OffsetBuffer::from_lengths(lengths);
         let null_buffer = present.map(NullBuffer::from);
@@ -81,3 +79,73 @@ impl ArrayBatchDecoder for ListArrayDecoder {
         Ok(array)
     }
 }
+
+/// Decodes lists which all have the same length into a [`FixedSizeListArray`],
+/// failing on the first list of another length.
+pub struct FixedSizeListArrayDecoder {
+    inner: ListArrayDecoder,
+    size: i32,
+}
+
+impl FixedSizeListArrayDecoder {
+    pub fn new(
+        column: &Column,
+        field: FieldRef,
+        size: i32,
+        stripe: &Stripe,
+        string_validation: StringValidation,
+    ) -> Result<Self> {
+        let inner = ListArrayDecoder::new(column, field, stripe, string_validation)?;
+        Ok(Self { inner, size })
+    }
+}
+
+impl ArrayBatchDecoder for FixedSizeListArrayDecoder {
+    fn next_batch(
+        &mut self,
+        batch_size: usize,
+        parent_present: Option<&[bool]>,
+    ) -> Result<ArrayRef> {
+        let array = self.inner.next_batch(batch_size, parent_present)?;
+        let list = array.as_list::<i32>();
+        let size = self.size as usize;
+        for index in 0..list.len() {
+            let length = list.value_length(index) as usize;
+            ensure!(
+                length == size || list.is_null(index),
+                FixedSizeListLengthSnafu {
+                    column: self.inner.column.clone(),
+                    size: self.size,
+                    length,
+                }
+            );
+        }
+
+        // Null lists have no values in ORC, but take up `size` values in Arrow
+        let values = match list.nulls() {
+            Some(nulls) if nulls.null_count() > 0 => {
+                let values = list.values().to_data();
+                let mut padded = MutableArrayData::new(vec![&values], true, list.len() * size);
+                let mut offset = 0;
+                for is_valid in nulls.iter() {
+                    if is_valid {
+                        padded.extend(0, offset, offset + size);
+                        offset += size;
+                    } else {
+                        padded.extend_nulls(size);
+                    }
+                }
+                make_array(padded.freeze())
+            }
+            _ => list.values().clone(),
+        };
+        let array = FixedSizeListArray::try_new(
+            self.inner.field.clone(),
+            self.size,
+            values,
+            list.nulls().cloned(),
+        )
+        .context(ArrowSnafu)?;
+        Ok(Arc::new(array))
+    }
+}
@@ -1,58 +1,166 @@
 use std::sync::Arc;
 
-use arrow::array::{ArrayRef, MapArray, StructArray};
+use arrow::array::{ArrayRef, ListArray, MapArray, StructArray};
 use arrow::buffer::{NullBuffer, OffsetBuffer};
-use arrow::datatypes::{Field, Fields};
-use snafu::ResultExt;
+use arrow::datatypes::{DataType as ArrowDataType, FieldRef, Fields};
+use snafu::{OptionExt, ResultExt};
 
-use crate::arrow_reader::column::{get_present_vec, Column};
+use crate::arrow_fields::keys_first;
+use crate::arrow_reader::column::Column;
 use crate::arrow_reader::decoder::{
-    array_decoder_factory, derive_present_vec, populate_lengths_with_nulls, ArrayBatchDecoder,
+    array_decoder_factory, derive_present_vec, next_child_batch, next_lengths,
+    populate_lengths_with_nulls, total_length, ArrayBatchDecoder, PresentStream, StringValidation,
 };
 use crate::arrow_reader::Stripe;
-use crate::error::{ArrowSnafu, Result};
+use crate::error::{ArrowSnafu, MismatchedSchemaSnafu, Result};
 use crate::proto::stream::Kind;
 use crate::reader::decode::get_rle_reader;
 
+/// Arrow array a map column is decoded into.
+enum MapShape {
+    /// A [`MapArray`], with the field of the entries struct holding the key and
+    /// value fields.
+    Map {
+        entries: FieldRef,
+        fields: Fields,
+        keys_sorted: bool,
+    },
+    /// A [`StructArray`] of two [`ListArray`]s, one of the keys and one of the values,
+    /// sharing the same offsets. Avoids the overhead of a [`MapArray`] downstream.
+    KeyValueLists {
+        fields: Fields,
+        key_field: FieldRef,
+        value_field: FieldRef,
+    },
+}
+
 pub struct MapArrayDecoder {
+    column: String,
     keys: Box<dyn ArrayBatchDecoder>,
     values: Box<dyn ArrayBatchDecoder>,
-    present: Option<Box<dyn Iterator<Item = bool> + Send>>,
+    present: Option<PresentStream>,
     lengths: Box<dyn Iterator<Item = Result<u64>> + Send>,
-    fields: Fields,
+    shape: MapShape,
+    /// Whether the keys come before the values in the decoded struct
+    keys_first: bool,
 }
 
 impl MapArrayDecoder {
-    pub fn new(column: &Column, stripe: &Stripe) -> Result<Self> {
-        let present = get_present_vec(column, stripe)?
-            .map(|iter| Box::new(iter.into_iter()) as Box<dyn Iterator<Item = bool> + Send>);
+    /// ORC doesn't record whether map keys are sorted, so `keys_sorted` is taken
+    /// as is from the requested Arrow type; it is up to the caller to know the
+    /// writer sorted them.
+    pub fn new(
+        column: &Column,
+        entries: FieldRef,
+        keys_sorted: bool,
+        stripe: &Stripe,
+        string_validation: StringValidation,
+    ) -> Result<Self> {
+        // Arrow requires the entries to be a non-nullable struct of key and value
+        let fields = match entries.data_type() {
+            ArrowDataType::Struct(fields) if fields.len() == 2 && !entries.is_nullable() => {
+                fields.clone()
+            }
+            _ => MismatchedSchemaSnafu {
+                orc_type: column.data_type().clone(),
+                arrow_type: ArrowDataType::Map(entries.clone(), keys_sorted),
+            }
+            .fail()?,
+        };
+        let keys_first = keys_first(&fields);
+        let (key_field, value_field) = if keys_first {
+            (fields[0].clone(), fields[1].clone())
+        } else {
+            (fields[1].clone(), fields[0].clone())
+        };
+        let shape = MapShape::Map {
+            entries,
+            fields,
+            keys_sorted,
+        };
+        Self::try_new(
+            column,
+            key_field,
+            value_field,
+            shape,
+            keys_first,
+            stripe,
+            string_validation,
+        )
+    }
+
+    /// Decode into a struct of two list fields, the first holding the keys and the
+    /// second the values of each map, unless they are named `values` and `keys`.
+    pub fn new_key_value_lists(
+        column: &Column,
+        fields: Fields,
+        stripe: &Stripe,
+        string_validation: StringValidation,
+    ) -> Result<Self> {
+        let keys_first = fields.len() != 2 || keys_first(&fields);
+        let (key_field, value_field) = match fields.iter().as_slice() {
+            [keys, values] if keys_first => match (keys.data_type(), values.data_type()) {
+                (ArrowDataType::List(key_field), ArrowDataType::List(value_field)) => {
+                    Some((key_field.clone(), value_field.clone()))
+                }
+                _ => None,
+            },
+            [values, keys] => match (keys.data_type(), values.data_type()) {
+                (ArrowDataType::List(key_field), ArrowDataType::List(value_field)) => {
+                    Some((key_field.clone(), value_field.clone()))
+                }
+                _ => None,
+            },
+            _ => None,
+        }
+        .context(MismatchedSchemaSnafu {
+            orc_type: column.data_type().clone(),
+            arrow_type: ArrowDataType::Struct(fields.clone()),
+        })?;
+        let shape = MapShape::KeyValueLists {
+            fields,
+            key_field: key_field.clone(),
+            value_field: value_field.clone(),
+        };
+        Self::try_new(
+            column,
+            key_field,
+            value_field,
+            shape,
+            keys_first,
+            stripe,
+            string_validation,
+        )
+    }
+
+    fn try_new(
+        column: &Column,
+        key_field: FieldRef,
+        value_field: FieldRef,
+        shape: MapShape,
+        keys_first: bool,
+        stripe: &Stripe,
+        string_validation: StringValidation,
+    ) -> Result<Self> {
+        let present = PresentStream::try_new(column, stripe)?;
 
         let keys_column = &column.children()[0];
-        let keys = array_decoder_factory(keys_column, stripe)?;
+        let keys = array_decoder_factory(keys_column, key_field, stripe, string_validation)?;
 
         let values_column = &column.children()[1];
-        let values = array_decoder_factory(values_column, stripe)?;
+        let values = array_decoder_factory(values_column, value_field, stripe, string_validation)?;
 
         let reader = stripe.stream_map.get(column, Kind::Length)?;
         let lengths = get_rle_reader(column, reader)?;
 
-        let keys_field = Field::new("keys", keys_column.data_type().to_arrow_data_type(), false);
-        let keys_field = Arc::new(keys_field);
-        let values_field = Field::new(
-            "values",
-            values_column.data_type().to_arrow_data_type(),
-            true,
-        );
-        let values_field = Arc::new(values_field);
-
-        let fields = Fields::from(vec![keys_field, values_field]);
-
         Ok(Self {
+            column: column.name().to_owned(),
             keys,
             values,
             present,
             lengths,
-            fields,
+            shape,
+            keys_first,
         })
     }
 }
@@ -63,7 +171,7 @@ impl ArrayBatchDecoder for MapArrayDecoder {
         batch_size: usize,
         parent_present: Option<&[bool]>,
     ) -> Result<ArrayRef> {
-        let present = derive_present_vec(&mut self.present, parent_present, batch_size);
+        let present = derive_present_vec(&mut self.present, parent_present, batch_size)?;
 
         // How many lengths we need to fetch
         let elements_to_fetch = if let Some(present) = &present {
@@ -71,33 +179,71 @@ impl ArrayBatchDecoder for MapArrayDecoder {
         } else {
             batch_size
         };
-        let lengths = self
-            .lengths
-            .by_ref()
-            .take(elements_to_fetch)
-            .collect::<Result<Vec<_>>>()?;
-        debug_assert_eq!(
-            lengths.len(),
-            elements_to_fetch,
-            "less lengths than expected in MapArray"
-        );
-        let total_length: u64 = lengths.iter().sum();
+        let lengths = next_lengths(&self.column, self.lengths.as_mut(), elements_to_fetch)?;
+        let total_length = total_length(&self.column, &lengths, i32::MAX as usize)?;
         // Fetch key and value arrays, each with total_length elements
-        // Fetch child array as one Array with total_length elements
-        let keys_array = self.keys.next_batch(total_length as usize, None)?;
-        let values_array = self.values.next_batch(total_length as usize, None)?;
-        // Compose the keys + values array into a StructArray with two entries
-        let entries =
-            StructArray::try_new(self.fields.clone(), vec![keys_array, values_array], None)
-                .context(ArrowSnafu)?;
+        let keys_array = next_child_batch(&self.column, self.keys.as_mut(), total_length, None)?;
+        let values_array =
+            next_child_batch(&self.column, self.values.as_mut(), total_length, None)?;
         let lengths = populate_lengths_with_nulls(lengths, batch_size, &present);
         let offsets = OffsetBuffer::from_lengths(lengths);
         let null_buffer = present.map(NullBuffer::from);
 
-        let field = Arc::new(Field::new_struct("entries", self.fields.clone(), false));
-        let array =
-            MapArray::try_new(field, offsets, entries, null_buffer, false).context(ArrowSnafu)?;
-        let array = Arc::new(array);
+        let order = |keys: ArrayRef, values: ArrayRef| {
+            if self.keys_first {
+                vec![keys, values]
+            } else {
+                vec![values, keys]
+            }
+        };
+        let array: ArrayRef = match &self.shape {
+            MapShape::Map {
+                entries,
+                fields,
+                keys_sorted,
+            } => {
+                // Compose the keys + values array into a StructArray with two entries
+                let entries_array =
+                    StructArray::try_new(fields.clone(), order(keys_array, values_array), None)
+                        .context(ArrowSnafu)?;
+                let array = MapArray::try_new(
+                    entries.clone(),
+                    offsets,
+                    entries_array,
+                    null_buffer,
+                    *keys_sorted,
+                )
+                .context(ArrowSnafu)?;
+                Arc::new(array)
+            }
+            MapShape::KeyValueLists {
+                fields,
+                key_field,
+                value_field,
+            } => {
+                let keys = ListArray::try_new(
+                    key_field.clone(),
+                    offsets.clone(),
+                    keys_array,
+                    null_buffer.clone(),
+                )
+                .context(ArrowSnafu)?;
+                let values = ListArray::try_new(
+                    value_field.clone(),
+                    offsets,
+                    values_array,
+                    null_buffer.clone(),
+                )
+                .context(ArrowSnafu)?;
+                let array = StructArray::try_new(
+                    fields.clone(),
+                    order(Arc::new(keys), Arc::new(values)),
+                    null_buffer,
+                )
+                .context(ArrowSnafu)?;
+                Arc::new(array)
+            }
+        };
         Ok(array)
     }
 }
@@ -1,46 +1,76 @@
-use std::sync::Arc;
+use std::panic::AssertUnwindSafe;
+use std::sync::{mpsc, Arc};
+use std::time::Duration;
 
-use arrow::array::{ArrayRef, BooleanArray, BooleanBuilder, PrimitiveArray, PrimitiveBuilder};
+use arrow::array::temporal_conversions::as_datetime_with_timezone;
+use arrow::array::timezone::Tz;
+use arrow::array::{
+    make_array, new_null_array, Array, ArrayRef, AsArray, BooleanArray, BooleanBuilder,
+    PrimitiveArray, PrimitiveBuilder,
+};
 use arrow::buffer::NullBuffer;
-use arrow::datatypes::{ArrowPrimitiveType, UInt64Type};
+use arrow::compute::cast;
+use arrow::datatypes::{
+    ArrowPrimitiveType, DataType as ArrowDataType, FieldRef, IntervalDayTimeType, IntervalUnit,
+    IntervalYearMonthType, UInt64Type,
+};
 use arrow::datatypes::{
-    Date32Type, Float32Type, Float64Type, Int16Type, Int32Type, Int64Type, Int8Type, SchemaRef,
-    TimestampNanosecondType,
+    Date32Type, Float16Type, Float32Type, Float64Type, Int16Type, Int32Type, Int64Type, Int8Type,
+    SchemaRef, TimeUnit, TimestampNanosecondType,
 };
-use arrow::record_batch::RecordBatch;
-use snafu::ResultExt;
+use arrow::record_batch::{RecordBatch, RecordBatchOptions};
+use half::f16;
+use snafu::{ensure, OptionExt, ResultExt};
 
-use crate::error::{self, Result};
+use crate::error::{
+    self, ArrowSnafu, ChildLengthMismatchSnafu, CorruptLengthSnafu, DecodeColumnSnafu,
+    InvalidInputSnafu, MismatchedSchemaSnafu, NullInNonNullableSnafu, OrcError, OutOfSpecSnafu,
+    Result, TruncatedStreamSnafu,
+};
 use crate::proto::stream::Kind;
 use crate::reader::decode::boolean_rle::BooleanIter;
-use crate::reader::decode::byte_rle::ByteRleIter;
 use crate::reader::decode::float::FloatIter;
-use crate::reader::decode::get_rle_reader;
+use crate::reader::decode::{
+    ensure_direct_encoding, get_boolean_reader, get_byte_rle_reader, get_rle_reader, NInt,
+    SkipValues,
+};
+use crate::reader::decompress::Decompressor;
 use crate::schema::DataType;
 use crate::stripe::Stripe;
 
-use self::list::ListArrayDecoder;
+use self::decimal::{new_decimal128_decoder, new_decimal_string_decoder};
+use self::list::{FixedSizeListArrayDecoder, ListArrayDecoder};
 use self::map::MapArrayDecoder;
-use self::string::{new_binary_decoder, new_string_decoder};
+use self::run_end::new_run_end_decoder;
+pub use self::string::StringValidation;
+use self::string::{
+    new_binary_decoder, new_large_binary_decoder, new_large_string_decoder, new_string_decoder,
+};
 use self::struct_decoder::StructArrayDecoder;
 
 use super::column::timestamp::TimestampIterator;
-use super::column::{get_present_vec, Column};
+use super::column::Column;
+use super::decode_pool::DecodePool;
+#[cfg(feature = "metrics")]
+use super::metrics::MetricsRecorder;
 
+mod decimal;
 mod list;
 mod map;
+mod run_end;
 mod string;
 mod struct_decoder;
+pub mod visitor;
 
 struct PrimitiveArrayDecoder<T: ArrowPrimitiveType> {
     iter: Box<dyn Iterator<Item = Result<T::Native>> + Send>,
-    present: Option<Box<dyn Iterator<Item = bool> + Send>>,
+    present: Option<PresentStream>,
 }
 
 impl<T: ArrowPrimitiveType> PrimitiveArrayDecoder<T> {
     pub fn new(
         iter: Box<dyn Iterator<Item = Result<T::Native>> + Send>,
-        present: Option<Box<dyn Iterator<Item = bool> + Send>>,
+        present: Option<PresentStream>,
     ) -> Self {
         Self { iter, present }
     }
@@ -50,19 +80,18 @@ impl<T: ArrowPrimitiveType> PrimitiveArrayDecoder<T> {
         batch_size: usize,
         parent_present: Option<&[bool]>,
     ) -> Result<PrimitiveArray<T>> {
-        let present = derive_present_vec(&mut self.present, parent_present, batch_size);
+        let present = derive_present_vec(&mut self.present, parent_present, batch_size)?;
 
         match &present {
             Some(present) => {
                 let mut builder = PrimitiveBuilder::<T>::with_capacity(batch_size);
                 for &is_present in present {
                     if is_present {
-                        // TODO: return as error instead
-                        let val = self
-                            .iter
-                            .next()
-                            .transpose()?
-                            .expect("array less than expected length");
+                        // Stop at the end of a truncated stream, leaving the parent
+                        // to report the shorter array
+                        let Some(val) = self.iter.next().transpose()? else {
+                            break;
+                        };
                         builder.append_value(val);
                     } else {
                         builder.append_null();
@@ -97,10 +126,7 @@ impl<T: ArrowPrimitiveType> ArrayBatchDecoder for PrimitiveArrayDecoder<T> {
 }
 
 type UInt64ArrayDecoder = PrimitiveArrayDecoder<UInt64Type>;
-type Int64ArrayDecoder = PrimitiveArrayDecoder<Int64Type>;
-type Int32ArrayDecoder = PrimitiveArrayDecoder<Int32Type>;
-type Int16ArrayDecoder = PrimitiveArrayDecoder<Int16Type>;
-type Int8ArrayDecoder = PrimitiveArrayDecoder<Int8Type>;
+type Float16ArrayDecoder = PrimitiveArrayDecoder<Float16Type>;
 type Float32ArrayDecoder = PrimitiveArrayDecoder<Float32Type>;
 type Float64ArrayDecoder = PrimitiveArrayDecoder<Float64Type>;
 type TimestampArrayDecoder = PrimitiveArrayDecoder<TimestampNanosecondType>;
@@ -108,13 +134,13 @@ type DateArrayDecoder = PrimitiveArrayDecoder<Date32Type>; // TODO: does ORC enc
 
 struct BooleanArrayDecoder {
     iter: Box<dyn Iterator<Item = Result<bool>> + Send>,
-    present: Option<Box<dyn Iterator<Item = bool> + Send>>,
+    present: Option<PresentStream>,
 }
 
 impl BooleanArrayDecoder {
     pub fn new(
         iter: Box<dyn Iterator<Item = Result<bool>> + Send>,
-        present: Option<Box<dyn Iterator<Item = bool> + Send>>,
+        present: Option<PresentStream>,
     ) -> Self {
         Self { iter, present }
     }
@@ -126,19 +152,18 @@ impl ArrayBatchDecoder for BooleanArrayDecoder {
         batch_size: usize,
         parent_present: Option<&[bool]>,
     ) -> Result<ArrayRef> {
-        let present = derive_present_vec(&mut self.present, parent_present, batch_size);
+        let present = derive_present_vec(&mut self.present, parent_present, batch_size)?;
 
         match &present {
             Some(present) => {
                 let mut builder = BooleanBuilder::with_capacity(batch_size);
                 for &is_present in present {
                     if is_present {
-                        // TODO: return as error instead
-                        let val = self
-                            .iter
-                            .next()
-                            .transpose()?
-                            .expect("array less than expected length");
+                        // Stop at the end of a truncated stream, leaving the parent
+                        // to report the shorter array
+                        let Some(val) = self.iter.next().transpose()? else {
+                            break;
+                        };
                         builder.append_value(val);
                     } else {
                         builder.append_null();
@@ -162,38 +187,129 @@ impl ArrayBatchDecoder for BooleanArrayDecoder {
     }
 }
 
-fn merge_parent_present(
-    parent_present: &[bool],
-    present: impl IntoIterator<Item = bool> + Send,
-) -> Vec<bool> {
-    // present must have len <= parent_present
-    let mut present = present.into_iter();
-    let mut merged_present = Vec::with_capacity(parent_present.len());
-    for &is_present in parent_present {
-        if is_present {
-            let p = present.next().expect("array less than expected length");
-            merged_present.push(p);
-        } else {
-            merged_present.push(false);
+/// Values of the present stream of a column, decoded as batches are requested.
+pub struct PresentStream {
+    column: String,
+    values: SkipValues<BooleanIter<Decompressor>>,
+}
+
+impl PresentStream {
+    /// `None` if the column has no nulls in this stripe, i.e. it has no present
+    /// stream or the stripe statistics record it has no nulls.
+    pub fn try_new(column: &Column, stripe: &Stripe) -> Result<Option<Self>> {
+        if column.stripe_has_null(stripe) == Some(false) {
+            return Ok(None);
         }
+        let present = stripe
+            .stream_map
+            .get_opt(column, Kind::Present)
+            .map(|reader| Self {
+                column: column.name().to_owned(),
+                values: get_boolean_reader(reader),
+            });
+        Ok(present)
+    }
+
+    /// Take the next `n` values, failing if the stream is shorter.
+    pub(crate) fn next_n(&mut self, n: usize) -> Result<Vec<bool>> {
+        let values = self.values.by_ref().take(n).collect::<Result<Vec<_>>>()?;
+        ensure!(
+            values.len() == n,
+            TruncatedStreamSnafu {
+                column: self.column.clone(),
+                kind: Kind::Present,
+            }
+        );
+        Ok(values)
     }
-    merged_present
+}
+
+/// Merge the nulls of a parent into those of a child, where `present` holds a value
+/// for each slot the parent is present in.
+fn merge_parent_present(parent_present: &[bool], present: Vec<bool>) -> Vec<bool> {
+    let mut present = present.into_iter();
+    parent_present
+        .iter()
+        .map(|&is_present| is_present && present.next().unwrap_or_default())
+        .collect()
 }
 
 fn derive_present_vec(
-    present: &mut Option<Box<dyn Iterator<Item = bool> + Send>>,
+    present: &mut Option<PresentStream>,
     parent_present: Option<&[bool]>,
     batch_size: usize,
-) -> Option<Vec<bool>> {
-    match (present, parent_present) {
+) -> Result<Option<Vec<bool>>> {
+    let present = match (present, parent_present) {
         (Some(present), Some(parent_present)) => {
-            let present = present.by_ref().take(batch_size);
+            let num_present = parent_present
+                .iter()
+                .filter(|&&is_present| is_present)
+                .count();
+            let present = present.next_n(num_present)?;
             Some(merge_parent_present(parent_present, present))
         }
-        (Some(present), None) => Some(present.by_ref().take(batch_size).collect::<Vec<_>>()),
+        (Some(present), None) => Some(present.next_n(batch_size)?),
         (None, Some(parent_present)) => Some(parent_present.to_vec()),
         (None, None) => None,
-    }
+    };
+    Ok(present)
+}
+
+/// Take the next `n` values of the length stream of a column, failing if the stream
+/// is shorter.
+fn next_lengths(
+    column: &str,
+    lengths: &mut (dyn Iterator<Item = Result<u64>> + Send),
+    n: usize,
+) -> Result<Vec<u64>> {
+    let lengths = lengths.take(n).collect::<Result<Vec<_>>>()?;
+    ensure!(
+        lengths.len() == n,
+        TruncatedStreamSnafu {
+            column,
+            kind: Kind::Length,
+        }
+    );
+    Ok(lengths)
+}
+
+/// Decode the next `n` values of a child column, failing if its streams hold fewer
+/// values, before building the parent array from it.
+fn next_child_batch(
+    column: &str,
+    child: &mut dyn ArrayBatchDecoder,
+    n: usize,
+    parent_present: Option<&[bool]>,
+) -> Result<ArrayRef> {
+    let array = child.next_batch(n, parent_present)?;
+    ensure!(
+        array.len() == n,
+        ChildLengthMismatchSnafu {
+            column,
+            expected: n,
+            got: array.len(),
+        }
+    );
+    Ok(array)
+}
+
+/// Largest buffer allocated upfront for the values of a batch.
+pub(crate) const MAX_PREALLOCATION: usize = 64 * 1024 * 1024;
+
+/// Sum the lengths of the values of a batch, failing if the total exceeds `max`
+/// (the largest offset of the Arrow array being built) so corrupt lengths are
+/// reported rather than overflowing or causing huge allocations.
+pub(crate) fn total_length(column: &str, lengths: &[u64], max: usize) -> Result<usize> {
+    lengths
+        .iter()
+        .try_fold(0usize, |total, &length| {
+            let length = usize::try_from(length).ok()?;
+            total.checked_add(length).filter(|&total| total <= max)
+        })
+        .context(CorruptLengthSnafu {
+            column,
+            msg: format!("total length of batch exceeds {max}"),
+        })
 }
 
 /// Fix the lengths to account for nulls (represented as 0 length)
@@ -232,9 +348,15 @@ pub struct NaiveStripeDecoder {
     stripe: Stripe,
     schema_ref: SchemaRef,
     decoders: Vec<Box<dyn ArrayBatchDecoder>>,
+    /// Present stream of the root struct, nulling whole rows in all columns
+    root_present: Option<PresentStream>,
     index: usize,
     batch_size: usize,
     number_of_rows: usize,
+    /// Threads decoding the columns of each batch along with the calling thread
+    decode_pool: Option<Arc<DecodePool>>,
+    #[cfg(feature = "metrics")]
+    metrics: Option<MetricsRecorder>,
 }
 
 impl Iterator for NaiveStripeDecoder {
@@ -253,6 +375,9 @@ impl Iterator for NaiveStripeDecoder {
     }
 }
 
+/// Decodes the values of an ORC column (and its children) into Arrow arrays, one
+/// batch at a time. [`array_decoder_factory`] builds the decoders of all the
+/// supported types, which custom decoders may wrap, see [`DecoderFactory`].
 pub trait ArrayBatchDecoder: Send {
     /// Used as base for decoding ORC columns into Arrow arrays. Provide an input `batch_size`
     /// which specifies the upper limit of the number of values returned in the output array.
@@ -272,110 +397,609 @@ pub trait ArrayBatchDecoder: Send {
     ) -> Result<ArrayRef>;
 }
 
+fn new_timestamp_decoder(column: &Column, stripe: &Stripe) -> Result<Box<dyn ArrayBatchDecoder>> {
+    let data = stripe.stream_map.get(column, Kind::Data)?;
+    let data = get_rle_reader(column, data)?;
+
+    let secondary = stripe.stream_map.get(column, Kind::Secondary)?;
+    let secondary = get_rle_reader(column, secondary)?;
+
+    let iter = Box::new(TimestampIterator::new(data, secondary));
+    let present = PresentStream::try_new(column, stripe)?;
+
+    Ok(Box::new(TimestampArrayDecoder::new(iter, present)))
+}
+
+/// Key of the field metadata holding the time zone timestamps are truncated in
+/// when decoded into dates. See [`array_decoder_factory`].
+pub const DATE_TIMEZONE_METADATA_KEY: &str = "orc.date_timezone";
+
+/// Builds the decoder of a root column in place of [`array_decoder_factory`],
+/// e.g. to decode a binary column with the default decoder and post-process its
+/// arrays into an extension type. Returns `None` to leave the column to the
+/// default decoder.
+pub type DecoderFactory = Arc<
+    dyn Fn(&Column, FieldRef, &Stripe) -> Result<Option<Box<dyn ArrayBatchDecoder>>> + Send + Sync,
+>;
+
+/// Build a decoder for an ORC column which will output Arrow arrays of the type
+/// specified by `field`.
+///
+/// Integer columns may be decoded into a wider Arrow integer type than their
+/// ORC type (e.g. an ORC `int` into Arrow `Int64`), which is useful when files
+/// of the same dataset were written with evolving schemas. Narrowing is not
+/// supported and returns an error.
+///
+/// Integer columns may also be decoded into Arrow intervals: year-month intervals
+/// from a number of months (for columns up to `int`), or day-time intervals from
+/// a number of milliseconds. See [`INTERVAL_UNIT_ATTRIBUTE`](crate::schema::INTERVAL_UNIT_ATTRIBUTE).
+///
+/// Decimal columns may be decoded into Arrow `Decimal128`, as long as it has at
+/// least as many integer and fractional digits as the ORC type, or into Arrow
+/// `Utf8`, as decimal text, which supports values of any precision.
+///
+/// Map columns may be decoded into a struct of two list fields, holding the keys
+/// and the values of each map respectively, instead of an Arrow map. When decoded
+/// into an Arrow map, its `keys_sorted` flag is passed through as ORC doesn't
+/// record it. In both cases, the key and value fields may come in either order
+/// if they are named `key` and `value` (or `keys` and `values`), otherwise the
+/// keys are expected first.
+///
+/// String columns may be decoded into Arrow binary types, keeping values which
+/// aren't valid UTF-8 as they are. When decoded into string types, such values
+/// fail decoding unless `string_validation` is [`StringValidation::Lossy`], which
+/// replaces invalid sequences. It applies to nested string columns too.
+///
+/// Timestamp columns may be decoded into any Arrow timestamp unit, coarser units
+/// truncating the values. Timestamps without time zone are decoded without one,
+/// while timestamps with local time zone (instants) need one, of any value.
+///
+/// Timestamp columns may also be decoded into Arrow `Date32`, keeping the date of
+/// each instant in UTC, or in the time zone given by the
+/// [`DATE_TIMEZONE_METADATA_KEY`] metadata of the field. That is either a fixed
+/// offset such as `+02:00` or, with the `chrono-tz` feature of `arrow`, a time
+/// zone name such as `Europe/Paris`.
 pub fn array_decoder_factory(
     column: &Column,
+    field: FieldRef,
     stripe: &Stripe,
+    string_validation: StringValidation,
 ) -> Result<Box<dyn ArrayBatchDecoder>> {
-    let decoder: Box<dyn ArrayBatchDecoder> = match column.data_type() {
+    // Strings are only kept as bytes when decoded into binary types
+    let utf8_validation = match string_validation {
+        StringValidation::AsBinary => StringValidation::Strict,
+        validation => validation,
+    };
+    let decoder: Box<dyn ArrayBatchDecoder> = match (column.data_type(), field.data_type()) {
         // TODO: try make branches more generic, reduce duplication
-        DataType::Boolean { .. } => {
+        (DataType::Boolean { .. }, ArrowDataType::Boolean) => {
+            ensure_direct_encoding(column)?;
             let iter = stripe.stream_map.get(column, Kind::Data)?;
-            let iter = Box::new(BooleanIter::new(iter));
-            let present = get_present_vec(column, stripe)?
-                .map(|iter| Box::new(iter.into_iter()) as Box<dyn Iterator<Item = bool> + Send>);
+            let iter = Box::new(get_boolean_reader(iter));
+            let present = PresentStream::try_new(column, stripe)?;
             Box::new(BooleanArrayDecoder::new(iter, present))
         }
-        DataType::Byte { .. } => {
-            let iter = stripe.stream_map.get(column, Kind::Data)?;
-            let iter = Box::new(ByteRleIter::new(iter).map(|value| value.map(|value| value as i8)));
-            let present = get_present_vec(column, stripe)?
-                .map(|iter| Box::new(iter.into_iter()) as Box<dyn Iterator<Item = bool> + Send>);
-            Box::new(Int8ArrayDecoder::new(iter, present))
+        (DataType::Byte { .. }, ArrowDataType::Int8) => {
+            new_byte_decoder::<Int8Type>(column, stripe)?
         }
-        DataType::Short { .. } => {
-            let iter = stripe.stream_map.get(column, Kind::Data)?;
-            let iter = get_rle_reader(column, iter)?;
-            let present = get_present_vec(column, stripe)?
-                .map(|iter| Box::new(iter.into_iter()) as Box<dyn Iterator<Item = bool> + Send>);
-            Box::new(Int16ArrayDecoder::new(iter, present))
+        (DataType::Byte { .. }, ArrowDataType::Int16) => {
+            new_byte_decoder::<Int16Type>(column, stripe)?
         }
-        DataType::Int { .. } => {
-            let iter = stripe.stream_map.get(column, Kind::Data)?;
-            let iter = get_rle_reader(column, iter)?;
-            let present = get_present_vec(column, stripe)?
-                .map(|iter| Box::new(iter.into_iter()) as Box<dyn Iterator<Item = bool> + Send>);
-            Box::new(Int32ArrayDecoder::new(iter, present))
+        (DataType::Byte { .. }, ArrowDataType::Int32) => {
+            new_byte_decoder::<Int32Type>(column, stripe)?
         }
-        DataType::Long { .. } => {
-            let iter = stripe.stream_map.get(column, Kind::Data)?;
-            let iter = get_rle_reader(column, iter)?;
-            let present = get_present_vec(column, stripe)?
-                .map(|iter| Box::new(iter.into_iter()) as Box<dyn Iterator<Item = bool> + Send>);
-            Box::new(Int64ArrayDecoder::new(iter, present))
+        (DataType::Byte { .. }, ArrowDataType::Int64) => {
+            new_byte_decoder::<Int64Type>(column, stripe)?
+        }
+        (DataType::Short { .. }, ArrowDataType::Int16) => {
+            new_int_decoder::<Int16Type>(column, stripe)?
         }
-        DataType::Float { .. } => {
+        (DataType::Short { .. } | DataType::Int { .. }, ArrowDataType::Int32) => {
+            new_int_decoder::<Int32Type>(column, stripe)?
+        }
+        (
+            DataType::Short { .. } | DataType::Int { .. } | DataType::Long { .. },
+            ArrowDataType::Int64,
+        ) => new_int_decoder::<Int64Type>(column, stripe)?,
+        (DataType::Byte { .. }, ArrowDataType::Interval(IntervalUnit::YearMonth)) => {
+            new_byte_decoder::<IntervalYearMonthType>(column, stripe)?
+        }
+        (
+            DataType::Short { .. } | DataType::Int { .. },
+            ArrowDataType::Interval(IntervalUnit::YearMonth),
+        ) => new_int_decoder::<IntervalYearMonthType>(column, stripe)?,
+        (
+            DataType::Byte { .. }
+            | DataType::Short { .. }
+            | DataType::Int { .. }
+            | DataType::Long { .. },
+            ArrowDataType::Interval(IntervalUnit::DayTime),
+        ) => new_day_time_decoder(column, stripe)?,
+        (DataType::Float { .. }, ArrowDataType::Float32) => {
+            ensure_direct_encoding(column)?;
             let iter = stripe.stream_map.get(column, Kind::Data)?;
             let iter = Box::new(FloatIter::new(iter, stripe.number_of_rows));
-            let present = get_present_vec(column, stripe)?
-                .map(|iter| Box::new(iter.into_iter()) as Box<dyn Iterator<Item = bool> + Send>);
+            let present = PresentStream::try_new(column, stripe)?;
             Box::new(Float32ArrayDecoder::new(iter, present))
         }
-        DataType::Double { .. } => {
+        (DataType::Float { .. }, ArrowDataType::Float16) => {
+            // ORC has no half precision type, so round the stored 32-bit floats
+            ensure_direct_encoding(column)?;
+            let iter = stripe.stream_map.get(column, Kind::Data)?;
+            let iter = Box::new(
+                FloatIter::<f32, _>::new(iter, stripe.number_of_rows)
+                    .map(|value| value.map(f16::from_f32)),
+            );
+            let present = PresentStream::try_new(column, stripe)?;
+            Box::new(Float16ArrayDecoder::new(iter, present))
+        }
+        (DataType::Double { .. }, ArrowDataType::Float64) => {
+            ensure_direct_encoding(column)?;
             let iter = stripe.stream_map.get(column, Kind::Data)?;
             let iter = Box::new(FloatIter::new(iter, stripe.number_of_rows));
-            let present = get_present_vec(column, stripe)?
-                .map(|iter| Box::new(iter.into_iter()) as Box<dyn Iterator<Item = bool> + Send>);
+            let present = PresentStream::try_new(column, stripe)?;
             Box::new(Float64ArrayDecoder::new(iter, present))
         }
-        DataType::String { .. } | DataType::Varchar { .. } | DataType::Char { .. } => {
-            new_string_decoder(column, stripe)?
+        // Dictionary encoded stripes decode into dictionary arrays and direct encoded
+        // ones into string arrays, so cast to whichever was requested
+        (
+            DataType::String { .. } | DataType::Varchar { .. } | DataType::Char { .. },
+            ArrowDataType::Utf8,
+        ) => Box::new(CastArrayDecoder {
+            inner: new_string_decoder(column, stripe, utf8_validation)?,
+            data_type: ArrowDataType::Utf8,
+        }),
+        (
+            DataType::String { .. } | DataType::Varchar { .. } | DataType::Char { .. },
+            ArrowDataType::Dictionary(key, value),
+        ) if **key == ArrowDataType::UInt64 && **value == ArrowDataType::Utf8 => {
+            Box::new(CastArrayDecoder {
+                inner: new_string_decoder(column, stripe, utf8_validation)?,
+                data_type: field.data_type().clone(),
+            })
         }
-        DataType::Binary { .. } => new_binary_decoder(column, stripe)?,
-        DataType::Decimal { .. } => todo!(),
-        DataType::Timestamp { .. } => {
-            let data = stripe.stream_map.get(column, Kind::Data)?;
-            let data = get_rle_reader(column, data)?;
-
-            let secondary = stripe.stream_map.get(column, Kind::Secondary)?;
-            let secondary = get_rle_reader(column, secondary)?;
-
-            let iter = Box::new(TimestampIterator::new(data, secondary));
-            let present = get_present_vec(column, stripe)?
-                .map(|iter| Box::new(iter.into_iter()) as Box<dyn Iterator<Item = bool> + Send>);
-
-            Box::new(TimestampArrayDecoder::new(iter, present))
+        (
+            DataType::String { .. } | DataType::Varchar { .. } | DataType::Char { .. },
+            ArrowDataType::LargeUtf8,
+        ) => new_large_string_decoder(column, stripe, utf8_validation)?,
+        (
+            DataType::String { .. } | DataType::Varchar { .. } | DataType::Char { .. },
+            ArrowDataType::Binary,
+        ) => Box::new(CastArrayDecoder {
+            inner: new_string_decoder(column, stripe, StringValidation::AsBinary)?,
+            data_type: ArrowDataType::Binary,
+        }),
+        (
+            DataType::String { .. } | DataType::Varchar { .. } | DataType::Char { .. },
+            ArrowDataType::Dictionary(key, value),
+        ) if **key == ArrowDataType::UInt64 && **value == ArrowDataType::Binary => {
+            Box::new(CastArrayDecoder {
+                inner: new_string_decoder(column, stripe, StringValidation::AsBinary)?,
+                data_type: field.data_type().clone(),
+            })
+        }
+        (
+            DataType::String { .. } | DataType::Varchar { .. } | DataType::Char { .. },
+            ArrowDataType::LargeBinary,
+        ) => new_large_string_decoder(column, stripe, StringValidation::AsBinary)?,
+        (DataType::Binary { .. }, ArrowDataType::Binary) => new_binary_decoder(column, stripe)?,
+        (DataType::Binary { .. }, ArrowDataType::LargeBinary) => {
+            new_large_binary_decoder(column, stripe)?
         }
-        DataType::TimestampWithLocalTimezone { .. } => todo!(),
-        DataType::Date { .. } => {
+        (DataType::Decimal { .. }, ArrowDataType::Utf8) => {
+            new_decimal_string_decoder(column, stripe)?
+        }
+        (DataType::Decimal { .. }, ArrowDataType::Decimal128(precision, scale)) => {
+            new_decimal128_decoder(column, stripe, *precision, *scale)?
+        }
+        (DataType::Timestamp { .. }, ArrowDataType::Timestamp(TimeUnit::Nanosecond, None)) => {
+            new_timestamp_decoder(column, stripe)?
+        }
+        // Coarser units truncate the decoded nanoseconds
+        (DataType::Timestamp { .. }, ArrowDataType::Timestamp(_, None)) => {
+            Box::new(CastArrayDecoder {
+                inner: new_timestamp_decoder(column, stripe)?,
+                data_type: field.data_type().clone(),
+            })
+        }
+        // Any time zone can be requested, as it only changes how the instants are
+        // displayed; a timestamp without time zone can't be read as one though
+        (DataType::TimestampWithLocalTimezone { .. }, ArrowDataType::Timestamp(unit, Some(_))) => {
+            let inner = new_timestamp_decoder(column, stripe)?;
+            let inner = match unit {
+                TimeUnit::Nanosecond => inner,
+                unit => Box::new(CastArrayDecoder {
+                    inner,
+                    data_type: ArrowDataType::Timestamp(unit.clone(), None),
+                }),
+            };
+            Box::new(TimezoneArrayDecoder {
+                inner,
+                data_type: field.data_type().clone(),
+            })
+        }
+        (
+            DataType::Timestamp { .. } | DataType::TimestampWithLocalTimezone { .. },
+            ArrowDataType::Date32,
+        ) => {
+            let timezone = field
+                .metadata()
+                .get(DATE_TIMEZONE_METADATA_KEY)
+                .map(|timezone| timezone.parse::<Tz>())
+                .transpose()
+                .context(ArrowSnafu)?;
+            Box::new(TimestampDateArrayDecoder {
+                inner: new_timestamp_decoder(column, stripe)?,
+                timezone,
+            })
+        }
+        (DataType::Date { .. }, ArrowDataType::Date32) => {
             let iter = stripe.stream_map.get(column, Kind::Data)?;
             let iter = get_rle_reader(column, iter)?;
-            let present = get_present_vec(column, stripe)?
-                .map(|iter| Box::new(iter.into_iter()) as Box<dyn Iterator<Item = bool> + Send>);
+            let present = PresentStream::try_new(column, stripe)?;
             Box::new(DateArrayDecoder::new(iter, present))
         }
-        DataType::Struct { .. } => Box::new(StructArrayDecoder::new(column, stripe)?),
-        DataType::List { .. } => Box::new(ListArrayDecoder::new(column, stripe)?),
-        DataType::Map { .. } => Box::new(MapArrayDecoder::new(column, stripe)?),
-        DataType::Union { .. } => todo!(),
+        (DataType::Struct { .. }, ArrowDataType::Struct(fields)) => Box::new(
+            StructArrayDecoder::new(column, fields.clone(), stripe, string_validation)?,
+        ),
+        (DataType::List { .. }, ArrowDataType::List(child)) => Box::new(ListArrayDecoder::new(
+            column,
+            child.clone(),
+            stripe,
+            string_validation,
+        )?),
+        (DataType::List { .. }, ArrowDataType::FixedSizeList(child, size)) => {
+            Box::new(FixedSizeListArrayDecoder::new(
+                column,
+                child.clone(),
+                *size,
+                stripe,
+                string_validation,
+            )?)
+        }
+        (DataType::Map { .. }, ArrowDataType::Map(entries, keys_sorted)) => {
+            Box::new(MapArrayDecoder::new(
+                column,
+                entries.clone(),
+                *keys_sorted,
+                stripe,
+                string_validation,
+            )?)
+        }
+        (DataType::Map { .. }, ArrowDataType::Struct(fields)) => {
+            Box::new(MapArrayDecoder::new_key_value_lists(
+                column,
+                fields.clone(),
+                stripe,
+                string_validation,
+            )?)
+        }
+        // Runs of equal values are found after decoding, so any type can be requested
+        (_, ArrowDataType::RunEndEncoded(run_ends, values)) => {
+            new_run_end_decoder(column, run_ends, values, stripe, string_validation)?
+        }
+        (orc_type, arrow_type) => MismatchedSchemaSnafu {
+            orc_type: orc_type.clone(),
+            arrow_type: arrow_type.clone(),
+        }
+        .fail()?,
+    };
+
+    if field.is_nullable() {
+        Ok(decoder)
+    } else {
+        Ok(Box::new(NonNullableArrayDecoder {
+            inner: decoder,
+            column: column.name().to_owned(),
+        }))
+    }
+}
+
+/// Ensures the decoded arrays of a column don't contain nulls, as their Arrow
+/// field is non-nullable.
+///
+/// Nulls inherited from a null parent (e.g. a null struct) are allowed, as the
+/// child must have a value in that slot regardless.
+struct NonNullableArrayDecoder {
+    inner: Box<dyn ArrayBatchDecoder>,
+    column: String,
+}
+
+impl ArrayBatchDecoder for NonNullableArrayDecoder {
+    fn next_batch(
+        &mut self,
+        batch_size: usize,
+        parent_present: Option<&[bool]>,
+    ) -> Result<ArrayRef> {
+        let array = self.inner.next_batch(batch_size, parent_present)?;
+        if let Some(nulls) = array.nulls() {
+            let has_null = match parent_present {
+                Some(parent_present) => nulls
+                    .iter()
+                    .zip(parent_present)
+                    .any(|(is_valid, &parent_is_present)| !is_valid && parent_is_present),
+                None => nulls.null_count() > 0,
+            };
+            ensure!(
+                !has_null,
+                NullInNonNullableSnafu {
+                    column: self.column.clone()
+                }
+            );
+        }
+        Ok(array)
+    }
+}
+
+/// Casts the decoded arrays of a column to the requested Arrow type, if the inner
+/// decoder produced a different one.
+struct CastArrayDecoder {
+    inner: Box<dyn ArrayBatchDecoder>,
+    data_type: ArrowDataType,
+}
+
+impl ArrayBatchDecoder for CastArrayDecoder {
+    fn next_batch(
+        &mut self,
+        batch_size: usize,
+        parent_present: Option<&[bool]>,
+    ) -> Result<ArrayRef> {
+        let array = self.inner.next_batch(batch_size, parent_present)?;
+        if array.data_type() == &self.data_type {
+            Ok(array)
+        } else {
+            cast(&array, &self.data_type).context(ArrowSnafu)
+        }
+    }
+}
+
+/// Decodes a column which isn't supported as nulls of the requested Arrow type,
+/// without reading any of its streams.
+struct NullArrayDecoder {
+    data_type: ArrowDataType,
+}
+
+impl ArrayBatchDecoder for NullArrayDecoder {
+    fn next_batch(
+        &mut self,
+        batch_size: usize,
+        _parent_present: Option<&[bool]>,
+    ) -> Result<ArrayRef> {
+        Ok(new_null_array(&self.data_type, batch_size))
+    }
+}
+
+/// Sets the time zone of the decoded timestamp arrays of a column, which doesn't
+/// change their values as those are relative to UTC.
+struct TimezoneArrayDecoder {
+    inner: Box<dyn ArrayBatchDecoder>,
+    data_type: ArrowDataType,
+}
+
+impl ArrayBatchDecoder for TimezoneArrayDecoder {
+    fn next_batch(
+        &mut self,
+        batch_size: usize,
+        parent_present: Option<&[bool]>,
+    ) -> Result<ArrayRef> {
+        let array = self.inner.next_batch(batch_size, parent_present)?;
+        let data = array
+            .into_data()
+            .into_builder()
+            .data_type(self.data_type.clone())
+            .build()
+            .context(ArrowSnafu)?;
+        Ok(make_array(data))
+    }
+}
+
+/// Truncates the decoded timestamps of a column to their date, in UTC unless a
+/// time zone is given.
+struct TimestampDateArrayDecoder {
+    inner: Box<dyn ArrayBatchDecoder>,
+    timezone: Option<Tz>,
+}
+
+impl ArrayBatchDecoder for TimestampDateArrayDecoder {
+    fn next_batch(
+        &mut self,
+        batch_size: usize,
+        parent_present: Option<&[bool]>,
+    ) -> Result<ArrayRef> {
+        const NANOSECONDS_IN_DAY: i64 = 86_400_000_000_000;
+        const SECONDS_IN_DAY: i64 = 86_400;
+
+        let array = self.inner.next_batch(batch_size, parent_present)?;
+        let array = array.as_primitive::<TimestampNanosecondType>();
+        let dates: PrimitiveArray<Date32Type> = match self.timezone {
+            None => array.unary(|nanoseconds| nanoseconds.div_euclid(NANOSECONDS_IN_DAY) as i32),
+            Some(timezone) => array.unary(|nanoseconds| {
+                // Any i64 of nanoseconds is in range of chrono
+                let local =
+                    as_datetime_with_timezone::<TimestampNanosecondType>(nanoseconds, timezone)
+                        .unwrap()
+                        .naive_local();
+                local.and_utc().timestamp().div_euclid(SECONDS_IN_DAY) as i32
+            }),
+        };
+        Ok(Arc::new(dates))
+    }
+}
+
+/// Decode an ORC byte column into any Arrow integer type at least as wide as [`i8`].
+fn new_byte_decoder<T>(column: &Column, stripe: &Stripe) -> Result<Box<dyn ArrayBatchDecoder>>
+where
+    T: ArrowPrimitiveType,
+    T::Native: From<i8>,
+{
+    ensure_direct_encoding(column)?;
+    let iter = stripe.stream_map.get(column, Kind::Data)?;
+    let iter = Box::new(
+        get_byte_rle_reader(iter).map(|value| value.map(|value| T::Native::from(value as i8))),
+    );
+    let present = PresentStream::try_new(column, stripe)?;
+    Ok(Box::new(PrimitiveArrayDecoder::<T>::new(iter, present)))
+}
+
+/// Decode an ORC integer column of milliseconds into Arrow day-time intervals,
+/// with the whole days split out of the milliseconds.
+fn new_day_time_decoder(column: &Column, stripe: &Stripe) -> Result<Box<dyn ArrayBatchDecoder>> {
+    const MILLISECONDS_IN_DAY: i64 = 86_400_000;
+
+    let iter = if let DataType::Byte { .. } = column.data_type() {
+        ensure_direct_encoding(column)?;
+        let iter = stripe.stream_map.get(column, Kind::Data)?;
+        Box::new(get_byte_rle_reader(iter).map(|value| value.map(|value| value as i8 as i64)))
+    } else {
+        let iter = stripe.stream_map.get(column, Kind::Data)?;
+        get_rle_reader::<i64>(column, iter)?
     };
+    let iter = Box::new(iter.map(|value| {
+        let millis = value?;
+        let days = i32::try_from(millis.div_euclid(MILLISECONDS_IN_DAY))
+            .ok()
+            .context(OutOfSpecSnafu {
+                msg: format!("Interval of {millis} milliseconds has too many days"),
+            })?;
+        let millis = millis.rem_euclid(MILLISECONDS_IN_DAY) as i32;
+        Ok(IntervalDayTimeType::make_value(days, millis))
+    }));
+    let present = PresentStream::try_new(column, stripe)?;
+    Ok(Box::new(PrimitiveArrayDecoder::<IntervalDayTimeType>::new(
+        iter, present,
+    )))
+}
 
-    Ok(decoder)
+/// Decode an RLE encoded ORC integer column directly into the native type of `T`.
+///
+/// As RLE decoding is independent of the ORC integer width, narrower columns
+/// (e.g. `short`) can be decoded straight into wider types (e.g. `i64`).
+fn new_int_decoder<T>(column: &Column, stripe: &Stripe) -> Result<Box<dyn ArrayBatchDecoder>>
+where
+    T: ArrowPrimitiveType,
+    T::Native: NInt,
+{
+    let iter = stripe.stream_map.get(column, Kind::Data)?;
+    let iter = get_rle_reader(column, iter)?;
+    let present = PresentStream::try_new(column, stripe)?;
+    Ok(Box::new(PrimitiveArrayDecoder::<T>::new(iter, present)))
+}
+
+/// Next batch of each of the `decoders`, with the time spent decoding it, which
+/// is only measured for the metrics (and zero otherwise).
+fn next_batches(
+    decoders: &mut [Box<dyn ArrayBatchDecoder>],
+    batch_size: usize,
+    parent_present: Option<&[bool]>,
+) -> Vec<(Result<ArrayRef>, Duration)> {
+    decoders
+        .iter_mut()
+        .map(|decoder| {
+            #[cfg(feature = "metrics")]
+            let start = std::time::Instant::now();
+            let array = decoder.next_batch(batch_size, parent_present);
+            #[cfg(feature = "metrics")]
+            let elapsed = start.elapsed();
+            #[cfg(not(feature = "metrics"))]
+            let elapsed = Duration::ZERO;
+            (array, elapsed)
+        })
+        .collect()
+}
+
+/// Context of errors decoding a root column, so they say which column of which
+/// stripe failed.
+fn decode_column_context(
+    column: &Column,
+    stripe: &Stripe,
+) -> DecodeColumnSnafu<String, u32, usize> {
+    DecodeColumnSnafu {
+        column: column.name().to_owned(),
+        column_id: column.column_id(),
+        stripe: stripe.stripe_offset,
+    }
 }
 
 impl NaiveStripeDecoder {
+    /// Like [`next_batches`] for all the columns, workers of `pool` decoding
+    /// contiguous groups of columns while this thread decodes the first group. The
+    /// decoders are moved to the workers and back, so are kept in column order.
+    fn next_batches_on_pool(
+        &mut self,
+        pool: &DecodePool,
+        batch_size: usize,
+        root_present: Option<Arc<[bool]>>,
+    ) -> Vec<(Result<ArrayRef>, Duration)> {
+        let threads = pool.decode_threads();
+        let group_size = (self.decoders.len() + threads - 1) / threads;
+        let mut decoders = std::mem::take(&mut self.decoders).into_iter().peekable();
+        let mut groups = vec![];
+        while decoders.peek().is_some() {
+            groups.push(decoders.by_ref().take(group_size).collect::<Vec<_>>());
+        }
+
+        let (sender, receiver) = mpsc::channel();
+        let mut groups = groups.into_iter().enumerate();
+        let (_, mut first_group) = groups.next().expect("no columns to decode");
+        for (index, mut decoders) in groups {
+            let sender = sender.clone();
+            let root_present = root_present.clone();
+            pool.spawn(move || {
+                let arrays = std::panic::catch_unwind(AssertUnwindSafe(|| {
+                    next_batches(&mut decoders, batch_size, root_present.as_deref())
+                }));
+                // Only fails if this thread panicked decoding its own group
+                let _ = sender.send((index, decoders, arrays));
+            });
+        }
+        drop(sender);
+        let arrays = next_batches(&mut first_group, batch_size, root_present.as_deref());
+
+        let mut results = vec![(0, first_group, Ok(arrays))];
+        results.extend(receiver.iter());
+        results.sort_by_key(|(index, _, _)| *index);
+        let mut arrays = Vec::with_capacity(results.len() * group_size);
+        for (_, decoders, group_arrays) in results {
+            self.decoders.extend(decoders);
+            arrays.extend(group_arrays.unwrap_or_else(|panic| std::panic::resume_unwind(panic)));
+        }
+        arrays
+    }
+
     fn inner_decode_next_batch(&mut self, remaining: usize) -> Result<Vec<ArrayRef>> {
         let chunk = self.batch_size.min(remaining);
 
         let mut fields = Vec::with_capacity(self.stripe.columns.len());
+        let root_present = self
+            .root_present
+            .as_mut()
+            .map(|present| present.next_n(chunk))
+            .transpose()?;
 
-        for decoder in &mut self.decoders {
-            let array = decoder.next_batch(chunk, None)?;
+        let arrays = match self.decode_pool.clone() {
+            Some(pool) if self.decoders.len() > 1 => {
+                self.next_batches_on_pool(&pool, chunk, root_present.map(Arc::from))
+            }
+            _ => next_batches(&mut self.decoders, chunk, root_present.as_deref()),
+        };
+        for ((array, elapsed), column) in arrays.into_iter().zip(&self.stripe.columns) {
+            let array = array.context(decode_column_context(column, &self.stripe))?;
+            #[cfg(feature = "metrics")]
+            if let Some(metrics) = &self.metrics {
+                metrics.record(column.column_id(), elapsed, &array);
+            }
+            #[cfg(not(feature = "metrics"))]
+            let _ = elapsed;
             if array.is_empty() {
                 break;
             } else {
                 fields.push(array);
             }
         }
+        #[cfg(feature = "metrics")]
+        if let Some(metrics) = &self.metrics {
+            metrics.finish_batch();
+        }
 
         Ok(fields)
     }
@@ -383,40 +1007,120 @@ impl NaiveStripeDecoder {
     fn decode_next_batch(&mut self, remaining: usize) -> Result<Option<RecordBatch>> {
         let fields = self.inner_decode_next_batch(remaining)?;
 
-        if fields.is_empty() {
+        if self.decoders.is_empty() {
+            // No columns projected (e.g. to count rows), so batches only carry a row count
+            let options =
+                RecordBatchOptions::new().with_row_count(Some(self.batch_size.min(remaining)));
+            Ok(Some(
+                RecordBatch::try_new_with_options(self.schema_ref.clone(), fields, &options)
+                    .context(error::ConvertRecordBatchSnafu)?,
+            ))
+        } else if fields.is_empty() {
             Ok(None)
         } else {
-            //TODO(weny): any better way?
-            let fields = self
-                .schema_ref
-                .fields
-                .into_iter()
-                .map(|field| field.name())
-                .zip(fields)
-                .collect::<Vec<_>>();
-
             Ok(Some(
-                RecordBatch::try_from_iter(fields).context(error::ConvertRecordBatchSnafu)?,
+                RecordBatch::try_new(self.schema_ref.clone(), fields)
+                    .context(error::ConvertRecordBatchSnafu)?,
             ))
         }
     }
 
     pub fn new(stripe: Stripe, schema_ref: SchemaRef, batch_size: usize) -> Result<Self> {
+        Self::try_new(stripe, schema_ref, batch_size, false)
+    }
+
+    /// Like [`Self::new`], decoding root columns which can't be read as their
+    /// (nullable) Arrow field as nulls if `skip_unsupported_columns` is set.
+    pub fn try_new(
+        stripe: Stripe,
+        schema_ref: SchemaRef,
+        batch_size: usize,
+        skip_unsupported_columns: bool,
+    ) -> Result<Self> {
+        Self::try_new_with_decoder_factory(
+            stripe,
+            schema_ref,
+            batch_size,
+            skip_unsupported_columns,
+            None,
+            StringValidation::default(),
+        )
+    }
+
+    /// Like [`Self::try_new`], building the decoders of root columns with
+    /// `decoder_factory` first, if given, and decoding string columns with
+    /// `string_validation` (see [`array_decoder_factory`]).
+    pub fn try_new_with_decoder_factory(
+        stripe: Stripe,
+        schema_ref: SchemaRef,
+        batch_size: usize,
+        skip_unsupported_columns: bool,
+        decoder_factory: Option<&DecoderFactory>,
+        string_validation: StringValidation,
+    ) -> Result<Self> {
+        ensure!(
+            stripe.columns.len() == schema_ref.fields().len(),
+            InvalidInputSnafu {
+                msg: format!(
+                    "Schema has {} fields but {} columns are projected",
+                    schema_ref.fields().len(),
+                    stripe.columns.len()
+                )
+            }
+        );
         let mut decoders = Vec::with_capacity(stripe.columns.len());
         let number_of_rows = stripe.number_of_rows;
 
-        for col in &stripe.columns {
-            let decoder = array_decoder_factory(col, &stripe)?;
+        for (col, field) in stripe.columns.iter().zip(schema_ref.fields()) {
+            let custom_decoder = match decoder_factory {
+                Some(decoder_factory) => decoder_factory(col, field.clone(), &stripe)
+                    .context(decode_column_context(col, &stripe))?,
+                None => None,
+            };
+            let decoder = match custom_decoder {
+                Some(decoder) => Ok(decoder),
+                None => array_decoder_factory(col, field.clone(), &stripe, string_validation),
+            };
+            let decoder = match decoder {
+                Err(OrcError::MismatchedSchema { .. })
+                    if skip_unsupported_columns && field.is_nullable() =>
+                {
+                    Box::new(NullArrayDecoder {
+                        data_type: field.data_type().clone(),
+                    })
+                }
+                decoder => decoder.context(decode_column_context(col, &stripe))?,
+            };
             decoders.push(decoder);
         }
+        let root_present = PresentStream::try_new(&stripe.root_column(), &stripe)?;
 
         Ok(Self {
             stripe,
             schema_ref,
             decoders,
+            root_present,
             index: 0,
             batch_size,
             number_of_rows,
+            decode_pool: None,
+            #[cfg(feature = "metrics")]
+            metrics: None,
         })
     }
+
+    /// Decode the columns of each batch on the threads of `decode_pool` too, each
+    /// reading, decompressing and decoding the streams of its own columns. Batches
+    /// are the same as when decoding on the calling thread only, as by default.
+    pub(crate) fn with_decode_pool(mut self, decode_pool: Option<Arc<DecodePool>>) -> Self {
+        self.decode_pool = decode_pool;
+        self
+    }
+
+    /// Record the decoding metrics of each column to `metrics`.
+    #[cfg(feature = "metrics")]
+    pub(crate) fn with_metrics(mut self, metrics: MetricsRecorder) -> Self {
+        self.metrics = Some(metrics);
+        self
+    }
 }
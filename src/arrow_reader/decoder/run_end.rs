@@ -0,0 +1,104 @@
+use std::sync::Arc;
+
+use arrow::array::{make_array, Array, ArrayRef, PrimitiveArray, RunArray, UInt64Array};
+use arrow::compute::kernels::cmp::distinct;
+use arrow::compute::take;
+use arrow::datatypes::{
+    ArrowNativeType, DataType as ArrowDataType, FieldRef, Int16Type, Int32Type, Int64Type,
+    RunEndIndexType,
+};
+use snafu::{OptionExt, ResultExt};
+
+use crate::arrow_reader::column::Column;
+use crate::arrow_reader::decoder::{array_decoder_factory, ArrayBatchDecoder, StringValidation};
+use crate::arrow_reader::Stripe;
+use crate::error::{ArrowSnafu, InvalidInputSnafu, MismatchedSchemaSnafu, Result};
+
+/// Decoder of a column into [`RunArray`]s of the given run ends and values fields,
+/// for columns with long runs of repeated values.
+pub fn new_run_end_decoder(
+    column: &Column,
+    run_ends: &FieldRef,
+    values: &FieldRef,
+    stripe: &Stripe,
+    string_validation: StringValidation,
+) -> Result<Box<dyn ArrayBatchDecoder>> {
+    let data_type = ArrowDataType::RunEndEncoded(run_ends.clone(), values.clone());
+    match run_ends.data_type() {
+        ArrowDataType::Int16 | ArrowDataType::Int32 | ArrowDataType::Int64 => (),
+        _ => MismatchedSchemaSnafu {
+            orc_type: column.data_type().clone(),
+            arrow_type: data_type.clone(),
+        }
+        .fail()?,
+    }
+    Ok(Box::new(RunEndArrayDecoder {
+        inner: array_decoder_factory(column, values.clone(), stripe, string_validation)?,
+        run_ends: run_ends.clone(),
+        data_type,
+    }))
+}
+
+/// Groups the values decoded by the inner decoder into runs of equal values, null
+/// values forming runs too.
+struct RunEndArrayDecoder {
+    inner: Box<dyn ArrayBatchDecoder>,
+    run_ends: FieldRef,
+    data_type: ArrowDataType,
+}
+
+impl ArrayBatchDecoder for RunEndArrayDecoder {
+    fn next_batch(
+        &mut self,
+        batch_size: usize,
+        parent_present: Option<&[bool]>,
+    ) -> Result<ArrayRef> {
+        let values = self.inner.next_batch(batch_size, parent_present)?;
+        let len = values.len();
+
+        let mut run_ends = vec![];
+        if len > 1 {
+            let changes = distinct(&values.slice(0, len - 1), &values.slice(1, len - 1))
+                .context(ArrowSnafu)?;
+            run_ends.extend(changes.values().set_indices().map(|index| index + 1));
+        }
+        if len > 0 {
+            run_ends.push(len);
+        }
+        let last_of_runs =
+            UInt64Array::from_iter_values(run_ends.iter().map(|&end| end as u64 - 1));
+        let values = take(&values, &last_of_runs, None).context(ArrowSnafu)?;
+
+        let array = match self.run_ends.data_type() {
+            ArrowDataType::Int16 => run_array::<Int16Type>(&run_ends, &values)?,
+            ArrowDataType::Int32 => run_array::<Int32Type>(&run_ends, &values)?,
+            ArrowDataType::Int64 => run_array::<Int64Type>(&run_ends, &values)?,
+            _ => unreachable!("checked when creating the decoder"),
+        };
+        // Use the field names and nullability which were requested
+        let data = array
+            .into_data()
+            .into_builder()
+            .data_type(self.data_type.clone())
+            .build()
+            .context(ArrowSnafu)?;
+        Ok(make_array(data))
+    }
+}
+
+fn run_array<R: RunEndIndexType>(run_ends: &[usize], values: &ArrayRef) -> Result<ArrayRef> {
+    let run_ends = run_ends
+        .iter()
+        .map(|&end| R::Native::from_usize(end))
+        .collect::<Option<Vec<_>>>()
+        .with_context(|| InvalidInputSnafu {
+            msg: format!(
+                "Batch of {} rows is too long for {} run ends",
+                run_ends.last().unwrap_or(&0),
+                R::DATA_TYPE
+            ),
+        })?;
+    let run_ends = PrimitiveArray::<R>::from_iter_values(run_ends);
+    let array = RunArray::try_new(&run_ends, values.as_ref()).context(ArrowSnafu)?;
+    Ok(Arc::new(array))
+}
@@ -2,93 +2,175 @@ use std::io::Read;
 use std::marker::PhantomData;
 use std::sync::Arc;
 
-use arrow::array::{ArrayRef, DictionaryArray, GenericByteArray, StringArray};
+use arrow::array::OffsetSizeTrait;
+use arrow::array::{
+    Array, ArrayRef, DictionaryArray, GenericBinaryArray, GenericByteArray, GenericStringArray,
+};
 use arrow::buffer::{Buffer, OffsetBuffer};
-use arrow::datatypes::{ByteArrayType, GenericBinaryType, GenericStringType};
-use snafu::ResultExt;
+use arrow::datatypes::{ByteArrayType, DataType as ArrowDataType, GenericBinaryType};
+use snafu::{ensure, ResultExt};
 
-use crate::arrow_reader::column::{get_present_vec, Column};
+use crate::arrow_reader::column::Column;
 use crate::arrow_reader::decoder::{
-    create_null_buffer, derive_present_vec, populate_lengths_with_nulls, UInt64ArrayDecoder,
+    create_null_buffer, derive_present_vec, next_lengths, populate_lengths_with_nulls,
+    total_length, CastArrayDecoder, PresentStream, UInt64ArrayDecoder, MAX_PREALLOCATION,
+};
+use crate::error::{
+    ArrowSnafu, InvalidUtf8Snafu, IoSnafu, OffsetOverflowSnafu, Result, TruncatedStreamSnafu,
 };
-use crate::error::{ArrowSnafu, IoSnafu, Result};
 use crate::proto::column_encoding::Kind as ColumnEncodingKind;
 use crate::proto::stream::Kind;
-use crate::reader::decode::{get_rle_reader, RleVersion};
+use crate::reader::decode::{get_rle_reader, get_unsigned_rle_reader};
 use crate::reader::decompress::Decompressor;
 use crate::stripe::Stripe;
 
 use super::ArrayBatchDecoder;
 
-// TODO: reduce duplication with string below
 pub fn new_binary_decoder(column: &Column, stripe: &Stripe) -> Result<Box<dyn ArrayBatchDecoder>> {
-    let present = get_present_vec(column, stripe)?
-        .map(|iter| Box::new(iter.into_iter()) as Box<dyn Iterator<Item = bool> + Send>);
-
-    let lengths = stripe.stream_map.get(column, Kind::Length)?;
-    let lengths = get_rle_reader::<u64, _>(column, lengths)?;
-
-    let bytes = Box::new(stripe.stream_map.get(column, Kind::Data)?);
-    Ok(Box::new(BinaryArrayDecoder::new(bytes, lengths, present)))
+    let decoder: BinaryArrayDecoder = new_direct_decoder(column, stripe)?;
+    Ok(Box::new(decoder))
 }
 
-pub fn new_string_decoder(column: &Column, stripe: &Stripe) -> Result<Box<dyn ArrayBatchDecoder>> {
-    let kind = column.encoding().kind();
-    let rle_version = RleVersion::from(kind);
-    let present = get_present_vec(column, stripe)?
-        .map(|iter| Box::new(iter.into_iter()) as Box<dyn Iterator<Item = bool> + Send>);
+/// Like [`new_binary_decoder`], with 64-bit offsets so a batch can hold more than
+/// 2GiB of values.
+pub fn new_large_binary_decoder(
+    column: &Column,
+    stripe: &Stripe,
+) -> Result<Box<dyn ArrayBatchDecoder>> {
+    let decoder = new_direct_decoder::<GenericBinaryType<i64>>(column, stripe)?;
+    Ok(Box::new(decoder))
+}
 
-    let lengths = stripe.stream_map.get(column, Kind::Length)?;
-    let lengths = rle_version.get_unsigned_rle_reader(lengths);
+/// How the values of string columns which aren't valid UTF-8 are decoded.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum StringValidation {
+    /// Fail, reporting the index of the first invalid value.
+    #[default]
+    Strict,
+    /// Replace invalid sequences by the replacement character, U+FFFD.
+    Lossy,
+    /// Decode into binary arrays of the bytes as they are.
+    AsBinary,
+}
 
-    match kind {
+/// Decoder of a string column into string arrays, or binary arrays with
+/// [`StringValidation::AsBinary`]. Dictionary encoded stripes are decoded into
+/// dictionary arrays.
+pub fn new_string_decoder(
+    column: &Column,
+    stripe: &Stripe,
+    validation: StringValidation,
+) -> Result<Box<dyn ArrayBatchDecoder>> {
+    match column.encoding().kind() {
         ColumnEncodingKind::Direct | ColumnEncodingKind::DirectV2 => {
-            let bytes = Box::new(stripe.stream_map.get(column, Kind::Data)?);
-            Ok(Box::new(DirectStringArrayDecoder::new(
-                bytes, lengths, present,
-            )))
+            let decoder: BinaryArrayDecoder = new_direct_decoder(column, stripe)?;
+            Ok(Utf8ArrayDecoder::new_boxed(decoder, validation))
         }
         ColumnEncodingKind::Dictionary | ColumnEncodingKind::DictionaryV2 => {
+            let present = PresentStream::try_new(column, stripe)?;
+            let lengths = stripe.stream_map.get(column, Kind::Length)?;
+            let lengths = get_unsigned_rle_reader(column, lengths);
+
             let bytes = Box::new(stripe.stream_map.get(column, Kind::DictionaryData)?);
             // TODO: is this always guaranteed to be set for all dictionaries?
             let dictionary_size = column.dictionary_size();
             debug_assert!(dictionary_size > 0, "dictionary cannot be empty");
             // We assume here we have fetched all the dictionary strings (according to size above)
-            let dictionary_strings = DirectStringArrayDecoder::new(bytes, lengths, None)
-                .next_byte_batch(dictionary_size, None)?;
-            let dictionary_strings = Arc::new(dictionary_strings);
+            let dictionary =
+                BinaryArrayDecoder::new(column, bytes, Kind::DictionaryData, lengths, None)
+                    .next_byte_batch(dictionary_size, None)?;
+            let dictionary: ArrayRef = match validation {
+                StringValidation::AsBinary => Arc::new(dictionary),
+                _ => Arc::new(binary_to_string(
+                    column.name(),
+                    Kind::DictionaryData,
+                    dictionary,
+                    0,
+                    validation,
+                )?),
+            };
 
             let indexes = stripe.stream_map.get(column, Kind::Data)?;
-            let indexes = rle_version.get_unsigned_rle_reader(indexes);
+            let indexes = get_unsigned_rle_reader(column, indexes);
             let indexes = UInt64ArrayDecoder::new(indexes, present);
 
             Ok(Box::new(DictionaryStringArrayDecoder::new(
-                indexes,
-                dictionary_strings,
+                indexes, dictionary,
             )?))
         }
     }
 }
 
-// TODO: check this offset size type
-pub type DirectStringArrayDecoder = GenericByteArrayDecoder<GenericStringType<i32>>;
+/// Like [`new_string_decoder`], with 64-bit offsets so a batch can hold more than
+/// 2GiB of strings.
+pub fn new_large_string_decoder(
+    column: &Column,
+    stripe: &Stripe,
+    validation: StringValidation,
+) -> Result<Box<dyn ArrayBatchDecoder>> {
+    match column.encoding().kind() {
+        ColumnEncodingKind::Direct | ColumnEncodingKind::DirectV2 => {
+            let decoder = new_direct_decoder::<GenericBinaryType<i64>>(column, stripe)?;
+            Ok(Utf8ArrayDecoder::new_boxed(decoder, validation))
+        }
+        // The dictionary of a stripe is decoded as a whole, so it is always small
+        // enough for 32-bit offsets
+        ColumnEncodingKind::Dictionary | ColumnEncodingKind::DictionaryV2 => {
+            let data_type = match validation {
+                StringValidation::AsBinary => ArrowDataType::LargeBinary,
+                _ => ArrowDataType::LargeUtf8,
+            };
+            Ok(Box::new(CastArrayDecoder {
+                inner: new_string_decoder(column, stripe, validation)?,
+                data_type,
+            }))
+        }
+    }
+}
+
+fn new_direct_decoder<T: ByteArrayType>(
+    column: &Column,
+    stripe: &Stripe,
+) -> Result<GenericByteArrayDecoder<T>> {
+    let present = PresentStream::try_new(column, stripe)?;
+
+    let lengths = stripe.stream_map.get(column, Kind::Length)?;
+    let lengths = get_rle_reader::<u64>(column, lengths)?;
+
+    let bytes = Box::new(stripe.stream_map.get(column, Kind::Data)?);
+    Ok(GenericByteArrayDecoder::new(
+        column,
+        bytes,
+        Kind::Data,
+        lengths,
+        present,
+    ))
+}
+
 pub type BinaryArrayDecoder = GenericByteArrayDecoder<GenericBinaryType<i32>>;
 
 pub struct GenericByteArrayDecoder<T: ByteArrayType> {
+    column: String,
     bytes: Box<Decompressor>,
+    /// Kind of the stream the bytes are read from
+    bytes_kind: Kind,
     lengths: Box<dyn Iterator<Item = Result<u64>> + Send>,
-    present: Option<Box<dyn Iterator<Item = bool> + Send>>,
+    present: Option<PresentStream>,
     phantom: PhantomData<T>,
 }
 
 impl<T: ByteArrayType> GenericByteArrayDecoder<T> {
     fn new(
+        column: &Column,
         bytes: Box<Decompressor>,
+        bytes_kind: Kind,
         lengths: Box<dyn Iterator<Item = Result<u64>> + Send>,
-        present: Option<Box<dyn Iterator<Item = bool> + Send>>,
+        present: Option<PresentStream>,
     ) -> Self {
         Self {
+            column: column.name().to_owned(),
             bytes,
+            bytes_kind,
             lengths,
             present,
             phantom: Default::default(),
@@ -100,7 +182,7 @@ impl<T: ByteArrayType> GenericByteArrayDecoder<T> {
         batch_size: usize,
         parent_present: Option<&[bool]>,
     ) -> Result<GenericByteArray<T>> {
-        let present = derive_present_vec(&mut self.present, parent_present, batch_size);
+        let present = derive_present_vec(&mut self.present, parent_present, batch_size)?;
 
         // How many lengths we need to fetch
         let elements_to_fetch = if let Some(present) = &present {
@@ -108,24 +190,29 @@ impl<T: ByteArrayType> GenericByteArrayDecoder<T> {
         } else {
             batch_size
         };
-        let lengths = self
-            .lengths
-            .by_ref()
-            .take(elements_to_fetch)
-            .collect::<Result<Vec<_>>>()?;
-        debug_assert_eq!(
-            lengths.len(),
-            elements_to_fetch,
-            "less lengths than expected in ByteArray"
+        let lengths = next_lengths(&self.column, self.lengths.as_mut(), elements_to_fetch)?;
+        let total_length = total_length(&self.column, &lengths, i64::MAX as usize)?;
+        ensure!(
+            T::Offset::IS_LARGE || total_length <= i32::MAX as usize,
+            OffsetOverflowSnafu {
+                column: self.column.clone(),
+            }
         );
-        let total_length: u64 = lengths.iter().sum();
-        // Fetch all data bytes at once
-        let mut bytes = Vec::with_capacity(total_length as usize);
+        // Fetch all data bytes at once, only trusting the lengths so far for the
+        // initial allocation as they could be corrupt
+        let mut bytes = Vec::with_capacity(total_length.min(MAX_PREALLOCATION));
         self.bytes
             .by_ref()
-            .take(total_length)
+            .take(total_length as u64)
             .read_to_end(&mut bytes)
             .context(IoSnafu)?;
+        ensure!(
+            bytes.len() == total_length,
+            TruncatedStreamSnafu {
+                column: self.column.clone(),
+                kind: self.bytes_kind,
+            }
+        );
         let bytes = Buffer::from(bytes);
         let lengths = populate_lengths_with_nulls(lengths, batch_size, &present);
         let offsets = OffsetBuffer::<T::Offset>::from_lengths(lengths);
@@ -149,13 +236,94 @@ impl<T: ByteArrayType> ArrayBatchDecoder for GenericByteArrayDecoder<T> {
     }
 }
 
+/// Decodes the bytes of string values into string arrays, checking they are valid
+/// UTF-8.
+pub struct Utf8ArrayDecoder<O: OffsetSizeTrait> {
+    inner: GenericByteArrayDecoder<GenericBinaryType<O>>,
+    validation: StringValidation,
+    /// Index of the next value in the stripe, counting nulls
+    index: usize,
+}
+
+impl<O: OffsetSizeTrait> Utf8ArrayDecoder<O> {
+    /// Decode into string arrays, unless `validation` asks for the binary arrays
+    /// decoded by `inner`.
+    fn new_boxed(
+        inner: GenericByteArrayDecoder<GenericBinaryType<O>>,
+        validation: StringValidation,
+    ) -> Box<dyn ArrayBatchDecoder> {
+        match validation {
+            StringValidation::AsBinary => Box::new(inner),
+            _ => Box::new(Self {
+                inner,
+                validation,
+                index: 0,
+            }),
+        }
+    }
+}
+
+impl<O: OffsetSizeTrait> ArrayBatchDecoder for Utf8ArrayDecoder<O> {
+    fn next_batch(
+        &mut self,
+        batch_size: usize,
+        parent_present: Option<&[bool]>,
+    ) -> Result<ArrayRef> {
+        let array = self.inner.next_byte_batch(batch_size, parent_present)?;
+        let len = array.len();
+        let array = binary_to_string(
+            &self.inner.column,
+            Kind::Data,
+            array,
+            self.index,
+            self.validation,
+        )?;
+        self.index += len;
+        Ok(Arc::new(array))
+    }
+}
+
+/// Convert binary values read from the stream of the given kind into strings,
+/// `first_index` being the index of the first value in the stream.
+fn binary_to_string<O: OffsetSizeTrait>(
+    column: &str,
+    kind: Kind,
+    array: GenericBinaryArray<O>,
+    first_index: usize,
+    validation: StringValidation,
+) -> Result<GenericStringArray<O>> {
+    // Values are only checked one by one when the array as a whole isn't valid
+    let error = match GenericStringArray::try_from_binary(array.clone()) {
+        Ok(array) => return Ok(array),
+        Err(error) => error,
+    };
+    if validation == StringValidation::Lossy {
+        return Ok(array
+            .iter()
+            .map(|value| value.map(String::from_utf8_lossy))
+            .collect());
+    }
+    let index = array
+        .iter()
+        .position(|value| value.is_some_and(|value| std::str::from_utf8(value).is_err()));
+    match index {
+        Some(index) => InvalidUtf8Snafu {
+            column,
+            kind,
+            index: first_index + index,
+        }
+        .fail(),
+        None => Err(error).context(ArrowSnafu),
+    }
+}
+
 pub struct DictionaryStringArrayDecoder {
     indexes: UInt64ArrayDecoder,
-    dictionary: Arc<StringArray>,
+    dictionary: ArrayRef,
 }
 
 impl DictionaryStringArrayDecoder {
-    fn new(indexes: UInt64ArrayDecoder, dictionary: Arc<StringArray>) -> Result<Self> {
+    fn new(indexes: UInt64ArrayDecoder, dictionary: ArrayRef) -> Result<Self> {
         Ok(Self {
             indexes,
             dictionary,
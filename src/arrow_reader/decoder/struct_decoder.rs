@@ -3,45 +3,70 @@ use std::sync::Arc;
 use arrow::{
     array::{ArrayRef, StructArray},
     buffer::NullBuffer,
-    datatypes::{Field, Fields},
+    datatypes::{DataType as ArrowDataType, Fields},
 };
-use snafu::ResultExt;
+use snafu::{ensure, ResultExt};
 
 use crate::error::Result;
 use crate::stripe::Stripe;
 use crate::{
-    arrow_reader::column::{get_present_vec, Column},
-    error::ArrowSnafu,
+    arrow_reader::column::Column,
+    error::{ArrowSnafu, MismatchedSchemaSnafu},
 };
 
-use super::{array_decoder_factory, derive_present_vec, ArrayBatchDecoder};
+use super::{
+    array_decoder_factory, derive_present_vec, next_child_batch, ArrayBatchDecoder, PresentStream,
+    StringValidation,
+};
 
 pub struct StructArrayDecoder {
+    column: String,
     fields: Fields,
     decoders: Vec<Box<dyn ArrayBatchDecoder>>,
-    present: Option<Box<dyn Iterator<Item = bool> + Send>>,
+    present: Option<PresentStream>,
 }
 
 impl StructArrayDecoder {
-    pub fn new(column: &Column, stripe: &Stripe) -> Result<Self> {
-        let present = get_present_vec(column, stripe)?
-            .map(|iter| Box::new(iter.into_iter()) as Box<dyn Iterator<Item = bool> + Send>);
+    pub fn new(
+        column: &Column,
+        fields: Fields,
+        stripe: &Stripe,
+        string_validation: StringValidation,
+    ) -> Result<Self> {
+        let present = PresentStream::try_new(column, stripe)?;
 
-        let decoders = column
-            .children()
+        // Fields are matched by name, so the ORC struct may have fields which aren't
+        // requested, whose streams are then left unread. If no name matches, fall
+        // back to matching by position, for renamed fields. Partial matches are
+        // ambiguous, so rejected.
+        let children = column.children();
+        let by_name = fields
             .iter()
-            .map(|child| array_decoder_factory(child, stripe))
-            .collect::<Result<Vec<_>>>()?;
-
-        let fields = column
-            .children()
-            .into_iter()
-            .map(Field::from)
-            .map(Arc::new)
+            .map(|field| children.iter().find(|child| child.name() == field.name()))
             .collect::<Vec<_>>();
-        let fields = Fields::from(fields);
+        let mismatch = || MismatchedSchemaSnafu {
+            orc_type: column.data_type().clone(),
+            arrow_type: ArrowDataType::Struct(fields.clone()),
+        };
+        let children = if by_name.iter().all(Option::is_some) {
+            by_name.into_iter().flatten().collect::<Vec<_>>()
+        } else {
+            ensure!(
+                by_name.iter().all(Option::is_none) && children.len() == fields.len(),
+                mismatch()
+            );
+            children.iter().collect()
+        };
+        let decoders = children
+            .into_iter()
+            .zip(fields.iter())
+            .map(|(child, field)| {
+                array_decoder_factory(child, field.clone(), stripe, string_validation)
+            })
+            .collect::<Result<Vec<_>>>()?;
 
         Ok(Self {
+            column: column.name().to_owned(),
             decoders,
             present,
             fields,
@@ -55,12 +80,14 @@ impl ArrayBatchDecoder for StructArrayDecoder {
         batch_size: usize,
         parent_present: Option<&[bool]>,
     ) -> Result<ArrayRef> {
-        let present = derive_present_vec(&mut self.present, parent_present, batch_size);
+        let present = derive_present_vec(&mut self.present, parent_present, batch_size)?;
 
         let child_arrays = self
             .decoders
             .iter_mut()
-            .map(|child| child.next_batch(batch_size, present.as_deref()))
+            .map(|child| {
+                next_child_batch(&self.column, child.as_mut(), batch_size, present.as_deref())
+            })
             .collect::<Result<Vec<_>>>()?;
 
         let null_buffer = present.map(NullBuffer::from);
@@ -0,0 +1,158 @@
+//! Decoding of primitive columns into callbacks rather than Arrow arrays, for
+//! consumers with their own in-memory format.
+
+use snafu::OptionExt;
+
+use crate::arrow_reader::column::Column;
+use crate::arrow_reader::Stripe;
+use crate::error::{Result, TruncatedStreamSnafu, UnsupportedVisitorTypeSnafu};
+use crate::proto::stream::Kind;
+use crate::reader::decode::float::FloatIter;
+use crate::reader::decode::{
+    ensure_direct_encoding, get_boolean_reader, get_byte_rle_reader, get_rle_reader,
+};
+use crate::schema::DataType;
+
+use super::{derive_present_vec, PresentStream};
+
+/// Receives the values of a column, one call per row. Null rows are visited with
+/// `is_null` set and a default value.
+///
+/// Only the methods for the type of the visited column are called, so the others
+/// can be left to their default, which ignores the value.
+pub trait RowVisitor {
+    fn visit_bool(&mut self, _value: bool, _is_null: bool) {}
+
+    /// Values of `tinyint`, `smallint`, `int` and `bigint` columns.
+    fn visit_i64(&mut self, _value: i64, _is_null: bool) {}
+
+    fn visit_f32(&mut self, _value: f32, _is_null: bool) {}
+
+    fn visit_f64(&mut self, _value: f64, _is_null: bool) {}
+}
+
+type ValueIter<T> = Box<dyn Iterator<Item = Result<T>> + Send>;
+
+enum Values {
+    Boolean(ValueIter<bool>),
+    Integer(ValueIter<i64>),
+    Float(ValueIter<f32>),
+    Double(ValueIter<f64>),
+}
+
+/// Alternative to the Arrow decoders for primitive columns, passing each value to
+/// a [`RowVisitor`] instead of building arrays.
+pub struct PrimitiveVisitorDecoder {
+    column: String,
+    values: Values,
+    present: Option<PresentStream>,
+    /// Rows of the stripe not visited yet
+    remaining: usize,
+}
+
+impl PrimitiveVisitorDecoder {
+    /// Fails if the column isn't a boolean, integer or floating point column.
+    pub fn try_new(column: &Column, stripe: &Stripe) -> Result<Self> {
+        let values = match column.data_type() {
+            DataType::Boolean { .. } => {
+                ensure_direct_encoding(column)?;
+                let data = stripe.stream_map.get(column, Kind::Data)?;
+                Values::Boolean(Box::new(get_boolean_reader(data)))
+            }
+            DataType::Byte { .. } => {
+                ensure_direct_encoding(column)?;
+                let data = stripe.stream_map.get(column, Kind::Data)?;
+                let iter =
+                    get_byte_rle_reader(data).map(|value| value.map(|value| value as i8 as i64));
+                Values::Integer(Box::new(iter))
+            }
+            DataType::Short { .. } | DataType::Int { .. } | DataType::Long { .. } => {
+                let data = stripe.stream_map.get(column, Kind::Data)?;
+                Values::Integer(get_rle_reader(column, data)?)
+            }
+            DataType::Float { .. } => {
+                ensure_direct_encoding(column)?;
+                let data = stripe.stream_map.get(column, Kind::Data)?;
+                Values::Float(Box::new(FloatIter::new(data, stripe.number_of_rows)))
+            }
+            DataType::Double { .. } => {
+                ensure_direct_encoding(column)?;
+                let data = stripe.stream_map.get(column, Kind::Data)?;
+                Values::Double(Box::new(FloatIter::new(data, stripe.number_of_rows)))
+            }
+            orc_type => {
+                return UnsupportedVisitorTypeSnafu {
+                    orc_type: orc_type.clone(),
+                }
+                .fail()
+            }
+        };
+        Ok(Self {
+            column: column.name().to_owned(),
+            values,
+            present: PresentStream::try_new(column, stripe)?,
+            remaining: stripe.number_of_rows,
+        })
+    }
+
+    /// Visit up to `batch_size` rows, returning how many were visited: 0 once the
+    /// column is exhausted.
+    pub fn visit_batch(
+        &mut self,
+        batch_size: usize,
+        visitor: &mut dyn RowVisitor,
+    ) -> Result<usize> {
+        let batch_size = batch_size.min(self.remaining);
+        let column = &self.column;
+        let present = derive_present_vec(&mut self.present, None, batch_size)?;
+        let visited = match &mut self.values {
+            Values::Boolean(iter) => visit(column, iter, present, batch_size, |value, is_null| {
+                visitor.visit_bool(value, is_null)
+            }),
+            Values::Integer(iter) => visit(column, iter, present, batch_size, |value, is_null| {
+                visitor.visit_i64(value, is_null)
+            }),
+            Values::Float(iter) => visit(column, iter, present, batch_size, |value, is_null| {
+                visitor.visit_f32(value, is_null)
+            }),
+            Values::Double(iter) => visit(column, iter, present, batch_size, |value, is_null| {
+                visitor.visit_f64(value, is_null)
+            }),
+        }?;
+        self.remaining -= visited;
+        Ok(visited)
+    }
+}
+
+fn visit<T: Default>(
+    column: &str,
+    iter: &mut ValueIter<T>,
+    present: Option<Vec<bool>>,
+    batch_size: usize,
+    mut visit: impl FnMut(T, bool),
+) -> Result<usize> {
+    match present {
+        Some(present) => {
+            for &is_present in &present {
+                if is_present {
+                    let value = iter.next().transpose()?.context(TruncatedStreamSnafu {
+                        column,
+                        kind: Kind::Data,
+                    })?;
+                    visit(value, false);
+                } else {
+                    visit(T::default(), true);
+                }
+            }
+            Ok(present.len())
+        }
+        None => {
+            let mut count = 0;
+            for value in iter.take(batch_size) {
+                visit(value?, false);
+                count += 1;
+            }
+            Ok(count)
+        }
+    }
+}
@@ -0,0 +1,149 @@
+//! Per column decoding metrics, see [`ArrowReader::decode_metrics`](super::ArrowReader::decode_metrics).
+//!
+//! Requires the `metrics` feature.
+
+use std::collections::BTreeMap;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use arrow::array::{Array, ArrayRef};
+
+/// Called with the metrics accumulated so far after each batch is decoded.
+pub type MetricsCallback = Arc<dyn Fn(&DecodeMetrics) + Send + Sync>;
+
+/// Cumulative decoding work of a root column.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct ColumnMetrics {
+    /// Time spent decoding the column's arrays, including reading and
+    /// decompressing its streams
+    pub decode_time: Duration,
+    /// Number of rows decoded, before any offset or limit is applied
+    pub rows: u64,
+    /// Memory size of the decoded arrays
+    pub decoded_bytes: u64,
+}
+
+/// Decoding work of each projected root column, by column id.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct DecodeMetrics {
+    columns: BTreeMap<u32, ColumnMetrics>,
+}
+
+impl DecodeMetrics {
+    /// Metrics of the root column with the given id, `None` if it wasn't decoded.
+    pub fn column(&self, column_id: u32) -> Option<&ColumnMetrics> {
+        self.columns.get(&column_id)
+    }
+
+    /// Metrics of all the decoded root columns, in column id order.
+    pub fn columns(&self) -> impl Iterator<Item = (u32, &ColumnMetrics)> {
+        self.columns
+            .iter()
+            .map(|(&column_id, metrics)| (column_id, metrics))
+    }
+
+    fn record(&mut self, column_id: u32, decode_time: Duration, array: &ArrayRef) {
+        let metrics = self.columns.entry(column_id).or_default();
+        metrics.decode_time += decode_time;
+        metrics.rows += array.len() as u64;
+        metrics.decoded_bytes += array.get_array_memory_size() as u64;
+    }
+}
+
+/// Accumulates the metrics of the stripe decoders of a reader.
+#[derive(Clone)]
+pub(crate) struct MetricsRecorder {
+    metrics: Arc<Mutex<DecodeMetrics>>,
+    callback: Option<MetricsCallback>,
+}
+
+impl MetricsRecorder {
+    pub fn new(callback: Option<MetricsCallback>) -> Self {
+        Self {
+            metrics: Default::default(),
+            callback,
+        }
+    }
+
+    pub fn metrics(&self) -> DecodeMetrics {
+        self.metrics.lock().unwrap().clone()
+    }
+
+    pub fn record(&self, column_id: u32, decode_time: Duration, array: &ArrayRef) {
+        self.metrics
+            .lock()
+            .unwrap()
+            .record(column_id, decode_time, array);
+    }
+
+    /// Invoke the callback, once all the columns of a batch are recorded.
+    pub fn finish_batch(&self) {
+        if let Some(callback) = &self.callback {
+            callback(&self.metrics.lock().unwrap());
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs::File;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    use crate::projection::ProjectionMask;
+    use crate::ArrowReaderBuilder;
+
+    use super::*;
+
+    #[test]
+    fn metrics_of_projected_columns() {
+        let path = format!(
+            "{}/tests/basic/data/alltypes.snappy.orc",
+            env!("CARGO_MANIFEST_DIR")
+        );
+        let f = File::open(path).unwrap();
+        let builder = ArrowReaderBuilder::try_new(f).unwrap();
+        let root_data_type = builder.file_metadata().root_data_type();
+        let projection = ProjectionMask::named_roots(root_data_type, &["int32", "utf8"]);
+        let column_ids = ["int32", "utf8"].map(|name| {
+            root_data_type
+                .children()
+                .iter()
+                .find(|col| col.name() == name)
+                .unwrap()
+                .data_type()
+                .column_index() as u32
+        });
+
+        let calls = Arc::new(AtomicUsize::new(0));
+        let callback_calls = calls.clone();
+        let mut reader = builder
+            .with_projection(projection)
+            .with_batch_size(4)
+            .with_metrics_callback(Arc::new(move |metrics: &DecodeMetrics| {
+                assert_eq!(2, metrics.columns().count());
+                callback_calls.fetch_add(1, Ordering::Relaxed);
+            }))
+            .build();
+        assert_eq!(DecodeMetrics::default(), reader.decode_metrics());
+        for batch in &mut reader {
+            batch.unwrap();
+        }
+
+        // 11 rows in batches of 4
+        assert_eq!(3, calls.load(Ordering::Relaxed));
+        let metrics = reader.decode_metrics();
+        assert_eq!(
+            column_ids.to_vec(),
+            metrics
+                .columns()
+                .map(|(column_id, _)| column_id)
+                .collect::<Vec<_>>()
+        );
+        for column_id in column_ids {
+            let column = metrics.column(column_id).unwrap();
+            assert_eq!(11, column.rows);
+            assert!(column.decoded_bytes > 0);
+            assert!(column.decode_time > Duration::ZERO);
+        }
+    }
+}
@@ -1,29 +1,100 @@
 use std::collections::HashMap;
+use std::fs::File;
+use std::ops::Range;
+use std::path::Path;
 use std::sync::Arc;
 
-use arrow::datatypes::{Schema, SchemaRef};
+use arrow::array::{ArrayRef, AsArray};
+use arrow::compute::concat_batches;
+use arrow::datatypes::{DataType as ArrowDataType, Field, FieldRef, Fields, Schema, SchemaRef};
 use arrow::error::ArrowError;
 use arrow::record_batch::{RecordBatch, RecordBatchReader};
+use snafu::{ensure, OptionExt, ResultExt};
 
-pub use self::decoder::NaiveStripeDecoder;
-use crate::error::Result;
+pub(crate) use self::coalesce::BatchCoalescer;
+pub(crate) use self::decode_pool::DecodePool;
+pub use self::decoder::{DecoderFactory, NaiveStripeDecoder, StringValidation};
+#[cfg(feature = "metrics")]
+use self::metrics::{DecodeMetrics, MetricsCallback, MetricsRecorder};
+use crate::error::{ArrowSnafu, InvalidInputSnafu, IoSnafu, OrcError, Result};
 use crate::projection::ProjectionMask;
-use crate::reader::metadata::{read_metadata, read_metadata_async, FileMetadata};
-use crate::reader::{AsyncChunkReader, ChunkReader};
-use crate::schema::RootDataType;
-use crate::stripe::Stripe;
+use crate::reader::decompress::CodecRegistry;
+use crate::reader::metadata::{
+    read_metadata, read_metadata_async, read_metadata_async_with_codecs, read_metadata_with_codecs,
+    FileMetadata,
+};
+use crate::reader::{
+    AsyncChunkReader, ChunkReader, IncrementalReader, ObservedReader, ReadObserver, ReadaheadReader,
+};
+use crate::schema::{DataType, NamedColumn, RootDataType};
+use crate::statistics::{ColumnStatistics, TypeStatistics};
+use crate::stripe::{Stripe, StripeMetadata, StripeSelection};
 use crate::ArrowStreamReader;
 
+mod coalesce;
 pub mod column;
+mod decode_pool;
 pub mod decoder;
+#[cfg(feature = "metrics")]
+pub mod metrics;
 
 pub const DEFAULT_BATCH_SIZE: usize = 8192;
 
+/// Whether to read a stripe, given its metadata and its index in the file. See
+/// [`ArrowReaderBuilder::with_stripe_filter`].
+pub type StripeFilter = Arc<dyn Fn(&StripeMetadata, usize) -> bool + Send + Sync>;
+
+/// Whether to read a row group, given the statistics of every column in it (indexed
+/// by column id, recording nothing for columns without a row index), the index of
+/// its stripe in the file and its index in the stripe. See
+/// [`ArrowReaderBuilder::with_row_group_filter`].
+pub type RowGroupFilter = Arc<dyn Fn(&[ColumnStatistics], usize, usize) -> bool + Send + Sync>;
+
+/// Open a local ORC file and read all its columns in batches of
+/// [`DEFAULT_BATCH_SIZE`] rows. Use [`ArrowReaderBuilder`] to configure the reader.
+///
+/// ```
+/// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+/// let mut rows = 0;
+/// for batch in datafusion_orc::read("tests/basic/data/test.orc")? {
+///     rows += batch?.num_rows();
+/// }
+/// println!("{rows} rows");
+/// # Ok(())
+/// # }
+/// ```
+pub fn read(path: impl AsRef<Path>) -> Result<ArrowReader<File>> {
+    let file = File::open(path).context(IoSnafu)?;
+    Ok(ArrowReaderBuilder::try_new(file)?.build())
+}
+
+/// Configures and builds readers of a file. Cloning a builder is cheap if its
+/// reader is, e.g. to build several readers with the same settings.
+#[derive(Clone)]
 pub struct ArrowReaderBuilder<R> {
     reader: R,
     file_metadata: Arc<FileMetadata>,
     batch_size: usize,
     projection: ProjectionMask,
+    schema_ref: Option<SchemaRef>,
+    offset: usize,
+    limit: Option<usize>,
+    row_group_aligned_batches: bool,
+    coalesce_batches: bool,
+    hive_acid_unwrap: bool,
+    skip_unsupported_columns: bool,
+    dictionary_columns: Vec<String>,
+    column_renames: HashMap<String, String>,
+    output_order: Vec<String>,
+    string_validation: StringValidation,
+    large_offset_fallback: bool,
+    force_nullable: bool,
+    decode_threads: usize,
+    decoder_factory: Option<DecoderFactory>,
+    stripe_filter: Option<StripeFilter>,
+    row_group_filter: Option<RowGroupFilter>,
+    #[cfg(feature = "metrics")]
+    metrics_callback: Option<MetricsCallback>,
 }
 
 impl<R> ArrowReaderBuilder<R> {
@@ -33,6 +104,25 @@ impl<R> ArrowReaderBuilder<R> {
             file_metadata,
             batch_size: DEFAULT_BATCH_SIZE,
             projection: ProjectionMask::all(),
+            schema_ref: None,
+            offset: 0,
+            limit: None,
+            row_group_aligned_batches: false,
+            coalesce_batches: false,
+            hive_acid_unwrap: false,
+            skip_unsupported_columns: false,
+            dictionary_columns: vec![],
+            column_renames: HashMap::new(),
+            output_order: vec![],
+            string_validation: StringValidation::default(),
+            large_offset_fallback: true,
+            force_nullable: false,
+            decode_threads: 1,
+            decoder_factory: None,
+            stripe_filter: None,
+            row_group_filter: None,
+            #[cfg(feature = "metrics")]
+            metrics_callback: None,
         }
     }
 
@@ -40,6 +130,17 @@ impl<R> ArrowReaderBuilder<R> {
         &self.file_metadata
     }
 
+    /// Total number of rows of the stripes of the file, from its metadata only,
+    /// e.g. to answer `count(*)` queries without reading any stripe. Ignores the
+    /// configured offset and limit.
+    pub fn row_count(&self) -> u64 {
+        self.file_metadata
+            .stripe_metadatas()
+            .iter()
+            .map(|stripe| stripe.number_of_rows())
+            .sum()
+    }
+
     pub fn with_batch_size(mut self, batch_size: usize) -> Self {
         self.batch_size = batch_size;
         self
@@ -49,15 +150,291 @@ impl<R> ArrowReaderBuilder<R> {
         self.projection = projection;
         self
     }
-}
 
-impl<R: ChunkReader> ArrowReaderBuilder<R> {
-    pub fn try_new(mut reader: R) -> Result<Self> {
-        let file_metadata = Arc::new(read_metadata(&mut reader)?);
-        Ok(Self::new(reader, file_metadata))
+    /// Decode into the provided Arrow schema instead of the one inferred from the
+    /// ORC types. Must contain one field per projected root column, in order.
+    ///
+    /// Narrower ORC integer columns can be read into wider Arrow integer types
+    /// (e.g. `int` as `Int64`), string columns as `LargeUtf8` or as
+    /// `Dictionary(UInt64, Utf8)` to keep dictionary encoded stripes as
+    /// dictionaries, binary columns as `LargeBinary`, and timestamps in any unit.
+    /// Types the ORC columns can't be read as are reported as a mismatched schema
+    /// error when reading the first stripe.
+    pub fn with_schema(mut self, schema: SchemaRef) -> Self {
+        self.schema_ref = Some(schema);
+        self
+    }
+
+    /// Skip the first `offset` rows of the file. Stripes lying entirely before the
+    /// offset are not read at all.
+    pub fn with_offset(mut self, offset: usize) -> Self {
+        self.offset = offset;
+        self
+    }
+
+    /// Stop reading once `limit` rows have been emitted, truncating the final batch.
+    pub fn with_limit(mut self, limit: usize) -> Self {
+        self.limit = Some(limit);
+        self
+    }
+
+    /// Same builder with its reader replaced by `map(reader)`, e.g. to wrap it.
+    fn map_reader<S>(self, map: impl FnOnce(R) -> S) -> ArrowReaderBuilder<S> {
+        ArrowReaderBuilder {
+            reader: map(self.reader),
+            file_metadata: self.file_metadata,
+            batch_size: self.batch_size,
+            projection: self.projection,
+            schema_ref: self.schema_ref,
+            offset: self.offset,
+            limit: self.limit,
+            row_group_aligned_batches: self.row_group_aligned_batches,
+            coalesce_batches: self.coalesce_batches,
+            hive_acid_unwrap: self.hive_acid_unwrap,
+            skip_unsupported_columns: self.skip_unsupported_columns,
+            dictionary_columns: self.dictionary_columns,
+            column_renames: self.column_renames,
+            output_order: self.output_order,
+            string_validation: self.string_validation,
+            large_offset_fallback: self.large_offset_fallback,
+            force_nullable: self.force_nullable,
+            decode_threads: self.decode_threads,
+            decoder_factory: self.decoder_factory,
+            stripe_filter: self.stripe_filter,
+            row_group_filter: self.row_group_filter,
+            #[cfg(feature = "metrics")]
+            metrics_callback: self.metrics_callback,
+        }
+    }
+
+    /// Report every byte range read when decoding stripes to `observer`.
+    ///
+    /// The file tail was already read when creating the builder; to observe those
+    /// reads too, wrap the reader in an [`ObservedReader`] before creating the builder.
+    pub fn with_observer(
+        self,
+        observer: Arc<dyn ReadObserver>,
+    ) -> ArrowReaderBuilder<ObservedReader<R>> {
+        self.map_reader(|reader| ObservedReader::new(reader, observer))
+    }
+
+    /// Read the streams of each stripe in small ranges as batches are decoded,
+    /// rather than all at once when reaching the stripe, so memory usage stays
+    /// bounded however large the stripes are. This trades a single read per stripe
+    /// for many smaller ones, and only applies to the sync reader.
+    ///
+    /// To also observe the incremental reads, call [`Self::with_observer`] first.
+    pub fn with_incremental_stream_reads(self) -> ArrowReaderBuilder<IncrementalReader<R>> {
+        self.map_reader(IncrementalReader::new)
+    }
+
+    /// Read up to `num_stripes` upcoming stripes on a background thread while the
+    /// current stripe is decoded. Stripes are read whole, including columns which
+    /// aren't projected, and reads stay synchronous if `num_stripes` is zero.
+    pub fn with_readahead(self, num_stripes: usize) -> ArrowReaderBuilder<ReadaheadReader<R>> {
+        let stripes = self
+            .file_metadata
+            .stripe_metadatas()
+            .iter()
+            .map(|stripe| stripe.offset()..stripe.footer_offset() + stripe.footer_length())
+            .collect();
+        self.map_reader(|reader| ReadaheadReader::new(reader, stripes, num_stripes))
+    }
+
+    /// Emit one batch per row group (as set by the file's row index stride) instead of
+    /// batches of the configured batch size, so batches line up with row group
+    /// statistics and indexes. Files written without row indexes keep using the batch
+    /// size. Note an offset will shift the boundaries of the batches.
+    pub fn with_row_group_aligned_batches(mut self, row_group_aligned_batches: bool) -> Self {
+        self.row_group_aligned_batches = row_group_aligned_batches;
+        self
+    }
+
+    /// Concatenate the decoded batches into batches of exactly the batch size, but
+    /// for the last one, rather than emitting the smaller batches found at the end
+    /// of stripes, after an offset, or with [`Self::with_row_group_aligned_batches`]
+    /// and row groups smaller than the batch size. Costs a copy of the batches
+    /// which are concatenated.
+    pub fn with_coalesce_batches(mut self, coalesce_batches: bool) -> Self {
+        self.coalesce_batches = coalesce_batches;
+        self
+    }
+
+    /// For files of Hive ACID tables, only read the `row` struct column and emit
+    /// its fields, the user columns, as the columns of the batches. Files without
+    /// the ACID layout are read as usual.
+    ///
+    /// This replaces any projection, and a schema given to [`Self::with_schema`]
+    /// describes the user columns.
+    pub fn with_hive_acid_unwrap(mut self, hive_acid_unwrap: bool) -> Self {
+        self.hive_acid_unwrap = hive_acid_unwrap;
+        self
+    }
+
+    /// Decode root columns which can't be read as their Arrow type (e.g. unions,
+    /// or nested types mismatching the schema given to [`Self::with_schema`]) as
+    /// nulls, instead of failing the whole read. Only applies to columns whose
+    /// Arrow field is nullable.
+    pub fn with_skip_unsupported_columns(mut self, skip_unsupported_columns: bool) -> Self {
+        self.skip_unsupported_columns = skip_unsupported_columns;
+        self
+    }
+
+    /// Decode the root string columns with the given ORC names as dictionaries
+    /// (`Dictionary(UInt64, Utf8)`), whatever their encoding in each stripe, and
+    /// all other string columns as dense `Utf8`. This applies to the `Utf8` fields
+    /// of a schema given to [`Self::with_schema`] too, matched to the columns by
+    /// position, and to the user columns of Hive ACID files when unwrapping them.
+    pub fn with_dictionary_columns(mut self, columns: &[&str]) -> Self {
+        self.dictionary_columns = columns.iter().map(|&column| column.to_owned()).collect();
+        self
+    }
+
+    /// Name the fields of the given root columns after the values of `renames`
+    /// instead of their ORC names, which are its keys. The columns are still read
+    /// from the ORC columns, and names of other columns are kept. This applies to
+    /// the fields of a schema given to [`Self::with_schema`] too, matched to the
+    /// columns by position, and to the user columns of Hive ACID files when
+    /// unwrapping them.
+    pub fn with_column_rename(mut self, renames: HashMap<String, String>) -> Self {
+        self.column_renames = renames;
+        self
+    }
+
+    /// Output the given columns first, in this order, rather than in the order of
+    /// the file, followed by the other projected columns in their usual order.
+    /// Columns are named as in the output schema (e.g. after renames). If a name
+    /// isn't in it, the reader only returns an [`OrcError::InvalidInput`] error.
+    pub fn with_output_order(mut self, columns: &[&str]) -> Self {
+        self.output_order = columns.iter().map(|&column| column.to_owned()).collect();
+        self
+    }
+
+    /// Choose how values of string columns which aren't valid UTF-8 are decoded,
+    /// failing by default. This applies to all the string fields of the schema,
+    /// including fields given to [`Self::with_schema`] and nested fields, which
+    /// [`StringValidation::AsBinary`] replaces by the matching binary types.
+    pub fn with_string_validation(mut self, string_validation: StringValidation) -> Self {
+        self.string_validation = string_validation;
+        self
+    }
+
+    /// Infer `LargeUtf8` and `LargeBinary` rather than `Utf8` and `Binary` for the
+    /// string and binary columns whose values add up to more than `i32::MAX` bytes
+    /// in a stripe, going by the stripe statistics, so that their batches fit. This
+    /// is the default; when disabled, batches whose values don't fit 32-bit offsets
+    /// fail to decode instead. Schemas given to [`Self::with_schema`] are kept.
+    pub fn with_large_offset_fallback(mut self, large_offset_fallback: bool) -> Self {
+        self.large_offset_fallback = large_offset_fallback;
+        self
+    }
+
+    /// Mark all the fields of the schema nullable, including fields given to
+    /// [`Self::with_schema`] and nested fields, e.g. for batches to be combined
+    /// with batches of nullable schemas. Map entries and keys stay non-nullable,
+    /// as Arrow requires. Columns without nulls are still decoded without nulls.
+    pub fn with_force_nullable(mut self, force_nullable: bool) -> Self {
+        self.force_nullable = force_nullable;
+        self
+    }
+
+    /// Decode the projected columns of each batch on up to `decode_threads`
+    /// threads, so their streams are decompressed and decoded in parallel. Batches
+    /// are the same as when decoding on a single thread, as by default. The
+    /// threads are started when building the reader and stop when it's dropped.
+    /// Async readers decode their batches on these threads too, not in the task
+    /// polling them.
+    pub fn with_decode_threads(mut self, decode_threads: usize) -> Self {
+        self.decode_threads = decode_threads;
+        self
+    }
+
+    /// Build the decoders of the projected root columns with `decoder_factory`,
+    /// falling back to the default decoders for the columns it returns `None` for.
+    /// Useful to decode extension types stored as ORC columns, as the schema given
+    /// to [`Self::with_schema`] may have fields the default decoders don't support.
+    pub fn with_decoder_factory(mut self, decoder_factory: DecoderFactory) -> Self {
+        self.decoder_factory = Some(decoder_factory);
+        self
+    }
+
+    /// Only read the stripes for which `stripe_filter` returns `true`, given their
+    /// metadata (row count, offset, statistics...) and index in the file, e.g. to
+    /// skip stripes whose statistics rule out the rows of a query. The offset
+    /// counts rows of the file, including those of skipped stripes, while the
+    /// limit counts rows read. The filter may be called more than once per stripe.
+    pub fn with_stripe_filter(mut self, stripe_filter: StripeFilter) -> Self {
+        self.stripe_filter = Some(stripe_filter);
+        self
+    }
+
+    /// Only read the row groups for which `row_group_filter` returns `true`, given
+    /// their statistics from the row index, e.g. to skip the row groups whose
+    /// statistics rule out the rows of a query. Only the bytes of the streams
+    /// holding the rows of the selected row groups are read, starting each stream
+    /// at the position of the row group in the row index.
+    ///
+    /// Stripes whose row index doesn't record a position in every projected stream
+    /// are read whole if any of their row groups is selected. Like for
+    /// [`Self::with_stripe_filter`], the offset counts rows of the file, including
+    /// those of skipped row groups, while the limit counts rows read. Files without
+    /// a row index are read whole.
+    pub fn with_row_group_filter(mut self, row_group_filter: RowGroupFilter) -> Self {
+        self.row_group_filter = Some(row_group_filter);
+        self
+    }
+
+    /// Fail reading a stream once more than `max_size` bytes have been decompressed
+    /// from it, guarding against files whose tiny compressed chunks expand
+    /// enormously. Defaults to
+    /// [`DEFAULT_MAX_DECOMPRESSED_STREAM_SIZE`](crate::reader::decompress::DEFAULT_MAX_DECOMPRESSED_STREAM_SIZE).
+    pub fn with_max_decompressed_stream_size(mut self, max_size: u64) -> Self {
+        self.file_metadata = Arc::new(
+            self.file_metadata
+                .map_compression(|compression| compression.with_max_stream_size(max_size)),
+        );
+        self
+    }
+
+    /// Fail reading a stripe once more than `max_size` bytes have been decompressed
+    /// from all its streams. Defaults to
+    /// [`DEFAULT_MAX_DECOMPRESSED_STRIPE_SIZE`](crate::reader::decompress::DEFAULT_MAX_DECOMPRESSED_STRIPE_SIZE).
+    pub fn with_max_decompressed_stripe_size(mut self, max_size: u64) -> Self {
+        self.file_metadata = Arc::new(
+            self.file_metadata
+                .map_compression(|compression| compression.with_max_stripe_size(max_size)),
+        );
+        self
+    }
+
+    /// Call `callback` with the decoding metrics accumulated so far after each
+    /// batch is decoded. Metrics are collected by the sync reader regardless, see
+    /// [`ArrowReader::decode_metrics`].
+    #[cfg(feature = "metrics")]
+    pub fn with_metrics_callback(mut self, callback: MetricsCallback) -> Self {
+        self.metrics_callback = Some(callback);
+        self
+    }
+
+    /// Number of rows to decode per batch.
+    pub(crate) fn decode_batch_size(&self) -> usize {
+        match self.file_metadata.row_index_stride() {
+            stride if self.row_group_aligned_batches && stride > 0 => stride,
+            // Same as the coalesced batches, which can't be empty
+            _ if self.coalesce_batches => self.batch_size.max(1),
+            _ => self.batch_size,
+        }
     }
 
-    pub fn build(self) -> ArrowReader<R> {
+    /// Build the cursor, positioned at the first stripe containing rows after the offset,
+    /// alongside the window of rows still to apply to the decoded batches.
+    pub(crate) fn build_cursor(self) -> (Cursor<R>, Option<SchemaRef>, RowWindow) {
+        let (stripe_index, offset) = locate_row(
+            &self.file_metadata,
+            self.stripe_filter.as_ref(),
+            self.offset,
+        );
+
         let projected_data_type = self
             .file_metadata
             .root_data_type()
@@ -66,16 +443,240 @@ impl<R: ChunkReader> ArrowReaderBuilder<R> {
             reader: self.reader,
             file_metadata: self.file_metadata,
             projected_data_type,
-            stripe_index: 0,
+            stripe_index,
+            stripe_filter: self.stripe_filter,
+            row_group_filter: self.row_group_filter,
+        };
+        let row_window = RowWindow {
+            offset,
+            limit: self.limit,
+        };
+        (cursor, self.schema_ref, row_window)
+    }
+
+    /// Build the cursor and the schema the stripes are decoded into, applying the
+    /// options shaping the schema, alongside the schema of the user columns when
+    /// unwrapping Hive ACID files and the order of the output columns, which fails
+    /// if it names unknown columns.
+    #[allow(clippy::type_complexity)]
+    pub(crate) fn build_cursor_and_schemas(
+        mut self,
+    ) -> (
+        Cursor<R>,
+        SchemaRef,
+        Option<SchemaRef>,
+        RowWindow,
+        Result<Option<Vec<usize>>>,
+    ) {
+        let dictionary_columns = std::mem::take(&mut self.dictionary_columns);
+        let column_renames = std::mem::take(&mut self.column_renames);
+        let output_order = std::mem::take(&mut self.output_order);
+        let string_validation = self.string_validation;
+        let large_offset_fallback = self.large_offset_fallback;
+        let force_nullable = self.force_nullable;
+        let root_data_type = self.file_metadata.root_data_type();
+        let hive_acid = self.hive_acid_unwrap && root_data_type.hive_acid_row().is_some();
+        let builder = if hive_acid {
+            let projection = ProjectionMask::named_roots(root_data_type, &["row"]);
+            self.with_projection(projection)
+        } else {
+            self
+        };
+        let (cursor, schema_ref, row_window) = builder.build_cursor();
+        let (schema_ref, row_schema_ref) = if hive_acid {
+            // Decode the row struct made of the user columns
+            let row_schema = match schema_ref {
+                Some(row_schema_ref) => row_schema_ref.as_ref().clone(),
+                None => {
+                    let schema = create_arrow_schema(&cursor);
+                    let schema = if large_offset_fallback {
+                        with_large_offset_fields(&schema, &cursor)
+                    } else {
+                        schema
+                    };
+                    let row_fields = match schema.field(0).data_type() {
+                        ArrowDataType::Struct(fields) => fields.clone(),
+                        // Checked to be a struct by hive_acid_row
+                        _ => unreachable!(),
+                    };
+                    Schema::new_with_metadata(row_fields, schema.metadata().clone())
+                }
+            };
+            let row_columns = hive_acid_row_columns(&cursor);
+            let row_schema = with_dictionary_fields(&row_schema, row_columns, &dictionary_columns);
+            let row = Field::new(
+                "row",
+                ArrowDataType::Struct(row_schema.fields().clone()),
+                true,
+            );
+            let schema = Schema::new_with_metadata(vec![row], row_schema.metadata().clone());
+            // Only rename the output columns, the struct is decoded by its ORC names
+            let row_schema = with_renamed_fields(&row_schema, row_columns, &column_renames);
+            (Arc::new(schema), Some(Arc::new(row_schema)))
+        } else {
+            let columns = cursor.projected_data_type.children();
+            let schema = match schema_ref {
+                Some(schema_ref) => {
+                    with_dictionary_fields(&schema_ref, columns, &dictionary_columns)
+                }
+                None => {
+                    let schema = create_arrow_schema(&cursor);
+                    let schema = with_dictionary_fields(&schema, columns, &dictionary_columns);
+                    if large_offset_fallback {
+                        with_large_offset_fields(&schema, &cursor)
+                    } else {
+                        schema
+                    }
+                }
+            };
+            let schema = with_renamed_fields(&schema, columns, &column_renames);
+            (Arc::new(schema), None)
+        };
+        let (schema_ref, row_schema_ref) = if string_validation == StringValidation::AsBinary {
+            (
+                with_binary_string_fields(schema_ref),
+                row_schema_ref.map(with_binary_string_fields),
+            )
+        } else {
+            (schema_ref, row_schema_ref)
+        };
+        let (schema_ref, row_schema_ref) = if force_nullable {
+            (
+                with_nullable_fields(schema_ref),
+                row_schema_ref.map(with_nullable_fields),
+            )
+        } else {
+            (schema_ref, row_schema_ref)
+        };
+        let output_schema = row_schema_ref.as_ref().unwrap_or(&schema_ref);
+        let output_columns = output_columns(output_schema, &output_order);
+        (
+            cursor,
+            schema_ref,
+            row_schema_ref,
+            row_window,
+            output_columns,
+        )
+    }
+}
+
+impl<R: ChunkReader> ArrowReaderBuilder<R> {
+    pub fn try_new(mut reader: R) -> Result<Self> {
+        let file_metadata = Arc::new(read_metadata(&mut reader)?);
+        Ok(Self::new(reader, file_metadata))
+    }
+
+    /// Like [`Self::try_new`], decompressing with the codecs of the given registry,
+    /// e.g. to read files using a custom compression codec.
+    pub fn try_new_with_codecs(mut reader: R, codecs: &CodecRegistry) -> Result<Self> {
+        let file_metadata = Arc::new(read_metadata_with_codecs(&mut reader, codecs)?);
+        Ok(Self::new(reader, file_metadata))
+    }
+
+    /// Build a reader producing the arrays of the single column at the given dotted
+    /// path (see [`RootDataType::column_id_for_path`]), stripe by stripe. Only the
+    /// root column containing it is decoded. Nested columns may only be reached
+    /// through struct fields.
+    pub fn build_column_reader(self, path: &str) -> Result<ColumnReader<R>> {
+        let root_data_type = self.file_metadata.root_data_type();
+        ensure!(
+            root_data_type.column_id_for_path(path).is_some(),
+            InvalidInputSnafu {
+                msg: format!("No column at path {path}")
+            }
+        );
+        let mut names = path.split('.').map(str::to_owned);
+        // Not empty, as the path was found
+        let root = names.next().unwrap();
+        let fields = names.collect::<Vec<_>>();
+        let mut data_type = root_data_type
+            .children()
+            .iter()
+            .find(|col| col.name() == root)
+            .unwrap()
+            .data_type();
+        for field in &fields {
+            data_type = match data_type {
+                DataType::Struct { children, .. } => children
+                    .iter()
+                    .find(|child| child.name() == field)
+                    .unwrap()
+                    .data_type(),
+                _ => InvalidInputSnafu {
+                    msg: format!("Column at path {path} is not nested in structs only"),
+                }
+                .fail()?,
+            };
+        }
+
+        let projection = ProjectionMask::named_roots(root_data_type, &[root]);
+        let reader = self.with_projection(projection).build();
+        Ok(ColumnReader { reader, fields })
+    }
+
+    pub fn build(mut self) -> ArrowReader<R> {
+        let batch_size = self.decode_batch_size();
+        let skip_unsupported_columns = self.skip_unsupported_columns;
+        let decode_threads = self.decode_threads;
+        let decoder_factory = self.decoder_factory.take();
+        let coalescer = self
+            .coalesce_batches
+            .then(|| BatchCoalescer::new(self.batch_size));
+        #[cfg(feature = "metrics")]
+        let metrics = MetricsRecorder::new(self.metrics_callback.take());
+        let string_validation = self.string_validation;
+        let (cursor, schema_ref, row_schema_ref, row_window, output_columns) =
+            self.build_cursor_and_schemas();
+        let (output_columns, build_error) = match output_columns {
+            Ok(output_columns) => (output_columns, None),
+            Err(err) => (None, Some(err)),
         };
-        let schema_ref = Arc::new(create_arrow_schema(&cursor));
         ArrowReader {
             cursor,
             schema_ref,
+            row_schema_ref,
             current_stripe: None,
-            batch_size: self.batch_size,
+            batch_size,
+            row_window,
+            skip_unsupported_columns,
+            string_validation,
+            decode_pool: (decode_threads > 1).then(|| Arc::new(DecodePool::new(decode_threads, 0))),
+            decoder_factory,
+            output_columns,
+            build_error,
+            coalescer,
+            #[cfg(feature = "metrics")]
+            metrics,
         }
     }
+
+    /// Eagerly read and decode all the batches of the file, with the configured
+    /// projection, offset and limit applied, e.g. to benchmark decoding. The
+    /// result is empty if there are no rows to read.
+    pub fn read_all(self) -> Result<Vec<RecordBatch>> {
+        self.build()
+            .collect::<std::result::Result<Vec<_>, _>>()
+            .context(ArrowSnafu)
+    }
+}
+
+impl<R: ChunkReader + Send + Sync + 'static> ArrowReaderBuilder<R> {
+    /// Read the first `n` rows (after the configured offset) into a single batch,
+    /// e.g. to preview a file. Streams are read incrementally, so only the start
+    /// of the first stripe's streams is fetched for small `n`. The batch is empty
+    /// if the file has no rows to read.
+    pub fn peek(self, n: usize) -> Result<RecordBatch> {
+        let reader = self
+            .with_limit(n)
+            .with_batch_size(n.max(1))
+            .with_incremental_stream_reads()
+            .build();
+        let schema = reader.schema();
+        let batches = reader
+            .collect::<std::result::Result<Vec<_>, _>>()
+            .context(ArrowSnafu)?;
+        concat_batches(&schema, &batches).context(ArrowSnafu)
+    }
 }
 
 impl<R: AsyncChunkReader + 'static> ArrowReaderBuilder<R> {
@@ -84,47 +685,170 @@ impl<R: AsyncChunkReader + 'static> ArrowReaderBuilder<R> {
         Ok(Self::new(reader, file_metadata))
     }
 
-    pub fn build_async(self) -> ArrowStreamReader<R> {
-        let projected_data_type = self
-            .file_metadata
-            .root_data_type()
-            .project(&self.projection);
-        let cursor = Cursor {
-            reader: self.reader,
-            file_metadata: self.file_metadata,
-            projected_data_type,
-            stripe_index: 0,
-        };
-        let schema_ref = Arc::new(create_arrow_schema(&cursor));
-        ArrowStreamReader::new(cursor, self.batch_size, schema_ref)
+    /// Like [`Self::try_new_async`], decompressing with the codecs of the given registry.
+    pub async fn try_new_async_with_codecs(mut reader: R, codecs: &CodecRegistry) -> Result<Self> {
+        let file_metadata = Arc::new(read_metadata_async_with_codecs(&mut reader, codecs).await?);
+        Ok(Self::new(reader, file_metadata))
+    }
+
+    /// Build a [`Stream`](futures::Stream) of record batches. Stripes are only
+    /// fetched and decoded as the stream is polled, so a slow consumer applies
+    /// backpressure to reading the file.
+    pub fn build_async(mut self) -> ArrowStreamReader<R> {
+        let batch_size = self.decode_batch_size();
+        let skip_unsupported_columns = self.skip_unsupported_columns;
+        let decode_threads = self.decode_threads;
+        let decoder_factory = self.decoder_factory.take();
+        let coalescer = self
+            .coalesce_batches
+            .then(|| BatchCoalescer::new(self.batch_size));
+        let string_validation = self.string_validation;
+        let (cursor, schema_ref, row_schema_ref, row_window, output_columns) =
+            self.build_cursor_and_schemas();
+        ArrowStreamReader::new(cursor, batch_size, schema_ref)
+            .with_row_schema(row_schema_ref)
+            .with_row_window(row_window)
+            .with_skip_unsupported_columns(skip_unsupported_columns)
+            .with_string_validation(string_validation)
+            .with_decode_pool(
+                (decode_threads > 1).then(|| Arc::new(DecodePool::new(decode_threads, 1))),
+            )
+            .with_decoder_factory(decoder_factory)
+            .with_output_columns(output_columns)
+            .with_coalescer(coalescer)
     }
 }
 
 pub struct ArrowReader<R> {
     cursor: Cursor<R>,
+    /// Schema the stripes are decoded into
     schema_ref: SchemaRef,
+    /// Schema of the user columns of Hive ACID files, when unwrapping them
+    row_schema_ref: Option<SchemaRef>,
     current_stripe: Option<Box<dyn Iterator<Item = Result<RecordBatch>> + Send>>,
     batch_size: usize,
+    row_window: RowWindow,
+    skip_unsupported_columns: bool,
+    string_validation: StringValidation,
+    /// Threads decoding the columns of each batch, if more than one
+    decode_pool: Option<Arc<DecodePool>>,
+    decoder_factory: Option<DecoderFactory>,
+    /// Indices of the columns to output, in order, when reordering them
+    output_columns: Option<Vec<usize>>,
+    /// Invalid option found when building the reader, returned instead of batches
+    build_error: Option<OrcError>,
+    coalescer: Option<BatchCoalescer>,
+    #[cfg(feature = "metrics")]
+    metrics: MetricsRecorder,
 }
 
 impl<R> ArrowReader<R> {
     pub fn total_row_count(&self) -> u64 {
         self.cursor.file_metadata.number_of_rows()
     }
+
+    /// Decoding time, rows and bytes of each projected root column so far.
+    #[cfg(feature = "metrics")]
+    pub fn decode_metrics(&self) -> DecodeMetrics {
+        self.metrics.metrics()
+    }
+
+    /// Schema of the record batches produced by this reader, after projection.
+    pub fn schema(&self) -> SchemaRef {
+        let schema_ref = self
+            .row_schema_ref
+            .clone()
+            .unwrap_or_else(|| self.schema_ref.clone());
+        reorder_schema(schema_ref, self.output_columns.as_deref())
+    }
+
+    /// Give back the underlying reader, e.g. to close a file before the reader
+    /// would otherwise be dropped. Batches already returned stay valid.
+    pub fn into_reader(self) -> R {
+        self.cursor.reader
+    }
+
+    /// Continue reading from the row at index `row` of the file, e.g. to paginate.
+    /// Stripes before it are skipped without being read, while the rows before it
+    /// in its stripe are decoded and dropped, as decoders can't seek. The offset
+    /// of the builder no longer applies, but the rows still to emit under its
+    /// limit are unchanged. Seeking past the last row ends the batches.
+    pub fn seek_to_row(&mut self, row: usize) {
+        let (stripe_index, offset) = locate_row(
+            &self.cursor.file_metadata,
+            self.cursor.stripe_filter.as_ref(),
+            row,
+        );
+        self.cursor.stripe_index = stripe_index;
+        self.current_stripe = None;
+        self.row_window.offset = offset;
+        if let Some(coalescer) = &mut self.coalescer {
+            coalescer.clear();
+        }
+    }
 }
 
 impl<R: ChunkReader> ArrowReader<R> {
+    /// Unwrap the Hive ACID row if needed, then reorder the columns if needed.
+    fn to_output(&self, batch: RecordBatch) -> std::result::Result<RecordBatch, ArrowError> {
+        to_output(
+            batch,
+            self.row_schema_ref.as_ref(),
+            self.output_columns.as_deref(),
+        )
+    }
+
     fn try_advance_stripe(&mut self) -> std::result::Result<Option<RecordBatch>, ArrowError> {
-        let stripe = self.cursor.next().transpose()?;
-        match stripe {
-            Some(stripe) => {
-                let decoder =
-                    NaiveStripeDecoder::new(stripe, self.schema_ref.clone(), self.batch_size)?;
-                self.current_stripe = Some(Box::new(decoder));
-                self.next().transpose()
+        loop {
+            let Some(selection) = self.cursor.next().transpose()? else {
+                return Ok(None);
+            };
+            let selected = selection.parts.iter().map(|(rows, _)| rows.clone());
+            self.row_window.select(selection.number_of_rows, selected);
+            if selection.parts.is_empty() {
+                continue;
             }
-            None => Ok(None),
+            let decoders = selection
+                .parts
+                .into_iter()
+                .map(|(_, stripe)| {
+                    let decoder = NaiveStripeDecoder::try_new_with_decoder_factory(
+                        stripe,
+                        self.schema_ref.clone(),
+                        self.batch_size,
+                        self.skip_unsupported_columns,
+                        self.decoder_factory.as_ref(),
+                        self.string_validation,
+                    )?
+                    .with_decode_pool(self.decode_pool.clone());
+                    #[cfg(feature = "metrics")]
+                    let decoder = decoder.with_metrics(self.metrics.clone());
+                    Ok(decoder)
+                })
+                .collect::<Result<Vec<_>>>()?;
+            self.current_stripe = Some(Box::new(decoders.into_iter().flatten()));
+            return self.next_unwindowed().transpose();
+        }
+    }
+}
+
+/// Replace the `row` struct column of Hive ACID files by its fields given the
+/// schema of the user columns, then reorder the columns if needed.
+pub(crate) fn to_output(
+    batch: RecordBatch,
+    row_schema_ref: Option<&SchemaRef>,
+    output_columns: Option<&[usize]>,
+) -> std::result::Result<RecordBatch, ArrowError> {
+    let batch = match row_schema_ref {
+        Some(row_schema_ref) => {
+            let row = batch.column(0).as_struct();
+            RecordBatch::try_new(row_schema_ref.clone(), row.columns().to_vec())?
         }
+        None => batch,
+    };
+    match output_columns {
+        Some(output_columns) => batch.project(output_columns),
+        None => Ok(batch),
     }
 }
 
@@ -138,16 +862,237 @@ pub fn create_arrow_schema<R>(cursor: &Cursor<R>) -> Schema {
     cursor.projected_data_type.create_arrow_schema(&metadata)
 }
 
+/// User columns of a Hive ACID file, the fields of the projected `row` struct.
+fn hive_acid_row_columns<R>(cursor: &Cursor<R>) -> &[NamedColumn] {
+    match cursor.projected_data_type.children()[0].data_type() {
+        DataType::Struct { children, .. } => children,
+        // Checked to be a struct by hive_acid_row
+        _ => unreachable!(),
+    }
+}
+
+/// Replace the `Utf8` type of the fields of the root `columns` with the given ORC
+/// names by a dictionary of strings, so those columns are decoded as dictionaries.
+/// Fields are matched to the columns by position, as when decoding.
+fn with_dictionary_fields(
+    schema: &Schema,
+    columns: &[NamedColumn],
+    dictionary_columns: &[String],
+) -> Schema {
+    let fields = schema
+        .fields()
+        .iter()
+        .enumerate()
+        .map(
+            |(index, field)| match (field.data_type(), columns.get(index)) {
+                (ArrowDataType::Utf8, Some(col))
+                    if dictionary_columns.iter().any(|name| name == col.name()) =>
+                {
+                    let data_type = ArrowDataType::Dictionary(
+                        Box::new(ArrowDataType::UInt64),
+                        Box::new(ArrowDataType::Utf8),
+                    );
+                    Arc::new(field.as_ref().clone().with_data_type(data_type))
+                }
+                _ => field.clone(),
+            },
+        )
+        .collect::<Vec<_>>();
+    Schema::new_with_metadata(fields, schema.metadata().clone())
+}
+
+/// Use 64-bit offsets for the string and binary fields of the inferred schema
+/// whose values don't fit 32-bit offsets in some stripe, see
+/// [`ArrowReaderBuilder::with_large_offset_fallback`].
+fn with_large_offset_fields<R>(schema: &Schema, cursor: &Cursor<R>) -> Schema {
+    let fields = schema
+        .fields()
+        .iter()
+        .zip(cursor.projected_data_type.children())
+        .map(|(field, col)| {
+            Arc::new(with_large_offset_field(
+                field,
+                col.data_type(),
+                &cursor.file_metadata,
+            ))
+        })
+        .collect::<Vec<_>>();
+    Schema::new_with_metadata(fields, schema.metadata().clone())
+}
+
+fn with_large_offset_field(
+    field: &Field,
+    data_type: &DataType,
+    file_metadata: &FileMetadata,
+) -> Field {
+    let nested = |field: &FieldRef, data_type: &DataType| {
+        Arc::new(with_large_offset_field(field, data_type, file_metadata))
+    };
+    let field = field.clone();
+    let too_large = || exceeds_small_offsets(file_metadata, data_type.column_index());
+    match (data_type, field.data_type().clone()) {
+        (
+            DataType::String { .. } | DataType::Varchar { .. } | DataType::Char { .. },
+            ArrowDataType::Utf8,
+        ) if too_large() => field.with_data_type(ArrowDataType::LargeUtf8),
+        (DataType::Binary { .. }, ArrowDataType::Binary) if too_large() => {
+            field.with_data_type(ArrowDataType::LargeBinary)
+        }
+        (DataType::Struct { children, .. }, ArrowDataType::Struct(fields)) => {
+            let fields = fields
+                .iter()
+                .zip(children)
+                .map(|(field, col)| nested(field, col.data_type()))
+                .collect::<Fields>();
+            field.with_data_type(ArrowDataType::Struct(fields))
+        }
+        (DataType::List { child, .. }, ArrowDataType::List(item)) => {
+            field.with_data_type(ArrowDataType::List(nested(&item, child)))
+        }
+        (DataType::Map { key, value, .. }, ArrowDataType::Map(entries, keys_sorted)) => {
+            let ArrowDataType::Struct(fields) = entries.data_type() else {
+                return field;
+            };
+            let fields = Fields::from(vec![nested(&fields[0], key), nested(&fields[1], value)]);
+            let entries = entries
+                .as_ref()
+                .clone()
+                .with_data_type(ArrowDataType::Struct(fields));
+            field.with_data_type(ArrowDataType::Map(Arc::new(entries), keys_sorted))
+        }
+        _ => field,
+    }
+}
+
+/// Whether the values of a string or binary column add up to more than `i32::MAX`
+/// bytes in any stripe, according to the stripe statistics.
+fn exceeds_small_offsets(file_metadata: &FileMetadata, column_index: usize) -> bool {
+    file_metadata.stripe_metadatas().iter().any(|stripe| {
+        let stats = stripe
+            .column_statistics()
+            .get(column_index)
+            .and_then(ColumnStatistics::type_statistics);
+        match stats {
+            Some(TypeStatistics::String { sum, .. } | TypeStatistics::Binary { sum }) => {
+                *sum > i32::MAX as i64
+            }
+            _ => false,
+        }
+    })
+}
+
+/// Rename the fields of the root `columns` whose ORC names are found in `renames`,
+/// see [`ArrowReaderBuilder::with_column_rename`]. Fields are matched to the
+/// columns by position, as when decoding.
+fn with_renamed_fields(
+    schema: &Schema,
+    columns: &[NamedColumn],
+    renames: &HashMap<String, String>,
+) -> Schema {
+    let fields = schema
+        .fields()
+        .iter()
+        .enumerate()
+        .map(
+            |(index, field)| match columns.get(index).and_then(|col| renames.get(col.name())) {
+                Some(name) => Arc::new(field.as_ref().clone().with_name(name)),
+                None => field.clone(),
+            },
+        )
+        .collect::<Vec<_>>();
+    Schema::new_with_metadata(fields, schema.metadata().clone())
+}
+
+/// Decode the string fields of the schema into the matching binary types, see
+/// [`StringValidation::AsBinary`].
+fn with_binary_string_fields(schema: SchemaRef) -> SchemaRef {
+    let fields = schema
+        .fields()
+        .iter()
+        .map(|field| Arc::new(with_binary_string_field(field)))
+        .collect::<Vec<_>>();
+    Arc::new(Schema::new_with_metadata(fields, schema.metadata().clone()))
+}
+
+fn with_binary_string_field(field: &Field) -> Field {
+    let nested = |field: &FieldRef| Arc::new(with_binary_string_field(field));
+    let field = field.clone();
+    match field.data_type().clone() {
+        ArrowDataType::Struct(fields) => {
+            let fields = fields.iter().map(nested).collect::<Fields>();
+            field.with_data_type(ArrowDataType::Struct(fields))
+        }
+        ArrowDataType::List(child) => field.with_data_type(ArrowDataType::List(nested(&child))),
+        ArrowDataType::LargeList(child) => {
+            field.with_data_type(ArrowDataType::LargeList(nested(&child)))
+        }
+        ArrowDataType::FixedSizeList(child, size) => {
+            field.with_data_type(ArrowDataType::FixedSizeList(nested(&child), size))
+        }
+        ArrowDataType::Map(entries, keys_sorted) => {
+            field.with_data_type(ArrowDataType::Map(nested(&entries), keys_sorted))
+        }
+        ArrowDataType::Utf8 => field.with_data_type(ArrowDataType::Binary),
+        ArrowDataType::LargeUtf8 => field.with_data_type(ArrowDataType::LargeBinary),
+        ArrowDataType::Dictionary(key, value) if *value == ArrowDataType::Utf8 => field
+            .with_data_type(ArrowDataType::Dictionary(
+                key,
+                Box::new(ArrowDataType::Binary),
+            )),
+        _ => field,
+    }
+}
+
+/// Mark the fields of the schema nullable, see [`ArrowReaderBuilder::with_force_nullable`].
+fn with_nullable_fields(schema: SchemaRef) -> SchemaRef {
+    let fields = schema
+        .fields()
+        .iter()
+        .map(|field| Arc::new(with_nullable_field(field)))
+        .collect::<Vec<_>>();
+    Arc::new(Schema::new_with_metadata(fields, schema.metadata().clone()))
+}
+
+fn with_nullable_field(field: &Field) -> Field {
+    let nested = |field: &FieldRef| Arc::new(with_nullable_field(field));
+    let field = field.clone().with_nullable(true);
+    match field.data_type().clone() {
+        ArrowDataType::Struct(fields) => {
+            let fields = fields.iter().map(nested).collect::<Fields>();
+            field.with_data_type(ArrowDataType::Struct(fields))
+        }
+        ArrowDataType::List(child) => field.with_data_type(ArrowDataType::List(nested(&child))),
+        ArrowDataType::LargeList(child) => {
+            field.with_data_type(ArrowDataType::LargeList(nested(&child)))
+        }
+        ArrowDataType::FixedSizeList(child, size) => {
+            field.with_data_type(ArrowDataType::FixedSizeList(nested(&child), size))
+        }
+        // Only the values of maps may be null
+        ArrowDataType::Map(entries, keys_sorted) => match entries.data_type() {
+            ArrowDataType::Struct(children) if children.len() == 2 => {
+                let children = Fields::from(vec![children[0].clone(), nested(&children[1])]);
+                let entries = entries
+                    .as_ref()
+                    .clone()
+                    .with_data_type(ArrowDataType::Struct(children));
+                field.with_data_type(ArrowDataType::Map(Arc::new(entries), keys_sorted))
+            }
+            _ => field,
+        },
+        _ => field,
+    }
+}
+
 impl<R: ChunkReader> RecordBatchReader for ArrowReader<R> {
     fn schema(&self) -> SchemaRef {
-        self.schema_ref.clone()
+        ArrowReader::schema(self)
     }
 }
 
-impl<R: ChunkReader> Iterator for ArrowReader<R> {
-    type Item = std::result::Result<RecordBatch, ArrowError>;
-
-    fn next(&mut self) -> Option<Self::Item> {
+impl<R: ChunkReader> ArrowReader<R> {
+    /// Next batch from the stripes, before applying the offset and limit.
+    fn next_unwindowed(&mut self) -> Option<std::result::Result<RecordBatch, ArrowError>> {
         match self.current_stripe.as_mut() {
             Some(stripe) => {
                 match stripe
@@ -163,30 +1108,259 @@ impl<R: ChunkReader> Iterator for ArrowReader<R> {
     }
 }
 
+impl<R: ChunkReader> ArrowReader<R> {
+    /// Next output batch, before coalescing.
+    fn next_uncoalesced(&mut self) -> Option<std::result::Result<RecordBatch, ArrowError>> {
+        if let Some(err) = self.build_error.take() {
+            // No batches follow the error
+            self.row_window.limit = Some(0);
+            return Some(Err(ArrowError::ExternalError(Box::new(err))));
+        }
+        while !self.row_window.is_exhausted() {
+            match self.next_unwindowed()? {
+                Ok(batch) => {
+                    if let Some(batch) = self.row_window.apply(batch) {
+                        return Some(self.to_output(batch));
+                    }
+                }
+                Err(err) => return Some(Err(err)),
+            }
+        }
+        None
+    }
+}
+
+impl<R: ChunkReader> Iterator for ArrowReader<R> {
+    type Item = std::result::Result<RecordBatch, ArrowError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let Some(mut coalescer) = self.coalescer.take() else {
+            return self.next_uncoalesced();
+        };
+        let batch = loop {
+            if let Some(batch) = coalescer.next_full() {
+                break Some(batch);
+            }
+            match self.next_uncoalesced() {
+                Some(Ok(batch)) => coalescer.push(batch),
+                Some(Err(err)) => break Some(Err(err)),
+                None => break coalescer.finish(),
+            }
+        };
+        self.coalescer = Some(coalescer);
+        batch
+    }
+}
+
+/// Reads the arrays of a single, possibly nested, column. See
+/// [`ArrowReaderBuilder::build_column_reader`].
+pub struct ColumnReader<R> {
+    reader: ArrowReader<R>,
+    /// Names of the struct fields leading from the root column to the column
+    fields: Vec<String>,
+}
+
+impl<R> ColumnReader<R> {
+    /// See [`ArrowReader::into_reader`].
+    pub fn into_reader(self) -> R {
+        self.reader.into_reader()
+    }
+}
+
+impl<R: ChunkReader> Iterator for ColumnReader<R> {
+    type Item = std::result::Result<ArrayRef, ArrowError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let batch = match self.reader.next()? {
+            Ok(batch) => batch,
+            Err(err) => return Some(Err(err)),
+        };
+        let mut array = batch.column(0).clone();
+        for field in &self.fields {
+            // Checked to be struct fields when building the reader
+            array = array.as_struct().column_by_name(field).unwrap().clone();
+        }
+        Some(Ok(array))
+    }
+}
+
+/// Indices of the columns of `schema` to output, with those named in `order`
+/// first, or `None` to keep them as they are. Fails if a name isn't in `schema`.
+pub(crate) fn output_columns(schema: &Schema, order: &[String]) -> Result<Option<Vec<usize>>> {
+    if order.is_empty() {
+        return Ok(None);
+    }
+    let mut indices = vec![];
+    for name in order {
+        let index = schema.index_of(name).ok().context(InvalidInputSnafu {
+            msg: format!("Column {name} of the output order is not in the output schema"),
+        })?;
+        if !indices.contains(&index) {
+            indices.push(index);
+        }
+    }
+    for index in 0..schema.fields().len() {
+        if !indices.contains(&index) {
+            indices.push(index);
+        }
+    }
+    Ok(Some(indices))
+}
+
+/// `schema_ref` with its fields in the order of `output_columns`, if given.
+pub(crate) fn reorder_schema(schema_ref: SchemaRef, output_columns: Option<&[usize]>) -> SchemaRef {
+    match output_columns {
+        // Indices of fields of this schema, from output_columns
+        Some(output_columns) => Arc::new(schema_ref.project(output_columns).unwrap()),
+        None => schema_ref,
+    }
+}
+
+/// Index of the stripe containing the row at index `row` of the file, and the index
+/// of the row within that stripe. Past the last row, this is the number of stripes
+/// and the number of rows beyond the end of the file.
+///
+/// If the stripe is rejected by `stripe_filter`, the rows to skip are all in it, so
+/// the index of the row is 0 to not skip any row of the next stripe read.
+fn locate_row(
+    file_metadata: &FileMetadata,
+    stripe_filter: Option<&StripeFilter>,
+    row: usize,
+) -> (usize, usize) {
+    let mut stripe_index = 0;
+    let mut offset = row;
+    for stripe in file_metadata.stripe_metadatas() {
+        let number_of_rows = stripe.number_of_rows() as usize;
+        if offset < number_of_rows {
+            if offset > 0 && stripe_filter.is_some_and(|filter| !filter(stripe, stripe_index)) {
+                offset = 0;
+            }
+            break;
+        }
+        offset -= number_of_rows;
+        stripe_index += 1;
+    }
+    (stripe_index, offset)
+}
+
+/// Rows still to skip and to emit when reading with an offset and/or a limit.
+#[derive(Debug, Clone, Copy, Default)]
+pub(crate) struct RowWindow {
+    offset: usize,
+    limit: Option<usize>,
+}
+
+impl RowWindow {
+    pub(crate) fn is_exhausted(&self) -> bool {
+        self.limit == Some(0)
+    }
+
+    /// Advance the window over the next `num_rows` rows, returning the offset and
+    /// length of the rows to keep, or `None` if none are kept.
+    pub(crate) fn advance(&mut self, num_rows: usize) -> Option<(usize, usize)> {
+        let skip = self.offset.min(num_rows);
+        self.offset -= skip;
+        let mut length = num_rows - skip;
+        if let Some(limit) = self.limit.as_mut() {
+            length = length.min(*limit);
+            *limit -= length;
+        }
+        (length > 0).then_some((skip, length))
+    }
+
+    /// Only the rows of `selected` out of the next `number_of_rows` rows are decoded,
+    /// so drop the rows left out from the offset, which counts all rows.
+    pub(crate) fn select(
+        &mut self,
+        number_of_rows: usize,
+        selected: impl IntoIterator<Item = Range<usize>>,
+    ) {
+        let offset = self.offset;
+        let skipped_in_selection: usize = selected
+            .into_iter()
+            .map(|rows| offset.saturating_sub(rows.start).min(rows.len()))
+            .sum();
+        self.offset = skipped_in_selection + offset.saturating_sub(number_of_rows);
+    }
+
+    /// Slice the batch to the rows within the window, returning `None` if no rows
+    /// of the batch are left after skipping the offset.
+    pub(crate) fn apply(&mut self, batch: RecordBatch) -> Option<RecordBatch> {
+        self.advance(batch.num_rows())
+            .map(|(offset, length)| batch.slice(offset, length))
+    }
+}
+
 pub struct Cursor<R> {
     pub(crate) reader: R,
     pub(crate) file_metadata: Arc<FileMetadata>,
     pub(crate) projected_data_type: RootDataType,
     pub(crate) stripe_index: usize,
+    pub(crate) stripe_filter: Option<StripeFilter>,
+    pub(crate) row_group_filter: Option<RowGroupFilter>,
+}
+
+impl<R> Cursor<R> {
+    /// Move past the stripes without rows, which have nothing to decode and may
+    /// lack the streams of their columns, and those rejected by the stripe filter.
+    pub(crate) fn skip_stripes(&mut self) {
+        let is_skipped = |info: &StripeMetadata, index| {
+            info.number_of_rows() == 0
+                || self
+                    .stripe_filter
+                    .as_ref()
+                    .is_some_and(|filter| !filter(info, index))
+        };
+        while self
+            .file_metadata
+            .stripe_metadatas()
+            .get(self.stripe_index)
+            .is_some_and(|info| is_skipped(info, self.stripe_index))
+        {
+            self.stripe_index += 1;
+        }
+    }
+}
+
+impl<R> Cursor<R> {
+    /// The row group filter, if the file has a row index to apply it to.
+    pub(crate) fn row_group_filter(&self) -> Option<RowGroupFilter> {
+        self.row_group_filter
+            .clone()
+            .filter(|_| self.file_metadata.row_index_stride() > 0)
+    }
 }
 
 impl<R: ChunkReader> Iterator for Cursor<R> {
-    type Item = Result<Stripe>;
+    type Item = Result<StripeSelection>;
 
     fn next(&mut self) -> Option<Self::Item> {
+        self.skip_stripes();
+        let row_group_filter = self.row_group_filter();
         self.file_metadata
             .stripe_metadatas()
             .get(self.stripe_index)
             .map(|info| {
-                let stripe = Stripe::new(
-                    &mut self.reader,
-                    &self.file_metadata,
-                    &self.projected_data_type.clone(),
-                    self.stripe_index,
-                    info,
-                );
+                let selection = match &row_group_filter {
+                    Some(filter) => Stripe::new_row_groups(
+                        &mut self.reader,
+                        &self.file_metadata,
+                        &self.projected_data_type,
+                        self.stripe_index,
+                        info,
+                        filter,
+                    ),
+                    None => Stripe::new(
+                        &mut self.reader,
+                        &self.file_metadata,
+                        &self.projected_data_type.clone(),
+                        self.stripe_index,
+                        info,
+                    )
+                    .map(StripeSelection::whole),
+                };
                 self.stripe_index += 1;
-                stripe
+                selection
             })
     }
 }
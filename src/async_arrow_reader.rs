@@ -1,5 +1,6 @@
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 use std::fmt::Formatter;
+use std::panic::AssertUnwindSafe;
 use std::pin::Pin;
 use std::sync::Arc;
 use std::task::{Context, Poll};
@@ -7,28 +8,47 @@ use std::task::{Context, Poll};
 use arrow::datatypes::SchemaRef;
 use arrow::error::ArrowError;
 use arrow::record_batch::RecordBatch;
+use bytes::Bytes;
+use futures::channel::oneshot;
 use futures::future::BoxFuture;
 use futures::{ready, Stream};
 use futures_util::FutureExt;
 use snafu::ResultExt;
 
 use crate::arrow_reader::column::Column;
-use crate::arrow_reader::{Cursor, NaiveStripeDecoder};
-use crate::error::{IoSnafu, Result};
+use crate::arrow_reader::{
+    reorder_schema, to_output, BatchCoalescer, Cursor, DecodePool, DecoderFactory,
+    NaiveStripeDecoder, RowGroupFilter, RowWindow, StringValidation,
+};
+use crate::error::{ArrowSnafu, IoSnafu, OrcError, Result};
+use crate::proto::stream::Kind;
 use crate::reader::metadata::FileMetadata;
 use crate::reader::AsyncChunkReader;
 use crate::schema::RootDataType;
-use crate::stripe::{deserialize_stripe_footer, StreamMap, Stripe, StripeMetadata};
+use crate::stripe::{
+    decode_row_indexes, deserialize_stripe_footer, ensure_column_encodings,
+    ensure_columns_unencrypted, row_index_streams, RowGroupPlan, StreamData, StreamMap, Stripe,
+    StripeMetadata, StripeSelection,
+};
 
 pub type BoxedDecoder = Box<dyn Iterator<Item = Result<RecordBatch>> + Send>;
 
+/// Stripe decoder handed back by the decode pool with its next batch, or the panic
+/// decoding it.
+type DecodedOnPool = (
+    BoxedDecoder,
+    std::thread::Result<Option<Result<RecordBatch>>>,
+);
+
 enum StreamState<T> {
     /// At the start of a new row group, or the end of the file stream
     Init,
     /// Decoding a batch
     Decoding(BoxedDecoder),
+    /// Decoding a batch on the decode pool, which hands the decoder back with it
+    DecodingOnPool(oneshot::Receiver<DecodedOnPool>),
     /// Reading data from input
-    Reading(BoxFuture<'static, Result<(StripeFactory<T>, Option<Stripe>)>>),
+    Reading(BoxFuture<'static, Result<(StripeFactory<T>, Option<StripeSelection>)>>),
     /// Error
     Error,
 }
@@ -38,6 +58,7 @@ impl<T> std::fmt::Debug for StreamState<T> {
         match self {
             StreamState::Init => write!(f, "StreamState::Init"),
             StreamState::Decoding(_) => write!(f, "StreamState::Decoding"),
+            StreamState::DecodingOnPool(_) => write!(f, "StreamState::DecodingOnPool"),
             StreamState::Reading(_) => write!(f, "StreamState::Reading"),
             StreamState::Error => write!(f, "StreamState::Error"),
         }
@@ -61,28 +82,61 @@ pub struct StripeFactory<R> {
 pub struct ArrowStreamReader<R: AsyncChunkReader> {
     factory: Option<Box<StripeFactory<R>>>,
     batch_size: usize,
+    /// Schema the stripes are decoded into
     schema_ref: SchemaRef,
+    /// Schema of the user columns of Hive ACID files, when unwrapping them
+    row_schema_ref: Option<SchemaRef>,
     state: StreamState<R>,
+    row_window: RowWindow,
+    skip_unsupported_columns: bool,
+    string_validation: StringValidation,
+    /// Threads decoding the batches, if more than one decodes the columns of each
+    decode_pool: Option<Arc<DecodePool>>,
+    decoder_factory: Option<DecoderFactory>,
+    /// Indices of the columns to output, in order, when reordering them
+    output_columns: Option<Vec<usize>>,
+    /// Invalid option found when building the stream, returned instead of batches
+    build_error: Option<OrcError>,
+    coalescer: Option<BatchCoalescer>,
 }
 
 impl<R: AsyncChunkReader + 'static> StripeFactory<R> {
-    pub async fn read_next_stripe_inner(&mut self, info: &StripeMetadata) -> Result<Stripe> {
+    pub async fn read_next_stripe_inner(
+        &mut self,
+        info: &StripeMetadata,
+    ) -> Result<StripeSelection> {
+        let row_group_filter = self.inner.row_group_filter();
         let inner = &mut self.inner;
 
         let stripe_offset = inner.stripe_index;
         inner.stripe_index += 1;
 
-        Stripe::new_async(
-            &mut inner.reader,
-            &inner.file_metadata,
-            &inner.projected_data_type,
-            stripe_offset,
-            info,
-        )
-        .await
+        match row_group_filter {
+            Some(filter) => {
+                Stripe::new_row_groups_async(
+                    &mut inner.reader,
+                    &inner.file_metadata,
+                    &inner.projected_data_type,
+                    stripe_offset,
+                    info,
+                    &filter,
+                )
+                .await
+            }
+            None => Stripe::new_async(
+                &mut inner.reader,
+                &inner.file_metadata,
+                &inner.projected_data_type,
+                stripe_offset,
+                info,
+            )
+            .await
+            .map(StripeSelection::whole),
+        }
     }
 
-    pub async fn read_next_stripe(mut self) -> Result<(Self, Option<Stripe>)> {
+    pub async fn read_next_stripe(mut self) -> Result<(Self, Option<StripeSelection>)> {
+        self.inner.skip_stripes();
         let info = self
             .inner
             .file_metadata
@@ -112,33 +166,173 @@ impl<R: AsyncChunkReader + 'static> ArrowStreamReader<R> {
             factory: Some(Box::new(cursor.into())),
             batch_size,
             schema_ref,
+            row_schema_ref: None,
             state: StreamState::Init,
+            row_window: RowWindow::default(),
+            skip_unsupported_columns: false,
+            string_validation: StringValidation::default(),
+            decode_pool: None,
+            decoder_factory: None,
+            output_columns: None,
+            build_error: None,
+            coalescer: None,
+        }
+    }
+
+    pub(crate) fn with_row_schema(mut self, row_schema_ref: Option<SchemaRef>) -> Self {
+        self.row_schema_ref = row_schema_ref;
+        self
+    }
+
+    pub(crate) fn with_row_window(mut self, row_window: RowWindow) -> Self {
+        self.row_window = row_window;
+        self
+    }
+
+    pub(crate) fn with_skip_unsupported_columns(mut self, skip_unsupported_columns: bool) -> Self {
+        self.skip_unsupported_columns = skip_unsupported_columns;
+        self
+    }
+
+    pub(crate) fn with_string_validation(mut self, string_validation: StringValidation) -> Self {
+        self.string_validation = string_validation;
+        self
+    }
+
+    pub(crate) fn with_decode_pool(mut self, decode_pool: Option<Arc<DecodePool>>) -> Self {
+        self.decode_pool = decode_pool;
+        self
+    }
+
+    pub(crate) fn with_decoder_factory(mut self, decoder_factory: Option<DecoderFactory>) -> Self {
+        self.decoder_factory = decoder_factory;
+        self
+    }
+
+    /// Reorder the columns, or fail the stream if they couldn't be resolved.
+    pub(crate) fn with_output_columns(
+        mut self,
+        output_columns: Result<Option<Vec<usize>>>,
+    ) -> Self {
+        match output_columns {
+            Ok(output_columns) => self.output_columns = output_columns,
+            Err(err) => self.build_error = Some(err),
         }
+        self
     }
 
+    pub(crate) fn with_coalescer(mut self, coalescer: Option<BatchCoalescer>) -> Self {
+        self.coalescer = coalescer;
+        self
+    }
+
+    /// Schema of the record batches produced by this stream, after projection.
     pub fn schema(&self) -> SchemaRef {
-        self.schema_ref.clone()
+        let schema_ref = self
+            .row_schema_ref
+            .clone()
+            .unwrap_or_else(|| self.schema_ref.clone());
+        reorder_schema(schema_ref, self.output_columns.as_deref())
+    }
+
+    /// Next batch, concatenating the batches of [`Self::poll_next_inner`] if coalescing.
+    fn poll_next_coalesced(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+    ) -> Poll<Option<Result<RecordBatch>>> {
+        let Some(mut coalescer) = self.coalescer.take() else {
+            return self.poll_next_inner(cx);
+        };
+        let poll = loop {
+            if let Some(batch) = coalescer.next_full() {
+                break Poll::Ready(Some(batch.context(ArrowSnafu)));
+            }
+            match self.as_mut().poll_next_inner(cx) {
+                Poll::Ready(Some(Ok(batch))) => coalescer.push(batch),
+                Poll::Ready(Some(Err(err))) => break Poll::Ready(Some(Err(err))),
+                Poll::Ready(None) => {
+                    break Poll::Ready(coalescer.finish().map(|batch| batch.context(ArrowSnafu)))
+                }
+                Poll::Pending => break Poll::Pending,
+            }
+        };
+        self.coalescer = Some(coalescer);
+        poll
+    }
+
+    /// Next batch of the current stripe decoder. Batches are decoded on the decode
+    /// pool if any, so that the threads decoding their columns don't block the task.
+    fn poll_next_decoded(&mut self, cx: &mut Context<'_>) -> Poll<Option<Result<RecordBatch>>> {
+        loop {
+            match &mut self.state {
+                StreamState::Decoding(decoder) => {
+                    let Some(pool) = self.decode_pool.clone() else {
+                        return Poll::Ready(decoder.next());
+                    };
+                    let StreamState::Decoding(mut decoder) =
+                        std::mem::replace(&mut self.state, StreamState::Error)
+                    else {
+                        unreachable!()
+                    };
+                    let (sender, receiver) = oneshot::channel();
+                    pool.spawn(move || {
+                        let batch = std::panic::catch_unwind(AssertUnwindSafe(|| decoder.next()));
+                        // Only fails if the stream was dropped
+                        let _ = sender.send((decoder, batch));
+                    });
+                    self.state = StreamState::DecodingOnPool(receiver);
+                }
+                StreamState::DecodingOnPool(receiver) => {
+                    let (decoder, batch) =
+                        ready!(receiver.poll_unpin(cx)).expect("decode pool dropped a batch");
+                    self.state = StreamState::Decoding(decoder);
+                    return Poll::Ready(
+                        batch.unwrap_or_else(|panic| std::panic::resume_unwind(panic)),
+                    );
+                }
+                _ => unreachable!("no stripe decoder"),
+            }
+        }
     }
 
     fn poll_next_inner(
         mut self: Pin<&mut Self>,
         cx: &mut Context<'_>,
     ) -> Poll<Option<Result<RecordBatch>>> {
+        if let Some(err) = self.build_error.take() {
+            // No batches follow the error
+            self.state = StreamState::Error;
+            return Poll::Ready(Some(Err(err)));
+        }
         loop {
+            if self.row_window.is_exhausted() {
+                return Poll::Ready(None);
+            }
             match &mut self.state {
-                StreamState::Decoding(decoder) => match decoder.next() {
-                    Some(Ok(batch)) => {
-                        return Poll::Ready(Some(Ok(batch)));
-                    }
-                    Some(Err(e)) => {
-                        self.state = StreamState::Error;
-                        return Poll::Ready(Some(Err(e)));
+                StreamState::Decoding(_) | StreamState::DecodingOnPool(_) => {
+                    match ready!(self.poll_next_decoded(cx)) {
+                        Some(Ok(batch)) => {
+                            if let Some(batch) = self.row_window.apply(batch) {
+                                let batch = to_output(
+                                    batch,
+                                    self.row_schema_ref.as_ref(),
+                                    self.output_columns.as_deref(),
+                                );
+                                return Poll::Ready(Some(batch.context(ArrowSnafu)));
+                            }
+                        }
+                        Some(Err(e)) => {
+                            self.state = StreamState::Error;
+                            return Poll::Ready(Some(Err(e)));
+                        }
+                        None => self.state = StreamState::Init,
                     }
-                    None => self.state = StreamState::Init,
-                },
+                }
                 StreamState::Init => {
                     let factory = self.factory.take().expect("lost factory");
                     if factory.is_end {
+                        // Keep ending if polled again, e.g. when coalescing batches
+                        self.factory = Some(factory);
                         return Poll::Ready(None);
                     }
 
@@ -147,14 +341,28 @@ impl<R: AsyncChunkReader + 'static> ArrowStreamReader<R> {
                     self.state = StreamState::Reading(fut)
                 }
                 StreamState::Reading(f) => match ready!(f.poll_unpin(cx)) {
-                    Ok((factory, Some(stripe))) => {
+                    Ok((factory, Some(selection))) => {
                         self.factory = Some(Box::new(factory));
-                        match NaiveStripeDecoder::new(
-                            stripe,
-                            self.schema_ref.clone(),
-                            self.batch_size,
-                        ) {
-                            Ok(decoder) => {
+                        let selected = selection.parts.iter().map(|(rows, _)| rows.clone());
+                        self.row_window.select(selection.number_of_rows, selected);
+                        let decoders = selection
+                            .parts
+                            .into_iter()
+                            .map(|(_, stripe)| {
+                                NaiveStripeDecoder::try_new_with_decoder_factory(
+                                    stripe,
+                                    self.schema_ref.clone(),
+                                    self.batch_size,
+                                    self.skip_unsupported_columns,
+                                    self.decoder_factory.as_ref(),
+                                    self.string_validation,
+                                )
+                                .map(|decoder| decoder.with_decode_pool(self.decode_pool.clone()))
+                            })
+                            .collect::<Result<Vec<_>>>();
+                        match decoders {
+                            Ok(decoders) => {
+                                let decoder = decoders.into_iter().flatten();
                                 self.state = StreamState::Decoding(Box::new(decoder));
                             }
                             Err(e) => {
@@ -183,7 +391,7 @@ impl<R: AsyncChunkReader + 'static> Stream for ArrowStreamReader<R> {
     type Item = std::result::Result<RecordBatch, ArrowError>;
 
     fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
-        self.poll_next_inner(cx)
+        self.poll_next_coalesced(cx)
             .map_err(|e| ArrowError::ExternalError(Box::new(e)))
     }
 }
@@ -197,13 +405,15 @@ impl Stripe {
         stripe: usize,
         info: &StripeMetadata,
     ) -> Result<Self> {
+        ensure_columns_unencrypted(file_metadata, projected_data_type)?;
         let compression = file_metadata.compression();
 
         let footer = reader
             .get_bytes(info.footer_offset(), info.footer_length())
             .await
             .context(IoSnafu)?;
-        let footer = Arc::new(deserialize_stripe_footer(&footer, compression)?);
+        let footer = Arc::new(deserialize_stripe_footer(&footer, compression.as_ref())?);
+        ensure_column_encodings(&footer, projected_data_type)?;
 
         //TODO(weny): add tz
         let columns = projected_data_type
@@ -212,16 +422,27 @@ impl Stripe {
             .map(|col| Column::new(col.name(), col.data_type(), &footer, info.number_of_rows()))
             .collect();
 
-        let mut stream_map = HashMap::new();
+        let projected_column_ids = projected_data_type
+            .children()
+            .iter()
+            .flat_map(|col| col.data_type().all_indices())
+            .collect::<HashSet<_>>();
+
+        let mut stream_map = StreamMap::new(compression);
         let mut stream_offset = info.offset();
         for stream in &footer.streams {
             let length = stream.length();
             let column_id = stream.column();
             let kind = stream.kind();
+            // The present stream of the root struct nulls whole rows, so is always read
+            let is_root_present = column_id == 0 && kind == Kind::Present;
+            if !projected_column_ids.contains(&(column_id as usize)) && !is_root_present {
+                stream_offset += length;
+                continue;
+            }
             let data = Column::read_stream_async(reader, stream_offset, length).await?;
 
-            // TODO(weny): filter out unused streams.
-            stream_map.insert((column_id, kind), data);
+            stream_map.insert(column_id, kind, data);
 
             stream_offset += length;
         }
@@ -230,11 +451,85 @@ impl Stripe {
             footer,
             columns,
             stripe_offset: stripe,
-            stream_map: Arc::new(StreamMap {
-                inner: stream_map,
-                compression,
-            }),
+            stream_map: Arc::new(stream_map),
+            number_of_rows: info.number_of_rows() as usize,
+            column_statistics: info.column_statistics().to_vec(),
+        })
+    }
+
+    /// Like [`Stripe::new_row_groups`], fetching only the byte ranges of the streams
+    /// holding the rows of the selected row groups.
+    pub(crate) async fn new_row_groups_async<R: AsyncChunkReader>(
+        reader: &mut R,
+        file_metadata: &Arc<FileMetadata>,
+        projected_data_type: &RootDataType,
+        stripe: usize,
+        info: &StripeMetadata,
+        filter: &RowGroupFilter,
+    ) -> Result<StripeSelection> {
+        ensure_columns_unencrypted(file_metadata, projected_data_type)?;
+        let compression = file_metadata.compression();
+
+        let footer = reader
+            .get_bytes(info.footer_offset(), info.footer_length())
+            .await
+            .context(IoSnafu)?;
+        let footer = Arc::new(deserialize_stripe_footer(&footer, compression.as_ref())?);
+        ensure_column_encodings(&footer, projected_data_type)?;
+
+        let mut row_indexes = vec![];
+        for (column_id, offset, length) in row_index_streams(&footer, info) {
+            let data = Column::read_stream_async(reader, offset, length).await?;
+            row_indexes.push((column_id, data));
+        }
+        let row_indexes = decode_row_indexes(&footer, row_indexes, compression.as_ref())?;
+        let plan = RowGroupPlan::try_new(
+            &footer,
+            info,
+            stripe,
+            projected_data_type,
+            &row_indexes,
+            file_metadata,
+            filter,
+        )?;
+
+        // Streams read whole, such as dictionaries, are shared by all ranges
+        let mut whole_streams = HashMap::new();
+        let mut parts = vec![];
+        for (rows, reads) in plan.ranges {
+            let mut stream_map = StreamMap::new(compression.clone());
+            for read in reads {
+                let data = match whole_streams.get(&read.offset) {
+                    Some(data) => Bytes::clone(data),
+                    None => {
+                        let data =
+                            Column::read_stream_async(reader, read.offset, read.length).await?;
+                        if read.position.is_none() {
+                            whole_streams.insert(read.offset, data.clone());
+                        }
+                        data
+                    }
+                };
+                stream_map.insert_data(
+                    read.column_id,
+                    read.kind,
+                    StreamData::Loaded(data),
+                    read.position,
+                );
+            }
+            let stripe = Stripe::with_rows(
+                &footer,
+                projected_data_type,
+                stripe,
+                rows.len(),
+                info,
+                stream_map,
+            );
+            parts.push((rows, stripe));
+        }
+        Ok(StripeSelection {
             number_of_rows: info.number_of_rows() as usize,
+            parts,
         })
     }
 }
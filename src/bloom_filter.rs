@@ -0,0 +1,159 @@
+//! Bloom filters stored in the index section of stripes, one per row group,
+//! used to skip stripes which can't contain a given value.
+//!
+//! Hashing follows the Java implementation: strings and binary values are
+//! hashed with the 64-bit variant of Murmur3 used by ORC, and the bit positions
+//! are derived from the two 32-bit halves of that hash.
+
+use crate::proto;
+
+/// Seed of [`murmur3_64`] used for bloom filters.
+const MURMUR3_SEED: u64 = 104729;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BloomFilter {
+    num_hash_functions: u32,
+    bitset: Vec<u64>,
+}
+
+impl BloomFilter {
+    /// Empty bloom filter of `num_words * 64` bits.
+    pub fn new(num_words: usize, num_hash_functions: u32) -> Self {
+        Self {
+            num_hash_functions,
+            bitset: vec![0; num_words],
+        }
+    }
+
+    /// `None` if the filter has no bits, in which case nothing can be ruled out.
+    pub fn from_proto(filter: &proto::BloomFilter) -> Option<Self> {
+        // Newer writers store the bits as little endian bytes instead of longs
+        let bitset = match &filter.utf8bitset {
+            Some(bytes) => bytes
+                .chunks_exact(8)
+                .map(|word| u64::from_le_bytes(word.try_into().unwrap()))
+                .collect(),
+            None => filter.bitset.clone(),
+        };
+        if bitset.is_empty() {
+            return None;
+        }
+        Some(Self {
+            num_hash_functions: filter.num_hash_functions(),
+            bitset,
+        })
+    }
+
+    pub fn num_hash_functions(&self) -> u32 {
+        self.num_hash_functions
+    }
+
+    pub fn bitset(&self) -> &[u64] {
+        &self.bitset
+    }
+
+    /// Add a string or binary value, hashed from its raw bytes.
+    pub fn add_bytes(&mut self, value: &[u8]) {
+        for position in self.positions(murmur3_64(value)) {
+            self.bitset[position / 64] |= 1 << (position % 64);
+        }
+    }
+
+    /// Whether a string or binary value may have been added. `false` means it
+    /// definitely wasn't.
+    pub fn test_bytes(&self, value: &[u8]) -> bool {
+        self.positions(murmur3_64(value))
+            .all(|position| self.bitset[position / 64] & (1 << (position % 64)) != 0)
+    }
+
+    fn positions(&self, hash: u64) -> impl Iterator<Item = usize> {
+        let num_bits = self.bitset.len() * 64;
+        let hash1 = hash as i32;
+        let hash2 = (hash >> 32) as i32;
+        (1..=self.num_hash_functions as i32).map(move |i| {
+            let combined = hash1.wrapping_add(i.wrapping_mul(hash2));
+            let combined = if combined < 0 { !combined } else { combined };
+            combined as usize % num_bits
+        })
+    }
+}
+
+/// The 64-bit Murmur3 hash of ORC, which unlike the usual 128-bit variant only
+/// keeps a single 64-bit state.
+fn murmur3_64(data: &[u8]) -> u64 {
+    const C1: u64 = 0x87c37b91114253d5;
+    const C2: u64 = 0x4cf5ad432745937f;
+    const M: u64 = 5;
+    const N1: u64 = 0x52dce729;
+
+    let mut hash = MURMUR3_SEED;
+    let mut blocks = data.chunks_exact(8);
+    for block in blocks.by_ref() {
+        let k = u64::from_le_bytes(block.try_into().unwrap())
+            .wrapping_mul(C1)
+            .rotate_left(31)
+            .wrapping_mul(C2);
+        hash ^= k;
+        hash = hash.rotate_left(27).wrapping_mul(M).wrapping_add(N1);
+    }
+
+    let tail = blocks.remainder();
+    if !tail.is_empty() {
+        let k = tail
+            .iter()
+            .rev()
+            .fold(0u64, |k, &byte| (k << 8) | byte as u64)
+            .wrapping_mul(C1)
+            .rotate_left(31)
+            .wrapping_mul(C2);
+        hash ^= k;
+    }
+
+    hash ^= data.len() as u64;
+    fmix64(hash)
+}
+
+fn fmix64(mut hash: u64) -> u64 {
+    hash ^= hash >> 33;
+    hash = hash.wrapping_mul(0xff51afd7ed558ccd);
+    hash ^= hash >> 33;
+    hash = hash.wrapping_mul(0xc4ceb9fe1a85ec53);
+    hash ^= hash >> 33;
+    hash
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn added_values_are_found() {
+        let mut filter = BloomFilter::new(16, 3);
+        let values: [&[u8]; 4] = [b"", b"abc", b"0123456789abcdef", &[0xff; 13]];
+        for value in values {
+            filter.add_bytes(value);
+        }
+        for value in values {
+            assert!(filter.test_bytes(value));
+        }
+        assert!(!filter.test_bytes(b"missing"));
+    }
+
+    #[test]
+    fn utf8_bitset() {
+        let mut filter = BloomFilter::new(4, 2);
+        filter.add_bytes(b"value");
+        let proto = proto::BloomFilter {
+            num_hash_functions: Some(2),
+            bitset: vec![],
+            utf8bitset: Some(
+                filter
+                    .bitset()
+                    .iter()
+                    .flat_map(|w| w.to_le_bytes())
+                    .collect(),
+            ),
+        };
+        assert_eq!(Some(filter), BloomFilter::from_proto(&proto));
+    }
+}
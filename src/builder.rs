@@ -3,6 +3,7 @@ use arrow::array::{ArrayBuilder, ArrayRef};
 /// BoxedArrayBuilder
 ///
 /// It implements the [ArrayBuilder] and [Sized] trait.
+#[allow(dead_code)]
 pub struct BoxedArrayBuilder {
     pub(crate) builder: Box<dyn ArrayBuilder>,
 }
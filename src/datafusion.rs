@@ -0,0 +1,422 @@
+//! Integration with [DataFusion](https://docs.rs/datafusion).
+//!
+//! Requires the `datafusion` feature.
+
+use arrow::datatypes::{DataType as ArrowDataType, Schema};
+use datafusion::common::stats::Precision;
+use datafusion::common::{ColumnStatistics as DfColumnStatistics, ScalarValue, Statistics};
+use datafusion::logical_expr::{BinaryExpr, Expr, Operator};
+
+use crate::reader::metadata::FileMetadata;
+use crate::schema::RootDataType;
+use crate::statistics::{ColumnStatistics, TypeStatistics};
+use crate::stripe::StripeMetadata;
+
+/// Convert the file level ORC statistics into DataFusion [`Statistics`] for the
+/// root columns in `schema`, matched to ORC columns by name.
+pub fn file_statistics(file_metadata: &FileMetadata, schema: &Schema) -> Statistics {
+    to_datafusion_statistics(
+        file_metadata.number_of_rows(),
+        file_metadata.column_file_statistics(),
+        file_metadata.root_data_type(),
+        schema,
+    )
+}
+
+/// Convert the ORC statistics of a single stripe into DataFusion [`Statistics`] for
+/// the root columns in `schema`, matched to ORC columns by name.
+pub fn stripe_statistics(
+    file_metadata: &FileMetadata,
+    stripe_metadata: &StripeMetadata,
+    schema: &Schema,
+) -> Statistics {
+    to_datafusion_statistics(
+        stripe_metadata.number_of_rows(),
+        stripe_metadata.column_statistics(),
+        file_metadata.root_data_type(),
+        schema,
+    )
+}
+
+/// Whether rows of the stripe may satisfy `predicate`, according to its statistics,
+/// so stripes for which this is `false` can be skipped.
+///
+/// Understands `IS NULL` and `IS NOT NULL` on root columns, matched by name, and
+/// their combinations with `AND` and `OR`. Stripes may match any other predicate.
+pub fn stripe_may_match(
+    file_metadata: &FileMetadata,
+    stripe_metadata: &StripeMetadata,
+    predicate: &Expr,
+) -> bool {
+    let column_statistics = |expr: &Expr| match expr {
+        Expr::Column(column) => file_metadata
+            .root_data_type()
+            .children()
+            .iter()
+            .find(|col| col.name() == column.name)
+            .and_then(|col| {
+                stripe_metadata
+                    .column_statistics()
+                    .get(col.data_type().column_index())
+            }),
+        _ => None,
+    };
+    match predicate {
+        // Only skip if the writer recorded there are no nulls
+        Expr::IsNull(expr) => {
+            column_statistics(expr).map_or(true, |stats| stats.recorded_has_null() != Some(false))
+        }
+        // Number of values excludes nulls, only skip if the writer recorded there
+        // are none
+        Expr::IsNotNull(expr) => column_statistics(expr)
+            .map_or(true, |stats| stats.recorded_number_of_values() != Some(0)),
+        Expr::BinaryExpr(BinaryExpr {
+            left,
+            op: Operator::And,
+            right,
+        }) => {
+            stripe_may_match(file_metadata, stripe_metadata, left)
+                && stripe_may_match(file_metadata, stripe_metadata, right)
+        }
+        Expr::BinaryExpr(BinaryExpr {
+            left,
+            op: Operator::Or,
+            right,
+        }) => {
+            stripe_may_match(file_metadata, stripe_metadata, left)
+                || stripe_may_match(file_metadata, stripe_metadata, right)
+        }
+        _ => true,
+    }
+}
+
+fn to_datafusion_statistics(
+    number_of_rows: u64,
+    column_statistics: &[ColumnStatistics],
+    root_data_type: &RootDataType,
+    schema: &Schema,
+) -> Statistics {
+    let column_statistics = schema
+        .fields()
+        .iter()
+        .map(|field| {
+            root_data_type
+                .children()
+                .iter()
+                .find(|col| col.name() == field.name())
+                .and_then(|col| column_statistics.get(col.data_type().column_index()))
+                .map(|stats| convert_column_statistics(number_of_rows, stats, field.data_type()))
+                .unwrap_or_else(DfColumnStatistics::new_unknown)
+        })
+        .collect();
+    Statistics {
+        num_rows: Precision::Exact(number_of_rows as usize),
+        total_byte_size: Precision::Absent,
+        column_statistics,
+    }
+}
+
+fn convert_column_statistics(
+    number_of_rows: u64,
+    stats: &ColumnStatistics,
+    data_type: &ArrowDataType,
+) -> DfColumnStatistics {
+    // Number of values excludes nulls, and is unknown if the writer didn't record it
+    let null_count = stats
+        .recorded_number_of_values()
+        .map(|values| number_of_rows.saturating_sub(values) as usize);
+    let (min_value, max_value) = match stats.type_statistics() {
+        // No non-null values to derive min/max from
+        Some(_) if stats.recorded_number_of_values() == Some(0) => (None, None),
+        Some(type_statistics) => convert_min_max(stats, type_statistics, data_type),
+        None => (None, None),
+    };
+    DfColumnStatistics {
+        null_count: null_count.map(Precision::Exact).unwrap_or_default(),
+        max_value: max_value.map(Precision::Exact).unwrap_or_default(),
+        min_value: min_value.map(Precision::Exact).unwrap_or_default(),
+        distinct_count: Precision::Absent,
+    }
+}
+
+fn convert_min_max(
+    stats: &ColumnStatistics,
+    type_statistics: &TypeStatistics,
+    data_type: &ArrowDataType,
+) -> (Option<ScalarValue>, Option<ScalarValue>) {
+    match (type_statistics, data_type) {
+        (TypeStatistics::Integer { min, max, .. }, ArrowDataType::Int8) => (
+            Some(ScalarValue::Int8(Some(*min as i8))),
+            Some(ScalarValue::Int8(Some(*max as i8))),
+        ),
+        (TypeStatistics::Integer { min, max, .. }, ArrowDataType::Int16) => (
+            Some(ScalarValue::Int16(Some(*min as i16))),
+            Some(ScalarValue::Int16(Some(*max as i16))),
+        ),
+        (TypeStatistics::Integer { min, max, .. }, ArrowDataType::Int32) => (
+            Some(ScalarValue::Int32(Some(*min as i32))),
+            Some(ScalarValue::Int32(Some(*max as i32))),
+        ),
+        (TypeStatistics::Integer { min, max, .. }, ArrowDataType::Int64) => (
+            Some(ScalarValue::Int64(Some(*min))),
+            Some(ScalarValue::Int64(Some(*max))),
+        ),
+        (TypeStatistics::Double { min, max, .. }, ArrowDataType::Float32) => (
+            Some(ScalarValue::Float32(Some(*min as f32))),
+            Some(ScalarValue::Float32(Some(*max as f32))),
+        ),
+        (TypeStatistics::Double { min, max, .. }, ArrowDataType::Float64) => (
+            Some(ScalarValue::Float64(Some(*min))),
+            Some(ScalarValue::Float64(Some(*max))),
+        ),
+        (TypeStatistics::String { .. }, ArrowDataType::Utf8) => {
+            let (min, max) = stats.string_min_max().unzip();
+            (
+                min.map(|min| ScalarValue::Utf8(Some(min.to_owned()))),
+                max.map(|max| ScalarValue::Utf8(Some(max.to_owned()))),
+            )
+        }
+        (TypeStatistics::String { .. }, ArrowDataType::LargeUtf8) => {
+            let (min, max) = stats.string_min_max().unzip();
+            (
+                min.map(|min| ScalarValue::LargeUtf8(Some(min.to_owned()))),
+                max.map(|max| ScalarValue::LargeUtf8(Some(max.to_owned()))),
+            )
+        }
+        (TypeStatistics::String { .. }, ArrowDataType::Binary) => {
+            let (min, max) = stats.binary_min_max().unzip();
+            (
+                min.map(|min| ScalarValue::Binary(Some(min.to_vec()))),
+                max.map(|max| ScalarValue::Binary(Some(max.to_vec()))),
+            )
+        }
+        (TypeStatistics::String { .. }, ArrowDataType::LargeBinary) => {
+            let (min, max) = stats.binary_min_max().unzip();
+            (
+                min.map(|min| ScalarValue::LargeBinary(Some(min.to_vec()))),
+                max.map(|max| ScalarValue::LargeBinary(Some(max.to_vec()))),
+            )
+        }
+        (TypeStatistics::Date { min, max }, ArrowDataType::Date32) => (
+            Some(ScalarValue::Date32(Some(*min))),
+            Some(ScalarValue::Date32(Some(*max))),
+        ),
+        (TypeStatistics::Bucket { true_count }, ArrowDataType::Boolean) => {
+            // Min is true only if every value is true, so is unknown without the
+            // number of values, and max is true if any value is true
+            let min = stats
+                .recorded_number_of_values()
+                .map(|values| *true_count == values);
+            let max = *true_count > 0;
+            (
+                min.map(|min| ScalarValue::Boolean(Some(min))),
+                Some(ScalarValue::Boolean(Some(max))),
+            )
+        }
+        // TODO: decimal and timestamp statistics. Binary columns only record their
+        // total length
+        _ => (None, None),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+    use std::fs::File;
+
+    use arrow::datatypes::Field;
+
+    use crate::ArrowReaderBuilder;
+
+    use super::*;
+
+    #[test]
+    fn alltypes_file_statistics() {
+        let path = format!(
+            "{}/tests/basic/data/alltypes.snappy.orc",
+            env!("CARGO_MANIFEST_DIR")
+        );
+        let f = File::open(path).unwrap();
+        let builder = ArrowReaderBuilder::try_new(f).unwrap();
+        let file_metadata = builder.file_metadata();
+        let schema = file_metadata
+            .root_data_type()
+            .create_arrow_schema(&HashMap::new());
+        let statistics = file_statistics(file_metadata, &schema);
+
+        assert_eq!(statistics.num_rows, Precision::Exact(11));
+        let expected = [
+            (
+                "boolean",
+                ScalarValue::Boolean(Some(false)),
+                ScalarValue::Boolean(Some(true)),
+            ),
+            (
+                "int16",
+                ScalarValue::Int16(Some(i16::MIN)),
+                ScalarValue::Int16(Some(i16::MAX)),
+            ),
+            (
+                "int32",
+                ScalarValue::Int32(Some(i32::MIN)),
+                ScalarValue::Int32(Some(i32::MAX)),
+            ),
+            (
+                "int64",
+                ScalarValue::Int64(Some(i64::MIN)),
+                ScalarValue::Int64(Some(i64::MAX)),
+            ),
+            (
+                "float32",
+                ScalarValue::Float32(Some(f32::NEG_INFINITY)),
+                ScalarValue::Float32(Some(f32::INFINITY)),
+            ),
+            (
+                "float64",
+                ScalarValue::Float64(Some(f64::NEG_INFINITY)),
+                ScalarValue::Float64(Some(f64::INFINITY)),
+            ),
+            (
+                "utf8",
+                ScalarValue::Utf8(Some("".to_owned())),
+                ScalarValue::Utf8(Some("🤔".to_owned())),
+            ),
+            (
+                "date32",
+                ScalarValue::Date32(Some(-141427)),
+                ScalarValue::Date32(Some(2932896)),
+            ),
+        ];
+        for (name, min, max) in expected {
+            let index = schema.index_of(name).unwrap();
+            let column = &statistics.column_statistics[index];
+            assert_eq!(column.null_count, Precision::Exact(2), "{name}");
+            assert_eq!(column.min_value, Precision::Exact(min), "{name}");
+            assert_eq!(column.max_value, Precision::Exact(max), "{name}");
+        }
+
+        // Binary statistics only record the total length
+        let index = schema.index_of("binary").unwrap();
+        let column = &statistics.column_statistics[index];
+        assert_eq!(column.min_value, Precision::Absent);
+        assert_eq!(column.max_value, Precision::Absent);
+    }
+
+    #[test]
+    fn unrecorded_number_of_values() {
+        use crate::proto;
+
+        let stats = |proto_stats: proto::ColumnStatistics| {
+            ColumnStatistics::try_from(&proto_stats).unwrap()
+        };
+        let string_stats = stats(proto::ColumnStatistics {
+            string_statistics: Some(proto::StringStatistics {
+                minimum: Some("a".to_owned()),
+                maximum: Some("b".to_owned()),
+                ..Default::default()
+            }),
+            ..Default::default()
+        });
+        let column = convert_column_statistics(10, &string_stats, &ArrowDataType::Utf8);
+        assert_eq!(column.null_count, Precision::Absent);
+        assert_eq!(column.min_value, Precision::Absent);
+        assert_eq!(column.max_value, Precision::Absent);
+
+        let bucket_stats = proto::ColumnStatistics {
+            bucket_statistics: Some(proto::BucketStatistics { count: vec![10] }),
+            ..Default::default()
+        };
+        let column =
+            convert_column_statistics(10, &stats(bucket_stats.clone()), &ArrowDataType::Boolean);
+        assert_eq!(column.null_count, Precision::Absent);
+        assert_eq!(column.min_value, Precision::Absent);
+        let max = ScalarValue::Boolean(Some(true));
+        assert_eq!(column.max_value, Precision::Exact(max));
+
+        let column = convert_column_statistics(
+            10,
+            &stats(proto::ColumnStatistics {
+                number_of_values: Some(10),
+                ..bucket_stats
+            }),
+            &ArrowDataType::Boolean,
+        );
+        assert_eq!(column.null_count, Precision::Exact(0));
+        let min = ScalarValue::Boolean(Some(true));
+        assert_eq!(column.min_value, Precision::Exact(min));
+    }
+
+    #[test]
+    fn string_statistics_as_binary() {
+        let path = format!(
+            "{}/tests/basic/data/alltypes.snappy.orc",
+            env!("CARGO_MANIFEST_DIR")
+        );
+        let f = File::open(path).unwrap();
+        let builder = ArrowReaderBuilder::try_new(f).unwrap();
+        let file_metadata = builder.file_metadata();
+        let schema = Schema::new(vec![
+            Field::new("utf8", ArrowDataType::Binary, true),
+            Field::new("binary", ArrowDataType::Binary, true),
+        ]);
+        let statistics = file_statistics(file_metadata, &schema);
+
+        let column = &statistics.column_statistics[0];
+        let min = ScalarValue::Binary(Some(vec![]));
+        let max = ScalarValue::Binary(Some("🤔".as_bytes().to_vec()));
+        assert_eq!(column.min_value, Precision::Exact(min));
+        assert_eq!(column.max_value, Precision::Exact(max));
+        let column = &statistics.column_statistics[1];
+        assert_eq!(column.min_value, Precision::Absent);
+        assert_eq!(column.max_value, Precision::Absent);
+    }
+
+    #[test]
+    fn null_checks_prune_stripes() {
+        use datafusion::prelude::{col, lit};
+
+        let path = format!(
+            "{}/tests/basic/data/long_bool_gzip.orc",
+            env!("CARGO_MANIFEST_DIR")
+        );
+        let f = File::open(path).unwrap();
+        let builder = ArrowReaderBuilder::try_new(f).unwrap();
+        let file_metadata = builder.file_metadata();
+        // Single stripe, recorded as having no nulls in column `long`
+        let stripe_metadata = &file_metadata.stripe_metadatas()[0];
+        let may_match =
+            |predicate: Expr| stripe_may_match(file_metadata, stripe_metadata, &predicate);
+
+        assert!(!may_match(col("long").is_null()));
+        assert!(may_match(col("long").is_not_null()));
+        assert!(!may_match(
+            col("long").is_null().and(col("long").is_not_null())
+        ));
+        assert!(may_match(
+            col("long").is_null().or(col("long").is_not_null())
+        ));
+        // Unknown columns and unsupported predicates can't prune
+        assert!(may_match(col("missing").is_null()));
+        assert!(may_match(col("long").eq(lit(1_i64))));
+
+        let path = format!(
+            "{}/tests/basic/data/alltypes.snappy.orc",
+            env!("CARGO_MANIFEST_DIR")
+        );
+        let f = File::open(path).unwrap();
+        let builder = ArrowReaderBuilder::try_new(f).unwrap();
+        let file_metadata = builder.file_metadata();
+        // Every column has both nulls and values
+        let stripe_metadata = &file_metadata.stripe_metadatas()[0];
+        assert!(stripe_may_match(
+            file_metadata,
+            stripe_metadata,
+            &col("int32").is_null()
+        ));
+        assert!(stripe_may_match(
+            file_metadata,
+            stripe_metadata,
+            &col("int32").is_not_null()
+        ));
+    }
+}
@@ -1,12 +1,14 @@
 use std::io;
 use std::string::FromUtf8Error;
 
+use arrow::datatypes::DataType as ArrowDataType;
 use arrow::error::ArrowError;
 pub use snafu::prelude::*;
 use snafu::Location;
 
 use crate::proto;
 use crate::proto::r#type::Kind;
+use crate::schema::DataType;
 
 #[derive(Debug, Snafu)]
 #[snafu(visibility(pub))]
@@ -29,6 +31,94 @@ pub enum OrcError {
     #[snafu(display("Invalid input, message: {}", msg))]
     InvalidInput { msg: String, location: Location },
 
+    #[snafu(display("Schema of file {} is incompatible with the first file: {}", file, msg))]
+    IncompatibleSchema {
+        file: usize,
+        msg: String,
+        location: Location,
+    },
+
+    #[snafu(display(
+        "failed decoding column `{}` (id {}) in stripe {}: {}",
+        column,
+        column_id,
+        stripe,
+        source
+    ))]
+    DecodeColumn {
+        column: String,
+        column_id: u32,
+        stripe: usize,
+        #[snafu(source(from(OrcError, Box::new)))]
+        source: Box<OrcError>,
+        location: Location,
+    },
+
+    #[snafu(display("{:?} stream of column {} is shorter than expected", kind, column))]
+    TruncatedStream {
+        column: String,
+        kind: proto::stream::Kind,
+        location: Location,
+    },
+
+    #[snafu(display(
+        "Children of column {} have {} values where {} were expected",
+        column,
+        got,
+        expected
+    ))]
+    ChildLengthMismatch {
+        column: String,
+        expected: usize,
+        got: usize,
+        location: Location,
+    },
+
+    #[snafu(display(
+        "Invalid UTF-8 in value {} of the {:?} stream of column {}",
+        index,
+        kind,
+        column
+    ))]
+    InvalidUtf8 {
+        column: String,
+        kind: proto::stream::Kind,
+        /// Index of the value in the stripe, counting nulls, or of the dictionary
+        /// entry
+        index: usize,
+        location: Location,
+    },
+
+    #[snafu(display("Corrupt length stream of column {}: {}", column, msg))]
+    CorruptLength {
+        column: String,
+        msg: String,
+        location: Location,
+    },
+
+    #[snafu(display(
+        "Values of column {} in a batch exceed the 2GiB addressable by 32-bit offsets, \
+         read it as a large Arrow type or with a smaller batch size",
+        column
+    ))]
+    OffsetOverflow { column: String, location: Location },
+
+    #[snafu(display(
+        "List of length {} in column {} doesn't fit its Arrow fixed size list of size {}",
+        length,
+        column,
+        size
+    ))]
+    FixedSizeListLength {
+        column: String,
+        size: i32,
+        length: usize,
+        location: Location,
+    },
+
+    #[snafu(display("No codec registered for compression kind {}", kind))]
+    UnsupportedCompression { kind: i32, location: Location },
+
     #[snafu(display("Out of spec, message: {}", msg))]
     OutOfSpec { msg: String, location: Location },
 
@@ -59,9 +149,55 @@ pub enum OrcError {
     #[snafu(display("No types found"))]
     NoTypes { location: Location },
 
+    #[snafu(display("Corrupt schema: {}", msg))]
+    CorruptSchema { msg: String, location: Location },
+
     #[snafu(display("unsupported type: {:?}", kind))]
     UnsupportedType { location: Location, kind: Kind },
 
+    #[snafu(display(
+        "ORC type {} has values which don't fit Arrow type {}, which needs at least as many integer and fractional digits",
+        orc,
+        arrow
+    ))]
+    DecimalPrecision {
+        location: Location,
+        orc: DataType,
+        arrow: ArrowDataType,
+    },
+
+    #[snafu(display(
+        "Mismatched schema, ORC type {} cannot be decoded as Arrow type {}",
+        orc_type,
+        arrow_type
+    ))]
+    MismatchedSchema {
+        location: Location,
+        orc_type: DataType,
+        arrow_type: ArrowDataType,
+    },
+
+    #[snafu(display(
+        "Column '{}' is encrypted, reading encrypted columns is not supported",
+        name
+    ))]
+    EncryptionUnsupported { location: Location, name: String },
+
+    #[snafu(display(
+        "Column '{}' contains nulls but its Arrow field is non-nullable",
+        column
+    ))]
+    NullInNonNullable { location: Location, column: String },
+
+    #[snafu(display(
+        "ORC type {} cannot be visited, only primitive columns are supported",
+        orc_type
+    ))]
+    UnsupportedVisitorType {
+        location: Location,
+        orc_type: DataType,
+    },
+
     #[snafu(display("Field not found: {:?}", name))]
     FieldNotFound { location: Location, name: String },
 
@@ -99,6 +235,26 @@ pub enum OrcError {
     #[snafu(display("unexpected: {}", msg))]
     Unexpected { location: Location, msg: String },
 
+    #[snafu(display("Invalid layout of stripe {}: {}", stripe, msg))]
+    InvalidStripeLayout {
+        location: Location,
+        stripe: usize,
+        msg: String,
+    },
+
+    #[snafu(display("Corrupt stripe footer: {}", msg))]
+    CorruptStripeFooter { location: Location, msg: String },
+
+    #[snafu(display("Corrupt compressed stream: {}", msg))]
+    CorruptCompression { location: Location, msg: String },
+
+    #[snafu(display("Decompressed {} exceeds the limit of {} bytes", scope, limit))]
+    DecompressionLimitExceeded {
+        location: Location,
+        scope: &'static str,
+        limit: u64,
+    },
+
     #[snafu(display("Failed to build zstd decoder: {}", source))]
     BuildZstdDecoder {
         location: Location,
@@ -128,6 +284,20 @@ pub enum OrcError {
         source: arrow::error::ArrowError,
         location: Location,
     },
+
+    #[cfg(feature = "arrow2")]
+    #[snafu(display("arrow2 error: {}", source))]
+    Arrow2 {
+        source: arrow2::error::Error,
+        location: Location,
+    },
+
+    #[cfg(feature = "arrow2")]
+    #[snafu(display("ORC type {} cannot be decoded into arrow2", orc_type))]
+    UnsupportedArrow2Type {
+        location: Location,
+        orc_type: DataType,
+    },
 }
 
 pub type Result<T> = std::result::Result<T, OrcError>;
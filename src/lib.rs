@@ -1,7 +1,14 @@
+#[cfg(feature = "arrow2")]
+pub mod arrow2_reader;
+mod arrow_fields;
 pub mod arrow_reader;
 pub mod async_arrow_reader;
+pub mod bloom_filter;
 pub(crate) mod builder;
+#[cfg(feature = "datafusion")]
+pub mod datafusion;
 pub mod error;
+pub mod multi_file_reader;
 pub mod projection;
 pub mod proto;
 pub mod reader;
@@ -9,5 +16,6 @@ pub mod schema;
 pub mod statistics;
 pub mod stripe;
 
-pub use arrow_reader::{ArrowReader, ArrowReaderBuilder};
+pub use arrow_reader::{read, ArrowReader, ArrowReaderBuilder};
 pub use async_arrow_reader::ArrowStreamReader;
+pub use multi_file_reader::{MultiFileReader, MultiFileReaderBuilder};
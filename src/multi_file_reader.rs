@@ -0,0 +1,144 @@
+//! Read several ORC files sharing a schema, such as the files of a partitioned
+//! dataset, as a single sequence of record batches.
+
+use std::collections::{HashMap, VecDeque};
+
+use arrow::datatypes::SchemaRef;
+use arrow::error::ArrowError;
+use arrow::record_batch::{RecordBatch, RecordBatchReader};
+use snafu::ensure;
+
+use crate::arrow_reader::{ArrowReader, ArrowReaderBuilder, DEFAULT_BATCH_SIZE};
+use crate::error::{IncompatibleSchemaSnafu, InvalidInputSnafu, Result};
+use crate::projection::ProjectionMask;
+use crate::reader::ChunkReader;
+
+pub struct MultiFileReaderBuilder<R> {
+    builders: Vec<ArrowReaderBuilder<R>>,
+    batch_size: usize,
+    projection: ProjectionMask,
+}
+
+impl<R: ChunkReader> MultiFileReaderBuilder<R> {
+    /// Read the metadata of each file, in order, and check they all have the
+    /// same schema as the first one.
+    pub fn try_new(readers: impl IntoIterator<Item = R>) -> Result<Self> {
+        let builders = readers
+            .into_iter()
+            .map(ArrowReaderBuilder::try_new)
+            .collect::<Result<Vec<_>>>()?;
+        ensure!(
+            !builders.is_empty(),
+            InvalidInputSnafu {
+                msg: "No files to read"
+            }
+        );
+
+        // Compare the Arrow schemas as they don't include the column indices
+        let schemas = builders.iter().map(|builder| {
+            builder
+                .file_metadata()
+                .root_data_type()
+                .create_arrow_schema(&HashMap::new())
+        });
+        let mut schemas = schemas.enumerate();
+        let (_, first) = schemas.next().unwrap();
+        for (file, schema) in schemas {
+            ensure!(
+                schema.fields() == first.fields(),
+                IncompatibleSchemaSnafu {
+                    file,
+                    msg: format!("expected {:?}, found {:?}", first.fields(), schema.fields()),
+                }
+            );
+        }
+
+        Ok(Self {
+            builders,
+            batch_size: DEFAULT_BATCH_SIZE,
+            projection: ProjectionMask::all(),
+        })
+    }
+
+    pub fn with_batch_size(mut self, batch_size: usize) -> Self {
+        self.batch_size = batch_size;
+        self
+    }
+
+    /// Projection applied to every file. As the files share a schema, it can be
+    /// built from the root data type of any of them.
+    pub fn with_projection(mut self, projection: ProjectionMask) -> Self {
+        self.projection = projection;
+        self
+    }
+
+    /// Total number of rows across all the files.
+    pub fn total_row_count(&self) -> u64 {
+        self.builders
+            .iter()
+            .map(|builder| builder.file_metadata().number_of_rows())
+            .sum()
+    }
+
+    pub fn build(self) -> MultiFileReader<R> {
+        let mut builders = VecDeque::from(self.builders);
+        // Checked to not be empty when creating the builder
+        let first = builders.pop_front().unwrap();
+        let current = first
+            .with_projection(self.projection.clone())
+            .with_batch_size(self.batch_size)
+            .build();
+        MultiFileReader {
+            schema_ref: current.schema(),
+            current: Some(current),
+            remaining: builders,
+            batch_size: self.batch_size,
+            projection: self.projection,
+        }
+    }
+}
+
+/// Reads the files one after the other, only opening the next file once the
+/// previous one is exhausted. All batches share the schema of the first file.
+pub struct MultiFileReader<R> {
+    schema_ref: SchemaRef,
+    current: Option<ArrowReader<R>>,
+    remaining: VecDeque<ArrowReaderBuilder<R>>,
+    batch_size: usize,
+    projection: ProjectionMask,
+}
+
+impl<R> MultiFileReader<R> {
+    /// Schema of the record batches produced by this reader, after projection.
+    pub fn schema(&self) -> SchemaRef {
+        self.schema_ref.clone()
+    }
+}
+
+impl<R: ChunkReader> Iterator for MultiFileReader<R> {
+    type Item = std::result::Result<RecordBatch, ArrowError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            if let Some(batch) = self.current.as_mut().and_then(Iterator::next) {
+                return Some(batch);
+            }
+            let builder = self.remaining.pop_front()?;
+            // Decode into the schema of the first file, so that e.g. schema level
+            // metadata doesn't vary between batches
+            self.current = Some(
+                builder
+                    .with_projection(self.projection.clone())
+                    .with_batch_size(self.batch_size)
+                    .with_schema(self.schema_ref.clone())
+                    .build(),
+            );
+        }
+    }
+}
+
+impl<R: ChunkReader> RecordBatchReader for MultiFileReader<R> {
+    fn schema(&self) -> SchemaRef {
+        MultiFileReader::schema(self)
+    }
+}
@@ -1,11 +1,16 @@
 pub mod decode;
 pub mod decompress;
+#[cfg(feature = "http")]
+pub mod http;
 pub mod metadata;
 
 use std::fs::File;
 use std::io::{BufReader, Read, Seek, SeekFrom};
+use std::ops::Range;
+use std::sync::mpsc::{sync_channel, Receiver};
+use std::sync::{Arc, Mutex};
 
-use bytes::Bytes;
+use bytes::{Buf, Bytes};
 use futures_util::future::BoxFuture;
 use futures_util::FutureExt;
 use tokio::io::{AsyncRead, AsyncReadExt, AsyncSeek, AsyncSeekExt};
@@ -30,6 +35,13 @@ pub trait ChunkReader {
             .read_exact(&mut bytes)?;
         Ok(bytes.into())
     }
+
+    /// Shared handle used to read the streams of a stripe incrementally while
+    /// decoding, instead of reading them all when loading the stripe. `None` unless
+    /// wrapped in an [`IncrementalReader`].
+    fn byte_range_source(&self) -> Option<Arc<dyn ByteRangeSource>> {
+        None
+    }
 }
 
 impl ChunkReader for File {
@@ -51,6 +63,224 @@ impl ChunkReader for File {
     }
 }
 
+impl ChunkReader for Bytes {
+    type T = bytes::buf::Reader<Bytes>;
+
+    fn len(&self) -> u64 {
+        Bytes::len(self) as u64
+    }
+
+    fn get_read(&self, offset_from_start: u64) -> std::io::Result<Self::T> {
+        Ok(self.slice(offset_from_start as usize..).reader())
+    }
+}
+
+/// Notified of the byte ranges fetched from a file, e.g. for cache warming or cost
+/// analysis.
+pub trait ReadObserver: Send + Sync {
+    /// Called before reading `length` bytes starting at offset `start`.
+    fn on_read(&self, start: u64, length: u64);
+}
+
+/// Wraps a [`ChunkReader`] or [`AsyncChunkReader`] to report every
+/// `get_bytes` call to a [`ReadObserver`].
+#[derive(Clone)]
+pub struct ObservedReader<R> {
+    inner: R,
+    observer: Arc<dyn ReadObserver>,
+}
+
+impl<R> ObservedReader<R> {
+    pub fn new(inner: R, observer: Arc<dyn ReadObserver>) -> Self {
+        Self { inner, observer }
+    }
+
+    pub fn into_inner(self) -> R {
+        self.inner
+    }
+}
+
+impl<R: ChunkReader> ChunkReader for ObservedReader<R> {
+    type T = R::T;
+
+    fn len(&self) -> u64 {
+        self.inner.len()
+    }
+
+    fn get_read(&self, offset_from_start: u64) -> std::io::Result<Self::T> {
+        self.inner.get_read(offset_from_start)
+    }
+
+    fn get_bytes(&self, offset_from_start: u64, length: u64) -> std::io::Result<Bytes> {
+        self.observer.on_read(offset_from_start, length);
+        self.inner.get_bytes(offset_from_start, length)
+    }
+}
+
+/// Reads arbitrary byte ranges of a file, and can be shared between the streams
+/// of a stripe being decoded.
+pub trait ByteRangeSource: Send + Sync {
+    fn read_range(&self, offset_from_start: u64, length: u64) -> std::io::Result<Bytes>;
+}
+
+/// Wraps a [`ChunkReader`] so the streams of each stripe are read in small ranges
+/// as batches are decoded, keeping memory usage independent of the stripe size.
+///
+/// Only the sync reader reads incrementally. Wrap any [`ObservedReader`] in this
+/// reader, not the other way around, for the incremental reads to be observed.
+pub struct IncrementalReader<R> {
+    inner: Arc<R>,
+}
+
+// Not derived, as cloning the shared inner reader doesn't require `R: Clone`
+impl<R> Clone for IncrementalReader<R> {
+    fn clone(&self) -> Self {
+        Self {
+            inner: self.inner.clone(),
+        }
+    }
+}
+
+impl<R> IncrementalReader<R> {
+    pub fn new(inner: R) -> Self {
+        Self {
+            inner: Arc::new(inner),
+        }
+    }
+}
+
+impl<R: ChunkReader + Send + Sync + 'static> ChunkReader for IncrementalReader<R> {
+    type T = R::T;
+
+    fn len(&self) -> u64 {
+        self.inner.len()
+    }
+
+    fn get_read(&self, offset_from_start: u64) -> std::io::Result<Self::T> {
+        self.inner.get_read(offset_from_start)
+    }
+
+    fn get_bytes(&self, offset_from_start: u64, length: u64) -> std::io::Result<Bytes> {
+        self.inner.get_bytes(offset_from_start, length)
+    }
+
+    fn byte_range_source(&self) -> Option<Arc<dyn ByteRangeSource>> {
+        Some(Arc::new(IncrementalReader {
+            inner: self.inner.clone(),
+        }))
+    }
+}
+
+impl<R: ChunkReader + Send + Sync> ByteRangeSource for IncrementalReader<R> {
+    fn read_range(&self, offset_from_start: u64, length: u64) -> std::io::Result<Bytes> {
+        self.inner.get_bytes(offset_from_start, length)
+    }
+}
+
+/// Wraps a [`ChunkReader`] to read upcoming stripes on a background thread while
+/// the current one is decoded, keeping at most `readahead` stripes in memory ahead
+/// of the one being decoded.
+///
+/// Whole stripes are read, including columns which aren't projected. Reads outside
+/// the stripes read ahead (e.g. of the file tail) go straight to the inner reader,
+/// as do all reads when `readahead` is zero.
+pub struct ReadaheadReader<R> {
+    inner: Arc<R>,
+    /// Byte ranges of the stripes, in file order
+    stripes: Vec<Range<u64>>,
+    readahead: usize,
+    state: Mutex<ReadaheadState>,
+}
+
+#[derive(Default)]
+struct ReadaheadState {
+    /// Start offset and bytes of the last stripe received from the background thread
+    current: Option<(u64, Bytes)>,
+    /// `None` until the first read, which decides the first stripe to read ahead
+    receiver: Option<Receiver<std::io::Result<(u64, Bytes)>>>,
+}
+
+impl<R> ReadaheadReader<R> {
+    pub fn new(inner: R, stripes: Vec<Range<u64>>, readahead: usize) -> Self {
+        Self {
+            inner: Arc::new(inner),
+            stripes,
+            readahead,
+            state: Mutex::default(),
+        }
+    }
+}
+
+impl<R: ChunkReader + Send + Sync + 'static> ReadaheadReader<R> {
+    /// Start reading the stripes from the one containing `offset` onwards.
+    fn spawn_readahead(&self, offset: u64) -> Receiver<std::io::Result<(u64, Bytes)>> {
+        let (sender, receiver) = sync_channel(self.readahead);
+        let stripes = self
+            .stripes
+            .iter()
+            .filter(|stripe| stripe.end > offset)
+            .cloned()
+            .collect::<Vec<_>>();
+        let inner = self.inner.clone();
+        std::thread::spawn(move || {
+            for stripe in stripes {
+                let bytes = inner.get_bytes(stripe.start, stripe.end - stripe.start);
+                let stop = bytes.is_err();
+                // Fails once the reader is dropped
+                if sender
+                    .send(bytes.map(|bytes| (stripe.start, bytes)))
+                    .is_err()
+                    || stop
+                {
+                    break;
+                }
+            }
+        });
+        receiver
+    }
+}
+
+impl<R: ChunkReader + Send + Sync + 'static> ChunkReader for ReadaheadReader<R> {
+    type T = R::T;
+
+    fn len(&self) -> u64 {
+        self.inner.len()
+    }
+
+    fn get_read(&self, offset_from_start: u64) -> std::io::Result<Self::T> {
+        self.inner.get_read(offset_from_start)
+    }
+
+    fn get_bytes(&self, offset_from_start: u64, length: u64) -> std::io::Result<Bytes> {
+        if self.readahead == 0 {
+            return self.inner.get_bytes(offset_from_start, length);
+        }
+        let mut state = self.state.lock().unwrap();
+        if state.receiver.is_none() {
+            state.receiver = Some(self.spawn_readahead(offset_from_start));
+        }
+        loop {
+            if let Some((start, bytes)) = &state.current {
+                if offset_from_start < *start {
+                    // Behind the stripes read ahead
+                    break;
+                }
+                let offset = (offset_from_start - start) as usize;
+                if offset + length as usize <= bytes.len() {
+                    return Ok(bytes.slice(offset..offset + length as usize));
+                }
+            }
+            // Move on to the next stripe, as stripes are read in file order
+            match state.receiver.as_ref().unwrap().recv() {
+                Ok(stripe) => state.current = Some(stripe?),
+                // All stripes were read
+                Err(_) => break,
+            }
+        }
+        self.inner.get_bytes(offset_from_start, length)
+    }
+}
+
 #[allow(clippy::len_without_is_empty)]
 pub trait AsyncChunkReader: Send {
     // TODO: this is only used for file tail, so replace with load_metadata?
@@ -96,3 +326,18 @@ impl AsyncChunkReader for Box<dyn AsyncChunkReader> {
         self.as_mut().get_bytes(offset_from_start, length)
     }
 }
+
+impl<R: AsyncChunkReader> AsyncChunkReader for ObservedReader<R> {
+    fn len(&mut self) -> BoxFuture<'_, std::io::Result<u64>> {
+        self.inner.len()
+    }
+
+    fn get_bytes(
+        &mut self,
+        offset_from_start: u64,
+        length: u64,
+    ) -> BoxFuture<'_, std::io::Result<Bytes>> {
+        self.observer.on_read(offset_from_start, length);
+        self.inner.get_bytes(offset_from_start, length)
+    }
+}
@@ -8,19 +8,25 @@ use num::PrimInt;
 use crate::arrow_reader::column::Column;
 use crate::error::{InvalidColumnEncodingSnafu, Result};
 use crate::proto::column_encoding::Kind as ProtoColumnKind;
+use crate::reader::decompress::Decompressor;
 
+use self::boolean_rle::BooleanIter;
+use self::byte_rle::ByteRleIter;
 use self::rle_v1::RleReaderV1;
 use self::rle_v2::RleReaderV2;
-use self::util::{signed_msb_decode, signed_zigzag_decode};
+use self::util::{signed_msb_decode, signed_zigzag_decode, signed_zigzag_encode};
 
 pub mod boolean_rle;
 pub mod byte_rle;
+pub mod decimal;
 pub mod float;
 pub mod rle_v1;
 pub mod rle_v2;
 mod util;
 pub mod variable_length;
 
+/// Version of integer Run Length Encoding used by a column, determined solely
+/// from the column's encoding kind.
 #[derive(Clone, Copy, Debug)]
 pub enum RleVersion {
     V1,
@@ -28,15 +34,22 @@ pub enum RleVersion {
 }
 
 impl RleVersion {
-    pub fn get_unsigned_rle_reader<R: Read + Send + 'static>(
+    pub fn get_rle_reader<N: NInt, R: Read + Send + 'static>(
         &self,
         reader: R,
-    ) -> Box<dyn Iterator<Item = Result<u64>> + Send> {
+    ) -> Box<dyn Iterator<Item = Result<N>> + Send> {
         match self {
-            RleVersion::V1 => Box::new(RleReaderV1::new(reader)),
-            RleVersion::V2 => Box::new(RleReaderV2::new(reader)),
+            RleVersion::V1 => Box::new(RleReaderV1::<N, _>::new(reader)),
+            RleVersion::V2 => Box::new(RleReaderV2::<N, _>::new(reader)),
         }
     }
+
+    pub fn get_unsigned_rle_reader<R: Read + Send + 'static>(
+        &self,
+        reader: R,
+    ) -> Box<dyn Iterator<Item = Result<u64>> + Send> {
+        self.get_rle_reader(reader)
+    }
 }
 
 impl From<ProtoColumnKind> for RleVersion {
@@ -48,13 +61,103 @@ impl From<ProtoColumnKind> for RleVersion {
     }
 }
 
-pub fn get_rle_reader<N: NInt, R: Read + Send + 'static>(
+/// Get the integer RLE reader for a directly encoded column, such as the data stream
+/// of integer columns or the length stream of binary, list and map columns.
+///
+/// Returns an error if the column uses a dictionary encoding, which is only valid
+/// for string columns (see [`get_unsigned_rle_reader`]).
+pub fn get_rle_reader<N: NInt>(
     column: &Column,
-    reader: R,
+    reader: Decompressor,
 ) -> Result<Box<dyn Iterator<Item = Result<N>> + Send>> {
     match column.encoding().kind() {
-        ProtoColumnKind::Direct => Ok(Box::new(RleReaderV1::<N, _>::new(reader))),
-        ProtoColumnKind::DirectV2 => Ok(Box::new(RleReaderV2::<N, _>::new(reader))),
+        kind @ (ProtoColumnKind::Direct | ProtoColumnKind::DirectV2) => {
+            let values_to_skip = reader.values_to_skip();
+            let iter = RleVersion::from(kind).get_rle_reader(reader);
+            Ok(skip_values(iter, values_to_skip))
+        }
+        k => InvalidColumnEncodingSnafu {
+            name: column.name(),
+            encoding: k,
+        }
+        .fail(),
+    }
+}
+
+/// Get the unsigned integer RLE reader for the length and dictionary index streams
+/// of string columns, which may use either a direct or dictionary encoding.
+pub fn get_unsigned_rle_reader(
+    column: &Column,
+    reader: Decompressor,
+) -> Box<dyn Iterator<Item = Result<u64>> + Send> {
+    let values_to_skip = reader.values_to_skip();
+    let iter = RleVersion::from(column.encoding().kind()).get_unsigned_rle_reader(reader);
+    skip_values(iter, values_to_skip)
+}
+
+/// Get the boolean reader for a stream, such as the present stream of a column.
+pub fn get_boolean_reader(reader: Decompressor) -> SkipValues<BooleanIter<Decompressor>> {
+    let values_to_skip = reader.values_to_skip();
+    SkipValues::new(BooleanIter::new(reader), values_to_skip)
+}
+
+/// Get the byte RLE reader for a stream, such as the data stream of byte columns or
+/// the tags of union columns.
+pub fn get_byte_rle_reader(reader: Decompressor) -> SkipValues<ByteRleIter<Decompressor>> {
+    let values_to_skip = reader.values_to_skip();
+    SkipValues::new(ByteRleIter::new(reader), values_to_skip)
+}
+
+/// Boxed integer readers only pay for skipping values when a stream starts in the
+/// middle of a run.
+fn skip_values<N: Send + 'static>(
+    iter: Box<dyn Iterator<Item = Result<N>> + Send>,
+    values_to_skip: u64,
+) -> Box<dyn Iterator<Item = Result<N>> + Send> {
+    if values_to_skip == 0 {
+        iter
+    } else {
+        Box::new(SkipValues::new(iter, values_to_skip))
+    }
+}
+
+/// Skips the first values of a stream when first polled, for streams starting from
+/// a row index position in the middle of a run of values (see
+/// [`Decompressor::values_to_skip`]), so the first value is that of the position.
+pub struct SkipValues<I> {
+    iter: I,
+    values_to_skip: u64,
+}
+
+impl<I> SkipValues<I> {
+    fn new(iter: I, values_to_skip: u64) -> Self {
+        Self {
+            iter,
+            values_to_skip,
+        }
+    }
+}
+
+impl<T, I: Iterator<Item = Result<T>>> Iterator for SkipValues<I> {
+    type Item = Result<T>;
+
+    #[inline]
+    fn next(&mut self) -> Option<Self::Item> {
+        while self.values_to_skip > 0 {
+            self.values_to_skip -= 1;
+            if let Err(err) = self.iter.next()? {
+                return Some(Err(err));
+            }
+        }
+        self.iter.next()
+    }
+}
+
+/// Ensure a column which doesn't use integer RLE (e.g. boolean or float columns)
+/// has the only encoding valid for it, [`ProtoColumnKind::Direct`].
+pub fn ensure_direct_encoding(column: &Column) -> Result<()> {
+    match column.encoding().kind() {
+        ProtoColumnKind::Direct => Ok(()),
         k => InvalidColumnEncodingSnafu {
             name: column.name(),
             encoding: k,
@@ -98,6 +201,14 @@ pub trait NInt:
         self
     }
 
+    /// Bits of the value as encoded in integer RLE streams: zigzag encoded for
+    /// signed integers, as is for unsigned ones.
+    #[inline]
+    fn zigzag_encode(self) -> u64 {
+        // Default for unsigned (signed should override this)
+        self.to_u64().unwrap()
+    }
+
     #[inline]
     fn decode_signed_from_msb(self, _encoded_byte_size: usize) -> Self {
         // Default noop for unsigned (signed should override this)
@@ -137,6 +248,11 @@ impl NInt for i16 {
         signed_zigzag_decode(self)
     }
 
+    #[inline]
+    fn zigzag_encode(self) -> u64 {
+        signed_zigzag_encode(self)
+    }
+
     #[inline]
     fn decode_signed_from_msb(self, encoded_byte_size: usize) -> Self {
         signed_msb_decode(self, encoded_byte_size)
@@ -167,6 +283,11 @@ impl NInt for i32 {
         signed_zigzag_decode(self)
     }
 
+    #[inline]
+    fn zigzag_encode(self) -> u64 {
+        signed_zigzag_encode(self)
+    }
+
     #[inline]
     fn decode_signed_from_msb(self, encoded_byte_size: usize) -> Self {
         signed_msb_decode(self, encoded_byte_size)
@@ -197,6 +318,11 @@ impl NInt for i64 {
         signed_zigzag_decode(self)
     }
 
+    #[inline]
+    fn zigzag_encode(self) -> u64 {
+        signed_zigzag_encode(self)
+    }
+
     #[inline]
     fn decode_signed_from_msb(self, encoded_byte_size: usize) -> Self {
         signed_msb_decode(self, encoded_byte_size)
@@ -222,3 +348,76 @@ impl NInt for u64 {
         Self::from_be_bytes(b)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use bytes::Bytes;
+
+    use crate::error::OrcError;
+    use crate::proto::{ColumnEncoding, StripeFooter};
+    use crate::schema::DataType;
+
+    use super::*;
+
+    fn encoding(kind: ProtoColumnKind) -> ColumnEncoding {
+        ColumnEncoding {
+            kind: Some(kind as i32),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn mixed_direct_and_direct_v2_columns() {
+        let footer = Arc::new(StripeFooter {
+            columns: vec![
+                encoding(ProtoColumnKind::Direct),
+                encoding(ProtoColumnKind::Direct),
+                encoding(ProtoColumnKind::DirectV2),
+                encoding(ProtoColumnKind::DictionaryV2),
+            ],
+            ..Default::default()
+        });
+        let v1_column = Column::new("v1", &DataType::Int { column_index: 1 }, &footer, 5);
+        let v2_column = Column::new("v2", &DataType::Long { column_index: 2 }, &footer, 5);
+        let dict_column = Column::new("dict", &DataType::Int { column_index: 3 }, &footer, 5);
+
+        let stream =
+            |data: &'static [u8]| Decompressor::new(Bytes::from_static(data), None, vec![]);
+
+        // RLEv1 run of 5 values, starting at 1 with delta 1
+        let v1_data = stream(&[0x02, 0x01, 0x02]);
+        let values = get_rle_reader::<i32>(&v1_column, v1_data)
+            .unwrap()
+            .collect::<Result<Vec<_>>>()
+            .unwrap();
+        assert_eq!(values, vec![1, 2, 3, 4, 5]);
+
+        // Same, starting from a row index position in the middle of the run
+        let v1_data = stream(&[0x02, 0x01, 0x02]).with_start(0, 2);
+        let values = get_rle_reader::<i32>(&v1_column, v1_data)
+            .unwrap()
+            .collect::<Result<Vec<_>>>()
+            .unwrap();
+        assert_eq!(values, vec![3, 4, 5]);
+
+        // RLEv2 short repeat of 5 repeated 5 times
+        let v2_data = stream(&[0x02, 0x0a]);
+        let values = get_rle_reader::<i64>(&v2_column, v2_data)
+            .unwrap()
+            .collect::<Result<Vec<_>>>()
+            .unwrap();
+        assert_eq!(values, vec![5, 5, 5, 5, 5]);
+
+        // Integer columns cannot be dictionary encoded
+        let err = get_rle_reader::<i32>(&dict_column, stream(&[])).err();
+        assert!(matches!(
+            err,
+            Some(OrcError::InvalidColumnEncoding {
+                encoding: ProtoColumnKind::DictionaryV2,
+                ..
+            })
+        ));
+    }
+}
@@ -97,4 +97,18 @@ mod test {
             vec![true, false, false, false, false, false, false, false]
         )
     }
+
+    #[test]
+    fn most_significant_bit_first() {
+        // One literal byte; the first value is in the most significant bit
+        let data = [0xff, 0b10100000];
+
+        let data = &mut data.as_ref();
+
+        let iter = BooleanIter::new(data).collect::<Result<Vec<_>>>().unwrap();
+        assert_eq!(
+            iter,
+            vec![true, false, true, false, false, false, false, false]
+        )
+    }
 }
@@ -0,0 +1,130 @@
+use std::io::Read;
+
+use num::{BigInt, Signed, Zero};
+use snafu::ensure;
+
+use crate::error::{OutOfSpecSnafu, Result};
+
+use super::util::{read_u8, try_read_u8};
+
+/// Largest absolute scale of decimal values, as ORC decimals have at most 38
+/// digits. Per-row scales beyond it are corrupt.
+pub(crate) const MAX_DECIMAL_SCALE: i32 = 38;
+
+/// Iterator over the unscaled values of a decimal column, encoded as zigzag
+/// base 128 varints of unbounded length.
+pub struct UnboundedVarintIter<R: Read> {
+    reader: R,
+}
+
+impl<R: Read> UnboundedVarintIter<R> {
+    pub fn new(reader: R) -> Self {
+        Self { reader }
+    }
+
+    fn read_varint(&mut self, first_byte: u8) -> Result<BigInt> {
+        let mut num = BigInt::zero();
+        let mut offset = 0;
+        let mut byte = first_byte;
+        loop {
+            num |= BigInt::from(byte & 0x7F) << offset;
+            offset += 7;
+            if byte & 0x80 == 0 {
+                break;
+            }
+            byte = read_u8(&mut self.reader)?;
+        }
+        // Zigzag decode
+        let is_negative = num.bit(0);
+        num >>= 1;
+        Ok(if is_negative { -num - 1 } else { num })
+    }
+}
+
+impl<R: Read> Iterator for UnboundedVarintIter<R> {
+    type Item = Result<BigInt>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        match try_read_u8(&mut self.reader) {
+            Ok(Some(byte)) => Some(self.read_varint(byte)),
+            Ok(None) => None,
+            Err(err) => Some(Err(err)),
+        }
+    }
+}
+
+/// Format an unscaled decimal value as decimal text, with trailing zeros of the
+/// fractional part removed (e.g. `-1.5` for `-1500` with a scale of `3`). Fails
+/// if the scale is out of range, as it sets how many zeros are written.
+pub fn format_decimal(value: &BigInt, scale: i32) -> Result<String> {
+    ensure!(
+        (-MAX_DECIMAL_SCALE..=MAX_DECIMAL_SCALE).contains(&scale),
+        OutOfSpecSnafu {
+            msg: format!("decimal scale {scale} is out of range"),
+        }
+    );
+    let mut digits = value.abs().to_string();
+    let sign = if value.is_negative() { "-" } else { "" };
+    if scale <= 0 {
+        if !value.is_zero() {
+            digits.extend(std::iter::repeat('0').take(scale.unsigned_abs() as usize));
+        }
+        return Ok(format!("{sign}{digits}"));
+    }
+
+    let scale = scale as usize;
+    if digits.len() <= scale {
+        digits.insert_str(0, &"0".repeat(scale + 1 - digits.len()));
+    }
+    let (integer, fraction) = digits.split_at(digits.len() - scale);
+    let fraction = fraction.trim_end_matches('0');
+    if fraction.is_empty() {
+        Ok(format!("{sign}{integer}"))
+    } else {
+        Ok(format!("{sign}{integer}.{fraction}"))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn unbounded_varints() {
+        // 0, -1, 1, -64 and 2^70 (zigzag encoded as 2^71)
+        let mut data = vec![0x00, 0x01, 0x02, 0x7F];
+        data.extend([0x80; 10]);
+        data.push(0x02);
+
+        let values = UnboundedVarintIter::new(data.as_slice())
+            .collect::<Result<Vec<_>>>()
+            .unwrap();
+        let expected = [0, -1, 1, -64]
+            .into_iter()
+            .map(BigInt::from)
+            .chain([BigInt::from(1) << 70])
+            .collect::<Vec<_>>();
+        assert_eq!(expected, values);
+    }
+
+    #[test]
+    fn format() {
+        let cases = [
+            (12345, 2, "123.45"),
+            (-12345, 2, "-123.45"),
+            (-5, 3, "-0.005"),
+            (1500, 3, "1.5"),
+            (100, 2, "1"),
+            (0, 2, "0"),
+            (12, -2, "1200"),
+            (0, -2, "0"),
+        ];
+        for (value, scale, expected) in cases {
+            let actual = format_decimal(&BigInt::from(value), scale).unwrap();
+            assert_eq!(expected, actual);
+        }
+        for scale in [39, -39, i32::MIN, i32::MAX] {
+            assert!(format_decimal(&BigInt::from(1), scale).is_err());
+        }
+    }
+}
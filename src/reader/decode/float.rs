@@ -32,7 +32,8 @@ impl Float for f64 {
     }
 }
 
-/// An iterator
+/// An iterator over the values of a float or double stream. Values are decoded
+/// bit for bit, keeping the sign and payload of NaNs and the sign of zeros.
 pub struct FloatIter<T: Float, R: std::io::Read> {
     reader: R,
     remaining: usize,
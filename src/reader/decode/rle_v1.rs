@@ -15,20 +15,22 @@
 // specific language governing permissions and limitations
 // under the License.
 
-//! Handling decoding of Integer Run Length Encoded V1 data in ORC files
+//! Handling decoding and encoding of Integer Run Length Encoded V1 data in ORC files
 
-use std::io::Read;
+use std::io::{Read, Write};
 
-use snafu::OptionExt;
+use snafu::{OptionExt, ResultExt};
 
-use crate::error::{OutOfSpecSnafu, Result};
+use crate::error::{IoSnafu, OutOfSpecSnafu, Result};
 
 use super::{
-    util::{read_u8, read_varint_zigzagged, try_read_u8},
+    util::{read_u8, read_varint_zigzagged, try_read_u8, write_varint},
     NInt,
 };
 
 const MAX_RUN_LENGTH: usize = 130;
+const MIN_RUN_LENGTH: usize = 3;
+const MAX_LITERALS: usize = 128;
 
 /// Decodes a stream of Integer Run Length Encoded version 1 bytes.
 pub struct RleReaderV1<N: NInt, R: Read> {
@@ -117,10 +119,100 @@ impl<N: NInt, R: Read> Iterator for RleReaderV1<N, R> {
     }
 }
 
+/// Encodes integers as Integer Run Length Encoded version 1 bytes, readable with
+/// [`RleReaderV1`]. Values are buffered until a full run is known, so
+/// [`finish`](Self::finish) must be called to write the last ones.
+pub struct RleWriterV1<N: NInt, W: Write> {
+    writer: W,
+    buffer: Vec<N>,
+}
+
+impl<N: NInt, W: Write> RleWriterV1<N, W> {
+    pub fn new(writer: W) -> Self {
+        Self {
+            writer,
+            buffer: Vec::with_capacity(MAX_RUN_LENGTH),
+        }
+    }
+
+    pub fn write(&mut self, value: N) -> Result<()> {
+        self.buffer.push(value);
+        if self.buffer.len() == MAX_RUN_LENGTH {
+            self.flush()?;
+        }
+        Ok(())
+    }
+
+    /// Write the buffered values, returning the inner writer.
+    pub fn finish(mut self) -> Result<W> {
+        self.flush()?;
+        Ok(self.writer)
+    }
+
+    fn flush(&mut self) -> Result<()> {
+        let values = std::mem::take(&mut self.buffer);
+        let mut literals_start = 0;
+        let mut index = 0;
+        while index < values.len() {
+            let run_length = fixed_delta_run_length(&values[index..]);
+            if run_length < MIN_RUN_LENGTH {
+                index += 1;
+                if index - literals_start == MAX_LITERALS {
+                    self.write_literals(&values[literals_start..index])?;
+                    literals_start = index;
+                }
+                continue;
+            }
+            self.write_literals(&values[literals_start..index])?;
+            let delta = values[index + 1].to_i128().unwrap() - values[index].to_i128().unwrap();
+            let header = [(run_length - MIN_RUN_LENGTH) as u8, delta as i8 as u8];
+            self.writer.write_all(&header).context(IoSnafu)?;
+            write_varint(&mut self.writer, values[index].zigzag_encode())?;
+            index += run_length;
+            literals_start = index;
+        }
+        self.write_literals(&values[literals_start..])?;
+        self.buffer = values;
+        self.buffer.clear();
+        Ok(())
+    }
+
+    fn write_literals(&mut self, literals: &[N]) -> Result<()> {
+        if literals.is_empty() {
+            return Ok(());
+        }
+        let header = (literals.len() as i8).wrapping_neg() as u8;
+        self.writer.write_all(&[header]).context(IoSnafu)?;
+        for literal in literals {
+            write_varint(&mut self.writer, literal.zigzag_encode())?;
+        }
+        Ok(())
+    }
+}
+
+/// Length of the run of values with the same delta (fitting a byte) at the start
+/// of `values`.
+fn fixed_delta_run_length<N: NInt>(values: &[N]) -> usize {
+    if values.len() < 2 {
+        return values.len();
+    }
+    let delta = values[1].to_i128().unwrap() - values[0].to_i128().unwrap();
+    if i8::try_from(delta).is_err() {
+        return 1;
+    }
+    let length = values
+        .windows(2)
+        .take_while(|pair| pair[1].to_i128().unwrap() - pair[0].to_i128().unwrap() == delta)
+        .count();
+    (length + 1).min(MAX_RUN_LENGTH)
+}
+
 #[cfg(test)]
 mod tests {
     use std::io::Cursor;
 
+    use crate::reader::decode::util::random_test_values;
+
     use super::*;
 
     #[test]
@@ -150,4 +242,38 @@ mod tests {
 
         Ok(())
     }
+
+    fn round_trip<N: NInt>() {
+        for seed in 0..20 {
+            let values = random_test_values::<N>(seed, 2000);
+            let mut writer = RleWriterV1::new(vec![]);
+            for &value in &values {
+                writer.write(value).unwrap();
+            }
+            let data = writer.finish().unwrap();
+            let decoded = RleReaderV1::<N, _>::new(Cursor::new(data))
+                .collect::<Result<Vec<_>>>()
+                .unwrap();
+            assert_eq!(values, decoded, "seed {seed}");
+        }
+    }
+
+    #[test]
+    fn random_round_trip() {
+        round_trip::<i16>();
+        round_trip::<i32>();
+        round_trip::<i64>();
+        round_trip::<u64>();
+    }
+
+    #[test]
+    fn write_run_and_literals() {
+        let mut writer = RleWriterV1::<u64, _>::new(vec![]);
+        for value in [7, 7, 7, 7, 2, 3, 6] {
+            writer.write(value).unwrap();
+        }
+        let data = writer.finish().unwrap();
+        // Run of 4 with delta 0, then 3 literals
+        assert_eq!(vec![0x01, 0x00, 0x07, 0xfd, 0x02, 0x03, 0x06], data);
+    }
 }
@@ -1,14 +1,21 @@
+//! Handling decoding and encoding of Integer Run Length Encoded V2 data in ORC files
+
 pub mod delta;
 pub mod direct;
 pub mod patched_base;
 pub mod short_repeat;
-use std::io::Read;
+use std::io::{Read, Write};
+
+use snafu::ResultExt;
 
-use crate::error::Result;
+use crate::error::{IoSnafu, Result};
 
-use super::{util::try_read_u8, NInt};
+use super::util::{rle_v2_encode_bit_width, try_read_u8, write_varint};
+use super::NInt;
 
 const MAX_RUN_LENGTH: usize = 512;
+const MIN_REPEAT_LENGTH: usize = 3;
+const MAX_SHORT_REPEAT_LENGTH: usize = 10;
 
 pub struct RleReaderV2<N: NInt, R: Read> {
     reader: R,
@@ -67,6 +74,142 @@ impl<N: NInt, R: Read> Iterator for RleReaderV2<N, R> {
     }
 }
 
+/// Encodes integers as Integer Run Length Encoded version 2 bytes, readable with
+/// [`RleReaderV2`]. Values are buffered until a full run is known, so
+/// [`finish`](Self::finish) must be called to write the last ones.
+///
+/// Repeated values are written as short repeat or delta runs, runs with a fixed
+/// delta as delta runs and other values as direct runs. The patched base
+/// sub-encoding, which only makes outliers cheaper, isn't used.
+pub struct RleWriterV2<N: NInt, W: Write> {
+    writer: W,
+    buffer: Vec<N>,
+}
+
+impl<N: NInt, W: Write> RleWriterV2<N, W> {
+    pub fn new(writer: W) -> Self {
+        Self {
+            writer,
+            buffer: Vec::with_capacity(MAX_RUN_LENGTH),
+        }
+    }
+
+    pub fn write(&mut self, value: N) -> Result<()> {
+        self.buffer.push(value);
+        if self.buffer.len() == MAX_RUN_LENGTH {
+            self.flush()?;
+        }
+        Ok(())
+    }
+
+    /// Write the buffered values, returning the inner writer.
+    pub fn finish(mut self) -> Result<W> {
+        self.flush()?;
+        Ok(self.writer)
+    }
+
+    fn flush(&mut self) -> Result<()> {
+        let values = std::mem::take(&mut self.buffer);
+        let mut literals_start = 0;
+        let mut index = 0;
+        while index < values.len() {
+            let (run_length, delta) = fixed_delta_run::<N>(&values[index..]);
+            if run_length < MIN_REPEAT_LENGTH {
+                index += 1;
+                continue;
+            }
+            self.write_direct(&values[literals_start..index])?;
+            if delta == 0 && run_length <= MAX_SHORT_REPEAT_LENGTH {
+                self.write_short_repeat(values[index], run_length)?;
+            } else {
+                self.write_fixed_delta(values[index], delta, run_length)?;
+            }
+            index += run_length;
+            literals_start = index;
+        }
+        self.write_direct(&values[literals_start..])?;
+        self.buffer = values;
+        self.buffer.clear();
+        Ok(())
+    }
+
+    fn write_short_repeat(&mut self, value: N, run_length: usize) -> Result<()> {
+        let value = value.zigzag_encode();
+        let byte_width = ((64 - value.leading_zeros() as usize + 7) / 8).max(1);
+        let header = ((byte_width - 1) << 3 | (run_length - MIN_REPEAT_LENGTH)) as u8;
+        self.writer.write_all(&[header]).context(IoSnafu)?;
+        self.writer
+            .write_all(&value.to_be_bytes()[8 - byte_width..])
+            .context(IoSnafu)
+    }
+
+    fn write_fixed_delta(&mut self, base: N, delta: i128, run_length: usize) -> Result<()> {
+        // Encoded delta bit width of 0 for a fixed delta
+        self.write_header(0b1100_0000, run_length)?;
+        write_varint(&mut self.writer, base.zigzag_encode())?;
+        // Checked to fit an i64 when finding the run
+        let delta = delta as i64;
+        write_varint(&mut self.writer, ((delta << 1) ^ (delta >> 63)) as u64)
+    }
+
+    fn write_direct(&mut self, values: &[N]) -> Result<()> {
+        for values in values.chunks(MAX_RUN_LENGTH) {
+            let max = values.iter().map(|value| value.zigzag_encode()).max();
+            let bit_width = 64 - max.unwrap_or(0).leading_zeros() as usize;
+            let (encoded_bit_width, bit_width) = rle_v2_encode_bit_width(bit_width);
+            self.write_header(0b0100_0000 | encoded_bit_width << 1, values.len())?;
+
+            // Big endian bit packing
+            let mut bytes = Vec::with_capacity((values.len() * bit_width + 7) / 8);
+            let mut current = 0u8;
+            let mut used_bits = 0;
+            for value in values {
+                let value = value.zigzag_encode();
+                for bit in (0..bit_width).rev() {
+                    current = current << 1 | (value >> bit) as u8 & 1;
+                    used_bits += 1;
+                    if used_bits == 8 {
+                        bytes.push(current);
+                        current = 0;
+                        used_bits = 0;
+                    }
+                }
+            }
+            if used_bits > 0 {
+                bytes.push(current << (8 - used_bits));
+            }
+            self.writer.write_all(&bytes).context(IoSnafu)?;
+        }
+        Ok(())
+    }
+
+    /// Two header bytes of the direct, patched base and delta sub-encodings, with
+    /// the 9 bits of the run length after the given first bits.
+    fn write_header(&mut self, first_bits: u8, run_length: usize) -> Result<()> {
+        let length = run_length - 1;
+        let header = [first_bits | (length >> 8) as u8, length as u8];
+        self.writer.write_all(&header).context(IoSnafu)
+    }
+}
+
+/// Length and delta of the run of values with the same delta at the start of
+/// `values`. Deltas must fit `N` (and an `i64`), as they're decoded into it.
+fn fixed_delta_run<N: NInt>(values: &[N]) -> (usize, i128) {
+    if values.len() < 2 {
+        return (values.len(), 0);
+    }
+    let max_delta = N::max_value().to_i128().unwrap().min(i64::MAX as i128);
+    let delta = values[1].to_i128().unwrap() - values[0].to_i128().unwrap();
+    if delta.abs() > max_delta {
+        return (1, 0);
+    }
+    let length = values
+        .windows(2)
+        .take_while(|pair| pair[1].to_i128().unwrap() - pair[0].to_i128().unwrap() == delta)
+        .count();
+    ((length + 1).min(MAX_RUN_LENGTH), delta)
+}
+
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
 enum EncodingType {
     ShortRepeat,
@@ -94,6 +237,8 @@ mod tests {
 
     use std::io::Cursor;
 
+    use crate::reader::decode::util::random_test_values;
+
     use super::*;
 
     #[test]
@@ -266,4 +411,42 @@ mod tests {
 
         assert_eq!(a, expected);
     }
+
+    fn round_trip<N: NInt>() {
+        for seed in 0..20 {
+            let values = random_test_values::<N>(seed, 2000);
+            let mut writer = RleWriterV2::new(vec![]);
+            for &value in &values {
+                writer.write(value).unwrap();
+            }
+            let data = writer.finish().unwrap();
+            let decoded = RleReaderV2::<N, _>::new(Cursor::new(data))
+                .collect::<Result<Vec<_>>>()
+                .unwrap();
+            assert_eq!(values, decoded, "seed {seed}");
+        }
+    }
+
+    #[test]
+    fn random_round_trip() {
+        round_trip::<i16>();
+        round_trip::<i32>();
+        round_trip::<i64>();
+        round_trip::<u64>();
+    }
+
+    #[test]
+    fn write_short_repeat_and_direct() {
+        let mut writer = RleWriterV2::<u64, _>::new(vec![]);
+        for value in [
+            10000, 10000, 10000, 10000, 10000, 23713, 43806, 57005, 48879,
+        ] {
+            writer.write(value).unwrap();
+        }
+        let data = writer.finish().unwrap();
+        let expected = [
+            0x0a, 0x27, 0x10, 0x5e, 0x03, 0x5c, 0xa1, 0xab, 0x1e, 0xde, 0xad, 0xbe, 0xef,
+        ];
+        assert_eq!(expected.as_slice(), data);
+    }
 }
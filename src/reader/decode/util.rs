@@ -1,4 +1,4 @@
-use std::io::Read;
+use std::io::{Read, Write};
 
 use num::Signed;
 use snafu::{OptionExt, ResultExt};
@@ -265,6 +265,31 @@ fn read_varint_n<N: NInt, R: Read>(r: &mut R) -> Result<N> {
     Ok(num)
 }
 
+/// Encode as Base 128 Unsigned Varint, see [`read_varint_zigzagged`].
+pub fn write_varint(writer: &mut impl Write, mut value: u64) -> Result<()> {
+    let mut buffer = Vec::with_capacity(10);
+    while value >= 0x80 {
+        buffer.push(value as u8 | 0x80);
+        value >>= 7;
+    }
+    buffer.push(value as u8);
+    writer.write_all(&buffer).context(IoSnafu)
+}
+
+/// Inverse of [`rle_v2_decode_bit_width`]: the encoded bit width (and the actual
+/// bit width it stands for) to pack values of `bit_width` bits with.
+pub fn rle_v2_encode_bit_width(bit_width: usize) -> (u8, usize) {
+    match bit_width {
+        0..=1 => (0, 1),
+        2..=24 => (bit_width as u8 - 1, bit_width),
+        25..=32 => (27, 32),
+        33..=40 => (28, 40),
+        41..=48 => (29, 48),
+        49..=56 => (30, 56),
+        _ => (31, 64),
+    }
+}
+
 pub fn read_varint_zigzagged<N: NInt, R: Read>(r: &mut R) -> Result<N> {
     Ok(read_varint_n::<N, _>(r)?.zigzag_decode())
 }
@@ -328,6 +353,13 @@ pub fn signed_zigzag_decode<N: NInt + Signed>(encoded: N) -> N {
     without_sign_bit ^ -sign_bit
 }
 
+/// Inverse of [`signed_zigzag_decode`], into the 64 bits written to streams.
+#[inline]
+pub fn signed_zigzag_encode<N: NInt + Signed>(value: N) -> u64 {
+    let value = value.to_i64().unwrap();
+    ((value << 1) ^ (value >> 63)) as u64
+}
+
 /// MSB indicates if value is negated (1 if negative, else positive). Note we
 /// take the MSB of the encoded number which might be smaller than N, hence
 /// we need the encoded number byte size to find this MSB.
@@ -344,6 +376,53 @@ pub fn signed_msb_decode<N: NInt + Signed>(encoded: N, encoded_byte_size: usize)
     }
 }
 
+/// Values mixing repeats, fixed delta runs and random values of random widths,
+/// including the extremes of `N`, for round trip tests of the encoders.
+#[cfg(test)]
+pub fn random_test_values<N: NInt>(seed: u64, count: usize) -> Vec<N> {
+    // xorshift64
+    let mut state = seed | 1;
+    let mut next = move || {
+        state ^= state << 13;
+        state ^= state >> 7;
+        state ^= state << 17;
+        state
+    };
+    let random_value = |next: &mut dyn FnMut() -> u64| {
+        let value = next() >> (next() % 64);
+        match next() % 8 {
+            0 => N::min_value(),
+            1 => N::max_value(),
+            _ => N::from_u64(value),
+        }
+    };
+
+    let mut values = Vec::with_capacity(count);
+    while values.len() < count {
+        let length = (next() % 600) as usize + 1;
+        match next() % 3 {
+            0 => {
+                let value = random_value(&mut next);
+                values.extend(std::iter::repeat(value).take(length % 20 + 1));
+            }
+            1 => {
+                let delta = N::from_u64(next() % 300);
+                let mut value = random_value(&mut next);
+                for _ in 0..length {
+                    values.push(value);
+                    value = match value.checked_add(&delta) {
+                        Some(value) => value,
+                        None => break,
+                    };
+                }
+            }
+            _ => values.extend((0..length).map(|_| random_value(&mut next))),
+        }
+    }
+    values.truncate(count);
+    values
+}
+
 #[cfg(test)]
 mod tests {
     use std::io::Cursor;
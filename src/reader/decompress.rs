@@ -1,24 +1,47 @@
 // Modified from https://github.com/DataEngineeringLabs/orc-format/blob/416490db0214fc51d53289253c0ee91f7fc9bc17/src/read/decompress/mod.rs
 //! Contains [`Decompressor`]
 
+use std::collections::HashMap;
 use std::io::Read;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
 
 use bytes::{Bytes, BytesMut};
 use fallible_streaming_iterator::FallibleStreamingIterator;
-use snafu::ResultExt;
+use snafu::{ensure, OptionExt, ResultExt};
 
 use crate::error::{self, OrcError};
 use crate::proto::{self, CompressionKind};
+use crate::reader::ByteRangeSource;
 
 // Spec states default is 256K
 const DEFAULT_COMPRESSION_BLOCK_SIZE: u64 = 256 * 1024;
 
-#[derive(Clone, Copy, Debug)]
+/// Minimum number of bytes fetched at once when reading a stream incrementally.
+const INCREMENTAL_READ_SIZE: u64 = 64 * 1024;
+
+/// First bytes of gzip data.
+const GZIP_MAGIC: [u8; 2] = [0x1f, 0x8b];
+
+/// Default maximum number of bytes decompressed from a single stream.
+pub const DEFAULT_MAX_DECOMPRESSED_STREAM_SIZE: u64 = 4 * 1024 * 1024 * 1024;
+
+/// Default maximum number of bytes decompressed from all the streams of a stripe.
+pub const DEFAULT_MAX_DECOMPRESSED_STRIPE_SIZE: u64 = 16 * 1024 * 1024 * 1024;
+
+#[derive(Clone, Debug)]
 pub struct Compression {
-    compression_type: CompressionType,
+    codec: Arc<dyn CompressionCodec>,
     /// No compression chunk will decompress to larger than this size.
     /// Use to size the scratch buffer appropriately.
     max_decompressed_block_size: usize,
+    /// Guards against small compressed chunks expanding to far more data than the
+    /// file holds.
+    max_stream_size: u64,
+    max_stripe_size: u64,
+    /// Bytes decompressed so far from the streams of the current stripe, shared by
+    /// the clones made for each of its streams.
+    stripe_decompressed: Arc<AtomicU64>,
 }
 
 impl Compression {
@@ -26,32 +49,74 @@ impl Compression {
         kind: proto::CompressionKind,
         compression_block_size: Option<u64>,
     ) -> Option<Self> {
-        let max_decompressed_block_size =
-            compression_block_size.unwrap_or(DEFAULT_COMPRESSION_BLOCK_SIZE) as usize;
-        match kind {
-            CompressionKind::None => None,
-            CompressionKind::Zlib => Some(Self {
-                compression_type: CompressionType::Zlib,
-                max_decompressed_block_size,
-            }),
-            CompressionKind::Snappy => Some(Self {
-                compression_type: CompressionType::Snappy,
-                max_decompressed_block_size,
-            }),
-            CompressionKind::Lzo => Some(Self {
-                compression_type: CompressionType::Lzo,
-                max_decompressed_block_size,
-            }),
-            CompressionKind::Lz4 => Some(Self {
-                compression_type: CompressionType::Lz4,
-                max_decompressed_block_size,
-            }),
-            CompressionKind::Zstd => Some(Self {
-                compression_type: CompressionType::Zstd,
-                max_decompressed_block_size,
-            }),
+        let compression_type = match kind {
+            CompressionKind::None => return None,
+            CompressionKind::Zlib => CompressionType::Zlib,
+            CompressionKind::Snappy => CompressionType::Snappy,
+            CompressionKind::Lzo => CompressionType::Lzo,
+            CompressionKind::Lz4 => CompressionType::Lz4,
+            CompressionKind::Zstd => CompressionType::Zstd,
+        };
+        Some(Self::new(
+            Arc::new(compression_type),
+            compression_block_size,
+        ))
+    }
+
+    fn new(codec: Arc<dyn CompressionCodec>, compression_block_size: Option<u64>) -> Self {
+        Self {
+            codec,
+            max_decompressed_block_size: compression_block_size
+                .unwrap_or(DEFAULT_COMPRESSION_BLOCK_SIZE)
+                as usize,
+            max_stream_size: DEFAULT_MAX_DECOMPRESSED_STREAM_SIZE,
+            max_stripe_size: DEFAULT_MAX_DECOMPRESSED_STRIPE_SIZE,
+            stripe_decompressed: Arc::new(AtomicU64::new(0)),
+        }
+    }
+
+    /// Fail reading a stream once more than `max_stream_size` bytes have been
+    /// decompressed from it.
+    pub fn with_max_stream_size(mut self, max_stream_size: u64) -> Self {
+        self.max_stream_size = max_stream_size;
+        self
+    }
+
+    /// Fail reading a stripe once more than `max_stripe_size` bytes have been
+    /// decompressed from all its streams.
+    pub fn with_max_stripe_size(mut self, max_stripe_size: u64) -> Self {
+        self.max_stripe_size = max_stripe_size;
+        self
+    }
+
+    /// Copy to decompress the streams of a new stripe with, counting their
+    /// decompressed bytes towards the stripe limit from zero.
+    pub(crate) fn for_stripe(&self) -> Self {
+        Self {
+            stripe_decompressed: Arc::new(AtomicU64::new(0)),
+            ..self.clone()
         }
     }
+
+    /// Maximum size of a chunk, compressed or not, as set by the writer.
+    pub fn block_size(&self) -> usize {
+        self.max_decompressed_block_size
+    }
+}
+
+/// Decompresses the chunks of a stream compressed with a given codec.
+///
+/// Implemented by the built-in [`CompressionType`]s. Other codecs can be
+/// registered in a [`CodecRegistry`] to read files using them.
+pub trait CompressionCodec: std::fmt::Debug + Send + Sync {
+    /// Decompress a single chunk into `scratch`, replacing its contents.
+    /// `max_decompressed_size` is the compression block size of the file.
+    fn decompress(
+        &self,
+        compressed: &[u8],
+        max_decompressed_size: usize,
+        scratch: &mut Vec<u8>,
+    ) -> Result<(), OrcError>;
 }
 
 #[derive(Clone, Copy, Debug)]
@@ -63,6 +128,117 @@ pub enum CompressionType {
     Zstd,
 }
 
+impl CompressionCodec for CompressionType {
+    fn decompress(
+        &self,
+        compressed_bytes: &[u8],
+        max_decompressed_size: usize,
+        scratch: &mut Vec<u8>,
+    ) -> Result<(), OrcError> {
+        match self {
+            // Some writers wrap chunks in gzip rather than writing raw deflate. Raw
+            // deflate can't start with the gzip magic, whose first byte would be a
+            // block of the reserved type.
+            CompressionType::Zlib if compressed_bytes.starts_with(&GZIP_MAGIC) => {
+                let mut gz = flate2::read::GzDecoder::new(compressed_bytes);
+                scratch.clear();
+                gz.read_to_end(scratch).context(error::IoSnafu)?;
+            }
+            CompressionType::Zlib => {
+                let mut gz = flate2::read::DeflateDecoder::new(compressed_bytes);
+                scratch.clear();
+                gz.read_to_end(scratch).context(error::IoSnafu)?;
+            }
+            CompressionType::Zstd => {
+                let mut reader =
+                    zstd::Decoder::new(compressed_bytes).context(error::BuildZstdDecoderSnafu)?;
+                scratch.clear();
+                reader.read_to_end(scratch).context(error::IoSnafu)?;
+            }
+            CompressionType::Snappy => {
+                let len = snap::raw::decompress_len(compressed_bytes)
+                    .context(error::BuildSnappyDecoderSnafu)?;
+                scratch.resize(len, 0);
+                let mut decoder = snap::raw::Decoder::new();
+                decoder
+                    .decompress(compressed_bytes, scratch)
+                    .context(error::BuildSnappyDecoderSnafu)?;
+            }
+            CompressionType::Lzo => {
+                let decompressed = lzokay_native::decompress_all(compressed_bytes, None)
+                    .context(error::BuildLzoDecoderSnafu)?;
+                // TODO: better way to utilize scratch here
+                scratch.clear();
+                scratch.extend(decompressed);
+            }
+            CompressionType::Lz4 => {
+                let decompressed =
+                    lz4_flex::block::decompress(compressed_bytes, max_decompressed_size)
+                        .context(error::BuildLz4DecoderSnafu)?;
+                // TODO: better way to utilize scratch here
+                scratch.clear();
+                scratch.extend(decompressed);
+            }
+        };
+        Ok(())
+    }
+}
+
+/// Codecs to decompress files with, keyed by the compression kind value of the
+/// file postscript.
+///
+/// The default registry holds the codecs of the ORC specification. Custom codecs
+/// can be added for kinds outside of it, or replace the built-in ones.
+#[derive(Clone, Debug)]
+pub struct CodecRegistry {
+    codecs: HashMap<i32, Arc<dyn CompressionCodec>>,
+}
+
+impl Default for CodecRegistry {
+    fn default() -> Self {
+        let codecs = [
+            (CompressionKind::Zlib, CompressionType::Zlib),
+            (CompressionKind::Snappy, CompressionType::Snappy),
+            (CompressionKind::Lzo, CompressionType::Lzo),
+            (CompressionKind::Lz4, CompressionType::Lz4),
+            (CompressionKind::Zstd, CompressionType::Zstd),
+        ]
+        .into_iter()
+        .map(|(kind, codec)| (kind as i32, Arc::new(codec) as Arc<dyn CompressionCodec>))
+        .collect();
+        Self { codecs }
+    }
+}
+
+impl CodecRegistry {
+    pub fn with_codec(mut self, kind: i32, codec: Arc<dyn CompressionCodec>) -> Self {
+        self.codecs.insert(kind, codec);
+        self
+    }
+
+    /// Compression of a file with the given postscript compression kind, `None`
+    /// if uncompressed.
+    pub fn compression(
+        &self,
+        kind: Option<i32>,
+        compression_block_size: Option<u64>,
+    ) -> Result<Option<Compression>, OrcError> {
+        match kind.unwrap_or_default() {
+            kind if kind == CompressionKind::None as i32 => Ok(None),
+            kind => {
+                let codec = self
+                    .codecs
+                    .get(&kind)
+                    .context(error::UnsupportedCompressionSnafu { kind })?;
+                Ok(Some(Compression::new(
+                    codec.clone(),
+                    compression_block_size,
+                )))
+            }
+        }
+    }
+}
+
 /// Indicates length of block and whether it's compressed or not.
 #[derive(Debug, PartialEq, Eq)]
 enum CompressionHeader {
@@ -86,73 +262,82 @@ fn decode_header(bytes: [u8; 3]) -> CompressionHeader {
     }
 }
 
-fn decompress_block(
-    compression: Compression,
-    compressed_bytes: &[u8],
-    scratch: &mut Vec<u8>,
-) -> Result<(), OrcError> {
-    match compression.compression_type {
-        CompressionType::Zlib => {
-            let mut gz = flate2::read::DeflateDecoder::new(compressed_bytes);
-            scratch.clear();
-            gz.read_to_end(scratch).context(error::IoSnafu)?;
-        }
-        CompressionType::Zstd => {
-            let mut reader =
-                zstd::Decoder::new(compressed_bytes).context(error::BuildZstdDecoderSnafu)?;
-            scratch.clear();
-            reader.read_to_end(scratch).context(error::IoSnafu)?;
-        }
-        CompressionType::Snappy => {
-            let len = snap::raw::decompress_len(compressed_bytes)
-                .context(error::BuildSnappyDecoderSnafu)?;
-            scratch.resize(len, 0);
-            let mut decoder = snap::raw::Decoder::new();
-            decoder
-                .decompress(compressed_bytes, scratch)
-                .context(error::BuildSnappyDecoderSnafu)?;
-        }
-        CompressionType::Lzo => {
-            let decompressed = lzokay_native::decompress_all(compressed_bytes, None)
-                .context(error::BuildLzoDecoderSnafu)?;
-            // TODO: better way to utilize scratch here
-            scratch.clear();
-            scratch.extend(decompressed);
-        }
-        CompressionType::Lz4 => {
-            let decompressed = lz4_flex::block::decompress(
-                compressed_bytes,
-                compression.max_decompressed_block_size,
-            )
-            .context(error::BuildLz4DecoderSnafu)?;
-            // TODO: better way to utilize scratch here
-            scratch.clear();
-            scratch.extend(decompressed);
-        }
-    };
-    Ok(())
-}
-
 enum State {
     Original(Bytes),
     Compressed(Vec<u8>),
 }
 
+/// Remainder of a stream not fetched yet, when reading it incrementally.
+struct PendingRange {
+    source: Arc<dyn ByteRangeSource>,
+    offset: u64,
+    length: u64,
+}
+
 struct DecompressorIter {
+    /// Bytes of the stream fetched but not consumed yet
     stream: BytesMut,
+    pending: Option<PendingRange>,
     current: Option<State>, // when we have compression but the value is original
     compression: Option<Compression>,
     scratch: Vec<u8>,
+    /// Total bytes decompressed from the stream so far
+    decompressed: u64,
 }
 
 impl DecompressorIter {
     pub fn new(stream: Bytes, compression: Option<Compression>, scratch: Vec<u8>) -> Self {
         Self {
             stream: BytesMut::from(stream.as_ref()),
+            pending: None,
+            current: None,
+            compression,
+            scratch,
+            decompressed: 0,
+        }
+    }
+
+    fn new_incremental(
+        source: Arc<dyn ByteRangeSource>,
+        offset: u64,
+        length: u64,
+        compression: Option<Compression>,
+        scratch: Vec<u8>,
+    ) -> Self {
+        Self {
+            stream: BytesMut::new(),
+            pending: Some(PendingRange {
+                source,
+                offset,
+                length,
+            }),
             current: None,
             compression,
             scratch,
+            decompressed: 0,
+        }
+    }
+
+    /// Fetch more of the stream until at least `length` bytes are buffered, or the
+    /// whole stream has been fetched.
+    fn fill(&mut self, length: usize) -> Result<(), OrcError> {
+        let Some(pending) = self.pending.as_mut() else {
+            return Ok(());
+        };
+        if self.stream.len() >= length || pending.length == 0 {
+            return Ok(());
         }
+        let to_read = ((length - self.stream.len()) as u64)
+            .max(INCREMENTAL_READ_SIZE)
+            .min(pending.length);
+        let bytes = pending
+            .source
+            .read_range(pending.offset, to_read)
+            .context(error::IoSnafu)?;
+        pending.offset += to_read;
+        pending.length -= to_read;
+        self.stream.extend_from_slice(&bytes);
+        Ok(())
     }
 
     pub fn into_inner(self) -> Vec<u8> {
@@ -170,24 +355,92 @@ impl FallibleStreamingIterator for DecompressorIter {
 
     #[inline]
     fn advance(&mut self) -> Result<(), Self::Error> {
+        self.fill(1)?;
         if self.stream.is_empty() {
             self.current = None;
             return Ok(());
         }
 
-        match self.compression {
+        // Cloned (cheaply) as more of the stream may need to be fetched
+        match self.compression.clone() {
             Some(compression) => {
                 // TODO: take stratch from current State::Compressed for re-use
+                self.fill(3)?;
+                ensure!(
+                    self.stream.len() >= 3,
+                    error::CorruptCompressionSnafu {
+                        msg: "truncated chunk header"
+                    }
+                );
                 let header = self.stream.split_to(3);
                 let header = [header[0], header[1], header[2]];
-                match decode_header(header) {
-                    CompressionHeader::Original(length) => {
-                        let original = self.stream.split_to(length as usize);
+                let header = decode_header(header);
+                let length = match header {
+                    CompressionHeader::Original(length) | CompressionHeader::Compressed(length) => {
+                        length as usize
+                    }
+                };
+                ensure!(
+                    length <= compression.block_size(),
+                    error::CorruptCompressionSnafu {
+                        msg: format!(
+                            "chunk length {length} exceeds compression block size {}",
+                            compression.block_size()
+                        )
+                    }
+                );
+                self.fill(length)?;
+                ensure!(
+                    length <= self.stream.len(),
+                    error::CorruptCompressionSnafu {
+                        msg: format!(
+                            "chunk length {length} exceeds remaining stream length {}",
+                            self.stream.len()
+                        )
+                    }
+                );
+                match header {
+                    CompressionHeader::Original(_) => {
+                        let original = self.stream.split_to(length);
                         self.current = Some(State::Original(original.into()));
                     }
-                    CompressionHeader::Compressed(length) => {
-                        let compressed = self.stream.split_to(length as usize);
-                        decompress_block(compression, &compressed, &mut self.scratch)?;
+                    CompressionHeader::Compressed(_) => {
+                        let compressed = self.stream.split_to(length);
+                        compression.codec.decompress(
+                            &compressed,
+                            compression.block_size(),
+                            &mut self.scratch,
+                        )?;
+                        ensure!(
+                            self.scratch.len() <= compression.block_size(),
+                            error::CorruptCompressionSnafu {
+                                msg: format!(
+                                    "decompressed chunk length {} exceeds compression block size {}",
+                                    self.scratch.len(),
+                                    compression.block_size()
+                                )
+                            }
+                        );
+                        let length = self.scratch.len() as u64;
+                        self.decompressed += length;
+                        ensure!(
+                            self.decompressed <= compression.max_stream_size,
+                            error::DecompressionLimitExceededSnafu {
+                                scope: "stream",
+                                limit: compression.max_stream_size,
+                            }
+                        );
+                        let stripe_decompressed = compression
+                            .stripe_decompressed
+                            .fetch_add(length, Ordering::Relaxed)
+                            + length;
+                        ensure!(
+                            stripe_decompressed <= compression.max_stripe_size,
+                            error::DecompressionLimitExceededSnafu {
+                                scope: "stripe",
+                                limit: compression.max_stripe_size,
+                            }
+                        );
                         self.current = Some(State::Compressed(std::mem::take(&mut self.scratch)));
                     }
                 };
@@ -195,8 +448,7 @@ impl FallibleStreamingIterator for DecompressorIter {
             }
             None => {
                 // TODO: take stratch from current State::Compressed for re-use
-                self.current = Some(State::Original(self.stream.clone().into()));
-                self.stream.clear();
+                self.current = Some(State::Original(self.stream.split().freeze()));
                 Ok(())
             }
         }
@@ -216,6 +468,7 @@ pub struct Decompressor {
     decompressor: DecompressorIter,
     offset: usize,
     is_first: bool,
+    values_to_skip: u64,
 }
 
 impl Decompressor {
@@ -225,9 +478,51 @@ impl Decompressor {
             decompressor: DecompressorIter::new(stream, compression, scratch),
             offset: 0,
             is_first: true,
+            values_to_skip: 0,
+        }
+    }
+
+    /// Creates a [`Decompressor`] fetching the `length` bytes of the stream starting
+    /// at `offset` from `source` as they are needed, rather than all upfront.
+    pub fn new_incremental(
+        source: Arc<dyn ByteRangeSource>,
+        offset: u64,
+        length: u64,
+        compression: Option<Compression>,
+        scratch: Vec<u8>,
+    ) -> Self {
+        Self {
+            decompressor: DecompressorIter::new_incremental(
+                source,
+                offset,
+                length,
+                compression,
+                scratch,
+            ),
+            offset: 0,
+            is_first: true,
+            values_to_skip: 0,
         }
     }
 
+    /// Start reading from a position of the stream recorded in a row index, for
+    /// streams whose data starts at the compressed chunk (or, if uncompressed, at the
+    /// byte) of that position: skip the first `decompressed_offset` bytes of the
+    /// chunk, then the first `values_to_skip` values decoded from that point, see
+    /// [`Self::values_to_skip`].
+    pub(crate) fn with_start(mut self, decompressed_offset: usize, values_to_skip: u64) -> Self {
+        self.offset = decompressed_offset;
+        self.values_to_skip = values_to_skip;
+        self
+    }
+
+    /// Number of values the decoder of this stream must skip before its first value,
+    /// as the stream starts in the middle of a run of values. Applied by the readers
+    /// of [`crate::reader::decode`].
+    pub fn values_to_skip(&self) -> u64 {
+        self.values_to_skip
+    }
+
     /// Returns the internal memory region, so it can be re-used
     pub fn into_inner(self) -> Vec<u8> {
         self.decompressor.into_inner()
@@ -236,14 +531,15 @@ impl Decompressor {
 
 impl std::io::Read for Decompressor {
     fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+        let to_io_error = |err| std::io::Error::new(std::io::ErrorKind::InvalidData, err);
         if self.is_first {
             self.is_first = false;
-            self.decompressor.advance().unwrap();
+            self.decompressor.advance().map_err(to_io_error)?;
         }
         let current = self.decompressor.get();
         let current = if let Some(current) = current {
             if current.len() == self.offset {
-                self.decompressor.advance().unwrap();
+                self.decompressor.advance().map_err(to_io_error)?;
                 self.offset = 0;
                 let current = self.decompressor.get();
                 if let Some(current) = current {
@@ -251,8 +547,15 @@ impl std::io::Read for Decompressor {
                 } else {
                     return Ok(0);
                 }
+            } else if let Some(current) = current.get(self.offset..) {
+                current
             } else {
-                &current[self.offset..]
+                let msg = format!(
+                    "start offset {} past the end of a chunk of {} bytes",
+                    self.offset,
+                    current.len()
+                );
+                return Err(to_io_error(error::CorruptCompressionSnafu { msg }.build()));
             }
         } else {
             return Ok(0);
@@ -292,4 +595,186 @@ mod tests {
         let actual = decode_header(bytes);
         assert_eq!(expected, actual);
     }
+
+    /// Encode a chunk header for a chunk of `length` bytes.
+    fn encode_header(length: usize, is_original: bool) -> [u8; 3] {
+        let value = ((length as u32) << 1) | is_original as u32;
+        let bytes = value.to_le_bytes();
+        [bytes[0], bytes[1], bytes[2]]
+    }
+
+    #[test]
+    fn mixed_original_and_compressed_chunks() {
+        use std::io::Write;
+
+        let original = b"stored verbatim, ";
+        let mut encoder =
+            flate2::write::DeflateEncoder::new(vec![], flate2::Compression::default());
+        encoder.write_all(b"then deflated").unwrap();
+        let compressed = encoder.finish().unwrap();
+
+        let mut stream = vec![];
+        stream.extend(encode_header(original.len(), true));
+        stream.extend(original);
+        stream.extend(encode_header(compressed.len(), false));
+        stream.extend(&compressed);
+
+        let compression = Compression::from_proto(CompressionKind::Zlib, None).unwrap();
+        let mut decompressor = Decompressor::new(stream.into(), Some(compression), vec![]);
+        let mut actual = String::new();
+        decompressor.read_to_string(&mut actual).unwrap();
+        assert_eq!("stored verbatim, then deflated", actual);
+    }
+
+    #[test]
+    fn reject_chunk_larger_than_block_size() {
+        let compression = Compression::from_proto(CompressionKind::Zlib, Some(4)).unwrap();
+        // 5 uncompressed = [0x0b, 0x00, 0x00], exceeding the block size of 4
+        let stream = Bytes::from_static(&[0b1011, 0, 0, 1, 2, 3, 4, 5]);
+
+        let mut iter = DecompressorIter::new(stream.clone(), Some(compression.clone()), vec![]);
+        let err = iter.advance().unwrap_err();
+        assert!(
+            matches!(err, OrcError::CorruptCompression { .. }),
+            "unexpected error: {err}"
+        );
+
+        let mut decompressor = Decompressor::new(stream, Some(compression), vec![]);
+        let err = decompressor.read_to_end(&mut vec![]).unwrap_err();
+        assert!(
+            err.to_string()
+                .contains("chunk length 5 exceeds compression block size 4"),
+            "unexpected error: {err}"
+        );
+    }
+
+    #[test]
+    fn reject_stream_exceeding_decompression_limits() {
+        use std::io::Write;
+
+        // Each chunk of 64KiB of zeros deflates to a few dozen bytes
+        let mut encoder =
+            flate2::write::DeflateEncoder::new(vec![], flate2::Compression::default());
+        encoder.write_all(&[0; 64 * 1024]).unwrap();
+        let chunk = encoder.finish().unwrap();
+        let mut stream = vec![];
+        for _ in 0..4 {
+            stream.extend(encode_header(chunk.len(), false));
+            stream.extend(&chunk);
+        }
+        let stream = Bytes::from(stream);
+
+        let compression = Compression::from_proto(CompressionKind::Zlib, None).unwrap();
+        let mut decompressor = Decompressor::new(
+            stream.clone(),
+            Some(compression.clone().with_max_stream_size(128 * 1024)),
+            vec![],
+        );
+        let err = decompressor.read_to_end(&mut vec![]).unwrap_err();
+        assert!(
+            err.to_string()
+                .contains("Decompressed stream exceeds the limit of 131072 bytes"),
+            "unexpected error: {err}"
+        );
+
+        // Each stream fits, but not both streams of the stripe
+        let compression = compression.with_max_stripe_size(384 * 1024).for_stripe();
+        let mut decompressor = Decompressor::new(stream.clone(), Some(compression.clone()), vec![]);
+        assert_eq!(256 * 1024, decompressor.read_to_end(&mut vec![]).unwrap());
+        let mut decompressor = Decompressor::new(stream, Some(compression), vec![]);
+        let err = decompressor.read_to_end(&mut vec![]).unwrap_err();
+        assert!(
+            err.to_string()
+                .contains("Decompressed stripe exceeds the limit of 393216 bytes"),
+            "unexpected error: {err}"
+        );
+    }
+
+    #[test]
+    fn gzip_wrapped_zlib_chunks() {
+        use std::io::Write;
+
+        let mut encoder = flate2::write::GzEncoder::new(vec![], flate2::Compression::default());
+        encoder.write_all(b"gzip wrapped").unwrap();
+        let compressed = encoder.finish().unwrap();
+        let mut stream = vec![];
+        stream.extend(encode_header(compressed.len(), false));
+        stream.extend(&compressed);
+
+        let compression = Compression::from_proto(CompressionKind::Zlib, None).unwrap();
+        let mut decompressor = Decompressor::new(stream.into(), Some(compression), vec![]);
+        let mut actual = String::new();
+        decompressor.read_to_string(&mut actual).unwrap();
+        assert_eq!("gzip wrapped", actual);
+    }
+
+    /// Flips every bit of the chunk.
+    #[derive(Debug)]
+    struct XorCodec;
+
+    impl CompressionCodec for XorCodec {
+        fn decompress(
+            &self,
+            compressed: &[u8],
+            _max_decompressed_size: usize,
+            scratch: &mut Vec<u8>,
+        ) -> Result<(), OrcError> {
+            scratch.clear();
+            scratch.extend(compressed.iter().map(|byte| byte ^ 0xFF));
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn custom_codec() {
+        const XOR: i32 = 100;
+        let codecs = CodecRegistry::default().with_codec(XOR, Arc::new(XorCodec));
+
+        let compressed = b"custom codec".map(|byte| byte ^ 0xFF);
+        let mut stream = vec![];
+        stream.extend(encode_header(compressed.len(), false));
+        stream.extend(compressed);
+
+        let compression = codecs.compression(Some(XOR), None).unwrap();
+        let mut decompressor = Decompressor::new(stream.into(), compression, vec![]);
+        let mut actual = String::new();
+        decompressor.read_to_string(&mut actual).unwrap();
+        assert_eq!("custom codec", actual);
+
+        let err = CodecRegistry::default()
+            .compression(Some(XOR), None)
+            .unwrap_err();
+        assert!(
+            matches!(err, OrcError::UnsupportedCompression { kind: XOR, .. }),
+            "unexpected error: {err}"
+        );
+        assert!(codecs
+            .compression(Some(CompressionKind::None as i32), None)
+            .unwrap()
+            .is_none());
+    }
+
+    #[test]
+    fn incremental_reads() {
+        use crate::reader::IncrementalReader;
+
+        // 200 chunks of 1000 bytes, after an unrelated prefix
+        let mut file = b"prefix".to_vec();
+        let mut expected = vec![];
+        for chunk in 0..200u32 {
+            let data = vec![chunk as u8; 1000];
+            file.extend(encode_header(data.len(), false));
+            file.extend(data.iter().map(|byte| byte ^ 0xFF));
+            expected.extend(data);
+        }
+        let length = file.len() as u64 - 6;
+
+        let compression = Compression::new(Arc::new(XorCodec), Some(1000));
+        let source = Arc::new(IncrementalReader::new(Bytes::from(file)));
+        let mut decompressor =
+            Decompressor::new_incremental(source, 6, length, Some(compression), vec![]);
+        let mut actual = vec![];
+        decompressor.read_to_end(&mut actual).unwrap();
+        assert_eq!(expected, actual);
+    }
 }
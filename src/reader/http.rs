@@ -0,0 +1,137 @@
+//! Reading files over HTTP(S), fetching byte ranges with `Range` requests.
+
+use std::io;
+
+use bytes::Bytes;
+use futures_util::future::BoxFuture;
+use futures_util::FutureExt;
+use reqwest::header::{ACCEPT_RANGES, CONTENT_LENGTH, CONTENT_RANGE, RANGE};
+use reqwest::{Client, IntoUrl, Response, StatusCode, Url};
+
+use super::AsyncChunkReader;
+
+/// Reads a file served over HTTP(S). The server must support range requests,
+/// as only the byte ranges being decoded are fetched.
+pub struct HttpReader {
+    client: Client,
+    url: Url,
+    /// Length of the file, once known
+    length: Option<u64>,
+}
+
+impl HttpReader {
+    pub fn new(url: impl IntoUrl) -> io::Result<Self> {
+        Self::with_client(Client::new(), url)
+    }
+
+    /// Like [`Self::new`], sending the requests with the given client, e.g. one
+    /// configured with timeouts or default headers.
+    pub fn with_client(client: Client, url: impl IntoUrl) -> io::Result<Self> {
+        let url = url.into_url().map_err(other_error)?;
+        Ok(Self {
+            client,
+            url,
+            length: None,
+        })
+    }
+
+    /// Length from a `HEAD` request or, if the server doesn't give it there, from
+    /// the `Content-Range` of a request for the first byte.
+    async fn fetch_length(&self) -> io::Result<u64> {
+        let response = self.client.head(self.url.clone()).send().await;
+        if let Ok(response) = response.and_then(Response::error_for_status) {
+            let headers = response.headers();
+            if headers
+                .get(ACCEPT_RANGES)
+                .is_some_and(|value| value == "none")
+            {
+                return Err(no_range_support(&self.url));
+            }
+            let length = headers
+                .get(CONTENT_LENGTH)
+                .and_then(|value| value.to_str().ok()?.parse().ok());
+            if let Some(length) = length {
+                return Ok(length);
+            }
+        }
+
+        let response = self.get_range(0, 1).await?;
+        response
+            .headers()
+            .get(CONTENT_RANGE)
+            .and_then(|value| {
+                let (_, length) = value.to_str().ok()?.rsplit_once('/')?;
+                length.parse().ok()
+            })
+            .ok_or_else(|| other_error(format!("No length of {} in Content-Range", self.url)))
+    }
+
+    /// Request `length` bytes from `offset`, failing unless the server replies with
+    /// only those.
+    async fn get_range(&self, offset: u64, length: u64) -> io::Result<Response> {
+        let range = format!("bytes={}-{}", offset, offset + length - 1);
+        let response = self
+            .client
+            .get(self.url.clone())
+            .header(RANGE, range)
+            .send()
+            .await
+            .and_then(Response::error_for_status)
+            .map_err(other_error)?;
+        if response.status() != StatusCode::PARTIAL_CONTENT {
+            return Err(no_range_support(&self.url));
+        }
+        Ok(response)
+    }
+}
+
+impl AsyncChunkReader for HttpReader {
+    fn len(&mut self) -> BoxFuture<'_, io::Result<u64>> {
+        async move {
+            if let Some(length) = self.length {
+                return Ok(length);
+            }
+            let length = self.fetch_length().await?;
+            self.length = Some(length);
+            Ok(length)
+        }
+        .boxed()
+    }
+
+    fn get_bytes(
+        &mut self,
+        offset_from_start: u64,
+        length: u64,
+    ) -> BoxFuture<'_, io::Result<Bytes>> {
+        async move {
+            if length == 0 {
+                return Ok(Bytes::new());
+            }
+            let response = self.get_range(offset_from_start, length).await?;
+            let bytes = response.bytes().await.map_err(other_error)?;
+            if bytes.len() as u64 != length {
+                return Err(io::Error::new(
+                    io::ErrorKind::UnexpectedEof,
+                    format!(
+                        "Requested {length} bytes at offset {offset_from_start} of {}, got {}",
+                        self.url,
+                        bytes.len()
+                    ),
+                ));
+            }
+            Ok(bytes)
+        }
+        .boxed()
+    }
+}
+
+fn no_range_support(url: &Url) -> io::Error {
+    io::Error::new(
+        io::ErrorKind::Unsupported,
+        format!("Server of {url} doesn't support range requests"),
+    )
+}
+
+fn other_error(err: impl Into<Box<dyn std::error::Error + Send + Sync>>) -> io::Error {
+    io::Error::new(io::ErrorKind::Other, err)
+}
@@ -27,16 +27,18 @@ use std::io::Read;
 
 use bytes::{Bytes, BytesMut};
 use prost::Message;
-use snafu::{OptionExt, ResultExt};
+use snafu::{ensure, OptionExt, ResultExt};
 
 use crate::error::{self, EmptyFileSnafu, OutOfSpecSnafu, Result};
 use crate::proto::{self, Footer, Metadata, PostScript};
 use crate::reader::decompress::Decompressor;
-use crate::schema::RootDataType;
+use crate::schema::{DataType, RootDataType};
 use crate::statistics::ColumnStatistics;
-use crate::stripe::StripeMetadata;
+use crate::stripe::{
+    compressed_data_size, deserialize_stripe_footer, is_index_stream, StripeMetadata,
+};
 
-use super::decompress::Compression;
+use super::decompress::{CodecRegistry, Compression};
 use super::{AsyncChunkReader, ChunkReader};
 
 const DEFAULT_FOOTER_SIZE: u64 = 16 * 1024;
@@ -51,16 +53,21 @@ pub struct FileMetadata {
     column_statistics: Vec<ColumnStatistics>,
     stripes: Vec<StripeMetadata>,
     user_custom_metadata: HashMap<String, Vec<u8>>,
+    /// Root column indices of the encrypted column subtrees
+    encrypted_column_roots: Vec<usize>,
+    /// Number of rows per row group, 0 if the file has no row indexes
+    row_index_stride: usize,
+    postscript: PostScript,
+    footer: Footer,
 }
 
 impl FileMetadata {
-    fn from_proto(
+    pub(crate) fn from_proto(
         postscript: &proto::PostScript,
         footer: &proto::Footer,
         metadata: &proto::Metadata,
+        compression: Option<Compression>,
     ) -> Result<Self> {
-        let compression =
-            Compression::from_proto(postscript.compression(), postscript.compression_block_size);
         let root_data_type = RootDataType::from_proto(&footer.types)?;
         let number_of_rows = footer.number_of_rows();
         let column_statistics = footer
@@ -79,6 +86,13 @@ impl FileMetadata {
             .iter()
             .map(|kv| (kv.name().to_owned(), kv.value().to_vec()))
             .collect::<HashMap<_, _>>();
+        let encrypted_column_roots = footer
+            .encryption
+            .iter()
+            .flat_map(|encryption| &encryption.variants)
+            .map(|variant| variant.root() as usize)
+            .collect();
+        let row_index_stride = footer.row_index_stride() as usize;
 
         Ok(Self {
             compression,
@@ -87,6 +101,10 @@ impl FileMetadata {
             column_statistics,
             stripes,
             user_custom_metadata,
+            encrypted_column_roots,
+            row_index_stride,
+            postscript: postscript.clone(),
+            footer: footer.clone(),
         })
     }
 
@@ -95,7 +113,23 @@ impl FileMetadata {
     }
 
     pub fn compression(&self) -> Option<Compression> {
+        self.compression.clone()
+    }
+
+    /// Copy of the metadata decompressing with the result of `f` instead.
+    pub(crate) fn map_compression(&self, f: impl FnOnce(Compression) -> Compression) -> Self {
+        Self {
+            compression: self.compression.clone().map(f),
+            ..self.clone()
+        }
+    }
+
+    /// Maximum decompressed size of a compression chunk, or `None` if the file
+    /// is uncompressed.
+    pub fn compression_block_size(&self) -> Option<u64> {
         self.compression
+            .as_ref()
+            .map(|compression| compression.block_size() as u64)
     }
 
     pub fn root_data_type(&self) -> &RootDataType {
@@ -110,12 +144,172 @@ impl FileMetadata {
         &self.stripes
     }
 
+    /// Total length of all the stripes in the file, as stored (so compressed, if
+    /// the file is).
+    pub fn stripes_total_length(&self) -> u64 {
+        self.stripes.iter().map(StripeMetadata::total_length).sum()
+    }
+
+    /// Index of the first row of each stripe within the file, e.g. to find which
+    /// stripes cover a range of rows before decoding any of them.
+    pub fn stripe_first_rows(&self) -> Vec<u64> {
+        self.stripes
+            .iter()
+            .scan(0, |first_row, stripe| {
+                let current = *first_row;
+                *first_row += stripe.number_of_rows();
+                Some(current)
+            })
+            .collect()
+    }
+
+    /// Statistics of the column at the given dotted path (see
+    /// [`RootDataType::column_id_for_path`]) in each stripe, in order, without
+    /// reading any stripe.
+    ///
+    /// Fails if the file doesn't record the statistics of a stripe.
+    pub fn column_stripe_stats(&self, path: &str) -> Result<Vec<ColumnStatistics>> {
+        let column_id =
+            self.root_data_type
+                .column_id_for_path(path)
+                .context(error::InvalidInputSnafu {
+                    msg: format!("No column at path {path}"),
+                })?;
+        self.stripes
+            .iter()
+            .enumerate()
+            .map(|(stripe, info)| {
+                info.column_statistics()
+                    .get(column_id as usize)
+                    .cloned()
+                    .context(OutOfSpecSnafu {
+                        msg: format!("No statistics of column {path} in stripe {stripe}"),
+                    })
+            })
+            .collect()
+    }
+
     pub fn user_custom_metadata(&self) -> &HashMap<String, Vec<u8>> {
         &self.user_custom_metadata
     }
+
+    /// Root column indices of column subtrees which are encrypted, using ORC
+    /// column encryption.
+    pub fn encrypted_column_roots(&self) -> &[usize] {
+        &self.encrypted_column_roots
+    }
+
+    /// Number of rows in each row group of a stripe (except possibly the last),
+    /// or 0 if the file was written without row indexes.
+    pub fn row_index_stride(&self) -> usize {
+        self.row_index_stride
+    }
+
+    /// The file footer as decoded from the file, for reading fields which don't
+    /// have a typed accessor yet.
+    ///
+    /// Unstable: the protobuf definitions may change with the ORC specification,
+    /// so prefer the typed accessors where they exist.
+    pub fn raw_footer(&self) -> &Footer {
+        &self.footer
+    }
+
+    /// The file postscript as decoded from the file, for reading fields which don't
+    /// have a typed accessor yet.
+    ///
+    /// Unstable: the protobuf definitions may change with the ORC specification,
+    /// so prefer the typed accessors where they exist.
+    pub fn raw_postscript(&self) -> &PostScript {
+        &self.postscript
+    }
+
+    /// Total length of the data streams of the given column and all of its nested
+    /// children across all stripes, as stored in the file. See
+    /// [`Stripe::column_compressed_size`](crate::stripe::Stripe::column_compressed_size).
+    ///
+    /// Only reads the stripe footers.
+    pub fn column_compressed_size<R: ChunkReader>(
+        &self,
+        reader: &mut R,
+        data_type: &DataType,
+    ) -> Result<u64> {
+        let mut size = 0;
+        for info in &self.stripes {
+            let footer = reader
+                .get_bytes(info.footer_offset(), info.footer_length())
+                .context(error::IoSnafu)?;
+            let footer = deserialize_stripe_footer(&footer, self.compression.as_ref())?;
+            size += compressed_data_size(&footer, data_type);
+        }
+        Ok(size)
+    }
+
+    /// Check the stripes fit within the file and that the streams listed in each
+    /// stripe footer add up to the index and data lengths declared for the stripe.
+    ///
+    /// Reads every stripe footer, so is intended to catch truncated or corrupted
+    /// files early, e.g. at ingestion. Errors on the first inconsistent stripe.
+    pub fn verify_layout<R: ChunkReader>(&self, reader: &mut R) -> Result<()> {
+        let file_length = reader.len();
+        for (stripe, info) in self.stripes.iter().enumerate() {
+            let stripe_end = info.footer_offset() + info.footer_length();
+            ensure!(
+                stripe_end <= file_length,
+                error::InvalidStripeLayoutSnafu {
+                    stripe,
+                    msg: format!("ends at byte {stripe_end} but the file has {file_length} bytes"),
+                }
+            );
+
+            let footer = reader
+                .get_bytes(info.footer_offset(), info.footer_length())
+                .context(error::IoSnafu)?;
+            let footer = deserialize_stripe_footer(&footer, self.compression.as_ref())?;
+            let (index_length, data_length) =
+                footer
+                    .streams
+                    .iter()
+                    .fold((0, 0), |(index_length, data_length), stream| {
+                        if is_index_stream(stream.kind()) {
+                            (index_length + stream.length(), data_length)
+                        } else {
+                            (index_length, data_length + stream.length())
+                        }
+                    });
+            ensure!(
+                index_length == info.index_length(),
+                error::InvalidStripeLayoutSnafu {
+                    stripe,
+                    msg: format!(
+                        "index streams have {index_length} bytes but the stripe declares {}",
+                        info.index_length()
+                    ),
+                }
+            );
+            ensure!(
+                data_length == info.data_length(),
+                error::InvalidStripeLayoutSnafu {
+                    stripe,
+                    msg: format!(
+                        "data streams have {data_length} bytes but the stripe declares {}",
+                        info.data_length()
+                    ),
+                }
+            );
+        }
+        Ok(())
+    }
 }
 
 pub fn read_metadata<R: ChunkReader>(reader: &mut R) -> Result<FileMetadata> {
+    read_metadata_with_codecs(reader, &CodecRegistry::default())
+}
+
+/// Like [`read_metadata`], decompressing with the codecs of the given registry.
+pub fn read_metadata_with_codecs<R: ChunkReader>(
+    reader: &mut R,
+    codecs: &CodecRegistry,
+) -> Result<FileMetadata> {
     let file_len = reader.len();
     if file_len == 0 {
         return EmptyFileSnafu.fail();
@@ -143,7 +337,7 @@ pub fn read_metadata<R: ChunkReader>(reader: &mut R) -> Result<FileMetadata> {
     let postscript = PostScript::decode(&tail_bytes[tail_bytes.len() - postscript_len as usize..])
         .context(error::DecodeProtoSnafu)?;
     let compression =
-        Compression::from_proto(postscript.compression(), postscript.compression_block_size);
+        codecs.compression(postscript.compression, postscript.compression_block_size)?;
     tail_bytes.truncate(tail_bytes.len() - postscript_len as usize);
 
     let footer_length = postscript.footer_length.context(error::OutOfSpecSnafu {
@@ -153,6 +347,13 @@ pub fn read_metadata<R: ChunkReader>(reader: &mut R) -> Result<FileMetadata> {
         msg: "Metadata length is empty",
     })?;
 
+    if footer_length + metadata_length + postscript_len + 1 > file_len {
+        return OutOfSpecSnafu {
+            msg: "File too small for given footer and metadata lengths",
+        }
+        .fail();
+    }
+
     // Ensure we have enough bytes for Footer and Metadata
     let mut tail_bytes = if footer_length + metadata_length > tail_bytes.len() as u64 {
         // Need second read
@@ -172,19 +373,27 @@ pub fn read_metadata<R: ChunkReader>(reader: &mut R) -> Result<FileMetadata> {
 
     let footer = deserialize_footer(
         &tail_bytes[tail_bytes.len() - footer_length as usize..],
-        compression,
+        compression.as_ref(),
     )?;
     tail_bytes.truncate(tail_bytes.len() - footer_length as usize);
 
     let metadata = deserialize_footer_metadata(
         &tail_bytes[tail_bytes.len() - metadata_length as usize..],
-        compression,
+        compression.as_ref(),
     )?;
 
-    FileMetadata::from_proto(&postscript, &footer, &metadata)
+    FileMetadata::from_proto(&postscript, &footer, &metadata, compression)
 }
 
 pub async fn read_metadata_async<R: AsyncChunkReader>(reader: &mut R) -> Result<FileMetadata> {
+    read_metadata_async_with_codecs(reader, &CodecRegistry::default()).await
+}
+
+/// Like [`read_metadata_async`], decompressing with the codecs of the given registry.
+pub async fn read_metadata_async_with_codecs<R: AsyncChunkReader>(
+    reader: &mut R,
+    codecs: &CodecRegistry,
+) -> Result<FileMetadata> {
     let file_len = reader.len().await.context(error::IoSnafu)?;
     if file_len == 0 {
         return EmptyFileSnafu.fail();
@@ -213,7 +422,7 @@ pub async fn read_metadata_async<R: AsyncChunkReader>(reader: &mut R) -> Result<
     let postscript = PostScript::decode(&tail_bytes[tail_bytes.len() - postscript_len as usize..])
         .context(error::DecodeProtoSnafu)?;
     let compression =
-        Compression::from_proto(postscript.compression(), postscript.compression_block_size);
+        codecs.compression(postscript.compression, postscript.compression_block_size)?;
     tail_bytes.truncate(tail_bytes.len() - postscript_len as usize);
 
     let footer_length = postscript.footer_length.context(error::OutOfSpecSnafu {
@@ -223,6 +432,13 @@ pub async fn read_metadata_async<R: AsyncChunkReader>(reader: &mut R) -> Result<
         msg: "Metadata length is empty",
     })?;
 
+    if footer_length + metadata_length + postscript_len + 1 > file_len {
+        return OutOfSpecSnafu {
+            msg: "File too small for given footer and metadata lengths",
+        }
+        .fail();
+    }
+
     // Ensure we have enough bytes for Footer and Metadata
     let mut tail_bytes = if footer_length + metadata_length > tail_bytes.len() as u64 {
         // Need second read
@@ -243,31 +459,34 @@ pub async fn read_metadata_async<R: AsyncChunkReader>(reader: &mut R) -> Result<
 
     let footer = deserialize_footer(
         &tail_bytes[tail_bytes.len() - footer_length as usize..],
-        compression,
+        compression.as_ref(),
     )?;
     tail_bytes.truncate(tail_bytes.len() - footer_length as usize);
 
     let metadata = deserialize_footer_metadata(
         &tail_bytes[tail_bytes.len() - metadata_length as usize..],
-        compression,
+        compression.as_ref(),
     )?;
 
-    FileMetadata::from_proto(&postscript, &footer, &metadata)
+    FileMetadata::from_proto(&postscript, &footer, &metadata, compression)
 }
 
-fn deserialize_footer(bytes: &[u8], compression: Option<Compression>) -> Result<Footer> {
+fn deserialize_footer(bytes: &[u8], compression: Option<&Compression>) -> Result<Footer> {
     let mut buffer = vec![];
     // TODO: refactor to not need Bytes::copy_from_slice
-    Decompressor::new(Bytes::copy_from_slice(bytes), compression, vec![])
+    Decompressor::new(Bytes::copy_from_slice(bytes), compression.cloned(), vec![])
         .read_to_end(&mut buffer)
         .context(error::IoSnafu)?;
     Footer::decode(buffer.as_slice()).context(error::DecodeProtoSnafu)
 }
 
-fn deserialize_footer_metadata(bytes: &[u8], compression: Option<Compression>) -> Result<Metadata> {
+fn deserialize_footer_metadata(
+    bytes: &[u8],
+    compression: Option<&Compression>,
+) -> Result<Metadata> {
     let mut buffer = vec![];
     // TODO: refactor to not need Bytes::copy_from_slice
-    Decompressor::new(Bytes::copy_from_slice(bytes), compression, vec![])
+    Decompressor::new(Bytes::copy_from_slice(bytes), compression.cloned(), vec![])
         .read_to_end(&mut buffer)
         .context(error::IoSnafu)?;
     Metadata::decode(buffer.as_slice()).context(error::DecodeProtoSnafu)
@@ -4,11 +4,36 @@ use std::sync::Arc;
 
 use snafu::{ensure, OptionExt};
 
-use crate::error::{NoTypesSnafu, Result, UnexpectedSnafu};
+use crate::arrow_fields::keys_first;
+use crate::arrow_reader::StringValidation;
+use crate::error::{CorruptSchemaSnafu, NoTypesSnafu, Result, UnexpectedSnafu};
 use crate::projection::ProjectionMask;
 use crate::proto;
 
-use arrow::datatypes::{DataType as ArrowDataType, Field, Schema, TimeUnit, UnionMode};
+use arrow::datatypes::{
+    DataType as ArrowDataType, Field, IntervalUnit, Schema, TimeUnit, UnionMode,
+};
+
+/// Root columns of the files of Hive ACID tables, the last one holding the user
+/// columns.
+const HIVE_ACID_COLUMNS: [&str; 6] = [
+    "operation",
+    "originalTransaction",
+    "bucket",
+    "rowId",
+    "currentTransaction",
+    "row",
+];
+
+/// Attribute of integer columns holding intervals, which are decoded into Arrow
+/// interval types by default: `YEAR_MONTH` for a number of months (in columns up
+/// to `int`), or `DAY_TIME` for a number of milliseconds.
+pub const INTERVAL_UNIT_ATTRIBUTE: &str = "orc.interval_unit";
+
+/// Precision of decimals whose type doesn't record it.
+const DEFAULT_DECIMAL_PRECISION: u32 = 38;
+/// Scale of decimals whose type doesn't record it.
+const DEFAULT_DECIMAL_SCALE: u32 = 10;
 
 /// Represents the root data type of the ORC file. Contains multiple named child types
 /// which map to the columns available. Allows projecting only specific columns from
@@ -42,14 +67,23 @@ impl RootDataType {
         let fields = self
             .children
             .iter()
-            .map(|col| {
-                let dt = col.data_type().to_arrow_data_type();
-                Field::new(col.name(), dt, true)
-            })
+            .map(NamedColumn::to_arrow_field)
             .collect::<Vec<_>>();
         Schema::new_with_metadata(fields, user_metadata.clone())
     }
 
+    /// The `row` struct column holding the user columns, if the columns are laid out
+    /// as in the files of Hive ACID (transactional) tables.
+    pub fn hive_acid_row(&self) -> Option<&NamedColumn> {
+        let names = self.children.iter().map(|col| col.name());
+        if !names.eq(HIVE_ACID_COLUMNS) {
+            return None;
+        }
+        self.children
+            .last()
+            .filter(|col| matches!(col.data_type(), DataType::Struct { .. }))
+    }
+
     /// Create new root data type based on mask of columns to project.
     pub fn project(&self, mask: &ProjectionMask) -> Self {
         // TODO: fix logic here to account for nested projection
@@ -65,9 +99,244 @@ impl RootDataType {
     /// Construct from protobuf types.
     pub fn from_proto(types: &[proto::Type]) -> Result<Self> {
         ensure!(!types.is_empty(), NoTypesSnafu {});
+        validate_type_graph(types)?;
         let children = parse_struct_children_from_proto(types, 0)?;
         Ok(Self { children })
     }
+
+    /// Render a human readable tree of the columns, one line per column with its
+    /// column index, name and ORC type. Leaf columns also show the Arrow type they
+    /// are decoded into by default.
+    ///
+    /// ```text
+    /// 0: struct
+    ///   1: a (int) -> Int32
+    ///   2: b (list<string>)
+    ///     3: item (string) -> Utf8
+    /// ```
+    pub fn to_tree_string(&self) -> String {
+        let mut tree = format!("{}: struct", self.column_index());
+        for child in &self.children {
+            write_tree(&mut tree, child.name(), child.data_type(), 1);
+        }
+        tree
+    }
+
+    /// Column index of the column at the given dotted path, e.g. `a.b` for field `b`
+    /// of the root struct column `a`. Children of lists, maps and unions are named
+    /// as in [`Self::to_tree_string`]: `item`, `key` and `value`, and the variant
+    /// index respectively.
+    pub fn column_id_for_path(&self, path: &str) -> Option<u32> {
+        let mut names = path.split('.');
+        let root = names.next()?;
+        let mut data_type = self
+            .children
+            .iter()
+            .find(|col| col.name() == root)?
+            .data_type();
+        for name in names {
+            data_type = data_type
+                .named_children()
+                .into_iter()
+                .find(|(child_name, _)| child_name == name)?
+                .1;
+        }
+        Some(data_type.column_index() as u32)
+    }
+
+    /// Dotted path of the column with the given column index, the inverse of
+    /// [`Self::column_id_for_path`]. The root struct (index 0) has no path.
+    pub fn path_for_column_id(&self, column_id: u32) -> Option<String> {
+        self.children
+            .iter()
+            .find_map(|col| find_path(col.name().to_owned(), col.data_type(), column_id as usize))
+    }
+
+    /// Mappings of the columns to the fields of `schema` which may lose information
+    /// when decoding, found without reading any data. As when reading with
+    /// [`ArrowReaderBuilder::with_schema`](crate::ArrowReaderBuilder::with_schema),
+    /// root columns are matched to the fields by position, so call this on the
+    /// projected type (see [`Self::project`]). Nested columns are checked too,
+    /// matched like the decoders do.
+    ///
+    /// Lossy UTF-8 validation is reported for the string columns decoded into
+    /// string types with `string_validation`, see
+    /// [`ArrowReaderBuilder::with_string_validation`](crate::ArrowReaderBuilder::with_string_validation).
+    pub fn check_lossless(
+        &self,
+        schema: &Schema,
+        string_validation: StringValidation,
+    ) -> Vec<Warning> {
+        let mut warnings = vec![];
+        let lossy_utf8 = string_validation == StringValidation::Lossy;
+        for (col, field) in self.children.iter().zip(schema.fields()) {
+            let path = col.name().to_owned();
+            check_lossless(path, col.data_type(), field, lossy_utf8, &mut warnings);
+        }
+        warnings
+    }
+}
+
+/// Mapping of an ORC column to an Arrow type which may lose information, see
+/// [`RootDataType::check_lossless`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Warning {
+    /// Dotted path of the column, see [`RootDataType::column_id_for_path`]
+    pub path: String,
+    pub kind: WarningKind,
+}
+
+/// How decoding into an Arrow type may lose information.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum WarningKind {
+    /// Timestamps decoded into a coarser unit than nanoseconds are truncated.
+    TimestampTruncated { unit: TimeUnit },
+    /// Timestamps decoded into dates lose their time of day.
+    TimestampToDate,
+    /// Decimals decoded into a smaller scale would lose fractional digits. The
+    /// reader rejects such mappings rather than rounding.
+    DecimalScaleReduced { orc_scale: u32, arrow_scale: i8 },
+    /// Floats decoded into half precision floats are rounded.
+    FloatRounded,
+    /// Strings which aren't valid UTF-8 have their invalid sequences replaced.
+    LossyUtf8,
+}
+
+fn check_lossless(
+    path: String,
+    data_type: &DataType,
+    field: &Field,
+    lossy_utf8: bool,
+    warnings: &mut Vec<Warning>,
+) {
+    let is_utf8 = match field.data_type() {
+        ArrowDataType::Utf8 | ArrowDataType::LargeUtf8 => true,
+        ArrowDataType::Dictionary(_, value) => **value == ArrowDataType::Utf8,
+        _ => false,
+    };
+    let kind = match (data_type, field.data_type()) {
+        (
+            DataType::Timestamp { .. } | DataType::TimestampWithLocalTimezone { .. },
+            ArrowDataType::Timestamp(unit, _),
+        ) if *unit != TimeUnit::Nanosecond => {
+            Some(WarningKind::TimestampTruncated { unit: unit.clone() })
+        }
+        (
+            DataType::Timestamp { .. } | DataType::TimestampWithLocalTimezone { .. },
+            ArrowDataType::Date32,
+        ) => Some(WarningKind::TimestampToDate),
+        (DataType::Decimal { scale, .. }, ArrowDataType::Decimal128(_, arrow_scale))
+            if (*arrow_scale as i64) < *scale as i64 =>
+        {
+            Some(WarningKind::DecimalScaleReduced {
+                orc_scale: *scale,
+                arrow_scale: *arrow_scale,
+            })
+        }
+        (DataType::Float { .. }, ArrowDataType::Float16) => Some(WarningKind::FloatRounded),
+        (DataType::String { .. } | DataType::Varchar { .. } | DataType::Char { .. }, _)
+            if lossy_utf8 && is_utf8 =>
+        {
+            Some(WarningKind::LossyUtf8)
+        }
+        _ => None,
+    };
+    if let Some(kind) = kind {
+        warnings.push(Warning {
+            path: path.clone(),
+            kind,
+        });
+    }
+
+    let children = data_type.named_children();
+    let child = |name: &str| children.iter().find(|(child_name, _)| child_name == name);
+    match field.data_type() {
+        ArrowDataType::Struct(fields)
+            if matches!(data_type, DataType::Map { .. }) && fields.len() == 2 =>
+        {
+            // Key and value lists
+            let lists = if keys_first(fields) {
+                [&fields[0], &fields[1]]
+            } else {
+                [&fields[1], &fields[0]]
+            };
+            for ((name, child), list) in children.iter().zip(lists) {
+                if let ArrowDataType::List(item) = list.data_type() {
+                    check_lossless(format!("{path}.{name}"), child, item, lossy_utf8, warnings);
+                }
+            }
+        }
+        ArrowDataType::Struct(fields) => {
+            // Matched by name, or by position if no name matches, like the decoder
+            let by_name = fields
+                .iter()
+                .map(|field| child(field.name()))
+                .collect::<Vec<_>>();
+            let children = if by_name.iter().all(Option::is_some) {
+                by_name.into_iter().flatten().collect()
+            } else if by_name.iter().all(Option::is_none) && children.len() == fields.len() {
+                children.iter().collect()
+            } else {
+                vec![]
+            };
+            for ((name, child), field) in children.into_iter().zip(fields) {
+                check_lossless(format!("{path}.{name}"), child, field, lossy_utf8, warnings);
+            }
+        }
+        ArrowDataType::List(item)
+        | ArrowDataType::LargeList(item)
+        | ArrowDataType::FixedSizeList(item, _) => {
+            if let Some((name, child)) = child("item") {
+                check_lossless(format!("{path}.{name}"), child, item, lossy_utf8, warnings);
+            }
+        }
+        ArrowDataType::Map(entries, _) => match entries.data_type() {
+            ArrowDataType::Struct(fields) if fields.len() == 2 => {
+                let fields = if keys_first(fields) {
+                    [&fields[0], &fields[1]]
+                } else {
+                    [&fields[1], &fields[0]]
+                };
+                for ((name, child), field) in children.iter().zip(fields) {
+                    check_lossless(format!("{path}.{name}"), child, field, lossy_utf8, warnings);
+                }
+            }
+            _ => {}
+        },
+        // Runs are found after decoding into the values type
+        ArrowDataType::RunEndEncoded(_, values) => {
+            check_lossless(path, data_type, values, lossy_utf8, warnings)
+        }
+        _ => {}
+    }
+}
+
+fn find_path(path: String, data_type: &DataType, column_index: usize) -> Option<String> {
+    if data_type.column_index() == column_index {
+        return Some(path);
+    }
+    data_type
+        .named_children()
+        .into_iter()
+        .find_map(|(name, child)| find_path(format!("{path}.{name}"), child, column_index))
+}
+
+fn write_tree(tree: &mut String, name: &str, data_type: &DataType, depth: usize) {
+    let indent = "  ".repeat(depth);
+    let column_index = data_type.column_index();
+    let type_name = data_type.type_name();
+    let children = data_type.named_children();
+    if children.is_empty() {
+        let arrow_type = data_type.to_arrow_data_type();
+        tree.push_str(&format!(
+            "\n{indent}{column_index}: {name} ({type_name}) -> {arrow_type}"
+        ));
+    } else {
+        tree.push_str(&format!("\n{indent}{column_index}: {name} ({type_name})"));
+        for (child_name, child) in children {
+            write_tree(tree, &child_name, child, depth + 1);
+        }
+    }
 }
 
 impl Display for RootDataType {
@@ -84,6 +353,8 @@ impl Display for RootDataType {
 pub struct NamedColumn {
     name: String,
     data_type: DataType,
+    /// Key/value attributes of the ORC type of the column
+    attributes: HashMap<String, String>,
 }
 
 impl NamedColumn {
@@ -94,6 +365,38 @@ impl NamedColumn {
     pub fn data_type(&self) -> &DataType {
         &self.data_type
     }
+
+    /// Attributes attached to the ORC type of this column by the writer, e.g. to
+    /// record an extension type.
+    pub fn attributes(&self) -> &HashMap<String, String> {
+        &self.attributes
+    }
+
+    /// The nullable Arrow field this column is decoded into by default, with the
+    /// type attributes as field metadata, so that Arrow extension types (set with
+    /// the `ARROW:extension:name` attribute) are kept. Integer columns with the
+    /// [`INTERVAL_UNIT_ATTRIBUTE`] are Arrow intervals.
+    pub fn to_arrow_field(&self) -> Field {
+        let interval_unit = self
+            .attributes
+            .get(INTERVAL_UNIT_ATTRIBUTE)
+            .map(String::as_str);
+        let data_type = match (interval_unit, &self.data_type) {
+            (
+                Some("YEAR_MONTH"),
+                DataType::Byte { .. } | DataType::Short { .. } | DataType::Int { .. },
+            ) => ArrowDataType::Interval(IntervalUnit::YearMonth),
+            (
+                Some("DAY_TIME"),
+                DataType::Byte { .. }
+                | DataType::Short { .. }
+                | DataType::Int { .. }
+                | DataType::Long { .. },
+            ) => ArrowDataType::Interval(IntervalUnit::DayTime),
+            _ => self.data_type.to_arrow_data_type(),
+        };
+        Field::new(self.name(), data_type, true).with_metadata(self.attributes.clone())
+    }
 }
 
 impl Display for NamedColumn {
@@ -102,6 +405,41 @@ impl Display for NamedColumn {
     }
 }
 
+/// Check the types form a tree rooted at a struct before recursing into them.
+/// Types are listed in pre-order, so subtypes always come after their parent,
+/// which also rules out cycles.
+fn validate_type_graph(types: &[proto::Type]) -> Result<()> {
+    ensure!(
+        types[0].kind() == proto::r#type::Kind::Struct,
+        CorruptSchemaSnafu {
+            msg: format!("Root type must be a struct, found {:?}", types[0].kind()),
+        }
+    );
+    for (index, ty) in types.iter().enumerate() {
+        for &subtype in &ty.subtypes {
+            let subtype = subtype as usize;
+            ensure!(
+                subtype < types.len(),
+                CorruptSchemaSnafu {
+                    msg: format!(
+                        "Type {index} references subtype {subtype}, but there are only {} types",
+                        types.len()
+                    ),
+                }
+            );
+            ensure!(
+                subtype > index,
+                CorruptSchemaSnafu {
+                    msg: format!(
+                        "Type {index} references subtype {subtype}, which doesn't come after it"
+                    ),
+                }
+            );
+        }
+    }
+    Ok(())
+}
+
 /// Helper function since this is duplicated for [`RootDataType`] and [`DataType::Struct`]
 /// parsing from proto.
 fn parse_struct_children_from_proto(
@@ -129,7 +467,16 @@ fn parse_struct_children_from_proto(
             let index = index as usize;
             let name = name.to_owned();
             let data_type = DataType::from_proto(types, index)?;
-            Ok(NamedColumn { name, data_type })
+            let attributes = types[index]
+                .attributes
+                .iter()
+                .map(|pair| (pair.key().to_owned(), pair.value().to_owned()))
+                .collect();
+            Ok(NamedColumn {
+                name,
+                data_type,
+                attributes,
+            })
         })
         .collect::<Result<Vec<_>>>()?;
     Ok(children)
@@ -268,18 +615,17 @@ impl DataType {
             | DataType::Date { .. } => vec![],
             DataType::Struct { children, .. } => children
                 .iter()
-                .flat_map(|col| col.data_type().children_indices())
+                .flat_map(|col| col.data_type().all_indices())
                 .collect(),
             DataType::List { child, .. } => child.all_indices(),
             DataType::Map { key, value, .. } => {
-                let mut indices = key.children_indices();
-                indices.extend(value.children_indices());
+                let mut indices = key.all_indices();
+                indices.extend(value.all_indices());
                 indices
             }
-            DataType::Union { variants, .. } => variants
-                .iter()
-                .flat_map(|dt| dt.children_indices())
-                .collect(),
+            DataType::Union { variants, .. } => {
+                variants.iter().flat_map(|dt| dt.all_indices()).collect()
+            }
         }
     }
 
@@ -377,10 +723,12 @@ impl DataType {
                     variants,
                 }
             }
+            // Files written before Hive 0.13 have decimals without precision nor
+            // scale, which are read with the defaults of the Java implementation
             Kind::Decimal => Self::Decimal {
                 column_index,
-                precision: ty.precision(),
-                scale: ty.scale(),
+                precision: ty.precision.unwrap_or(DEFAULT_DECIMAL_PRECISION),
+                scale: ty.scale.unwrap_or(DEFAULT_DECIMAL_SCALE),
             },
             Kind::Date => Self::Date { column_index },
             Kind::Varchar => Self::Varchar {
@@ -396,6 +744,67 @@ impl DataType {
         Ok(dt)
     }
 
+    /// Name of the type as written in an ORC schema string, e.g. `map<string,int>`.
+    pub fn type_name(&self) -> String {
+        match self {
+            DataType::Boolean { .. } => "boolean".to_owned(),
+            DataType::Byte { .. } => "tinyint".to_owned(),
+            DataType::Short { .. } => "smallint".to_owned(),
+            DataType::Int { .. } => "int".to_owned(),
+            DataType::Long { .. } => "bigint".to_owned(),
+            DataType::Float { .. } => "float".to_owned(),
+            DataType::Double { .. } => "double".to_owned(),
+            DataType::String { .. } => "string".to_owned(),
+            DataType::Varchar { max_length, .. } => format!("varchar({max_length})"),
+            DataType::Char { max_length, .. } => format!("char({max_length})"),
+            DataType::Binary { .. } => "binary".to_owned(),
+            DataType::Decimal {
+                precision, scale, ..
+            } => format!("decimal({precision},{scale})"),
+            DataType::Timestamp { .. } => "timestamp".to_owned(),
+            DataType::TimestampWithLocalTimezone { .. } => {
+                "timestamp with local time zone".to_owned()
+            }
+            DataType::Date { .. } => "date".to_owned(),
+            DataType::Struct { children, .. } => {
+                let children = children
+                    .iter()
+                    .map(|col| format!("{}:{}", col.name(), col.data_type().type_name()))
+                    .collect::<Vec<_>>();
+                format!("struct<{}>", children.join(","))
+            }
+            DataType::List { child, .. } => format!("list<{}>", child.type_name()),
+            DataType::Map { key, value, .. } => {
+                format!("map<{},{}>", key.type_name(), value.type_name())
+            }
+            DataType::Union { variants, .. } => {
+                let variants = variants.iter().map(DataType::type_name).collect::<Vec<_>>();
+                format!("uniontype<{}>", variants.join(","))
+            }
+        }
+    }
+
+    /// Direct children of nested types, named as their Arrow fields.
+    fn named_children(&self) -> Vec<(String, &DataType)> {
+        match self {
+            DataType::Struct { children, .. } => children
+                .iter()
+                .map(|col| (col.name().to_owned(), col.data_type()))
+                .collect(),
+            DataType::List { child, .. } => vec![("item".to_owned(), child.as_ref())],
+            DataType::Map { key, value, .. } => vec![
+                ("key".to_owned(), key.as_ref()),
+                ("value".to_owned(), value.as_ref()),
+            ],
+            DataType::Union { variants, .. } => variants
+                .iter()
+                .enumerate()
+                .map(|(index, variant)| (index.to_string(), variant))
+                .collect(),
+            _ => vec![],
+        }
+    }
+
     pub fn to_arrow_data_type(&self) -> ArrowDataType {
         match self {
             DataType::Boolean { .. } => ArrowDataType::Boolean,
@@ -413,19 +822,14 @@ impl DataType {
                 precision, scale, ..
             } => ArrowDataType::Decimal128(*precision as u8, *scale as i8),
             DataType::Timestamp { .. } => ArrowDataType::Timestamp(TimeUnit::Nanosecond, None),
+            // Instants are stored relative to UTC, unlike timestamps which have no
+            // time zone, so only they get one
             DataType::TimestampWithLocalTimezone { .. } => {
-                // TODO: get writer timezone
-                ArrowDataType::Timestamp(TimeUnit::Nanosecond, None)
+                ArrowDataType::Timestamp(TimeUnit::Nanosecond, Some("UTC".into()))
             }
             DataType::Date { .. } => ArrowDataType::Date32,
             DataType::Struct { children, .. } => {
-                let children = children
-                    .iter()
-                    .map(|col| {
-                        let dt = col.data_type().to_arrow_data_type();
-                        Field::new(col.name(), dt, true)
-                    })
-                    .collect();
+                let children = children.iter().map(NamedColumn::to_arrow_field).collect();
                 ArrowDataType::Struct(children)
             }
             DataType::List { child, .. } => {
@@ -439,7 +843,7 @@ impl DataType {
                 let value = Field::new("value", value, true);
 
                 let dt = ArrowDataType::Struct(vec![key, value].into());
-                let dt = Arc::new(Field::new("entries", dt, true));
+                let dt = Arc::new(Field::new("entries", dt, false));
                 ArrowDataType::Map(dt, false)
             }
             DataType::Union { variants, .. } => {
@@ -1,27 +1,94 @@
+use std::ops::Range;
+
 use crate::{error, proto};
 
 /// Contains statistics for a specific column, for the entire file
 /// or for a specific stripe.
-#[derive(Debug, Clone)]
+///
+/// The default statistics record nothing, as for columns without statistics.
+#[derive(Debug, Clone, Default)]
 pub struct ColumnStatistics {
-    number_of_values: u64,
-    /// Use aid in 'IS NULL' predicates
-    has_null: bool,
+    /// `None` if not recorded by the writer
+    number_of_values: Option<u64>,
+    /// Use aid in 'IS NULL' predicates; `None` if not recorded by the writer
+    has_null: Option<bool>,
     type_statistics: Option<TypeStatistics>,
 }
 
 impl ColumnStatistics {
     pub fn number_of_values(&self) -> u64 {
+        self.number_of_values.unwrap_or_default()
+    }
+
+    /// Like [`Self::number_of_values`], but `None` if the writer didn't record it,
+    /// rather than assuming there are no values.
+    pub fn recorded_number_of_values(&self) -> Option<u64> {
         self.number_of_values
     }
 
     pub fn has_null(&self) -> bool {
+        self.has_null.unwrap_or_default()
+    }
+
+    /// Like [`Self::has_null`], but `None` if the writer didn't record whether
+    /// there are nulls, rather than assuming there are none.
+    pub fn recorded_has_null(&self) -> Option<bool> {
         self.has_null
     }
 
     pub fn type_statistics(&self) -> Option<&TypeStatistics> {
         self.type_statistics.as_ref()
     }
+
+    /// Minimum and maximum values of a string column, `None` for other columns or
+    /// if it has no values.
+    pub fn string_min_max(&self) -> Option<(&str, &str)> {
+        match &self.type_statistics {
+            Some(TypeStatistics::String { min, max, .. }) if self.number_of_values() > 0 => {
+                Some((min, max))
+            }
+            _ => None,
+        }
+    }
+
+    /// Same as [`Self::string_min_max`], as bytes (which compare the same way),
+    /// e.g. to prune string columns read as Arrow binary. ORC only records the
+    /// total length of binary columns, so they have no minimum and maximum.
+    pub fn binary_min_max(&self) -> Option<(&[u8], &[u8])> {
+        self.string_min_max()
+            .map(|(min, max)| (min.as_bytes(), max.as_bytes()))
+    }
+}
+
+/// Ranges of rows of a stripe of `number_of_rows` rows whose row groups may
+/// satisfy `predicate`, according to the statistics of each row group of a column
+/// (see [`Column::stripe_row_group_statistics`](crate::arrow_reader::column::Column::stripe_row_group_statistics)).
+///
+/// Row groups hold `row_index_stride` rows, and adjacent matching row groups are
+/// merged into a single range. Row groups without statistics may always match.
+pub fn matching_row_ranges(
+    row_group_statistics: &[ColumnStatistics],
+    row_index_stride: usize,
+    number_of_rows: usize,
+    predicate: impl Fn(&ColumnStatistics) -> bool,
+) -> Vec<Range<usize>> {
+    let row_index_stride = match row_index_stride {
+        // A single row group, without a row index
+        0 => number_of_rows.max(1),
+        stride => stride,
+    };
+    let mut ranges: Vec<Range<usize>> = vec![];
+    for (row_group, start) in (0..number_of_rows).step_by(row_index_stride).enumerate() {
+        if !row_group_statistics.get(row_group).map_or(true, &predicate) {
+            continue;
+        }
+        let end = (start + row_index_stride).min(number_of_rows);
+        match ranges.last_mut() {
+            Some(range) if range.end == start => range.end = end,
+            _ => ranges.push(start..end),
+        }
+    }
+    ranges
 }
 
 #[derive(Debug, Clone)]
@@ -138,8 +205,8 @@ impl TryFrom<&proto::ColumnStatistics> for ColumnStatistics {
                 })
         };
         Ok(Self {
-            number_of_values: value.number_of_values(),
-            has_null: value.has_null(),
+            number_of_values: value.number_of_values,
+            has_null: value.has_null,
             type_statistics,
         })
     }
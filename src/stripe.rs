@@ -1,23 +1,34 @@
-use std::{collections::HashMap, io::Read, sync::Arc};
+use std::{
+    collections::{HashMap, HashSet},
+    io::Read,
+    ops::Range,
+    sync::Arc,
+};
 
 use bytes::Bytes;
 use prost::Message;
 use snafu::{OptionExt, ResultExt};
 
 use crate::{
-    arrow_reader::column::Column,
+    arrow_reader::{column::Column, RowGroupFilter},
     error::{self, IoSnafu},
-    error::{InvalidColumnSnafu, Result},
+    error::{CorruptStripeFooterSnafu, EncryptionUnsupportedSnafu, InvalidColumnSnafu, Result},
     proto::{self, stream::Kind, StripeFooter},
     reader::{
         decompress::{Compression, Decompressor},
         metadata::FileMetadata,
-        ChunkReader,
+        ByteRangeSource, ChunkReader,
     },
-    schema::RootDataType,
-    statistics::ColumnStatistics,
+    schema::{DataType, RootDataType},
+    statistics::{ColumnStatistics, TypeStatistics},
+};
+
+pub(crate) use self::row_groups::{
+    decode_row_indexes, row_index_streams, RowGroupPlan, StreamPosition,
 };
 
+mod row_groups;
+
 /// Stripe metadata parsed from the file tail metadata sections.
 /// Does not contain the actual stripe bytes, as those are decoded
 /// when they are required.
@@ -65,6 +76,11 @@ impl StripeMetadata {
     pub fn footer_offset(&self) -> u64 {
         self.offset + self.index_length + self.data_length
     }
+
+    /// Length of the whole stripe in the file: its index, data and footer.
+    pub fn total_length(&self) -> u64 {
+        self.index_length + self.data_length + self.footer_length
+    }
 }
 
 impl TryFrom<(&proto::StripeInformation, &proto::StripeStatistics)> for StripeMetadata {
@@ -93,9 +109,31 @@ pub struct Stripe {
     pub(crate) footer: Arc<StripeFooter>,
     pub(crate) columns: Vec<Column>,
     pub(crate) stripe_offset: usize,
-    /// <(ColumnId, Kind), Bytes>
     pub(crate) stream_map: Arc<StreamMap>,
     pub(crate) number_of_rows: usize,
+    /// Statistics of all columns (not only the projected ones) in this stripe
+    pub(crate) column_statistics: Vec<ColumnStatistics>,
+}
+
+/// Rows of a stripe to decode, when only reading the row groups selected by a
+/// [`RowGroupFilter`]: each range of adjacent row groups read is decoded as a stripe
+/// of its own.
+#[derive(Debug)]
+pub struct StripeSelection {
+    /// Rows of the whole stripe
+    pub(crate) number_of_rows: usize,
+    /// Rows of the stripe in each range, and the stripe decoding them
+    pub(crate) parts: Vec<(Range<usize>, Stripe)>,
+}
+
+impl StripeSelection {
+    /// Selection of all the rows of a stripe.
+    pub(crate) fn whole(stripe: Stripe) -> Self {
+        Self {
+            number_of_rows: stripe.number_of_rows,
+            parts: vec![(0..stripe.number_of_rows, stripe)],
+        }
+    }
 }
 
 impl Stripe {
@@ -106,12 +144,14 @@ impl Stripe {
         stripe: usize,
         info: &StripeMetadata,
     ) -> Result<Self> {
+        ensure_columns_unencrypted(file_metadata, projected_data_type)?;
         let compression = file_metadata.compression();
 
         let footer = reader
             .get_bytes(info.footer_offset(), info.footer_length())
             .context(IoSnafu)?;
-        let footer = Arc::new(deserialize_stripe_footer(&footer, compression)?);
+        let footer = Arc::new(deserialize_stripe_footer(&footer, compression.as_ref())?);
+        ensure_column_encodings(&footer, projected_data_type)?;
 
         //TODO(weny): add tz
         let columns = projected_data_type
@@ -120,16 +160,34 @@ impl Stripe {
             .map(|col| Column::new(col.name(), col.data_type(), &footer, info.number_of_rows()))
             .collect();
 
-        let mut stream_map = HashMap::new();
+        let projected_column_ids = projected_data_type
+            .children()
+            .iter()
+            .flat_map(|col| col.data_type().all_indices())
+            .collect::<HashSet<_>>();
+
+        let source = reader.byte_range_source();
+        let mut stream_map = match &source {
+            Some(source) => StreamMap::new_incremental(compression, source.clone()),
+            None => StreamMap::new(compression),
+        };
         let mut stream_offset = info.offset();
         for stream in &footer.streams {
             let length = stream.length();
             let column_id = stream.column();
             let kind = stream.kind();
-            let data = Column::read_stream(reader, stream_offset, length)?;
-
-            // TODO(weny): filter out unused streams.
-            stream_map.insert((column_id, kind), data);
+            // The present stream of the root struct nulls whole rows, so is always read
+            let is_root_present = column_id == 0 && kind == Kind::Present;
+            if !projected_column_ids.contains(&(column_id as usize)) && !is_root_present {
+                stream_offset += length;
+                continue;
+            }
+            if source.is_some() {
+                stream_map.insert_incremental(column_id, kind, stream_offset, length);
+            } else {
+                let data = Column::read_stream(reader, stream_offset, length)?;
+                stream_map.insert(column_id, kind, data);
+            }
 
             stream_offset += length;
         }
@@ -138,14 +196,116 @@ impl Stripe {
             footer,
             columns,
             stripe_offset: stripe,
-            stream_map: Arc::new(StreamMap {
-                inner: stream_map,
-                compression,
-            }),
+            stream_map: Arc::new(stream_map),
+            number_of_rows: info.number_of_rows() as usize,
+            column_statistics: info.column_statistics().to_vec(),
+        })
+    }
+
+    /// Like [`Self::new`], only reading the row groups for which `filter` returns
+    /// `true`, given their statistics from the row index. Each range of consecutive
+    /// row groups read is a stripe of its own, holding only the bytes of the streams
+    /// needed to decode its rows.
+    pub(crate) fn new_row_groups<R: ChunkReader>(
+        reader: &mut R,
+        file_metadata: &Arc<FileMetadata>,
+        projected_data_type: &RootDataType,
+        stripe: usize,
+        info: &StripeMetadata,
+        filter: &RowGroupFilter,
+    ) -> Result<StripeSelection> {
+        ensure_columns_unencrypted(file_metadata, projected_data_type)?;
+        let compression = file_metadata.compression();
+
+        let footer = reader
+            .get_bytes(info.footer_offset(), info.footer_length())
+            .context(IoSnafu)?;
+        let footer = Arc::new(deserialize_stripe_footer(&footer, compression.as_ref())?);
+        ensure_column_encodings(&footer, projected_data_type)?;
+
+        let mut row_indexes = vec![];
+        for (column_id, offset, length) in row_index_streams(&footer, info) {
+            let data = reader.get_bytes(offset, length).context(IoSnafu)?;
+            row_indexes.push((column_id, data));
+        }
+        let row_indexes = decode_row_indexes(&footer, row_indexes, compression.as_ref())?;
+        let plan = RowGroupPlan::try_new(
+            &footer,
+            info,
+            stripe,
+            projected_data_type,
+            &row_indexes,
+            file_metadata,
+            filter,
+        )?;
+
+        let source = reader.byte_range_source();
+        // Streams read whole, such as dictionaries, are shared by all ranges
+        let mut whole_streams = HashMap::new();
+        let mut parts = vec![];
+        for (rows, reads) in plan.ranges {
+            let mut stream_map = match &source {
+                Some(source) => StreamMap::new_incremental(compression.clone(), source.clone()),
+                None => StreamMap::new(compression.clone()),
+            };
+            for read in reads {
+                let data = if source.is_some() {
+                    StreamData::Incremental {
+                        offset: read.offset,
+                        length: read.length,
+                    }
+                } else if let Some(data) = whole_streams.get(&read.offset) {
+                    StreamData::Loaded(Bytes::clone(data))
+                } else {
+                    let data = Column::read_stream(reader, read.offset, read.length)?;
+                    if read.position.is_none() {
+                        whole_streams.insert(read.offset, data.clone());
+                    }
+                    StreamData::Loaded(data)
+                };
+                stream_map.insert_data(read.column_id, read.kind, data, read.position);
+            }
+            let stripe = Self::with_rows(
+                &footer,
+                projected_data_type,
+                stripe,
+                rows.len(),
+                info,
+                stream_map,
+            );
+            parts.push((rows, stripe));
+        }
+        Ok(StripeSelection {
             number_of_rows: info.number_of_rows() as usize,
+            parts,
         })
     }
 
+    /// Stripe decoding `number_of_rows` rows from the given streams, which may only
+    /// hold some row groups of the stripe.
+    pub(crate) fn with_rows(
+        footer: &Arc<StripeFooter>,
+        projected_data_type: &RootDataType,
+        stripe: usize,
+        number_of_rows: usize,
+        info: &StripeMetadata,
+        stream_map: StreamMap,
+    ) -> Self {
+        let columns = projected_data_type
+            .children()
+            .iter()
+            .map(|col| Column::new(col.name(), col.data_type(), footer, number_of_rows as u64))
+            .collect();
+        Self {
+            footer: footer.clone(),
+            columns,
+            stripe_offset: stripe,
+            stream_map: Arc::new(stream_map),
+            number_of_rows,
+            column_statistics: info.column_statistics().to_vec(),
+        }
+    }
+
     pub fn footer(&self) -> &Arc<StripeFooter> {
         &self.footer
     }
@@ -153,39 +313,491 @@ impl Stripe {
     pub fn stripe_offset(&self) -> usize {
         self.stripe_offset
     }
+
+    /// Projected root columns of this stripe.
+    pub fn columns(&self) -> &[Column] {
+        &self.columns
+    }
+
+    /// The root struct column, without its children, to read its own streams.
+    pub(crate) fn root_column(&self) -> Column {
+        let data_type = DataType::Struct {
+            column_index: 0,
+            children: vec![],
+        };
+        Column::new("", &data_type, &self.footer, self.number_of_rows as u64)
+    }
+
+    /// Bytes of the stream of the given kind for a column as stored in the file,
+    /// still compressed, e.g. to copy the stream into another file without
+    /// decoding it. `None` if the column has no such stream in this stripe, or
+    /// isn't projected.
+    pub fn raw_stream(&self, column: &Column, kind: Kind) -> Result<Option<Bytes>> {
+        self.stream_map.get_raw(column, kind)
+    }
+
+    /// Kinds and byte lengths of the streams present in this stripe for the given
+    /// column and all of its nested children, in the order they are stored.
+    ///
+    /// Useful for diagnosing decode errors caused by missing streams.
+    pub fn streams_for(&self, column: &Column) -> Vec<(Kind, usize)> {
+        let column_ids = column.data_type().all_indices();
+        self.footer
+            .streams
+            .iter()
+            .filter(|stream| column_ids.contains(&(stream.column() as usize)))
+            .map(|stream| (stream.kind(), stream.length() as usize))
+            .collect()
+    }
+
+    /// Dotted paths (see [`Column::children`] for the names of nested columns) and
+    /// dictionary sizes of the projected columns which are dictionary encoded in
+    /// this stripe, including nested ones.
+    pub fn dictionary_columns(&self) -> Vec<(String, usize)> {
+        fn visit(column: &Column, path: String, dictionaries: &mut Vec<(String, usize)>) {
+            if column.is_dictionary_encoded() {
+                dictionaries.push((path.clone(), column.dictionary_size()));
+            }
+            for child in column.children() {
+                visit(&child, format!("{path}.{}", child.name()), dictionaries);
+            }
+        }
+
+        let mut dictionaries = vec![];
+        for column in &self.columns {
+            visit(column, column.name().to_owned(), &mut dictionaries);
+        }
+        dictionaries
+    }
+
+    /// Total length of the data streams of the given column and all of its nested
+    /// children in this stripe, as stored in the file (so compressed, if the file
+    /// is). Index streams such as row indexes and bloom filters are excluded.
+    pub fn column_compressed_size(&self, column: &Column) -> u64 {
+        compressed_data_size(&self.footer, column.data_type())
+    }
+
+    /// Rough estimate of the memory taken by the Arrow arrays of the projected
+    /// columns once all rows of this stripe are decoded into their default Arrow
+    /// types, to size buffers ahead of decoding.
+    ///
+    /// Fixed width types are estimated from the number of values, and string and
+    /// binary columns from their total length in the stripe statistics (assuming
+    /// [`DEFAULT_VALUE_LENGTH`] bytes per value when missing).
+    pub fn estimated_decoded_bytes(&self) -> u64 {
+        self.columns
+            .iter()
+            .map(|column| self.estimated_size(column.data_type(), self.number_of_rows as u64))
+            .sum()
+    }
+
+    /// Estimated size of the array of a column with `slots` values, including nulls.
+    fn estimated_size(&self, data_type: &DataType, slots: u64) -> u64 {
+        let stats = self.column_statistics.get(data_type.column_index());
+        // Values of children of lists and maps, or of the column itself otherwise
+        let children_slots = match stats.and_then(ColumnStatistics::type_statistics) {
+            Some(TypeStatistics::Collection { total_children, .. }) => *total_children,
+            _ => slots,
+        };
+        let validity = (slots + 7) / 8;
+        let offsets = 4 * (slots + 1);
+        let fixed_width = |width: u64| validity + width * slots;
+        let variable_length = || {
+            let total_length = match stats.and_then(ColumnStatistics::type_statistics) {
+                Some(TypeStatistics::String { sum, .. } | TypeStatistics::Binary { sum }) => {
+                    *sum as u64
+                }
+                _ => DEFAULT_VALUE_LENGTH * slots,
+            };
+            validity + offsets + total_length
+        };
+
+        match data_type {
+            DataType::Boolean { .. } => validity + (slots + 7) / 8,
+            DataType::Byte { .. } => fixed_width(1),
+            DataType::Short { .. } => fixed_width(2),
+            DataType::Int { .. } | DataType::Float { .. } | DataType::Date { .. } => fixed_width(4),
+            DataType::Long { .. }
+            | DataType::Double { .. }
+            | DataType::Timestamp { .. }
+            | DataType::TimestampWithLocalTimezone { .. } => fixed_width(8),
+            DataType::Decimal { .. } => fixed_width(16),
+            DataType::String { .. }
+            | DataType::Varchar { .. }
+            | DataType::Char { .. }
+            | DataType::Binary { .. } => variable_length(),
+            DataType::Struct { children, .. } => {
+                validity
+                    + children
+                        .iter()
+                        .map(|child| self.estimated_size(child.data_type(), slots))
+                        .sum::<u64>()
+            }
+            DataType::List { child, .. } => {
+                validity + offsets + self.estimated_size(child, children_slots)
+            }
+            DataType::Map { key, value, .. } => {
+                validity
+                    + offsets
+                    + self.estimated_size(key, children_slots)
+                    + self.estimated_size(value, children_slots)
+            }
+            // Sparse unions: a type id per value, and every variant has all values
+            DataType::Union { variants, .. } => {
+                slots
+                    + variants
+                        .iter()
+                        .map(|variant| self.estimated_size(variant, slots))
+                        .sum::<u64>()
+            }
+        }
+    }
 }
 
-#[derive(Debug)]
+/// Length of string and binary values assumed when estimating decoded sizes
+/// without statistics.
+pub const DEFAULT_VALUE_LENGTH: u64 = 16;
+
+/// Where the bytes of a stream come from.
+#[derive(Debug, Clone, PartialEq)]
+pub enum StreamData {
+    /// Read when loading the stripe
+    Loaded(Bytes),
+    /// Left in the file, to be read incrementally from the stripe's
+    /// [`ByteRangeSource`] while decoding
+    Incremental { offset: u64, length: u64 },
+}
+
+/// Streams read for a stripe, indexed by column id so looking up the streams of a
+/// column doesn't depend on the total number of columns or streams.
 pub struct StreamMap {
-    pub inner: HashMap<(u32, Kind), Bytes>,
+    /// Kind and data of each stream, indexed by column id
+    pub inner: Vec<Vec<(Kind, StreamData)>>,
     pub compression: Option<Compression>,
+    source: Option<Arc<dyn ByteRangeSource>>,
+    /// Where decoding starts in the streams holding only the row groups read
+    positions: HashMap<(u32, Kind), StreamPosition>,
+}
+
+impl std::fmt::Debug for StreamMap {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("StreamMap")
+            .field("inner", &self.inner)
+            .field("compression", &self.compression)
+            .field("positions", &self.positions)
+            .finish_non_exhaustive()
+    }
 }
 
 impl StreamMap {
+    pub fn new(compression: Option<Compression>) -> Self {
+        Self {
+            inner: vec![],
+            compression: compression.map(|compression| compression.for_stripe()),
+            source: None,
+            positions: HashMap::new(),
+        }
+    }
+
+    /// Streams inserted with [`Self::insert_incremental`] are read from `source`.
+    pub fn new_incremental(
+        compression: Option<Compression>,
+        source: Arc<dyn ByteRangeSource>,
+    ) -> Self {
+        Self {
+            inner: vec![],
+            compression: compression.map(|compression| compression.for_stripe()),
+            source: Some(source),
+            positions: HashMap::new(),
+        }
+    }
+
+    pub fn insert(&mut self, column_id: u32, kind: Kind, data: Bytes) {
+        self.insert_data(column_id, kind, StreamData::Loaded(data), None);
+    }
+
+    pub fn insert_incremental(&mut self, column_id: u32, kind: Kind, offset: u64, length: u64) {
+        self.insert_data(
+            column_id,
+            kind,
+            StreamData::Incremental { offset, length },
+            None,
+        );
+    }
+
+    /// Insert a stream, which starts at the row index `position` of the first row
+    /// group read if only holding some row groups.
+    pub(crate) fn insert_data(
+        &mut self,
+        column_id: u32,
+        kind: Kind,
+        data: StreamData,
+        position: Option<StreamPosition>,
+    ) {
+        if let Some(position) = position {
+            self.positions.insert((column_id, kind), position);
+        }
+        let column_id = column_id as usize;
+        if column_id >= self.inner.len() {
+            self.inner.resize_with(column_id + 1, Vec::new);
+        }
+        self.inner[column_id].push((kind, data));
+    }
+
+    /// Raw (possibly compressed) data of the stream of the given kind for a column.
+    pub fn stream(&self, column_id: u32, kind: Kind) -> Option<&StreamData> {
+        // A column has at most a handful of streams, so a linear scan is cheapest
+        self.inner
+            .get(column_id as usize)?
+            .iter()
+            .find(|(stream_kind, _)| *stream_kind == kind)
+            .map(|(_, data)| data)
+    }
+
     pub fn get(&self, column: &Column, kind: Kind) -> Result<Decompressor> {
         self.get_opt(column, kind).context(InvalidColumnSnafu {
             name: column.name(),
         })
     }
 
+    /// Like [`Self::get`], without decompressing the stream, and `None` if the
+    /// column has no such stream.
+    pub fn get_raw(&self, column: &Column, kind: Kind) -> Result<Option<Bytes>> {
+        match self.stream(column.column_id(), kind) {
+            Some(StreamData::Loaded(data)) => Ok(Some(data.clone())),
+            Some(&StreamData::Incremental { offset, length }) => {
+                // Only inserted when created with a source
+                let Some(source) = &self.source else {
+                    return Ok(None);
+                };
+                let data = source.read_range(offset, length).context(IoSnafu)?;
+                Ok(Some(data))
+            }
+            None => Ok(None),
+        }
+    }
+
     pub fn get_opt(&self, column: &Column, kind: Kind) -> Option<Decompressor> {
-        let column_id = column.column_id();
+        let compression = self.compression.clone();
+        let decompressor = match self.stream(column.column_id(), kind)? {
+            StreamData::Loaded(data) => Decompressor::new(data.clone(), compression, vec![]),
+            &StreamData::Incremental { offset, length } => {
+                // Only inserted when created with a source
+                let source = self.source.clone()?;
+                Decompressor::new_incremental(source, offset, length, compression, vec![])
+            }
+        };
+        match self.positions.get(&(column.column_id(), kind)) {
+            Some(position) => {
+                Some(decompressor.with_start(position.decompressed_offset, position.values_to_skip))
+            }
+            None => Some(decompressor),
+        }
+    }
+}
 
-        self.inner
-            .get(&(column_id, kind))
-            .cloned()
-            .map(|data| Decompressor::new(data, self.compression, vec![]))
+/// Encrypted column data can't be decoded, so fail early if any projected column
+/// (or one of its children) is encrypted.
+pub(crate) fn ensure_columns_unencrypted(
+    file_metadata: &FileMetadata,
+    projected_data_type: &RootDataType,
+) -> Result<()> {
+    for &root in file_metadata.encrypted_column_roots() {
+        let encrypted = projected_data_type
+            .children()
+            .iter()
+            .find(|col| root == 0 || col.data_type().all_indices().contains(&root));
+        if let Some(col) = encrypted {
+            return EncryptionUnsupportedSnafu { name: col.name() }.fail();
+        }
     }
+    Ok(())
+}
+
+/// Stripe footers may list fewer column encodings than the file has columns, so
+/// fail early if the encoding of any projected column is missing.
+pub(crate) fn ensure_column_encodings(
+    footer: &StripeFooter,
+    projected_data_type: &RootDataType,
+) -> Result<()> {
+    for col in projected_data_type.children() {
+        let missing = col
+            .data_type()
+            .all_indices()
+            .into_iter()
+            .find(|&column_id| column_id >= footer.columns.len());
+        if let Some(column_id) = missing {
+            return CorruptStripeFooterSnafu {
+                msg: format!(
+                    "no encoding for column {column_id} (in column '{}'), only {} encodings",
+                    col.name(),
+                    footer.columns.len()
+                ),
+            }
+            .fail();
+        }
+    }
+    Ok(())
+}
+
+/// Whether the stream belongs to the index section of a stripe, rather than the
+/// data section.
+pub(crate) fn is_index_stream(kind: Kind) -> bool {
+    matches!(
+        kind,
+        Kind::RowIndex | Kind::BloomFilter | Kind::BloomFilterUtf8
+    )
+}
+
+/// Total length of the data streams of a column and its nested children.
+pub(crate) fn compressed_data_size(footer: &StripeFooter, data_type: &DataType) -> u64 {
+    let column_ids = data_type.all_indices();
+    footer
+        .streams
+        .iter()
+        .filter(|stream| {
+            !is_index_stream(stream.kind()) && column_ids.contains(&(stream.column() as usize))
+        })
+        .map(|stream| stream.length())
+        .sum()
 }
 
+/// Decompress and decode a stripe footer.
+///
+/// ORC has a single compression kind per file, set in the postscript: stripe
+/// footers have no field to declare another codec, and could not anyway as they
+/// are themselves compressed with the file's codec. So the footer and the streams
+/// of every stripe are always decompressed with `compression`.
 pub(crate) fn deserialize_stripe_footer(
     bytes: &[u8],
-    compression: Option<Compression>,
+    compression: Option<&Compression>,
 ) -> Result<StripeFooter> {
     let mut buffer = vec![];
     // TODO: refactor to not need Bytes::copy_from_slice
+    let compression = compression.map(Compression::for_stripe);
     Decompressor::new(Bytes::copy_from_slice(bytes), compression, vec![])
         .read_to_end(&mut buffer)
         .context(error::IoSnafu)?;
     StripeFooter::decode(buffer.as_slice()).context(error::DecodeProtoSnafu)
 }
+
+#[cfg(test)]
+mod tests {
+    use crate::error::OrcError;
+    use crate::projection::ProjectionMask;
+    use crate::proto::r#type::Kind as TypeKind;
+    use crate::proto::{Encryption, EncryptionVariant, Footer, Metadata, PostScript, Type};
+    use crate::reader::metadata::read_metadata;
+
+    use super::*;
+
+    fn encrypted_file_metadata() -> FileMetadata {
+        let types = vec![
+            Type {
+                kind: Some(TypeKind::Struct as i32),
+                subtypes: vec![1, 2],
+                field_names: vec!["a".to_owned(), "b".to_owned()],
+                ..Default::default()
+            },
+            Type {
+                kind: Some(TypeKind::Int as i32),
+                ..Default::default()
+            },
+            Type {
+                kind: Some(TypeKind::Int as i32),
+                ..Default::default()
+            },
+        ];
+        let footer = Footer {
+            types,
+            encryption: Some(Encryption {
+                variants: vec![EncryptionVariant {
+                    root: Some(2),
+                    ..Default::default()
+                }],
+                ..Default::default()
+            }),
+            ..Default::default()
+        };
+        FileMetadata::from_proto(&PostScript::default(), &footer, &Metadata::default(), None)
+            .unwrap()
+    }
+
+    #[test]
+    fn reject_encrypted_column() {
+        let file_metadata = Arc::new(encrypted_file_metadata());
+        assert_eq!(file_metadata.encrypted_column_roots(), &[2]);
+        let info = StripeMetadata::try_from((
+            &proto::StripeInformation::default(),
+            &proto::StripeStatistics::default(),
+        ))
+        .unwrap();
+
+        let err = Stripe::new(
+            &mut Bytes::new(),
+            &file_metadata,
+            file_metadata.root_data_type(),
+            0,
+            &info,
+        )
+        .unwrap_err();
+        assert!(
+            matches!(&err, OrcError::EncryptionUnsupported { name, .. } if name == "b"),
+            "{err}"
+        );
+
+        // Unencrypted columns can still be read
+        let root = file_metadata.root_data_type();
+        let projected = root.project(&ProjectionMask::named_roots(root, &["a"]));
+        assert!(ensure_columns_unencrypted(&file_metadata, &projected).is_ok());
+    }
+
+    #[test]
+    fn stream_map_matches_naive_lookup() {
+        let path = format!("{}/tests/basic/data/test.orc", env!("CARGO_MANIFEST_DIR"));
+        let mut file = std::fs::File::open(path).unwrap();
+        let file_metadata = Arc::new(read_metadata(&mut file).unwrap());
+        let info = &file_metadata.stripe_metadatas()[0];
+        let stripe = Stripe::new(
+            &mut file,
+            &file_metadata,
+            file_metadata.root_data_type(),
+            0,
+            info,
+        )
+        .unwrap();
+
+        // Locate each stream by scanning the footer, as if there was no index.
+        // Only streams of projected columns are read, which excludes the root struct
+        // except for its present stream.
+        let mut offset = info.offset();
+        for stream in &stripe.footer().streams {
+            let expected = (stream.column() != 0 || stream.kind() == Kind::Present)
+                .then(|| StreamData::Loaded(file.get_bytes(offset, stream.length()).unwrap()));
+            let actual = stripe.stream_map.stream(stream.column(), stream.kind());
+            assert_eq!(actual, expected.as_ref(), "{stream:?}");
+            offset += stream.length();
+        }
+
+        let num_columns = stripe.footer().columns.len() as u32;
+        for column_id in 1..=num_columns {
+            for kind in [
+                Kind::Present,
+                Kind::Data,
+                Kind::Length,
+                Kind::DictionaryData,
+            ] {
+                let in_footer = stripe
+                    .footer()
+                    .streams
+                    .iter()
+                    .any(|stream| stream.column() == column_id && stream.kind() == kind);
+                assert_eq!(
+                    stripe.stream_map.stream(column_id, kind).is_some(),
+                    in_footer,
+                    "column {column_id} {kind:?}"
+                );
+            }
+        }
+    }
+}
@@ -0,0 +1,419 @@
+//! Planning the reads of only some row groups of a stripe, starting the streams of
+//! each range of row groups at the positions recorded for it in the row index.
+
+use std::collections::HashMap;
+use std::io::Read;
+use std::ops::Range;
+
+use bytes::Bytes;
+use prost::Message;
+use snafu::ResultExt;
+
+use crate::arrow_reader::RowGroupFilter;
+use crate::error::{DecodeProtoSnafu, IoSnafu, Result};
+use crate::proto::column_encoding::Kind as ColumnEncodingKind;
+use crate::proto::{stream::Kind, RowIndex, StripeFooter};
+use crate::reader::decompress::{Compression, Decompressor};
+use crate::reader::metadata::FileMetadata;
+use crate::schema::{DataType, RootDataType};
+use crate::statistics::ColumnStatistics;
+
+use super::{is_index_stream, StripeMetadata};
+
+/// Largest number of bytes of a run of integers, bytes or booleans: a patched base
+/// run of RLE v2, with its header, base, 512 values and 31 patches.
+const MAX_RUN_BYTES: usize = 4 + 8 + 512 * 8 + 31 * 8;
+
+/// Position in a stream of the first value of a row group, as recorded in the
+/// row index.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) struct StreamPosition {
+    /// Offset in the stream of the compressed chunk holding the first value, or of
+    /// the first value itself if uncompressed
+    pub(crate) offset: u64,
+    /// Offset of the run holding the first value in its decompressed chunk
+    pub(crate) decompressed_offset: usize,
+    /// Number of values of that run before the first value
+    pub(crate) values_to_skip: u64,
+}
+
+/// How a stream records the position of a value within its run.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum RunSkip {
+    /// Not run length encoded, values start where recorded
+    None,
+    /// Number of values to skip in the run
+    Values,
+    /// Number of bytes to skip in the byte run, then of bits in the next byte
+    Bits,
+}
+
+impl RunSkip {
+    fn position_entries(self) -> usize {
+        match self {
+            RunSkip::None => 0,
+            RunSkip::Values => 1,
+            RunSkip::Bits => 2,
+        }
+    }
+
+    /// Bytes past the position of the next row group which may still hold values
+    /// of the previous one: the rest of a run, or of a compressed chunk.
+    fn slop(self, compression: Option<&Compression>) -> u64 {
+        let run_bytes = match self {
+            RunSkip::None => 0,
+            RunSkip::Values | RunSkip::Bits => MAX_RUN_BYTES,
+        };
+        match compression {
+            None => run_bytes as u64,
+            Some(compression) => {
+                // A chunk header is 3 bytes, and chunks are stored uncompressed when
+                // compressing them doesn't make them smaller
+                let block_size = compression.block_size().max(1);
+                let chunks = 1 + (run_bytes + block_size - 1) / block_size;
+                (chunks * (3 + block_size)) as u64
+            }
+        }
+    }
+}
+
+/// Streams of a column holding a position in the row index, with how they record
+/// positions within runs, in the order of the positions. Those of the present
+/// stream come first, if the column has one.
+fn positioned_streams(
+    data_type: Option<&DataType>,
+    encoding: ColumnEncodingKind,
+) -> Vec<(Kind, RunSkip)> {
+    let is_dictionary = matches!(
+        encoding,
+        ColumnEncodingKind::Dictionary | ColumnEncodingKind::DictionaryV2
+    );
+    match data_type {
+        // The root struct, which isn't projected
+        None | Some(DataType::Struct { .. }) => vec![],
+        Some(DataType::Boolean { .. }) => vec![(Kind::Data, RunSkip::Bits)],
+        Some(
+            DataType::Byte { .. }
+            | DataType::Short { .. }
+            | DataType::Int { .. }
+            | DataType::Long { .. }
+            | DataType::Date { .. }
+            | DataType::Union { .. },
+        ) => vec![(Kind::Data, RunSkip::Values)],
+        Some(DataType::Float { .. } | DataType::Double { .. }) => vec![(Kind::Data, RunSkip::None)],
+        Some(DataType::String { .. } | DataType::Varchar { .. } | DataType::Char { .. })
+            if is_dictionary =>
+        {
+            vec![(Kind::Data, RunSkip::Values)]
+        }
+        Some(
+            DataType::String { .. }
+            | DataType::Varchar { .. }
+            | DataType::Char { .. }
+            | DataType::Binary { .. },
+        ) => vec![(Kind::Data, RunSkip::None), (Kind::Length, RunSkip::Values)],
+        Some(DataType::Decimal { .. }) => {
+            vec![
+                (Kind::Data, RunSkip::None),
+                (Kind::Secondary, RunSkip::Values),
+            ]
+        }
+        Some(DataType::Timestamp { .. } | DataType::TimestampWithLocalTimezone { .. }) => {
+            vec![
+                (Kind::Data, RunSkip::Values),
+                (Kind::Secondary, RunSkip::Values),
+            ]
+        }
+        Some(DataType::List { .. } | DataType::Map { .. }) => vec![(Kind::Length, RunSkip::Values)],
+    }
+}
+
+/// Projected columns and their nested children, by column id.
+fn column_data_types(projected_data_type: &RootDataType) -> HashMap<u32, &DataType> {
+    fn visit<'a>(data_type: &'a DataType, data_types: &mut HashMap<u32, &'a DataType>) {
+        data_types.insert(data_type.column_index() as u32, data_type);
+        match data_type {
+            DataType::Struct { children, .. } => children
+                .iter()
+                .for_each(|child| visit(child.data_type(), data_types)),
+            DataType::List { child, .. } => visit(child, data_types),
+            DataType::Map { key, value, .. } => {
+                visit(key, data_types);
+                visit(value, data_types);
+            }
+            DataType::Union { variants, .. } => variants
+                .iter()
+                .for_each(|variant| visit(variant, data_types)),
+            _ => {}
+        }
+    }
+    let mut data_types = HashMap::new();
+    for col in projected_data_type.children() {
+        visit(col.data_type(), &mut data_types);
+    }
+    data_types
+}
+
+/// A stream, or the part of a stream needed for a range of row groups.
+#[derive(Debug, Clone)]
+pub(crate) struct StreamRead {
+    pub(crate) column_id: u32,
+    pub(crate) kind: Kind,
+    /// Offset of the bytes to read in the file
+    pub(crate) offset: u64,
+    pub(crate) length: u64,
+    /// Position of the first row group in the stream, or `None` to read it whole
+    pub(crate) position: Option<StreamPosition>,
+}
+
+/// Column ids, offsets in the file and lengths of the row index streams of a stripe.
+pub(crate) fn row_index_streams(
+    footer: &StripeFooter,
+    info: &StripeMetadata,
+) -> Vec<(u32, u64, u64)> {
+    let mut offset = info.offset();
+    let mut streams = vec![];
+    for stream in &footer.streams {
+        if stream.kind() == Kind::RowIndex {
+            streams.push((stream.column(), offset, stream.length()));
+        }
+        offset += stream.length();
+    }
+    streams
+}
+
+/// Decode the row index streams read for each column, indexed by column id, `None`
+/// for the columns without a row index.
+pub(crate) fn decode_row_indexes(
+    footer: &StripeFooter,
+    streams: Vec<(u32, Bytes)>,
+    compression: Option<&Compression>,
+) -> Result<Vec<Option<RowIndex>>> {
+    let mut row_indexes = vec![None; footer.columns.len()];
+    for (column_id, data) in streams {
+        let Some(row_index) = row_indexes.get_mut(column_id as usize) else {
+            continue;
+        };
+        let mut buffer = vec![];
+        Decompressor::new(data, compression.map(Compression::for_stripe), vec![])
+            .read_to_end(&mut buffer)
+            .context(IoSnafu)?;
+        *row_index = Some(RowIndex::decode(buffer.as_slice()).context(DecodeProtoSnafu)?);
+    }
+    Ok(row_indexes)
+}
+
+/// Ranges of rows of a stripe selected by a [`RowGroupFilter`], with the streams to
+/// read to decode each of them.
+#[derive(Debug)]
+pub(crate) struct RowGroupPlan {
+    pub(crate) ranges: Vec<(Range<usize>, Vec<StreamRead>)>,
+}
+
+impl RowGroupPlan {
+    /// Select the row groups to read with `filter`, reading the streams of the
+    /// projected columns from the position of each range of adjacent row groups.
+    /// Without a usable position for every stream, ranges can't be read on their
+    /// own, so the whole stripe is read if any row group is selected.
+    pub(crate) fn try_new(
+        footer: &StripeFooter,
+        info: &StripeMetadata,
+        stripe: usize,
+        projected_data_type: &RootDataType,
+        row_indexes: &[Option<RowIndex>],
+        file_metadata: &FileMetadata,
+        filter: &RowGroupFilter,
+    ) -> Result<Self> {
+        let number_of_rows = info.number_of_rows() as usize;
+        let stride = file_metadata.row_index_stride().max(1);
+        let row_groups = (number_of_rows + stride - 1) / stride;
+
+        let mut selected: Vec<Range<usize>> = vec![];
+        for row_group in 0..row_groups {
+            let statistics = row_indexes
+                .iter()
+                .map(|row_index| {
+                    let statistics = row_index
+                        .as_ref()
+                        .and_then(|row_index| row_index.entry.get(row_group))
+                        .and_then(|entry| entry.statistics.as_ref());
+                    statistics.map_or(Ok(ColumnStatistics::default()), TryFrom::try_from)
+                })
+                .collect::<Result<Vec<_>>>()?;
+            if !filter(&statistics, stripe, row_group) {
+                continue;
+            }
+            match selected.last_mut() {
+                Some(groups) if groups.end == row_group => groups.end += 1,
+                _ => selected.push(row_group..row_group + 1),
+            }
+        }
+        if selected.is_empty() {
+            return Ok(Self { ranges: vec![] });
+        }
+
+        let data_types = column_data_types(projected_data_type);
+        let mut streams = vec![];
+        let mut offset = info.offset();
+        for stream in &footer.streams {
+            let (column_id, kind, length) = (stream.column(), stream.kind(), stream.length());
+            // The present stream of the root struct nulls whole rows, so is always read
+            let is_root_present = column_id == 0 && kind == Kind::Present;
+            if !is_index_stream(kind) && (data_types.contains_key(&column_id) || is_root_present) {
+                streams.push(StreamRead {
+                    column_id,
+                    kind,
+                    offset,
+                    length,
+                    position: None,
+                });
+            }
+            offset += length;
+        }
+
+        let compression = file_metadata.compression();
+        let positions = stream_positions(
+            footer,
+            &streams,
+            &data_types,
+            row_indexes,
+            row_groups,
+            compression.as_ref(),
+        );
+        let Some(positions) = positions else {
+            return Ok(Self {
+                ranges: vec![(0..number_of_rows, streams)],
+            });
+        };
+
+        let ranges = selected
+            .into_iter()
+            .map(|groups| {
+                let rows = groups.start * stride..(groups.end * stride).min(number_of_rows);
+                let reads = streams
+                    .iter()
+                    .zip(&positions)
+                    .map(|(stream, positions)| {
+                        let Some((skip, positions)) = positions else {
+                            return stream.clone();
+                        };
+                        let start = positions[groups.start];
+                        let end = match positions.get(groups.end) {
+                            Some(next) => {
+                                (next.offset + skip.slop(compression.as_ref())).min(stream.length)
+                            }
+                            None => stream.length,
+                        };
+                        StreamRead {
+                            offset: stream.offset + start.offset,
+                            length: end - start.offset,
+                            position: Some(start),
+                            ..stream.clone()
+                        }
+                    })
+                    .collect();
+                (rows, reads)
+            })
+            .collect();
+        Ok(Self { ranges })
+    }
+}
+
+/// Positions of each row group in each of `streams`, with how they record positions
+/// within runs, or `None` for the streams read whole, such as dictionaries. `None`
+/// overall if a stream which should have positions has none usable.
+#[allow(clippy::type_complexity)]
+fn stream_positions(
+    footer: &StripeFooter,
+    streams: &[StreamRead],
+    data_types: &HashMap<u32, &DataType>,
+    row_indexes: &[Option<RowIndex>],
+    row_groups: usize,
+    compression: Option<&Compression>,
+) -> Option<Vec<Option<(RunSkip, Vec<StreamPosition>)>>> {
+    let mut column_positions = HashMap::new();
+    for stream in streams {
+        if column_positions.contains_key(&stream.column_id) {
+            continue;
+        }
+        let column_id = stream.column_id;
+        let has_present = streams
+            .iter()
+            .any(|stream| stream.column_id == column_id && stream.kind == Kind::Present);
+        let encoding = footer.columns.get(column_id as usize)?.kind();
+        let mut layout = positioned_streams(data_types.get(&column_id).copied(), encoding);
+        if has_present {
+            layout.insert(0, (Kind::Present, RunSkip::Bits));
+        }
+        let row_index = row_indexes.get(column_id as usize)?.as_ref()?;
+        let positions = parse_positions(row_index, &layout, row_groups, compression.is_some())?;
+        column_positions.insert(column_id, positions);
+    }
+
+    streams
+        .iter()
+        .map(|stream| {
+            let Some(positions) = column_positions[&stream.column_id].get(&stream.kind) else {
+                return Some(None);
+            };
+            let (skip, positions) = positions.clone();
+            // Positions of later row groups can't be before those of earlier ones
+            let mut previous = 0;
+            for position in &positions {
+                if position.offset < previous || position.offset > stream.length {
+                    return None;
+                }
+                previous = position.offset;
+            }
+            Some(Some((skip, positions)))
+        })
+        .collect()
+}
+
+/// Split the positions of each row group of a column between its streams, `None`
+/// if they don't match the streams expected for the column.
+fn parse_positions(
+    row_index: &RowIndex,
+    layout: &[(Kind, RunSkip)],
+    row_groups: usize,
+    is_compressed: bool,
+) -> Option<HashMap<Kind, (RunSkip, Vec<StreamPosition>)>> {
+    if row_index.entry.len() != row_groups {
+        return None;
+    }
+    let entries_per_stream =
+        |skip: RunSkip| 1 + usize::from(is_compressed) + skip.position_entries();
+    let expected_entries: usize = layout
+        .iter()
+        .map(|&(_, skip)| entries_per_stream(skip))
+        .sum();
+
+    let mut positions: HashMap<_, _> = layout
+        .iter()
+        .map(|&(kind, skip)| (kind, (skip, Vec::with_capacity(row_groups))))
+        .collect();
+    for entry in &row_index.entry {
+        if entry.positions.len() != expected_entries {
+            return None;
+        }
+        let mut entries = entry.positions.iter().copied();
+        for &(kind, skip) in layout {
+            // Checked there are enough entries for every stream
+            let mut next = || entries.next().unwrap();
+            let offset = next();
+            let decompressed_offset = if is_compressed { next() } else { 0 };
+            let values_to_skip = match skip {
+                RunSkip::None => 0,
+                RunSkip::Values => next(),
+                RunSkip::Bits => next().saturating_mul(8).saturating_add(next()),
+            };
+            let position = StreamPosition {
+                offset,
+                decompressed_offset: usize::try_from(decompressed_offset).ok()?,
+                values_to_skip,
+            };
+            positions.get_mut(&kind)?.1.push(position);
+        }
+    }
+    Some(positions)
+}
@@ -0,0 +1,94 @@
+//! Reading fixtures served over HTTP by a local server.
+
+use std::convert::Infallible;
+use std::net::SocketAddr;
+
+use bytes::Bytes;
+use datafusion_orc::arrow_reader::ArrowReaderBuilder;
+use datafusion_orc::reader::http::HttpReader;
+use futures_util::TryStreamExt;
+use hyper::header::{CONTENT_LENGTH, CONTENT_RANGE, RANGE};
+use hyper::service::{make_service_fn, service_fn};
+use hyper::{Body, Method, Request, Response, Server, StatusCode};
+
+use crate::basic_path;
+
+/// Serve `data` on a local port, answering range requests with only the requested
+/// bytes if `ranges` is set, and with all of them otherwise.
+fn serve(data: Bytes, ranges: bool) -> SocketAddr {
+    let make_service = make_service_fn(move |_| {
+        let data = data.clone();
+        async move {
+            Ok::<_, Infallible>(service_fn(move |request| {
+                let response = respond(&request, &data, ranges);
+                async move { Ok::<_, Infallible>(response) }
+            }))
+        }
+    });
+    let server = Server::bind(&([127, 0, 0, 1], 0).into()).serve(make_service);
+    let address = server.local_addr();
+    tokio::spawn(server);
+    address
+}
+
+fn respond(request: &Request<Body>, data: &Bytes, ranges: bool) -> Response<Body> {
+    let range = request
+        .headers()
+        .get(RANGE)
+        .and_then(|range| range.to_str().ok()?.strip_prefix("bytes="))
+        .and_then(|range| {
+            let (start, end) = range.split_once('-')?;
+            Some(start.parse::<usize>().ok()?..end.parse::<usize>().ok()? + 1)
+        });
+    match range {
+        Some(range) if ranges && request.method() == Method::GET => {
+            let content_range = format!("bytes {}-{}/{}", range.start, range.end - 1, data.len());
+            Response::builder()
+                .status(StatusCode::PARTIAL_CONTENT)
+                .header(CONTENT_RANGE, content_range)
+                .body(data.slice(range).into())
+                .unwrap()
+        }
+        _ => Response::builder()
+            .header(CONTENT_LENGTH, data.len())
+            .body(data.clone().into())
+            .unwrap(),
+    }
+}
+
+#[tokio::test]
+pub async fn http_reader_test() {
+    let data = Bytes::from(std::fs::read(basic_path("test.orc")).unwrap());
+    let expected = ArrowReaderBuilder::try_new(data.clone())
+        .unwrap()
+        .build()
+        .collect::<Result<Vec<_>, _>>()
+        .unwrap();
+
+    let address = serve(data, true);
+    let reader = HttpReader::new(format!("http://{address}/test.orc")).unwrap();
+    let batches = ArrowReaderBuilder::try_new_async(reader)
+        .await
+        .unwrap()
+        .build_async()
+        .try_collect::<Vec<_>>()
+        .await
+        .unwrap();
+    assert_eq!(expected, batches);
+}
+
+#[tokio::test]
+pub async fn http_reader_without_ranges_test() {
+    let data = Bytes::from(std::fs::read(basic_path("test.orc")).unwrap());
+    let address = serve(data, false);
+    let reader = HttpReader::new(format!("http://{address}/test.orc")).unwrap();
+    let err = ArrowReaderBuilder::try_new_async(reader)
+        .await
+        .err()
+        .unwrap()
+        .to_string();
+    assert!(
+        err.contains("doesn't support range requests"),
+        "unexpected error: {err}"
+    );
+}
@@ -1,15 +1,56 @@
+use std::collections::{HashMap, HashSet};
 use std::fs::File;
+use std::ops::Range;
+use std::sync::{Arc, Mutex};
 
+use arrow::array::{
+    as_run_array, Array, ArrayRef, AsArray, BinaryArray, Float16Array, LargeBinaryArray,
+    LargeStringArray, StringArray, UInt32Array,
+};
+use arrow::datatypes::{
+    DataType, Date32Type, Decimal128Type, Field, Fields, Float16Type, Float32Type, Float64Type,
+    Int16Type, Int32Type, Int64Type, IntervalDayTimeType, IntervalUnit, IntervalYearMonthType,
+    Schema, TimeUnit, TimestampMillisecondType, TimestampNanosecondType,
+};
+use arrow::error::ArrowError;
 use arrow::record_batch::RecordBatch;
 use arrow::util::pretty;
-use datafusion_orc::arrow_reader::{ArrowReader, ArrowReaderBuilder};
+use bytes::Bytes;
+use datafusion_orc::arrow_reader::decoder::visitor::{PrimitiveVisitorDecoder, RowVisitor};
+use datafusion_orc::arrow_reader::decoder::DATE_TIMEZONE_METADATA_KEY;
+use datafusion_orc::arrow_reader::decoder::{
+    array_decoder_factory, ArrayBatchDecoder, NaiveStripeDecoder,
+};
+use datafusion_orc::arrow_reader::{
+    ArrowReader, ArrowReaderBuilder, ColumnReader, DecoderFactory, RowGroupFilter, StringValidation,
+};
 use datafusion_orc::async_arrow_reader::ArrowStreamReader;
+use datafusion_orc::error::OrcError;
+use datafusion_orc::multi_file_reader::MultiFileReader;
+use datafusion_orc::multi_file_reader::MultiFileReaderBuilder;
 use datafusion_orc::projection::ProjectionMask;
-use futures_util::TryStreamExt;
+use datafusion_orc::proto::r#type::Kind as TypeKind;
+use datafusion_orc::proto::stream::Kind;
+use datafusion_orc::proto::{StripeFooter, Type};
+use datafusion_orc::reader::metadata::read_metadata;
+use datafusion_orc::reader::metadata::FileMetadata;
+use datafusion_orc::reader::{AsyncChunkReader, ChunkReader, ObservedReader, ReadObserver};
+use datafusion_orc::schema::{RootDataType, Warning, WarningKind, INTERVAL_UNIT_ATTRIBUTE};
+use datafusion_orc::statistics::{matching_row_ranges, ColumnStatistics, TypeStatistics};
+use datafusion_orc::stripe::Stripe;
+use futures::future::BoxFuture;
+use futures::FutureExt;
+use futures_util::{StreamExt, TryStreamExt};
+use half::f16;
+use num::BigInt;
+use prost::Message;
 
 use crate::misc::{LONG_BOOL_EXPECTED, LONG_STRING_DICT_EXPECTED, LONG_STRING_EXPECTED};
 
+#[cfg(feature = "http")]
+mod http;
 mod misc;
+mod synthetic;
 
 fn new_arrow_reader(path: &str, fields: &[&str]) -> ArrowReader<File> {
     let f = File::open(path).expect("no file found");
@@ -335,6 +376,84 @@ pub async fn v0_file_test_async() {
     assert_eq!(1_920_800, total_rows);
 }
 
+/// demo-11-zlib.orc only has int and string columns
+#[test]
+pub fn v0_all_types_test() {
+    let reader = ArrowReaderBuilder::try_new(synthetic::v0_file())
+        .unwrap()
+        .build();
+    let batches = reader.collect::<Result<Vec<_>, _>>().unwrap();
+    let expected = [
+        "+---------+---------+----------+-------+---------------+-------+--------+--------+------------+--------+-------------------------+------------+----------------+",
+        "| boolean | tinyint | smallint | int   | bigint        | float | double | string | dictionary | binary | timestamp               | date       | decimal        |",
+        "+---------+---------+----------+-------+---------------+-------+--------+--------+------------+--------+-------------------------+------------+----------------+",
+        "| true    | -1      | -2       | -3    | -4            | 1.5   | 1.5    | a      | x          | 00ff   | 2015-01-01T00:00:00     | 1970-01-01 | 123.4500000000 |",
+        "| false   | 0       | 0        | 0     | 0             | 0.0   | 0.0    |        | y          |        | 2015-01-01T00:00:01.500 | 2015-01-01 | -1.0000000000  |",
+        "| true    | 127     | 300      | 70000 | 1099511627776 | -2.25 | -2.25  | ccc    | x          | 01     | 2014-12-31T23:59:59     | 1969-12-31 | 0.0000000007   |",
+        "+---------+---------+----------+-------+---------------+-------+--------+--------+------------+--------+-------------------------+------------+----------------+",
+    ];
+    assert_batches_eq(&batches, &expected);
+}
+
+#[test]
+pub fn check_lossless_test() {
+    let builder = ArrowReaderBuilder::try_new(synthetic::v0_file()).unwrap();
+    let root_data_type = builder.file_metadata().root_data_type();
+    let projection = ProjectionMask::named_roots(root_data_type, &["int", "timestamp", "decimal"]);
+    let schema = Schema::new(vec![
+        Field::new("int", DataType::Int64, true),
+        // Matched by position, as when reading with this schema
+        Field::new("ts", DataType::Timestamp(TimeUnit::Millisecond, None), true),
+        // The decimal column has a scale of 10
+        Field::new("decimal", DataType::Decimal128(38, 4), true),
+    ]);
+    let warnings = root_data_type
+        .project(&projection)
+        .check_lossless(&schema, StringValidation::Strict);
+    assert_eq!(
+        vec![
+            Warning {
+                path: "timestamp".to_owned(),
+                kind: WarningKind::TimestampTruncated {
+                    unit: TimeUnit::Millisecond
+                },
+            },
+            Warning {
+                path: "decimal".to_owned(),
+                kind: WarningKind::DecimalScaleReduced {
+                    orc_scale: 10,
+                    arrow_scale: 4
+                },
+            },
+        ],
+        warnings
+    );
+
+    // The default schema is lossless
+    let schema = root_data_type.create_arrow_schema(&HashMap::new());
+    assert_eq!(
+        Vec::<Warning>::new(),
+        root_data_type.check_lossless(&schema, StringValidation::Strict)
+    );
+
+    // String columns decoded into strings lose invalid UTF-8 with lossy validation
+    let builder = ArrowReaderBuilder::try_new(synthetic::string_file(&[1], b"a")).unwrap();
+    let root_data_type = builder.file_metadata().root_data_type();
+    let schema = root_data_type.create_arrow_schema(&HashMap::new());
+    assert_eq!(
+        vec![Warning {
+            path: "a".to_owned(),
+            kind: WarningKind::LossyUtf8,
+        }],
+        root_data_type.check_lossless(&schema, StringValidation::Lossy)
+    );
+    let schema = Schema::new(vec![Field::new("a", DataType::Binary, true)]);
+    assert_eq!(
+        Vec::<Warning>::new(),
+        root_data_type.check_lossless(&schema, StringValidation::Lossy)
+    );
+}
+
 #[test]
 pub fn alltypes_test() {
     let compressions = ["none", "snappy", "zlib", "lzo", "zstd", "lz4"];
@@ -364,6 +483,3733 @@ pub fn alltypes_test() {
     }
 }
 
+/// Order dependent checksum of the visited values, and the number of nulls.
+#[derive(Debug, Default, PartialEq)]
+struct Checksum {
+    rows: u64,
+    sum: u64,
+    nulls: usize,
+}
+
+impl Checksum {
+    fn add(&mut self, bits: u64, is_null: bool) {
+        self.rows += 1;
+        if is_null {
+            self.nulls += 1;
+        } else {
+            self.sum = self.sum.wrapping_mul(31).wrapping_add(bits);
+        }
+    }
+}
+
+impl RowVisitor for Checksum {
+    fn visit_bool(&mut self, value: bool, is_null: bool) {
+        self.add(value as u64, is_null);
+    }
+
+    fn visit_i64(&mut self, value: i64, is_null: bool) {
+        self.add(value as u64, is_null);
+    }
+
+    fn visit_f32(&mut self, value: f32, is_null: bool) {
+        self.add(value.to_bits() as u64, is_null);
+    }
+
+    fn visit_f64(&mut self, value: f64, is_null: bool) {
+        self.add(value.to_bits(), is_null);
+    }
+}
+
+#[test]
+pub fn row_visitor_test() {
+    let mut file = File::open(basic_path("alltypes.none.orc")).unwrap();
+    let file_metadata = Arc::new(read_metadata(&mut file).unwrap());
+    let stripe = Stripe::new(
+        &mut file,
+        &file_metadata,
+        file_metadata.root_data_type(),
+        0,
+        &file_metadata.stripe_metadatas()[0],
+    )
+    .unwrap();
+    let batch = new_arrow_reader_root(&basic_path("alltypes.none.orc"))
+        .next()
+        .unwrap()
+        .unwrap();
+
+    for column in stripe.columns() {
+        let mut decoder = match PrimitiveVisitorDecoder::try_new(column, &stripe) {
+            Ok(decoder) => decoder,
+            Err(err) => {
+                assert!(
+                    matches!(column.name(), "binary" | "utf8" | "date32"),
+                    "{}: {err}",
+                    column.name()
+                );
+                continue;
+            }
+        };
+        let mut checksum = Checksum::default();
+        while decoder.visit_batch(4, &mut checksum).unwrap() > 0 {}
+
+        let array = batch.column_by_name(column.name()).unwrap();
+        let mut expected = Checksum::default();
+        for row in 0..array.len() {
+            let is_null = array.is_null(row);
+            match array.data_type() {
+                DataType::Boolean => expected.visit_bool(array.as_boolean().value(row), is_null),
+                DataType::Int16 => {
+                    expected.visit_i64(array.as_primitive::<Int16Type>().value(row).into(), is_null)
+                }
+                DataType::Int32 => {
+                    expected.visit_i64(array.as_primitive::<Int32Type>().value(row).into(), is_null)
+                }
+                DataType::Int64 => {
+                    expected.visit_i64(array.as_primitive::<Int64Type>().value(row), is_null)
+                }
+                DataType::Float32 => {
+                    expected.visit_f32(array.as_primitive::<Float32Type>().value(row), is_null)
+                }
+                DataType::Float64 => {
+                    expected.visit_f64(array.as_primitive::<Float64Type>().value(row), is_null)
+                }
+                data_type => panic!("unexpected type {data_type}"),
+            }
+        }
+        assert_eq!(expected, checksum, "{}", column.name());
+        assert_eq!(2, checksum.nulls);
+    }
+}
+
+/// Decodes binary columns with the default decoder, reversing the bytes of each value.
+struct ReversingDecoder {
+    inner: Box<dyn ArrayBatchDecoder>,
+}
+
+impl ArrayBatchDecoder for ReversingDecoder {
+    fn next_batch(
+        &mut self,
+        batch_size: usize,
+        parent_present: Option<&[bool]>,
+    ) -> datafusion_orc::error::Result<ArrayRef> {
+        let array = self.inner.next_batch(batch_size, parent_present)?;
+        let reversed = array
+            .as_binary::<i32>()
+            .iter()
+            .map(|value| value.map(|value| value.iter().rev().copied().collect::<Vec<_>>()))
+            .collect::<BinaryArray>();
+        Ok(Arc::new(reversed))
+    }
+}
+
+fn reversing_decoder_factory() -> DecoderFactory {
+    Arc::new(|column, field, stripe| {
+        if field.metadata().get("reverse").is_none() {
+            return Ok(None);
+        }
+        let field = Arc::new(field.as_ref().clone().with_metadata(HashMap::new()));
+        let inner = array_decoder_factory(column, field, stripe, StringValidation::Strict)?;
+        Ok(Some(Box::new(ReversingDecoder { inner })))
+    })
+}
+
+fn reversing_builder<R>(builder: ArrowReaderBuilder<R>) -> ArrowReaderBuilder<R> {
+    let projection = ProjectionMask::named_roots(
+        builder.file_metadata().root_data_type(),
+        &["int32", "binary"],
+    );
+    let metadata = HashMap::from([("reverse".to_owned(), String::new())]);
+    let schema = Schema::new(vec![
+        Field::new("int32", DataType::Int32, true),
+        Field::new("binary", DataType::Binary, true).with_metadata(metadata),
+    ]);
+    builder
+        .with_projection(projection)
+        .with_schema(Arc::new(schema))
+        .with_decoder_factory(reversing_decoder_factory())
+}
+
+#[test]
+pub fn decoder_factory_test() {
+    let path = basic_path("alltypes.snappy.orc");
+    let builder = ArrowReaderBuilder::try_new(File::open(&path).unwrap()).unwrap();
+    let batches = reversing_builder(builder).read_all().unwrap();
+    let expected = [
+        "+-------------+--------------------------+",
+        "| int32       | binary                   |",
+        "+-------------+--------------------------+",
+        "|             |                          |",
+        "| 0           |                          |",
+        "| 1           | 61                       |",
+        "| -1          | 20                       |",
+        "| 2147483647  | 65646f636e65             |",
+        "| -2147483648 | 65646f636564             |",
+        "| 50          | 8fa5e58c92e58a86e7a7a4e5 |",
+        "| 51          | 8fa4e5b19ce6a497e88996e6 |",
+        "| 52          | 9faee58cb8e59f8ee5b488e9 |",
+        "| 53          | 94a49ff0                 |",
+        "|             |                          |",
+        "+-------------+--------------------------+",
+    ];
+    assert_batches_eq(&batches, &expected);
+}
+
+#[tokio::test]
+pub async fn async_decoder_factory_test() {
+    let path = basic_path("alltypes.snappy.orc");
+    let builder = ArrowReaderBuilder::try_new(File::open(&path).unwrap()).unwrap();
+    let expected = reversing_builder(builder).read_all().unwrap();
+    let f = tokio::fs::File::open(&path).await.unwrap();
+    let builder = ArrowReaderBuilder::try_new_async(f).await.unwrap();
+    let reader = reversing_builder(builder).build_async();
+    let batches = reader.try_collect::<Vec<_>>().await.unwrap();
+    assert_eq!(expected, batches);
+}
+
+fn new_arrow_reader_with_schema(path: &str, field: &str, data_type: DataType) -> ArrowReader<File> {
+    let f = File::open(path).expect("no file found");
+    let builder = ArrowReaderBuilder::try_new(f).unwrap();
+    let projection =
+        ProjectionMask::named_roots(builder.file_metadata().root_data_type(), &[field]);
+    let schema = Arc::new(Schema::new(vec![Field::new(field, data_type, true)]));
+    builder
+        .with_projection(projection)
+        .with_schema(schema)
+        .build()
+}
+
+#[test]
+pub fn widen_integers_test() {
+    let test_path = basic_path("test.orc");
+    let alltypes_path = basic_path("alltypes.none.orc");
+    let cases = [
+        (&test_path, "tinyint_simple", DataType::Int16),
+        (&test_path, "tinyint_simple", DataType::Int32),
+        (&test_path, "tinyint_simple", DataType::Int64),
+        (&alltypes_path, "int16", DataType::Int32),
+        (&alltypes_path, "int16", DataType::Int64),
+        (&alltypes_path, "int32", DataType::Int64),
+    ];
+    for (path, column, data_type) in cases {
+        let expected = new_arrow_reader(path, &[column])
+            .collect::<Result<Vec<_>, _>>()
+            .unwrap();
+        let expected = arrow::compute::cast(expected[0].column(0), &data_type).unwrap();
+
+        let batches = new_arrow_reader_with_schema(path, column, data_type.clone())
+            .collect::<Result<Vec<_>, _>>()
+            .unwrap();
+        let actual = batches[0].column(0);
+        assert_eq!(actual.data_type(), &data_type);
+        assert_eq!(actual, &expected, "widening {column} to {data_type}");
+    }
+}
+
+#[test]
+pub fn run_end_encoded_test() {
+    let path = basic_path("test.orc");
+    // Nulls form runs of their own
+    let cases = [
+        ("int_short_repeated", 3),
+        ("bigint_other", 4),
+        ("int_delta", 5),
+        ("utf8_increase", 5),
+    ];
+    for (column, num_runs) in cases {
+        let expected = new_arrow_reader(&path, &[column])
+            .collect::<Result<Vec<_>, _>>()
+            .unwrap();
+        let expected = expected[0].column(0);
+
+        let data_type = DataType::RunEndEncoded(
+            Arc::new(Field::new("run_ends", DataType::Int32, false)),
+            Arc::new(Field::new("values", expected.data_type().clone(), true)),
+        );
+        let batches = new_arrow_reader_with_schema(&path, column, data_type.clone())
+            .collect::<Result<Vec<_>, _>>()
+            .unwrap();
+        let actual = batches[0].column(0);
+        assert_eq!(actual.data_type(), &data_type);
+        let runs = as_run_array::<Int32Type>(actual);
+        assert_eq!(expected.len(), runs.len());
+        assert_eq!(num_runs, runs.values().len(), "{column}");
+
+        // Expand the runs back into a dense array
+        let indices = (0..runs.len())
+            .map(|index| runs.get_physical_index(index) as u32)
+            .collect::<Vec<_>>();
+        let dense = arrow::compute::take(runs.values(), &UInt32Array::from(indices), None).unwrap();
+        assert_eq!(expected, &dense, "{column}");
+        // Consecutive runs have different values
+        let values = runs.values();
+        for index in 1..values.len() {
+            assert_ne!(
+                values.slice(index - 1, 1).to_data(),
+                values.slice(index, 1).to_data(),
+                "{column}"
+            );
+        }
+    }
+}
+
+#[test]
+pub fn narrow_integers_test() {
+    let path = basic_path("alltypes.none.orc");
+    let err = new_arrow_reader_with_schema(&path, "int64", DataType::Int32)
+        .collect::<Result<Vec<_>, _>>()
+        .unwrap_err();
+    assert!(err.to_string().contains("Mismatched schema"), "{err}");
+}
+
+#[test]
+pub fn override_types_test() {
+    let path = basic_path("test.orc");
+    let cases = [
+        ("str_direct", DataType::LargeUtf8),
+        ("utf8_increase", DataType::LargeUtf8),
+        (
+            "timestamp_simple",
+            DataType::Timestamp(TimeUnit::Microsecond, None),
+        ),
+        (
+            "timestamp_simple",
+            DataType::Timestamp(TimeUnit::Millisecond, None),
+        ),
+        (
+            "timestamp_simple",
+            DataType::Timestamp(TimeUnit::Second, None),
+        ),
+    ];
+    for (column, data_type) in cases {
+        let expected = new_arrow_reader(&path, &[column])
+            .collect::<Result<Vec<_>, _>>()
+            .unwrap();
+        let expected = arrow::compute::cast(expected[0].column(0), &data_type).unwrap();
+
+        let batches = new_arrow_reader_with_schema(&path, column, data_type.clone())
+            .collect::<Result<Vec<_>, _>>()
+            .unwrap();
+        let actual = batches[0].column(0);
+        assert_eq!(actual.data_type(), &data_type);
+        assert_eq!(actual, &expected, "reading {column} as {data_type}");
+    }
+
+    let batches = new_arrow_reader_with_schema(
+        &path,
+        "timestamp_simple",
+        DataType::Timestamp(TimeUnit::Millisecond, None),
+    )
+    .collect::<Result<Vec<_>, _>>()
+    .unwrap();
+    let expected = [
+        "+-------------------------+",
+        "| timestamp_simple        |",
+        "+-------------------------+",
+        "| 2023-04-01T20:15:30.002 |",
+        "| 2021-08-22T07:26:44.525 |",
+        "| 2023-01-01T00:00:00     |",
+        "| 2023-02-01T00:00:00     |",
+        "| 2023-03-01T00:00:00     |",
+        "+-------------------------+",
+    ];
+    assert_batches_eq(&batches, &expected);
+}
+
+fn read_synthetic_as(file: Bytes, data_type: DataType) -> Result<Vec<RecordBatch>, ArrowError> {
+    let schema = Arc::new(Schema::new(vec![Field::new("a", data_type, true)]));
+    ArrowReaderBuilder::try_new(file)
+        .unwrap()
+        .with_schema(schema)
+        .build()
+        .collect()
+}
+
+#[test]
+pub fn float_special_values_test() {
+    // Include a NaN with a payload and sign, which must not be canonicalized
+    let doubles = [
+        f64::NAN,
+        -f64::NAN,
+        f64::from_bits(0x7FF0_0000_0000_0001),
+        f64::INFINITY,
+        f64::NEG_INFINITY,
+        -0.0,
+        0.0,
+        f64::MIN_POSITIVE / 2.0,
+    ];
+    let data = doubles
+        .iter()
+        .flat_map(|value| value.to_le_bytes())
+        .collect();
+    let file = synthetic::float_file(TypeKind::Double, doubles.len() as u64, data);
+    let batches = read_synthetic_as(file, DataType::Float64).unwrap();
+    let actual = batches[0].column(0).as_primitive::<Float64Type>();
+    let expected = doubles
+        .iter()
+        .map(|value| value.to_bits())
+        .collect::<Vec<_>>();
+    let actual = actual
+        .values()
+        .iter()
+        .map(|value| value.to_bits())
+        .collect::<Vec<_>>();
+    assert_eq!(expected, actual);
+
+    let floats = [
+        f32::NAN,
+        -f32::NAN,
+        f32::from_bits(0x7F80_0001),
+        f32::INFINITY,
+        f32::NEG_INFINITY,
+        -0.0,
+        0.0,
+        f32::MIN_POSITIVE / 2.0,
+    ];
+    let data = floats
+        .iter()
+        .flat_map(|value| value.to_le_bytes())
+        .collect();
+    let file = synthetic::float_file(TypeKind::Float, floats.len() as u64, data);
+    let batches = read_synthetic_as(file, DataType::Float32).unwrap();
+    let actual = batches[0].column(0).as_primitive::<Float32Type>();
+    let expected = floats
+        .iter()
+        .map(|value| value.to_bits())
+        .collect::<Vec<_>>();
+    let actual = actual
+        .values()
+        .iter()
+        .map(|value| value.to_bits())
+        .collect::<Vec<_>>();
+    assert_eq!(expected, actual);
+}
+
+#[test]
+pub fn large_offsets_test() {
+    let file = synthetic::string_file(&[1, 2, 0], b"abc");
+    let batches = read_synthetic_as(file, DataType::LargeUtf8).unwrap();
+    let expected: ArrayRef = Arc::new(LargeStringArray::from(vec!["a", "bc", ""]));
+    assert_eq!(&expected, batches[0].column(0));
+
+    // Strings too long for 32-bit offsets in total, but not individually
+    let lengths = [1 << 30, 1 << 30, 1 << 30];
+    let file = synthetic::string_file(&lengths, b"abc");
+    let err = read_synthetic_as(file.clone(), DataType::Utf8)
+        .unwrap_err()
+        .to_string();
+    assert!(err.contains("exceed the 2GiB addressable"), "{err}");
+    // Large offsets get past the lengths, to find the data is missing
+    let err = read_synthetic_as(file, DataType::LargeUtf8)
+        .unwrap_err()
+        .to_string();
+    assert!(err.contains("Data stream of column a is shorter"), "{err}");
+}
+
+#[cfg(feature = "arrow2")]
+#[test]
+pub fn arrow2_corrupt_string_lengths_test() {
+    let read = |file| {
+        ArrowReaderBuilder::try_new(file)
+            .unwrap()
+            .build_arrow2()
+            .unwrap()
+            .collect::<Result<Vec<_>, _>>()
+            .unwrap_err()
+            .to_string()
+    };
+    // Lengths overflowing u64 when summed, and lengths too large for 32-bit offsets
+    for lengths in [&[u64::MAX, 1][..], &[1 << 30, 1 << 30, 1 << 30]] {
+        let err = read(synthetic::string_file(lengths, b"abc"));
+        assert!(
+            err.contains("Corrupt length stream of column a"),
+            "unexpected error: {err}"
+        );
+    }
+    // Lengths longer than the data
+    let err = read(synthetic::string_file(&[2, 4], b"abc"));
+    assert!(err.contains("Data stream of column a is shorter"), "{err}");
+}
+
+#[test]
+pub fn large_offset_fallback_test() {
+    let data_types = |file, large_offset_fallback| {
+        let reader = ArrowReaderBuilder::try_new(file)
+            .unwrap()
+            .with_large_offset_fallback(large_offset_fallback)
+            .build();
+        let schema = reader.schema();
+        let batches = reader.collect::<Result<Vec<_>, _>>().unwrap();
+        assert_eq!(2, batches[0].num_rows());
+        assert_eq!(schema, batches[0].schema());
+        let nested = |field: &Field| match field.data_type() {
+            DataType::Struct(fields) => fields[0].data_type().clone(),
+            data_type => panic!("not a struct: {data_type}"),
+        };
+        let fields = schema.fields();
+        [
+            fields[0].data_type().clone(),
+            fields[1].data_type().clone(),
+            nested(&fields[2]),
+        ]
+    };
+
+    // The values of a stripe don't fit 32-bit offsets, going by its statistics
+    let file = synthetic::strings_file_with_total_length(i32::MAX as i64 + 1);
+    assert_eq!(
+        [
+            DataType::LargeUtf8,
+            DataType::LargeBinary,
+            DataType::LargeUtf8
+        ],
+        data_types(file.clone(), true)
+    );
+    assert_eq!(
+        [DataType::Utf8, DataType::Binary, DataType::Utf8],
+        data_types(file, false)
+    );
+
+    let file = synthetic::strings_file_with_total_length(i32::MAX as i64);
+    assert_eq!(
+        [DataType::Utf8, DataType::Binary, DataType::Utf8],
+        data_types(file, true)
+    );
+}
+
+#[test]
+pub fn string_validation_test() {
+    // "ab", then "\xFFc" which isn't valid UTF-8, then "d"
+    let file = synthetic::string_file(&[2, 2, 1], b"ab\xFFcd");
+    let read = |validation, large| {
+        let mut builder = ArrowReaderBuilder::try_new(file.clone())
+            .unwrap()
+            .with_string_validation(validation)
+            .with_batch_size(1);
+        if large {
+            let schema = Schema::new(vec![Field::new("a", DataType::LargeUtf8, true)]);
+            builder = builder.with_schema(Arc::new(schema));
+        }
+        let reader = builder.build();
+        let schema = reader.schema();
+        let batches = reader.collect::<Result<Vec<_>, _>>()?;
+        Ok::<_, ArrowError>(arrow::compute::concat_batches(&schema, &batches).unwrap())
+    };
+
+    for large in [false, true] {
+        let err = read(StringValidation::Strict, large).unwrap_err();
+        assert!(
+            err.to_string()
+                .contains("Invalid UTF-8 in value 1 of the Data stream of column a"),
+            "{err}"
+        );
+    }
+
+    let batch = read(StringValidation::Lossy, false).unwrap();
+    let expected: ArrayRef = Arc::new(StringArray::from(vec!["ab", "\u{FFFD}c", "d"]));
+    assert_eq!(&expected, batch.column(0));
+    // The validation isn't recorded in the output schema
+    assert!(batch.schema().field(0).metadata().is_empty());
+    let batch = read(StringValidation::Lossy, true).unwrap();
+    let expected: ArrayRef = Arc::new(LargeStringArray::from(vec!["ab", "\u{FFFD}c", "d"]));
+    assert_eq!(&expected, batch.column(0));
+
+    let values: [&[u8]; 3] = [b"ab", b"\xFFc", b"d"];
+    let batch = read(StringValidation::AsBinary, false).unwrap();
+    let expected: ArrayRef = Arc::new(BinaryArray::from(values.to_vec()));
+    assert_eq!(&expected, batch.column(0));
+    let batch = read(StringValidation::AsBinary, true).unwrap();
+    let expected: ArrayRef = Arc::new(LargeBinaryArray::from(values.to_vec()));
+    assert_eq!(&expected, batch.column(0));
+}
+
+#[test]
+pub fn max_decompressed_size_test() {
+    let reader = ArrowReaderBuilder::try_new(File::open(basic_path("alltypes.zlib.orc")).unwrap())
+        .unwrap()
+        .with_max_decompressed_stream_size(16)
+        .build();
+    let err = reader.collect::<Result<Vec<_>, _>>().unwrap_err();
+    assert!(
+        err.to_string()
+            .contains("Decompressed stream exceeds the limit of 16 bytes"),
+        "unexpected error: {err}"
+    );
+
+    let reader = ArrowReaderBuilder::try_new(File::open(basic_path("alltypes.zlib.orc")).unwrap())
+        .unwrap()
+        .with_max_decompressed_stripe_size(256)
+        .build();
+    let err = reader.collect::<Result<Vec<_>, _>>().unwrap_err();
+    assert!(
+        err.to_string()
+            .contains("Decompressed stripe exceeds the limit of 256 bytes"),
+        "unexpected error: {err}"
+    );
+
+    // The defaults are generous enough for any regular file
+    let batches = ArrowReaderBuilder::try_new(File::open(basic_path("alltypes.zlib.orc")).unwrap())
+        .unwrap()
+        .read_all()
+        .unwrap();
+    assert_eq!(
+        11,
+        batches.iter().map(|batch| batch.num_rows()).sum::<usize>()
+    );
+}
+
+#[test]
+pub fn raw_stream_test() {
+    let mut file = File::open(basic_path("alltypes.zlib.orc")).unwrap();
+    let file_metadata = Arc::new(read_metadata(&mut file).unwrap());
+    let stripe_metadata = &file_metadata.stripe_metadatas()[0];
+    let stripe = Stripe::new(
+        &mut file,
+        &file_metadata,
+        file_metadata.root_data_type(),
+        0,
+        stripe_metadata,
+    )
+    .unwrap();
+
+    let mut offset = stripe_metadata.offset();
+    for stream in &stripe.footer().streams {
+        let column = stripe
+            .columns()
+            .iter()
+            .find(|column| column.column_id() == stream.column());
+        if let Some(column) = column {
+            let raw = stripe.raw_stream(column, stream.kind()).unwrap().unwrap();
+            assert_eq!(stream.length(), raw.len() as u64, "{stream:?}");
+            let expected = file.get_bytes(offset, stream.length()).unwrap();
+            assert_eq!(expected, raw);
+        }
+        offset += stream.length();
+    }
+
+    let column = &stripe.columns()[0];
+    assert!(stripe
+        .raw_stream(column, Kind::DictionaryData)
+        .unwrap()
+        .is_none());
+}
+
+#[test]
+pub fn stream_inventory_test() {
+    let path = basic_path("nested_array.orc");
+    let mut f = File::open(path).expect("no file found");
+    let file_metadata = Arc::new(read_metadata(&mut f).unwrap());
+    let stripe = Stripe::new(
+        &mut f,
+        &file_metadata,
+        file_metadata.root_data_type(),
+        0,
+        &file_metadata.stripe_metadatas()[0],
+    )
+    .unwrap();
+
+    let column = &stripe.columns()[0];
+    let streams = stripe.streams_for(column);
+    for (kind, length) in &streams {
+        println!("{kind:?}: {length} bytes");
+    }
+    let kinds = streams
+        .iter()
+        .map(|(kind, _)| *kind)
+        .filter(|kind| *kind != Kind::RowIndex)
+        .collect::<Vec<_>>();
+    // The list's own streams, followed by the child int column's streams
+    assert_eq!(
+        kinds,
+        vec![Kind::Present, Kind::Length, Kind::Present, Kind::Data]
+    );
+}
+
+#[test]
+pub fn null_in_non_nullable_field_test() {
+    let path = basic_path("alltypes.none.orc");
+    let f = File::open(path).expect("no file found");
+    let builder = ArrowReaderBuilder::try_new(f).unwrap();
+    let projection =
+        ProjectionMask::named_roots(builder.file_metadata().root_data_type(), &["int32"]);
+    let schema = Arc::new(Schema::new(vec![Field::new(
+        "int32",
+        DataType::Int32,
+        false,
+    )]));
+    let err = builder
+        .with_projection(projection)
+        .with_schema(schema)
+        .build()
+        .collect::<Result<Vec<_>, _>>()
+        .unwrap_err();
+    assert!(
+        err.to_string()
+            .contains("Column 'int32' contains nulls but its Arrow field is non-nullable"),
+        "{err}"
+    );
+}
+
+/// Read the integer columns of the first 15,000 rows (three stripes) of demo-11-zlib.orc
+fn demo_11_first_stripes() -> RecordBatch {
+    let path = basic_path("demo-11-zlib.orc");
+    let reader = new_arrow_reader(&path, &["_col0", "_col4"]);
+    let schema = reader.schema();
+    let batches = reader.take(3).collect::<Result<Vec<_>, _>>().unwrap();
+    arrow::compute::concat_batches(&schema, &batches).unwrap()
+}
+
+#[test]
+pub fn offset_limit_test() {
+    let expected = demo_11_first_stripes();
+
+    // Offset and limit both straddle the 5,000 row stripe boundaries
+    for (offset, limit) in [(4_990, 20), (7_000, 4_000), (0, 5_001), (10_000, 1)] {
+        let path = basic_path("demo-11-zlib.orc");
+        let f = File::open(path).expect("no file found");
+        let builder = ArrowReaderBuilder::try_new(f).unwrap();
+        let projection = ProjectionMask::named_roots(
+            builder.file_metadata().root_data_type(),
+            &["_col0", "_col4"],
+        );
+        let reader = builder
+            .with_projection(projection)
+            .with_offset(offset)
+            .with_limit(limit)
+            .build();
+        let schema = reader.schema();
+        let batches = reader.collect::<Result<Vec<_>, _>>().unwrap();
+        let actual = arrow::compute::concat_batches(&schema, &batches).unwrap();
+        assert_eq!(
+            expected.slice(offset, limit),
+            actual,
+            "offset {offset} limit {limit}"
+        );
+    }
+}
+
+#[tokio::test]
+pub async fn offset_limit_test_async() {
+    let expected = demo_11_first_stripes();
+
+    let path = basic_path("demo-11-zlib.orc");
+    let f = tokio::fs::File::open(path).await.unwrap();
+    let builder = ArrowReaderBuilder::try_new_async(f).await.unwrap();
+    let projection = ProjectionMask::named_roots(
+        builder.file_metadata().root_data_type(),
+        &["_col0", "_col4"],
+    );
+    let reader = builder
+        .with_projection(projection)
+        .with_offset(7_000)
+        .with_limit(4_000)
+        .build_async();
+    let schema = reader.schema();
+    let batches = reader.try_collect::<Vec<_>>().await.unwrap();
+    let actual = arrow::compute::concat_batches(&schema, &batches).unwrap();
+    assert_eq!(expected.slice(7_000, 4_000), actual);
+}
+
+#[test]
+pub fn offset_past_end_test() {
+    let path = basic_path("alltypes.none.orc");
+    let f = File::open(path).expect("no file found");
+    let reader = ArrowReaderBuilder::try_new(f)
+        .unwrap()
+        .with_offset(100)
+        .build();
+    let batches = reader.collect::<Result<Vec<_>, _>>().unwrap();
+    assert!(batches.is_empty());
+}
+
+#[test]
+pub fn float16_test() {
+    let path = basic_path("alltypes.none.orc");
+    let expected = new_arrow_reader(&path, &["float32"])
+        .collect::<Result<Vec<_>, _>>()
+        .unwrap();
+    let expected = expected[0]
+        .column(0)
+        .as_primitive::<Float32Type>()
+        .iter()
+        .map(|value| value.map(f16::from_f32))
+        .collect::<Float16Array>();
+
+    let batches = new_arrow_reader_with_schema(&path, "float32", DataType::Float16)
+        .collect::<Result<Vec<_>, _>>()
+        .unwrap();
+    let actual = batches[0].column(0).as_primitive::<Float16Type>();
+    assert_eq!(actual, &expected);
+    // Values not representable in half precision are rounded to the nearest
+    assert_eq!(actual.value(6).to_f32(), 3.140625);
+    assert_eq!(actual.value(8).to_f32(), 1.0996094);
+}
+
+#[test]
+pub fn row_group_aligned_batches_test() {
+    let path = basic_path("demo-12-zlib.orc");
+    let f = File::open(path).expect("no file found");
+    let builder = ArrowReaderBuilder::try_new(f).unwrap();
+    let row_index_stride = builder.file_metadata().row_index_stride();
+    assert_eq!(10_000, row_index_stride);
+    let projection =
+        ProjectionMask::named_roots(builder.file_metadata().root_data_type(), &["_col0"]);
+    let reader = builder
+        .with_projection(projection)
+        .with_batch_size(1024)
+        .with_row_group_aligned_batches(true)
+        .build();
+    let batch_sizes = reader
+        .map(|batch| batch.unwrap().num_rows())
+        .collect::<Vec<_>>();
+
+    // Single stripe of 1,920,800 rows
+    let (last, rest) = batch_sizes.split_last().unwrap();
+    assert_eq!(192, rest.len());
+    assert!(rest.iter().all(|&size| size == row_index_stride));
+    assert_eq!(800, *last);
+}
+
+#[test]
+pub fn non_default_row_index_stride_test() {
+    let file = synthetic::int_file(12_000, 5_000);
+    let builder = ArrowReaderBuilder::try_new(file).unwrap();
+    assert_eq!(5_000, builder.file_metadata().row_index_stride());
+    let batches = builder
+        .with_batch_size(1024)
+        .with_row_group_aligned_batches(true)
+        .build()
+        .collect::<Result<Vec<_>, _>>()
+        .unwrap();
+
+    let groups = batches
+        .iter()
+        .map(|batch| {
+            let values = batch.column(0).as_primitive::<Int32Type>();
+            (values.value(0), values.len())
+        })
+        .collect::<Vec<_>>();
+    assert_eq!(vec![(0, 5_000), (5_000, 5_000), (10_000, 2_000)], groups);
+}
+
+#[test]
+pub fn incremental_stream_reads_test() {
+    // Byte RLE runs of 130 bytes of 0xff, marking all 4M rows present
+    let num_rows = 4_000_000;
+    let mut present = [127, 0xff].repeat(num_rows / 8 / 130);
+    present.extend([(num_rows / 8 % 130 - 3) as u8, 0xff]);
+    let file = synthetic::int_file_with_present(num_rows as u64, 0, Some(&present));
+
+    let builder = ArrowReaderBuilder::try_new(file).unwrap();
+    let stripe = builder.file_metadata().stripe_metadatas()[0].clone();
+    let data_range = stripe.offset()..stripe.offset() + stripe.data_length();
+    let observer = Arc::new(RecordingObserver::default());
+    let mut reader = builder
+        .with_observer(observer.clone())
+        .with_incremental_stream_reads()
+        .build();
+    let stream_bytes_read = || {
+        observer
+            .ranges
+            .lock()
+            .unwrap()
+            .iter()
+            .filter(|(start, _)| data_range.contains(start))
+            .map(|&(_, length)| length)
+            .collect::<Vec<_>>()
+    };
+
+    let batch = reader.next().unwrap().unwrap();
+    assert_eq!(0, batch.column(0).as_primitive::<Int32Type>().value(0));
+    // Only the start of the present and data streams was read
+    let read = stream_bytes_read();
+    assert_eq!(2, read.len());
+    assert!(read.iter().sum::<u64>() < stripe.data_length() / 2);
+
+    let mut num_values = batch.num_rows();
+    let mut last = 0;
+    for batch in reader {
+        let batch = batch.unwrap();
+        num_values += batch.num_rows();
+        last = batch
+            .column(0)
+            .as_primitive::<Int32Type>()
+            .value(batch.num_rows() - 1);
+    }
+    assert_eq!(num_rows, num_values);
+    assert_eq!(num_rows as i32 - 1, last);
+    let read = stream_bytes_read();
+    assert!(read.len() > 2);
+    assert!(read.iter().all(|&length| length <= 64 * 1024));
+    assert_eq!(stripe.data_length(), read.iter().sum::<u64>());
+}
+
+#[test]
+pub fn corrupt_list_lengths_test() {
+    // Lengths overflowing u64 when summed, and a length too large for Arrow offsets
+    for lengths in [&[u64::MAX, 1][..], &[1 << 40]] {
+        let file = synthetic::list_file(lengths.len() as u64, lengths);
+        let err = ArrowReaderBuilder::try_new(file)
+            .unwrap()
+            .build()
+            .collect::<Result<Vec<_>, _>>()
+            .unwrap_err()
+            .to_string();
+        assert!(
+            err.contains("Corrupt length stream of column a"),
+            "unexpected error: {err}"
+        );
+    }
+}
+
+#[test]
+pub fn binary_bloom_filter_test() {
+    let uuids: [&[u8]; 3] = [&[0x01; 16], &[0x02; 16], b"\x7f\x00\xff content hash"];
+    let file = synthetic::binary_file_with_bloom_filter(&uuids);
+    let file_metadata = Arc::new(read_metadata(&mut file.clone()).unwrap());
+    let stripe = Stripe::new(
+        &mut file.clone(),
+        &file_metadata,
+        file_metadata.root_data_type(),
+        0,
+        &file_metadata.stripe_metadatas()[0],
+    )
+    .unwrap();
+    let column = &stripe.columns()[0];
+
+    for uuid in uuids {
+        assert!(column.stripe_may_contain(&stripe, uuid).unwrap());
+    }
+    assert!(!column.stripe_may_contain(&stripe, &[0x03; 16]).unwrap());
+    assert!(!column.stripe_may_contain(&stripe, b"").unwrap());
+}
+
+#[test]
+pub fn empty_bloom_filter_test() {
+    let uuids: [&[u8]; 2] = [&[0x01; 16], &[0x02; 16]];
+    // The first row group has an empty filter, so may contain anything
+    let file = synthetic::binary_file_with_bloom_filters(&uuids, 1);
+    let file_metadata = Arc::new(read_metadata(&mut file.clone()).unwrap());
+    let stripe = Stripe::new(
+        &mut file.clone(),
+        &file_metadata,
+        file_metadata.root_data_type(),
+        0,
+        &file_metadata.stripe_metadatas()[0],
+    )
+    .unwrap();
+    let column = &stripe.columns()[0];
+
+    let filters = column.stripe_bloom_filters(&stripe).unwrap().unwrap();
+    assert_eq!(2, filters.len());
+    assert!(filters[0].is_none());
+    assert!(!filters[1].as_ref().unwrap().test_bytes(&[0x03; 16]));
+    assert!(column.stripe_may_contain(&stripe, &[0x03; 16]).unwrap());
+}
+
+#[test]
+pub fn binary_min_max_test() {
+    let file = File::open(basic_path("alltypes.snappy.orc")).unwrap();
+    let builder = ArrowReaderBuilder::try_new(file).unwrap();
+    let file_metadata = builder.file_metadata();
+    let statistics = |name: &str| {
+        let column_id = file_metadata.root_data_type().column_id_for_path(name);
+        &file_metadata.column_file_statistics()[column_id.unwrap() as usize]
+    };
+
+    let utf8 = statistics("utf8");
+    assert_eq!(Some(("", "🤔")), utf8.string_min_max());
+    assert_eq!(
+        Some(("".as_bytes(), "🤔".as_bytes())),
+        utf8.binary_min_max()
+    );
+    // Only the total length of binary values is recorded
+    let binary = statistics("binary");
+    assert!(matches!(
+        binary.type_statistics(),
+        Some(TypeStatistics::Binary { .. })
+    ));
+    assert_eq!(None, binary.binary_min_max());
+    assert_eq!(None, statistics("int32").string_min_max());
+}
+
+#[test]
+pub fn row_group_statistics_test() {
+    let mut file = File::open(basic_path("demo-11-zlib.orc")).unwrap();
+    let file_metadata = Arc::new(read_metadata(&mut file).unwrap());
+    let stride = file_metadata.row_index_stride();
+    let stripe_metadata = &file_metadata.stripe_metadatas()[1];
+    let stripe = Stripe::new(
+        &mut file,
+        &file_metadata,
+        file_metadata.root_data_type(),
+        1,
+        stripe_metadata,
+    )
+    .unwrap();
+    // _col0 holds the row numbers, starting from 1
+    let column = &stripe.columns()[0];
+    let statistics = column
+        .stripe_row_group_statistics(&stripe)
+        .unwrap()
+        .unwrap();
+
+    let rows = stripe_metadata.number_of_rows();
+    let first_row = file_metadata.stripe_metadatas()[0].number_of_rows() + 1;
+    assert_eq!((rows as usize + stride - 1) / stride, statistics.len());
+    assert_eq!(
+        rows,
+        statistics.iter().map(|s| s.number_of_values()).sum::<u64>()
+    );
+    for (row_group, statistics) in statistics.iter().enumerate() {
+        let min = first_row as i64 + (row_group * stride) as i64;
+        match statistics.type_statistics() {
+            Some(TypeStatistics::Integer { min: actual, .. }) => assert_eq!(min, *actual),
+            other => panic!("unexpected statistics {other:?}"),
+        }
+    }
+}
+
+#[test]
+pub fn matching_row_ranges_test() {
+    let file = synthetic::int_file_with_row_index(500, 100);
+    let file_metadata = Arc::new(read_metadata(&mut file.clone()).unwrap());
+    let stripe_metadata = &file_metadata.stripe_metadatas()[0];
+    let stripe = Stripe::new(
+        &mut file.clone(),
+        &file_metadata,
+        file_metadata.root_data_type(),
+        0,
+        stripe_metadata,
+    )
+    .unwrap();
+    let statistics = stripe.columns()[0]
+        .stripe_row_group_statistics(&stripe)
+        .unwrap()
+        .unwrap();
+    assert_eq!(5, statistics.len());
+    let ranges = |predicate: &dyn Fn(i64, i64) -> bool| {
+        matching_row_ranges(
+            &statistics,
+            file_metadata.row_index_stride(),
+            stripe_metadata.number_of_rows() as usize,
+            |stats| match stats.type_statistics() {
+                Some(&TypeStatistics::Integer { min, max, .. }) => predicate(min, max),
+                _ => true,
+            },
+        )
+    };
+
+    // a = 150 OR a = 350 matches the 2nd and 4th row groups
+    let contains = |value| move |min, max| min <= value && value <= max;
+    assert_eq!(
+        vec![100..200, 300..400],
+        ranges(&|min, max| contains(150)(min, max) || contains(350)(min, max))
+    );
+    // Adjacent row groups are merged
+    assert_eq!(vec![100..500], ranges(&|_, max| max >= 150));
+    assert_eq!(Vec::<Range<usize>>::new(), ranges(&|min, _| min >= 500));
+    // Without a row index, the stripe is a single row group
+    assert_eq!(vec![0..500], matching_row_ranges(&[], 0, 500, |_| false));
+}
+
+/// Row group filter keeping the row groups whose statistics of integer column `a`
+/// (column 1) may contain one of `values`.
+fn int_row_group_filter(values: Vec<i64>) -> RowGroupFilter {
+    Arc::new(
+        move |statistics: &[ColumnStatistics], _, _| match statistics[1].type_statistics() {
+            Some(&TypeStatistics::Integer { min, max, .. }) => {
+                values.iter().any(|value| (min..=max).contains(value))
+            }
+            _ => true,
+        },
+    )
+}
+
+fn int_column_values(batches: &[RecordBatch]) -> Vec<i32> {
+    batches
+        .iter()
+        .flat_map(|batch| {
+            batch
+                .column(0)
+                .as_primitive::<Int32Type>()
+                .values()
+                .to_vec()
+        })
+        .collect()
+}
+
+#[test]
+pub fn row_group_filter_test() {
+    // Row groups of 15_030 rows and runs of 100 values, so runs cross row groups
+    let file = synthetic::int_file_with_row_positions(1_000_000, 15_030);
+    let read = |values: Vec<i64>, offset: usize, limit: Option<usize>| {
+        let builder = ArrowReaderBuilder::try_new(file.clone())
+            .unwrap()
+            .with_batch_size(4096)
+            .with_row_group_filter(int_row_group_filter(values))
+            .with_offset(offset);
+        let builder = match limit {
+            Some(limit) => builder.with_limit(limit),
+            None => builder,
+        };
+        int_column_values(&builder.read_all().unwrap())
+    };
+
+    let group = |index: i32| index * 15_030..((index + 1) * 15_030).min(1_000_000);
+    assert_eq!(group(3).collect::<Vec<_>>(), read(vec![50_000], 0, None));
+    // The first and last row groups, and two adjacent ones in between
+    let expected = group(0)
+        .chain(group(10))
+        .chain(group(11))
+        .chain(group(66))
+        .collect::<Vec<_>>();
+    assert_eq!(expected, read(vec![5, 160_000, 170_000, 999_999], 0, None));
+    // The offset counts the rows of skipped row groups, the limit rows read
+    let expected = (50_000..60_120).chain(group(10)).collect::<Vec<_>>();
+    assert_eq!(expected, read(vec![50_000, 160_000], 50_000, None));
+    assert_eq!(
+        (50_000..50_010).collect::<Vec<_>>(),
+        read(vec![50_000, 160_000], 50_000, Some(10))
+    );
+    assert_eq!(Vec::<i32>::new(), read(vec![-1], 0, None));
+}
+
+/// In-memory file for the async reader, recording the byte ranges read.
+struct RecordingAsyncReader {
+    file: Bytes,
+    ranges: Arc<Mutex<Vec<Range<u64>>>>,
+}
+
+impl AsyncChunkReader for RecordingAsyncReader {
+    fn len(&mut self) -> BoxFuture<'_, std::io::Result<u64>> {
+        let len = self.file.len() as u64;
+        async move { Ok(len) }.boxed()
+    }
+
+    fn get_bytes(
+        &mut self,
+        offset_from_start: u64,
+        length: u64,
+    ) -> BoxFuture<'_, std::io::Result<Bytes>> {
+        let range = offset_from_start..offset_from_start + length;
+        self.ranges.lock().unwrap().push(range.clone());
+        let bytes = self.file.slice(range.start as usize..range.end as usize);
+        async move { Ok(bytes) }.boxed()
+    }
+}
+
+#[tokio::test]
+pub async fn async_row_group_filter_test() {
+    let file = synthetic::int_file_with_row_positions(1_000_000, 15_030);
+    let ranges = Arc::new(Mutex::new(vec![]));
+    let reader = RecordingAsyncReader {
+        file: file.clone(),
+        ranges: ranges.clone(),
+    };
+    let builder = ArrowReaderBuilder::try_new_async(reader).await.unwrap();
+    let file_metadata = builder.file_metadata().clone();
+    ranges.lock().unwrap().clear();
+    let batches = builder
+        .with_row_group_filter(int_row_group_filter(vec![50_000]))
+        .build_async()
+        .try_collect::<Vec<_>>()
+        .await
+        .unwrap();
+    assert_eq!(
+        (45_090..60_120).collect::<Vec<_>>(),
+        int_column_values(&batches)
+    );
+
+    let stripe_metadata = &file_metadata.stripe_metadatas()[0];
+    let footer = &file[stripe_metadata.footer_offset() as usize..]
+        [..stripe_metadata.footer_length() as usize];
+    let footer = StripeFooter::decode(footer).unwrap();
+    let [row_index, data] = &footer.streams[..] else {
+        panic!("unexpected streams {:?}", footer.streams);
+    };
+    let data_start = stripe_metadata.offset() + row_index.length();
+    let data_end = data_start + data.length();
+    let row_index = &file[stripe_metadata.offset() as usize..data_start as usize];
+    let row_index = datafusion_orc::proto::RowIndex::decode(row_index).unwrap();
+    // Offset of the run holding the first row of a row group in the data stream
+    let run_offset = |row_group: usize| data_start + row_index.entry[row_group].positions[0];
+    let ranges = ranges.lock().unwrap().clone();
+    let data_ranges = ranges
+        .iter()
+        .filter(|range| range.start < data_end && data_start < range.end)
+        .collect::<Vec<_>>();
+    let [data_range] = &data_ranges[..] else {
+        panic!("unexpected reads {ranges:?}");
+    };
+    assert_eq!(run_offset(3), data_range.start);
+    // The next row group starts in the middle of a run, read whole
+    assert!(data_range.end > run_offset(4), "{data_range:?}");
+    // Only the runs of the row group, and at most a run after it
+    assert!(
+        data_range.end - data_range.start < (data_end - data_start) / 5,
+        "read {data_range:?} of the data stream at {data_start}..{data_end}"
+    );
+}
+
+#[test]
+pub fn row_group_filter_compressed_test() {
+    // Compressed, with string and integer columns, in a stripe of 96 row groups
+    let path = basic_path("demo-12-zlib.orc");
+    let builder = ArrowReaderBuilder::try_new(File::open(&path).unwrap()).unwrap();
+    let stride = builder.file_metadata().row_index_stride();
+    assert_eq!(1, builder.file_metadata().stripe_metadatas().len());
+    let batches = builder.read_all().unwrap();
+    let full = arrow::compute::concat_batches(&batches[0].schema(), &batches).unwrap();
+
+    let selections: [fn(usize) -> bool; 2] = [
+        |row_group| row_group % 2 == 1,
+        |row_group| (40..43).contains(&row_group) || row_group == 95,
+    ];
+    for is_selected in selections {
+        let batches = ArrowReaderBuilder::try_new(File::open(&path).unwrap())
+            .unwrap()
+            .with_row_group_filter(Arc::new(move |_, _, row_group| is_selected(row_group)))
+            .read_all()
+            .unwrap();
+        let actual = arrow::compute::concat_batches(&full.schema(), &batches).unwrap();
+        let batches = ArrowReaderBuilder::try_new(File::open(&path).unwrap())
+            .unwrap()
+            .with_incremental_stream_reads()
+            .with_row_group_filter(Arc::new(move |_, _, row_group| is_selected(row_group)))
+            .read_all()
+            .unwrap();
+        let incremental = arrow::compute::concat_batches(&full.schema(), &batches).unwrap();
+        assert_eq!(actual, incremental);
+
+        let keep = (0..full.num_rows())
+            .map(|row| is_selected(row / stride))
+            .collect::<Vec<_>>();
+        let keep = arrow::array::BooleanArray::from(keep);
+        let expected = arrow::compute::filter_record_batch(&full, &keep).unwrap();
+        assert!(expected.num_rows() > 0);
+        assert_eq!(expected, actual);
+    }
+}
+
+#[test]
+pub fn stripe_has_null_test() {
+    // Byte RLE run of 10 bytes of 0xff, so only 80 values for 1000 rows
+    let present = [7, 0xff];
+    for has_null in [None, Some(true), Some(false)] {
+        let file = synthetic::int_file_with_has_null(1_000, &present, has_null);
+        let file_metadata = Arc::new(read_metadata(&mut file.clone()).unwrap());
+        let stripe = Stripe::new(
+            &mut file.clone(),
+            &file_metadata,
+            file_metadata.root_data_type(),
+            0,
+            &file_metadata.stripe_metadatas()[0],
+        )
+        .unwrap();
+        assert_eq!(has_null, stripe.columns()[0].stripe_has_null(&stripe));
+
+        let result = ArrowReaderBuilder::try_new(file)
+            .unwrap()
+            .build()
+            .collect::<Result<Vec<_>, _>>();
+        if has_null == Some(false) {
+            // The present stream isn't read at all
+            let batches = result.unwrap();
+            assert_eq!(1_000, batches[0].num_rows());
+            assert!(batches[0].column(0).nulls().is_none());
+        } else {
+            let err = result.unwrap_err().to_string();
+            assert!(err.contains("Present stream of column a"), "{err}");
+        }
+    }
+}
+
+#[test]
+pub fn truncated_length_stream_test() {
+    // Lengths of 2 lists (with no elements) for 3 rows
+    let file = synthetic::list_file(3, &[0, 0]);
+    let err = ArrowReaderBuilder::try_new(file)
+        .unwrap()
+        .build()
+        .collect::<Result<Vec<_>, _>>()
+        .unwrap_err()
+        .to_string();
+    assert!(
+        err.contains("Length stream of column a is shorter than expected"),
+        "unexpected error: {err}"
+    );
+}
+
+#[test]
+pub fn dictionary_columns_option_test() {
+    let path = basic_path("test.orc");
+    let builder = || {
+        let builder = ArrowReaderBuilder::try_new(File::open(&path).unwrap()).unwrap();
+        let projection = ProjectionMask::named_roots(
+            builder.file_metadata().root_data_type(),
+            &["str_direct", "utf8_increase"],
+        );
+        builder.with_projection(projection)
+    };
+    let expected = builder().read_all().unwrap();
+    let reader = builder().with_dictionary_columns(&["str_direct"]).build();
+    let dictionary_type =
+        DataType::Dictionary(Box::new(DataType::UInt64), Box::new(DataType::Utf8));
+    assert_eq!(&dictionary_type, reader.schema().field(0).data_type());
+    assert_eq!(&DataType::Utf8, reader.schema().field(1).data_type());
+
+    let batches = reader.collect::<Result<Vec<_>, _>>().unwrap();
+    let str_direct = batches[0].column(0);
+    assert_eq!(&dictionary_type, str_direct.data_type());
+    let str_direct = arrow::compute::cast(str_direct, &DataType::Utf8).unwrap();
+    assert_eq!(expected[0].column(0), &str_direct);
+    assert_eq!(expected[0].column(1), batches[0].column(1));
+
+    // A supplied schema takes the ORC names, matching its fields by position
+    let schema = Arc::new(Schema::new(vec![
+        Field::new("a", DataType::Utf8, true),
+        Field::new("b", DataType::Utf8, true),
+    ]));
+    let reader = builder()
+        .with_schema(schema)
+        .with_dictionary_columns(&["str_direct"])
+        .build();
+    assert_eq!("a", reader.schema().field(0).name());
+    assert_eq!(&dictionary_type, reader.schema().field(0).data_type());
+    assert_eq!(&DataType::Utf8, reader.schema().field(1).data_type());
+    let batches = reader.collect::<Result<Vec<_>, _>>().unwrap();
+    let str_direct = arrow::compute::cast(batches[0].column(0), &DataType::Utf8).unwrap();
+    assert_eq!(expected[0].column(0), &str_direct);
+}
+
+#[tokio::test]
+pub async fn async_dictionary_columns_option_test() {
+    let f = tokio::fs::File::open(basic_path("test.orc")).await.unwrap();
+    let builder = ArrowReaderBuilder::try_new_async(f).await.unwrap();
+    let projection = ProjectionMask::named_roots(
+        builder.file_metadata().root_data_type(),
+        &["str_direct", "utf8_increase"],
+    );
+    let schema = Arc::new(Schema::new(vec![
+        Field::new("a", DataType::Utf8, true),
+        Field::new("b", DataType::Utf8, true),
+    ]));
+    let reader = builder
+        .with_projection(projection)
+        .with_schema(schema)
+        .with_dictionary_columns(&["str_direct"])
+        .build_async();
+    let dictionary_type =
+        DataType::Dictionary(Box::new(DataType::UInt64), Box::new(DataType::Utf8));
+    let batches = reader.try_collect::<Vec<_>>().await.unwrap();
+    assert_eq!(&dictionary_type, batches[0].column(0).data_type());
+    assert_eq!(&DataType::Utf8, batches[0].column(1).data_type());
+}
+
+#[test]
+pub fn column_rename_test() {
+    let path = basic_path("alltypes.snappy.orc");
+    let expected = ArrowReaderBuilder::try_new(File::open(&path).unwrap())
+        .unwrap()
+        .read_all()
+        .unwrap();
+    let renames = HashMap::from([
+        ("int32".to_owned(), "id".to_owned()),
+        ("utf8".to_owned(), "name".to_owned()),
+    ]);
+    let reader = ArrowReaderBuilder::try_new(File::open(&path).unwrap())
+        .unwrap()
+        .with_column_rename(renames)
+        .build();
+    let names = reader
+        .schema()
+        .fields()
+        .iter()
+        .map(|field| field.name().to_owned())
+        .collect::<Vec<_>>();
+    assert_eq!(
+        vec!["boolean", "int16", "id", "int64", "float32", "float64", "binary", "name", "date32"],
+        names
+    );
+
+    let batches = reader.collect::<Result<Vec<_>, _>>().unwrap();
+    let batch_names = batches[0]
+        .schema()
+        .fields()
+        .iter()
+        .map(|field| field.name().to_owned())
+        .collect::<Vec<_>>();
+    assert_eq!(names, batch_names);
+    for (expected, actual) in expected[0].columns().iter().zip(batches[0].columns()) {
+        assert_eq!(expected, actual);
+    }
+
+    // A supplied schema is renamed by the ORC names, matching its fields by position
+    let fields = expected[0]
+        .schema()
+        .fields()
+        .iter()
+        .enumerate()
+        .map(|(index, field)| field.as_ref().clone().with_name(format!("c{index}")))
+        .collect::<Vec<_>>();
+    let reader = ArrowReaderBuilder::try_new(File::open(&path).unwrap())
+        .unwrap()
+        .with_schema(Arc::new(Schema::new(fields)))
+        .with_column_rename(HashMap::from([("int32".to_owned(), "id".to_owned())]))
+        .build();
+    assert_eq!("c1", reader.schema().field(1).name());
+    assert_eq!("id", reader.schema().field(2).name());
+    let batches = reader.collect::<Result<Vec<_>, _>>().unwrap();
+    assert_eq!("id", batches[0].schema().field(2).name());
+    assert_eq!(expected[0].column(2), batches[0].column(2));
+}
+
+#[test]
+pub fn force_nullable_test() {
+    // Non-nullable fields, though the columns hold nulls
+    let entries = Field::new(
+        "entries",
+        DataType::Struct(Fields::from(vec![
+            Field::new("key", DataType::Utf8, false),
+            Field::new("value", DataType::Int32, false),
+        ])),
+        false,
+    );
+    let schema = Arc::new(Schema::new(vec![Field::new(
+        "map",
+        DataType::Map(Arc::new(entries), false),
+        false,
+    )]));
+    let builder = || {
+        let f = File::open(basic_path("nested_map.orc")).unwrap();
+        ArrowReaderBuilder::try_new(f)
+            .unwrap()
+            .with_schema(schema.clone())
+    };
+    assert!(builder().read_all().is_err());
+
+    let reader = builder().with_force_nullable(true).build();
+    let map = reader.schema().field(0).clone();
+    assert!(map.is_nullable());
+    let DataType::Map(entries, _) = map.data_type() else {
+        panic!("unexpected type: {map:?}")
+    };
+    // Arrow requires map entries and keys to be non-nullable
+    assert!(!entries.is_nullable());
+    let DataType::Struct(children) = entries.data_type() else {
+        panic!("unexpected type: {entries:?}")
+    };
+    assert!(!children[0].is_nullable());
+    assert!(children[1].is_nullable());
+    let batches = reader.collect::<Result<Vec<_>, _>>().unwrap();
+    assert_eq!(1, batches[0].column(0).null_count());
+
+    // Inferred fields are nullable already, and still decoded without nulls
+    let f = File::open(basic_path("long_bool_gzip.orc")).unwrap();
+    let reader = ArrowReaderBuilder::try_new(f)
+        .unwrap()
+        .with_force_nullable(true)
+        .build();
+    assert!(reader
+        .schema()
+        .fields()
+        .iter()
+        .all(|field| field.is_nullable()));
+    let batches = reader.collect::<Result<Vec<_>, _>>().unwrap();
+    assert_eq!(0, batches[0].column(0).null_count());
+}
+
+#[test]
+pub fn seek_to_row_test() {
+    let path = basic_path("demo-12-zlib.orc");
+    let reader = || new_arrow_reader(&path, &["_col0", "_col5"]);
+    let batches = reader().take(30).collect::<Result<Vec<_>, _>>().unwrap();
+    let full_scan = arrow::compute::concat_batches(&batches[0].schema(), &batches).unwrap();
+
+    let mut reader = reader();
+    reader.seek_to_row(25000);
+    let batch = reader.next().unwrap().unwrap();
+    assert_eq!(full_scan.slice(25000, batch.num_rows()), batch);
+
+    // Seeking backwards
+    reader.seek_to_row(10);
+    let batch = reader.next().unwrap().unwrap();
+    assert_eq!(full_scan.slice(10, batch.num_rows()), batch);
+
+    reader.seek_to_row(reader.total_row_count() as usize);
+    assert!(reader.next().is_none());
+}
+
+/// Reads the file whole and in batches of each size up to its number of rows,
+/// checking they all decode to the same arrays.
+fn read_nested_lists(file: Bytes, num_rows: usize) -> RecordBatch {
+    let read = |batch_size: usize| {
+        let batches = ArrowReaderBuilder::try_new(file.clone())
+            .unwrap()
+            .with_batch_size(batch_size)
+            .read_all()
+            .unwrap();
+        arrow::compute::concat_batches(&batches[0].schema(), &batches).unwrap()
+    };
+    let batch = read(1024);
+    for batch_size in 1..num_rows {
+        assert_eq!(batch, read(batch_size), "batch size {batch_size}");
+    }
+    batch
+}
+
+#[test]
+pub fn nested_list_depth_2_test() {
+    // [[1, 2], [3]], null, [], [null, [4]], [[]], [[-5, 6, 7]]
+    let levels: [(&[u8], &[u64]); 2] = [
+        (&[0xFF, 0b1011_1100], &[2, 0, 2, 1, 1]),
+        (&[0xFF, 0b1101_1100], &[2, 1, 1, 0, 3]),
+    ];
+    let file = synthetic::nested_list_file(6, &levels, &[1, 2, 3, 4, -5, 6, 7]);
+    let batch = read_nested_lists(file, 6);
+    let expected = [
+        "+---------------+",
+        "| a             |",
+        "+---------------+",
+        "| [[1, 2], [3]] |",
+        "|               |",
+        "| []            |",
+        "| [, [4]]       |",
+        "| [[]]          |",
+        "| [[-5, 6, 7]]  |",
+        "+---------------+",
+    ];
+    assert_batches_eq(std::slice::from_ref(&batch), &expected);
+
+    let outer = batch.column(0).as_list::<i32>();
+    assert_eq!(&[0, 2, 2, 2, 4, 5, 6], outer.value_offsets());
+    assert!(outer.is_null(1) && outer.is_valid(2));
+    let inner = outer.values().as_list::<i32>();
+    assert_eq!(&[0, 2, 3, 3, 4, 4, 7], inner.value_offsets());
+    assert!(inner.is_null(2) && inner.is_valid(4));
+    let values = inner.values().as_primitive::<Int32Type>();
+    assert_eq!(&[1, 2, 3, 4, -5, 6, 7], values.values().as_ref());
+}
+
+#[test]
+pub fn nested_list_depth_3_test() {
+    // [[[1], [2, 3]], null], [], null, [[[]], [[4], null, [5]]], [[null]]
+    let levels: [(&[u8], &[u64]); 3] = [
+        (&[0xFF, 0b1101_1000], &[2, 0, 2, 1]),
+        (&[0xFF, 0b1011_1000], &[2, 1, 3, 1]),
+        (&[0xFF, 0b1111_0100], &[1, 2, 0, 1, 1]),
+    ];
+    let file = synthetic::nested_list_file(5, &levels, &[1, 2, 3, 4, 5]);
+    let batch = read_nested_lists(file, 5);
+    let expected = [
+        "+----------------------+",
+        "| a                    |",
+        "+----------------------+",
+        "| [[[1], [2, 3]], ]    |",
+        "| []                   |",
+        "|                      |",
+        "| [[[]], [[4], , [5]]] |",
+        "| [[]]                 |",
+        "+----------------------+",
+    ];
+    assert_batches_eq(std::slice::from_ref(&batch), &expected);
+
+    let outer = batch.column(0).as_list::<i32>();
+    assert_eq!(&[0, 2, 2, 2, 4, 5], outer.value_offsets());
+    assert!(outer.is_null(2) && outer.is_valid(1));
+    let middle = outer.values().as_list::<i32>();
+    assert_eq!(&[0, 2, 2, 3, 6, 7], middle.value_offsets());
+    assert!(middle.is_null(1));
+    let inner = middle.values().as_list::<i32>();
+    assert_eq!(&[0, 1, 3, 3, 4, 4, 5, 5], inner.value_offsets());
+    assert!(inner.is_null(4) && inner.is_null(6) && inner.is_valid(2));
+    let values = inner.values().as_primitive::<Int32Type>();
+    assert_eq!(&[1, 2, 3, 4, 5], values.values().as_ref());
+}
+
+#[test]
+pub fn fixed_size_list_test() {
+    // Rows [1, 2, 3], null, [4, 5, 6] and [7, 8, 9], as bits 1011 of present
+    let levels: [(&[u8], &[u64]); 1] = [(&[0xFF, 0b1011_0000], &[3, 3, 3])];
+    let file = synthetic::nested_list_file(4, &levels, &[1, 2, 3, 4, 5, 6, 7, 8, 9]);
+    let item = Arc::new(Field::new("item", DataType::Int32, true));
+    let data_type = DataType::FixedSizeList(item, 3);
+    for batch_size in 1..=4 {
+        let schema = Arc::new(Schema::new(vec![Field::new("a", data_type.clone(), true)]));
+        let batches = ArrowReaderBuilder::try_new(file.clone())
+            .unwrap()
+            .with_schema(schema)
+            .with_batch_size(batch_size)
+            .read_all()
+            .unwrap();
+        let batch = arrow::compute::concat_batches(&batches[0].schema(), &batches).unwrap();
+        assert_eq!(&data_type, batch.column(0).data_type());
+        let expected = [
+            "+-----------+",
+            "| a         |",
+            "+-----------+",
+            "| [1, 2, 3] |",
+            "|           |",
+            "| [4, 5, 6] |",
+            "| [7, 8, 9] |",
+            "+-----------+",
+        ];
+        assert_batches_eq(&[batch], &expected);
+    }
+
+    // The third list has 2 values
+    let levels: [(&[u8], &[u64]); 1] = [(&[0xFF, 0b1011_0000], &[3, 2, 3])];
+    let file = synthetic::nested_list_file(4, &levels, &[1, 2, 3, 4, 5, 7, 8, 9]);
+    let err = read_synthetic_as(file, data_type).unwrap_err().to_string();
+    assert!(
+        err.contains(
+            "List of length 2 in column a doesn't fit its Arrow fixed size list of size 3"
+        ),
+        "{err}"
+    );
+}
+
+#[test]
+pub fn list_of_structs_test() {
+    let file = synthetic::list_of_structs_file();
+    let batches = ArrowReaderBuilder::try_new(file)
+        .unwrap()
+        .read_all()
+        .unwrap();
+    let expected = [
+        "+-----------------------------+",
+        "| a                           |",
+        "+-----------------------------+",
+        "| [{a: 1, b: x}, ]            |",
+        "|                             |",
+        "| []                          |",
+        "| [{a: 2, b: yz}]             |",
+        "|                             |",
+        "| [{a: 3, b: }, {a: 4, b: w}] |",
+        "+-----------------------------+",
+    ];
+    assert_batches_eq(&batches, &expected);
+
+    let lists = batches[0].column(0).as_list::<i32>();
+    // Null lists are distinct from empty ones, and neither consume struct values
+    assert_eq!(&[0, 2, 2, 2, 3, 3, 5], lists.value_offsets());
+    assert!(lists.is_null(1) && lists.is_null(4) && lists.is_valid(2));
+    let structs = lists.values().as_struct();
+    assert_eq!(5, structs.len());
+    assert_eq!(1, structs.null_count());
+    assert!(structs.is_null(1));
+}
+
+#[test]
+pub fn truncated_list_child_test() {
+    // Lengths adding up to 3 list elements, but no elements
+    let file = synthetic::list_file(2, &[1, 2]);
+    let err = ArrowReaderBuilder::try_new(file)
+        .unwrap()
+        .build()
+        .collect::<Result<Vec<_>, _>>()
+        .unwrap_err()
+        .to_string();
+    assert!(
+        err.contains("Children of column a have 0 values where 3 were expected"),
+        "unexpected error: {err}"
+    );
+
+    // Elements 1, null and 2, but a single value
+    let file = synthetic::list_file_with_elements(2, &[1, 2], &[0xFF, 0b1010_0000], &[2]);
+    let err = ArrowReaderBuilder::try_new(file)
+        .unwrap()
+        .build()
+        .collect::<Result<Vec<_>, _>>()
+        .unwrap_err()
+        .to_string();
+    assert!(
+        err.contains("Children of column a have 2 values where 3 were expected"),
+        "unexpected error: {err}"
+    );
+}
+
+#[test]
+pub fn timestamp_to_date_test() {
+    // 2015-01-01T00:00:00, 23:59:59 the same day, the next day, and the second
+    // before 2015-01-01
+    let seconds = [0, 86_399, 86_400, -1];
+    // Days since the Unix epoch
+    let base = 16_436;
+    let file = synthetic::timestamp_file(TypeKind::Timestamp, &seconds);
+
+    let read_dates = |metadata: HashMap<String, String>| {
+        let field = Field::new("a", DataType::Date32, true).with_metadata(metadata);
+        let batches = ArrowReaderBuilder::try_new(file.clone())
+            .unwrap()
+            .with_schema(Arc::new(Schema::new(vec![field])))
+            .build()
+            .collect::<Result<Vec<_>, _>>()?;
+        Ok::<_, ArrowError>(
+            batches[0]
+                .column(0)
+                .as_primitive::<Date32Type>()
+                .values()
+                .to_vec(),
+        )
+    };
+    let utc = read_dates(HashMap::new()).unwrap();
+    assert_eq!(vec![base, base, base + 1, base - 1], utc);
+
+    let metadata = |timezone: &str| {
+        HashMap::from([(DATE_TIMEZONE_METADATA_KEY.to_owned(), timezone.to_owned())])
+    };
+    let east = read_dates(metadata("+02:00")).unwrap();
+    assert_eq!(vec![base, base + 1, base + 1, base], east);
+    let west = read_dates(metadata("-02:00")).unwrap();
+    assert_eq!(vec![base - 1, base, base, base - 1], west);
+
+    let err = read_dates(metadata("nowhere")).unwrap_err();
+    assert!(err.to_string().contains("Invalid timezone"), "{err}");
+}
+
+#[test]
+pub fn timestamp_timezone_test() {
+    // 2015-01-01T00:00:00 and an hour later
+    let seconds = [0, 3600];
+    let expected = [1_420_070_400_000_000_000, 1_420_074_000_000_000_000];
+
+    let file = synthetic::timestamp_file(TypeKind::Timestamp, &seconds);
+    let reader = ArrowReaderBuilder::try_new(file.clone()).unwrap().build();
+    let data_type = DataType::Timestamp(TimeUnit::Nanosecond, None);
+    assert_eq!(&data_type, reader.schema().field(0).data_type());
+    let batches = reader.collect::<Result<Vec<_>, _>>().unwrap();
+    let array = batches[0]
+        .column(0)
+        .as_primitive::<TimestampNanosecondType>();
+    assert_eq!(&data_type, array.data_type());
+    assert_eq!(expected, array.values().as_ref());
+
+    // Timestamps without time zone can't be read as instants
+    let schema = Arc::new(Schema::new(vec![Field::new(
+        "a",
+        DataType::Timestamp(TimeUnit::Nanosecond, Some("UTC".into())),
+        true,
+    )]));
+    let err = ArrowReaderBuilder::try_new(file)
+        .unwrap()
+        .with_schema(schema)
+        .build()
+        .collect::<Result<Vec<_>, _>>()
+        .unwrap_err();
+    assert!(err.to_string().contains("Mismatched schema"), "{err}");
+
+    let file = synthetic::timestamp_file(TypeKind::TimestampInstant, &seconds);
+    let reader = ArrowReaderBuilder::try_new(file.clone()).unwrap().build();
+    let data_type = DataType::Timestamp(TimeUnit::Nanosecond, Some("UTC".into()));
+    assert_eq!(&data_type, reader.schema().field(0).data_type());
+    let batches = reader.collect::<Result<Vec<_>, _>>().unwrap();
+    let array = batches[0]
+        .column(0)
+        .as_primitive::<TimestampNanosecondType>();
+    assert_eq!(&data_type, array.data_type());
+    assert_eq!(expected, array.values().as_ref());
+
+    // Other time zones and units only change how the same instants are represented
+    let data_type = DataType::Timestamp(TimeUnit::Millisecond, Some("+02:00".into()));
+    let schema = Arc::new(Schema::new(vec![Field::new("a", data_type.clone(), true)]));
+    let batches = ArrowReaderBuilder::try_new(file.clone())
+        .unwrap()
+        .with_schema(schema)
+        .build()
+        .collect::<Result<Vec<_>, _>>()
+        .unwrap();
+    let array = batches[0]
+        .column(0)
+        .as_primitive::<TimestampMillisecondType>();
+    assert_eq!(&data_type, array.data_type());
+    assert_eq!(
+        [1_420_070_400_000, 1_420_074_000_000],
+        array.values().as_ref()
+    );
+
+    // Instants can't be read as timestamps without time zone
+    let schema = Arc::new(Schema::new(vec![Field::new(
+        "a",
+        DataType::Timestamp(TimeUnit::Nanosecond, None),
+        true,
+    )]));
+    let err = ArrowReaderBuilder::try_new(file)
+        .unwrap()
+        .with_schema(schema)
+        .build()
+        .collect::<Result<Vec<_>, _>>()
+        .unwrap_err();
+    assert!(err.to_string().contains("Mismatched schema"), "{err}");
+}
+
+#[test]
+pub fn type_attributes_test() {
+    let attributes = [("ARROW:extension:name", "example.id"), ("origin", "test")];
+    let file = synthetic::attributed_file(100, &attributes);
+    let expected = attributes
+        .iter()
+        .map(|&(key, value)| (key.to_owned(), value.to_owned()))
+        .collect::<HashMap<_, _>>();
+
+    let reader = ArrowReaderBuilder::try_new(file).unwrap().build();
+    let schema = reader.schema();
+    assert_eq!(&expected, schema.field(0).metadata());
+    let DataType::Struct(fields) = schema.field(1).data_type() else {
+        panic!("unexpected type {}", schema.field(1).data_type());
+    };
+    assert!(schema.field(1).metadata().is_empty());
+    assert_eq!(&expected, fields[0].metadata());
+
+    let batches = reader.collect::<Result<Vec<_>, _>>().unwrap();
+    assert_eq!(schema, batches[0].schema());
+    assert_eq!(100, batches[0].num_rows());
+}
+
+#[test]
+pub fn interval_attribute_test() {
+    let file = synthetic::attributed_file(100, &[(INTERVAL_UNIT_ATTRIBUTE, "YEAR_MONTH")]);
+    let batches = ArrowReaderBuilder::try_new(file)
+        .unwrap()
+        .read_all()
+        .unwrap();
+    let months = batches[0].column(0).as_primitive::<IntervalYearMonthType>();
+    assert_eq!((0..100).collect::<Vec<_>>(), months.values().to_vec());
+    let c = batches[0].column(1).as_struct().column(0);
+    assert_eq!(&DataType::Interval(IntervalUnit::YearMonth), c.data_type());
+
+    let file = synthetic::attributed_file(100, &[(INTERVAL_UNIT_ATTRIBUTE, "DAY_TIME")]);
+    let batches = ArrowReaderBuilder::try_new(file.clone())
+        .unwrap()
+        .read_all()
+        .unwrap();
+    let intervals = batches[0].column(0).as_primitive::<IntervalDayTimeType>();
+    let expected = (0..100)
+        .map(|millis| IntervalDayTimeType::make_value(0, millis))
+        .collect::<Vec<_>>();
+    assert_eq!(expected, intervals.values().to_vec());
+}
+
+#[test]
+pub fn day_time_interval_test() {
+    let path = basic_path("alltypes.snappy.orc");
+    let read = |field: &str, data_type: DataType| {
+        new_arrow_reader_with_schema(&path, field, data_type).collect::<Result<Vec<_>, _>>()
+    };
+    let millis = read("int32", DataType::Int32).unwrap();
+    let millis = millis[0].column(0).as_primitive::<Int32Type>();
+    let batches = read("int32", DataType::Interval(IntervalUnit::DayTime)).unwrap();
+    let intervals = batches[0].column(0).as_primitive::<IntervalDayTimeType>();
+    assert_eq!(millis.nulls(), intervals.nulls());
+    for (millis, interval) in millis.values().iter().zip(intervals.values()) {
+        let millis = *millis as i64;
+        let (days, remainder) = IntervalDayTimeType::to_parts(*interval);
+        assert!((0..86_400_000).contains(&remainder));
+        assert_eq!(millis, days as i64 * 86_400_000 + remainder as i64);
+    }
+
+    // The days of the extreme int64 values don't fit
+    let err = read("int64", DataType::Interval(IntervalUnit::DayTime)).unwrap_err();
+    assert!(err.to_string().contains("has too many days"), "{err}");
+}
+
+#[test]
+pub fn skip_unsupported_columns_test() {
+    // Unions can't be read
+    let file = synthetic::int_and_union_struct_file(200);
+    let err = ArrowReaderBuilder::try_new(file.clone())
+        .unwrap()
+        .build()
+        .collect::<Result<Vec<_>, _>>()
+        .unwrap_err();
+    assert!(err.to_string().contains("Mismatched schema"), "{err}");
+
+    let reader = ArrowReaderBuilder::try_new(file)
+        .unwrap()
+        .with_skip_unsupported_columns(true)
+        .build();
+    let schema = reader.schema();
+    let batches = reader.collect::<Result<Vec<_>, _>>().unwrap();
+    let batch = arrow::compute::concat_batches(&schema, &batches).unwrap();
+    assert_eq!(200, batch.num_rows());
+    assert_eq!(
+        (0..200).collect::<Vec<_>>(),
+        batch
+            .column(0)
+            .as_primitive::<Int32Type>()
+            .values()
+            .to_vec()
+    );
+    let b = batch.column(1);
+    assert_eq!(schema.field(1).data_type(), b.data_type());
+    assert_eq!(200, b.null_count());
+}
+
+#[test]
+pub fn decode_error_context_test() {
+    // Lengths adding up to 6 bytes, but only 4 bytes of data
+    let file = synthetic::string_file(&[3, 3], b"abcd");
+    let err = ArrowReaderBuilder::try_new(file)
+        .unwrap()
+        .build()
+        .collect::<Result<Vec<_>, _>>()
+        .unwrap_err()
+        .to_string();
+    assert!(
+        err.contains(
+            "failed decoding column `a` (id 1) in stripe 0: \
+             Data stream of column a is shorter than expected"
+        ),
+        "unexpected error: {err}"
+    );
+}
+
+#[test]
+pub fn truncated_present_stream_test() {
+    // Byte RLE run of 10 bytes of 0xff, so only 80 values for 1000 rows
+    let file = synthetic::int_file_with_present(1_000, 0, Some(&[7, 0xff]));
+    let err = ArrowReaderBuilder::try_new(file)
+        .unwrap()
+        .build()
+        .collect::<Result<Vec<_>, _>>()
+        .unwrap_err()
+        .to_string();
+    assert!(
+        err.contains("Present stream of column a is shorter than expected"),
+        "{err}"
+    );
+
+    let file = synthetic::int_file_with_present(1_000, 0, Some(&[122, 0xff]));
+    let batches = ArrowReaderBuilder::try_new(file)
+        .unwrap()
+        .build()
+        .collect::<Result<Vec<_>, _>>()
+        .unwrap();
+    assert_eq!(1_000, batches[0].num_rows());
+}
+
+#[test]
+pub fn decimal_as_string_test() {
+    let large: BigInt = num::pow(BigInt::from(10), 40) + 1;
+    let values = [
+        Some((BigInt::from(12345), 2)),
+        None,
+        Some((BigInt::from(-5), 3)),
+        Some((BigInt::from(1500), 3)),
+        Some((BigInt::from(-7), 0)),
+        Some((large.clone(), 0)),
+        Some((-large, 20)),
+    ];
+    let file = synthetic::decimal_file(&values);
+    let schema = Arc::new(Schema::new(vec![Field::new("a", DataType::Utf8, true)]));
+    let batches = ArrowReaderBuilder::try_new(file)
+        .unwrap()
+        .with_schema(schema)
+        .build()
+        .collect::<Result<Vec<_>, _>>()
+        .unwrap();
+
+    let actual = batches[0].column(0).as_string::<i32>();
+    let actual = actual.iter().collect::<Vec<_>>();
+    let expected = vec![
+        Some("123.45"),
+        None,
+        Some("-0.005"),
+        Some("1.5"),
+        Some("-7"),
+        Some("10000000000000000000000000000000000000001"),
+        Some("-100000000000000000000.00000000000000000001"),
+    ];
+    assert_eq!(expected, actual);
+}
+
+#[test]
+pub fn decimal_as_decimal128_test() {
+    let values = [
+        Some((BigInt::from(12345), 2)),
+        None,
+        Some((BigInt::from(-5), 1)),
+        Some((BigInt::from(15), 0)),
+        Some((BigInt::from(-7), 2)),
+    ];
+    let file = synthetic::decimal_file(&values);
+    let schema = Arc::new(Schema::new(vec![Field::new(
+        "a",
+        DataType::Decimal128(38, 2),
+        true,
+    )]));
+    let batches = ArrowReaderBuilder::try_new(file)
+        .unwrap()
+        .with_schema(schema)
+        .build()
+        .collect::<Result<Vec<_>, _>>()
+        .unwrap();
+
+    let actual = batches[0].column(0).as_primitive::<Decimal128Type>();
+    assert_eq!(&DataType::Decimal128(38, 2), actual.data_type());
+    let actual = actual.iter().collect::<Vec<_>>();
+    assert_eq!(
+        vec![Some(12345), None, Some(-50), Some(1500), Some(-7)],
+        actual
+    );
+
+    // Digits beyond the scale of the type are not silently dropped
+    let file = synthetic::decimal_file(&[Some((BigInt::from(1), 3))]);
+    let schema = Arc::new(Schema::new(vec![Field::new(
+        "a",
+        DataType::Decimal128(38, 2),
+        true,
+    )]));
+    let err = ArrowReaderBuilder::try_new(file)
+        .unwrap()
+        .with_schema(schema)
+        .build()
+        .collect::<Result<Vec<_>, _>>()
+        .unwrap_err();
+    assert!(err.to_string().contains("Decimal128(38, 2)"), "{err}");
+}
+
+#[test]
+pub fn decimal_per_row_scale_test() {
+    // The same unscaled value with a different scale on each row, around nulls
+    // which have no scale
+    let values = [
+        Some((BigInt::from(5), 0)),
+        None,
+        Some((BigInt::from(5), 1)),
+        Some((BigInt::from(5), 2)),
+        None,
+        None,
+        Some((BigInt::from(-5), 1)),
+        Some((BigInt::from(0), 2)),
+        Some((BigInt::from(5), 0)),
+    ];
+    let file = synthetic::decimal_file(&values);
+    let expected = vec![
+        Some(500),
+        None,
+        Some(50),
+        Some(5),
+        None,
+        None,
+        Some(-50),
+        Some(0),
+        Some(500),
+    ];
+    // Scales stay aligned with their values across batches
+    for batch_size in 1..=values.len() {
+        let batches = ArrowReaderBuilder::try_new(file.clone())
+            .unwrap()
+            .with_batch_size(batch_size)
+            .read_all()
+            .unwrap();
+        let actual = batches
+            .iter()
+            .flat_map(|batch| {
+                let array = batch.column(0).as_primitive::<Decimal128Type>();
+                assert_eq!(&DataType::Decimal128(38, 2), array.data_type());
+                array.iter().collect::<Vec<_>>()
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(expected, actual, "batch size {batch_size}");
+    }
+}
+
+#[test]
+pub fn decimal_out_of_range_scale_test() {
+    for scale in [39, -39, i32::MIN, i32::MAX] {
+        let file = synthetic::decimal_file(&[Some((BigInt::from(5), scale))]);
+        // Both as Decimal128 and as strings
+        for data_type in [DataType::Decimal128(38, 2), DataType::Utf8] {
+            let schema = Arc::new(Schema::new(vec![Field::new("a", data_type, true)]));
+            let err = ArrowReaderBuilder::try_new(file.clone())
+                .unwrap()
+                .with_schema(schema)
+                .read_all()
+                .unwrap_err()
+                .to_string();
+            assert!(
+                err.contains(&format!("decimal scale {scale} is out of range")),
+                "unexpected error: {err}"
+            );
+        }
+    }
+}
+
+#[test]
+pub fn decimal_precision_test() {
+    // The ORC column is a decimal(38, 2)
+    let file = synthetic::decimal_file(&[Some((BigInt::from(12345), 2))]);
+    let schema = Arc::new(Schema::new(vec![Field::new(
+        "a",
+        DataType::Decimal128(10, 2),
+        true,
+    )]));
+    let err = ArrowReaderBuilder::try_new(file)
+        .unwrap()
+        .with_schema(schema)
+        .build()
+        .collect::<Result<Vec<_>, _>>()
+        .unwrap_err();
+    assert!(
+        err.to_string()
+            .contains("don't fit Arrow type Decimal128(10, 2)"),
+        "{err}"
+    );
+}
+
+#[test]
+pub fn decimal_truncated_stream_test() {
+    let values = [
+        Some((BigInt::from(12345), 2)),
+        None,
+        Some((BigInt::from(-5), 1)),
+        Some((BigInt::from(15), 0)),
+    ];
+    for truncated in [Kind::Data, Kind::Secondary] {
+        let file = synthetic::decimal_file_with_truncated_stream(&values, Some(truncated));
+        // Both the string and the Decimal128 decoders fail instead of returning
+        // fewer values
+        for data_type in [DataType::Utf8, DataType::Decimal128(38, 2)] {
+            let schema = Arc::new(Schema::new(vec![Field::new("a", data_type, true)]));
+            let err = ArrowReaderBuilder::try_new(file.clone())
+                .unwrap()
+                .with_schema(schema)
+                .build()
+                .collect::<Result<Vec<_>, _>>()
+                .unwrap_err()
+                .to_string();
+            let expected = format!("{truncated:?} stream of column a is shorter than expected");
+            assert!(err.contains(&expected), "{err}");
+        }
+    }
+}
+
+#[derive(Default)]
+struct RecordingObserver {
+    ranges: Mutex<Vec<(u64, u64)>>,
+}
+
+impl ReadObserver for RecordingObserver {
+    fn on_read(&self, start: u64, length: u64) {
+        self.ranges.lock().unwrap().push((start, length));
+    }
+}
+
+#[test]
+pub fn peek_test() {
+    let path = basic_path("f32_long_long_gzip.orc");
+    let builder = || ArrowReaderBuilder::try_new(File::open(&path).unwrap()).unwrap();
+    let read_bytes = |observer: &RecordingObserver| {
+        let ranges = observer.ranges.lock().unwrap();
+        ranges.iter().map(|(_, length)| length).sum::<u64>()
+    };
+
+    let observer = Arc::new(RecordingObserver::default());
+    let batch = builder().with_observer(observer.clone()).peek(5).unwrap();
+    let expected = builder().with_limit(5).read_all().unwrap();
+    assert_eq!(expected[0], batch);
+    let peek_bytes = read_bytes(&observer);
+
+    let observer = Arc::new(RecordingObserver::default());
+    builder()
+        .with_observer(observer.clone())
+        .read_all()
+        .unwrap();
+    let full_bytes = read_bytes(&observer);
+    assert!(
+        peek_bytes * 10 < full_bytes,
+        "peek read {peek_bytes} bytes, full scan {full_bytes} bytes"
+    );
+
+    let batch = builder().with_offset(1_000_000).peek(5).unwrap();
+    assert_eq!(0, batch.num_rows());
+    assert_eq!(1, batch.num_columns());
+}
+
+#[test]
+pub fn empty_projection_test() {
+    let path = basic_path("demo-11-zlib.orc");
+    let builder = ArrowReaderBuilder::try_new(File::open(&path).unwrap()).unwrap();
+    let projection = ProjectionMask::roots(builder.file_metadata().root_data_type(), []);
+    let observer = Arc::new(RecordingObserver::default());
+    let batches = builder
+        .with_projection(projection)
+        .with_observer(observer.clone())
+        .with_offset(10)
+        .read_all()
+        .unwrap();
+    assert!(batches.iter().all(|batch| batch.num_columns() == 0));
+    let rows = batches.iter().map(RecordBatch::num_rows).sum::<usize>();
+    assert_eq!(1_920_800 - 10, rows);
+    // Only stripe footers are read, the root struct having no streams
+    let stripes = observer.ranges.lock().unwrap().len();
+    assert_eq!(385, stripes);
+}
+
+#[test]
+pub fn row_count_test() {
+    for path in ["demo-11-zlib.orc", "alltypes.zlib.orc", "nested_map.orc"] {
+        let builder = ArrowReaderBuilder::try_new(File::open(basic_path(path)).unwrap()).unwrap();
+        let row_count = builder.row_count();
+        let batches = builder.read_all().unwrap();
+        let rows = batches.iter().map(RecordBatch::num_rows).sum::<usize>();
+        assert_eq!(rows as u64, row_count, "{path}");
+    }
+}
+
+#[tokio::test]
+pub async fn async_empty_projection_test() {
+    let f = tokio::fs::File::open(basic_path("demo-11-zlib.orc"))
+        .await
+        .unwrap();
+    let builder = ArrowReaderBuilder::try_new_async(f).await.unwrap();
+    let projection = ProjectionMask::roots(builder.file_metadata().root_data_type(), []);
+    let reader = builder.with_projection(projection).build_async();
+    let batches = reader.try_collect::<Vec<_>>().await.unwrap();
+    let rows = batches.iter().map(RecordBatch::num_rows).sum::<usize>();
+    assert_eq!(1_920_800, rows);
+}
+
+#[test]
+pub fn read_observer_test() {
+    let path = basic_path("test.orc");
+    let f = File::open(path).expect("no file found");
+    let builder = ArrowReaderBuilder::try_new(f).unwrap();
+    let file_metadata = Arc::new(builder.file_metadata().clone());
+    let root_data_type = file_metadata.root_data_type();
+    let projection = ProjectionMask::named_roots(root_data_type, &["int_delta"]);
+    let observer = Arc::new(RecordingObserver::default());
+    let reader = builder
+        .with_projection(projection.clone())
+        .with_observer(observer.clone())
+        .build();
+    reader.collect::<Result<Vec<_>, _>>().unwrap();
+    let mut actual = observer.ranges.lock().unwrap().clone();
+    actual.sort();
+
+    // Expect the stripe footer, the streams of the projected column and the
+    // present stream of the root struct
+    let stripe_metadata = &file_metadata.stripe_metadatas()[0];
+    let projected_data_type = root_data_type.project(&projection);
+    let stripe = Stripe::new(
+        &mut File::open(basic_path("test.orc")).unwrap(),
+        &file_metadata,
+        &projected_data_type,
+        0,
+        stripe_metadata,
+    )
+    .unwrap();
+    let column_id = projected_data_type.children()[0].data_type().column_index() as u32;
+    let mut expected = vec![(
+        stripe_metadata.footer_offset(),
+        stripe_metadata.footer_length(),
+    )];
+    let mut offset = stripe_metadata.offset();
+    for stream in &stripe.footer().streams {
+        if stream.column() == column_id || (stream.column() == 0 && stream.kind() == Kind::Present)
+        {
+            expected.push((offset, stream.length()));
+        }
+        offset += stream.length();
+    }
+    expected.sort();
+
+    assert_eq!(expected, actual);
+}
+
+#[test]
+pub fn schema_tree_test() {
+    let cases = [
+        (
+            "nested_struct.orc",
+            "0: struct
+  1: nest (struct<a:float,b:boolean>)
+    2: a (float) -> Float32
+    3: b (boolean) -> Boolean",
+        ),
+        (
+            "nested_map.orc",
+            "0: struct
+  1: map (map<string,int>)
+    2: key (string) -> Utf8
+    3: value (int) -> Int32",
+        ),
+    ];
+    for (path, expected) in cases {
+        let mut f = File::open(basic_path(path)).expect("no file found");
+        let metadata = read_metadata(&mut f).unwrap();
+        assert_eq!(expected, metadata.root_data_type().to_tree_string());
+    }
+}
+
+#[test]
+pub fn column_path_test() {
+    let cases = [
+        (
+            "nested_struct.orc",
+            vec![(1, "nest"), (2, "nest.a"), (3, "nest.b")],
+        ),
+        ("nested_map.orc", vec![(2, "map.key"), (3, "map.value")]),
+        ("nested_array.orc", vec![(1, "value"), (2, "value.item")]),
+    ];
+    for (path, columns) in cases {
+        let mut f = File::open(basic_path(path)).expect("no file found");
+        let metadata = read_metadata(&mut f).unwrap();
+        let root_data_type = metadata.root_data_type();
+        for (column_id, column_path) in columns {
+            assert_eq!(
+                Some(column_id),
+                root_data_type.column_id_for_path(column_path)
+            );
+            assert_eq!(
+                Some(column_path.to_owned()),
+                root_data_type.path_for_column_id(column_id)
+            );
+        }
+    }
+
+    let mut f = File::open(basic_path("nested_struct.orc")).expect("no file found");
+    let metadata = read_metadata(&mut f).unwrap();
+    let root_data_type = metadata.root_data_type();
+    assert_eq!(None, root_data_type.column_id_for_path("nest.c"));
+    assert_eq!(None, root_data_type.column_id_for_path("missing"));
+    assert_eq!(None, root_data_type.column_id_for_path(""));
+    assert_eq!(None, root_data_type.path_for_column_id(0));
+    assert_eq!(None, root_data_type.path_for_column_id(4));
+}
+
+#[test]
+pub fn field_metadata_test() {
+    let field_id = |id: &str| HashMap::from([("PARQUET:field_id".to_owned(), id.to_owned())]);
+    let children = vec![
+        Field::new("a", DataType::Float32, true).with_metadata(field_id("2")),
+        Field::new("b", DataType::Boolean, true).with_metadata(field_id("3")),
+    ];
+    let field =
+        Field::new("nest", DataType::Struct(children.into()), true).with_metadata(field_id("1"));
+    let schema = Arc::new(Schema::new_with_metadata(
+        vec![field],
+        HashMap::from([("key".to_owned(), "value".to_owned())]),
+    ));
+
+    let path = basic_path("nested_struct.orc");
+    let f = File::open(path).expect("no file found");
+    let builder = ArrowReaderBuilder::try_new(f).unwrap();
+    let projection =
+        ProjectionMask::named_roots(builder.file_metadata().root_data_type(), &["nest"]);
+    let batches = builder
+        .with_projection(projection)
+        .with_schema(schema.clone())
+        .build()
+        .collect::<Result<Vec<_>, _>>()
+        .unwrap();
+    assert_eq!(batches[0].schema(), schema);
+}
+
+#[test]
+pub fn verify_layout_test() {
+    let mut file = Bytes::from(std::fs::read(basic_path("alltypes.none.orc")).unwrap());
+    let metadata = read_metadata(&mut file).unwrap();
+    metadata.verify_layout(&mut file).unwrap();
+
+    // Uncompressed stripe footer, so grow a stream by a byte in place
+    let stripe = &metadata.stripe_metadatas()[0];
+    let footer_range =
+        stripe.footer_offset() as usize..(stripe.footer_offset() + stripe.footer_length()) as usize;
+    let mut footer = StripeFooter::decode(&file[footer_range.clone()]).unwrap();
+    let stream = footer
+        .streams
+        .iter_mut()
+        .find(|stream| stream.length() > 0 && stream.length() < 127)
+        .unwrap();
+    stream.length = Some(stream.length() + 1);
+    let footer = footer.encode_to_vec();
+    assert_eq!(footer_range.len(), footer.len());
+    let mut corrupted = file.to_vec();
+    corrupted[footer_range].copy_from_slice(&footer);
+    let mut corrupted = Bytes::from(corrupted);
+
+    let err = metadata.verify_layout(&mut corrupted).unwrap_err();
+    assert!(
+        err.to_string().starts_with("Invalid layout of stripe 0: "),
+        "{err}"
+    );
+}
+
+#[test]
+pub fn stripe_metadatas_test() {
+    let mut f = File::open(basic_path("demo-11-zlib.orc")).unwrap();
+    let file_length = f.metadata().unwrap().len();
+    let file_metadata = read_metadata(&mut f).unwrap();
+    let stripes = file_metadata.stripe_metadatas();
+
+    let rows = stripes
+        .iter()
+        .map(|stripe| stripe.number_of_rows())
+        .sum::<u64>();
+    assert_eq!(file_metadata.number_of_rows(), rows);
+
+    let first_rows = file_metadata.stripe_first_rows();
+    assert_eq!(stripes.len(), first_rows.len());
+    assert_eq!(0, first_rows[0]);
+    for (first_rows, stripe) in first_rows.windows(2).zip(stripes) {
+        assert_eq!(first_rows[0] + stripe.number_of_rows(), first_rows[1]);
+    }
+
+    // Stripes are laid out one after the other, after the 3 bytes of magic
+    assert_eq!(3, stripes[0].offset());
+    for pair in stripes.windows(2) {
+        assert_eq!(pair[0].offset() + pair[0].total_length(), pair[1].offset());
+    }
+    let total_length = file_metadata.stripes_total_length();
+    assert!(3 + total_length < file_length);
+
+    // Stripes can be filtered before deciding which to decode
+    let large = stripes
+        .iter()
+        .filter(|stripe| stripe.number_of_rows() > 5_000)
+        .count();
+    assert!(large < stripes.len());
+}
+
+#[test]
+pub fn column_stripe_stats_test() {
+    let mut f = File::open(basic_path("demo-11-zlib.orc")).unwrap();
+    let file_metadata = read_metadata(&mut f).unwrap();
+    // _col0 holds the row numbers, starting from 1
+    let stats = file_metadata.column_stripe_stats("_col0").unwrap();
+    assert_eq!(file_metadata.stripe_metadatas().len(), stats.len());
+    let first_rows = file_metadata.stripe_first_rows();
+    for ((stats, first_row), stripe) in stats
+        .iter()
+        .zip(first_rows)
+        .zip(file_metadata.stripe_metadatas())
+    {
+        match stats.type_statistics() {
+            Some(&TypeStatistics::Integer { min, max, .. }) => {
+                assert_eq!(first_row as i64 + 1, min);
+                assert_eq!((first_row + stripe.number_of_rows()) as i64, max);
+            }
+            other => panic!("unexpected statistics {other:?}"),
+        }
+    }
+
+    assert!(file_metadata.column_stripe_stats("missing").is_err());
+}
+
+#[test]
+pub fn estimated_decoded_bytes_test() {
+    for path in [
+        "demo-11-zlib.orc",
+        "string_long_long.orc",
+        "alltypes.none.orc",
+    ] {
+        let path = basic_path(path);
+        let mut f = File::open(&path).unwrap();
+        let file_metadata = Arc::new(read_metadata(&mut f).unwrap());
+        let info = &file_metadata.stripe_metadatas()[0];
+        let stripe = Stripe::new(
+            &mut f,
+            &file_metadata,
+            file_metadata.root_data_type(),
+            0,
+            info,
+        )
+        .unwrap();
+        let estimate = stripe.estimated_decoded_bytes() as f64;
+
+        // Decode the whole stripe in a single batch
+        let rows = info.number_of_rows() as usize;
+        let batch = ArrowReaderBuilder::try_new(File::open(&path).unwrap())
+            .unwrap()
+            .with_batch_size(rows)
+            .with_limit(rows)
+            .build()
+            .next()
+            .unwrap()
+            .unwrap();
+        let actual = batch
+            .columns()
+            .iter()
+            .map(|array| array.to_data().get_slice_memory_size().unwrap())
+            .sum::<usize>() as f64;
+        assert!(
+            actual / 2.0 <= estimate && estimate <= actual * 2.0,
+            "{path}: estimated {estimate} bytes, decoded {actual}"
+        );
+    }
+}
+
+#[test]
+pub fn dictionary_columns_test() {
+    let first_stripe = |path: &str| {
+        let mut file = File::open(basic_path(path)).unwrap();
+        let file_metadata = Arc::new(read_metadata(&mut file).unwrap());
+        Stripe::new(
+            &mut file,
+            &file_metadata,
+            file_metadata.root_data_type(),
+            0,
+            &file_metadata.stripe_metadatas()[0],
+        )
+        .unwrap()
+    };
+
+    let stripe = first_stripe("string_dict.orc");
+    let column = &stripe.columns()[0];
+    assert!(column.is_dictionary_encoded());
+    assert_eq!(vec![("dict".to_owned(), 2)], stripe.dictionary_columns());
+
+    let stripe = first_stripe("string_long.orc");
+    assert!(!stripe.columns()[0].is_dictionary_encoded());
+    assert!(stripe.dictionary_columns().is_empty());
+}
+
+#[test]
+pub fn column_compressed_size_test() {
+    for path in ["alltypes.zstd.orc", "nested_map.orc", "demo-11-zlib.orc"] {
+        let mut f = File::open(basic_path(path)).expect("no file found");
+        let file_metadata = Arc::new(read_metadata(&mut f).unwrap());
+        let root_data_type = file_metadata.root_data_type();
+
+        let mut stripes_size = 0;
+        for (index, stripe_metadata) in file_metadata.stripe_metadatas().iter().enumerate() {
+            let stripe = Stripe::new(
+                &mut f,
+                &file_metadata,
+                root_data_type,
+                index,
+                stripe_metadata,
+            )
+            .unwrap();
+            // Writers may also store a present stream for the root struct
+            let root_size: u64 = stripe
+                .footer()
+                .streams
+                .iter()
+                .filter(|stream| stream.column() == 0 && stream.kind() != Kind::RowIndex)
+                .map(|stream| stream.length())
+                .sum();
+            let stripe_size: u64 = stripe
+                .columns()
+                .iter()
+                .map(|column| stripe.column_compressed_size(column))
+                .sum();
+            assert_eq!(
+                stripe_metadata.data_length(),
+                root_size + stripe_size,
+                "{path}"
+            );
+            stripes_size += stripe_size;
+        }
+
+        let file_size = root_data_type
+            .children()
+            .iter()
+            .map(|col| {
+                file_metadata
+                    .column_compressed_size(&mut f, col.data_type())
+                    .unwrap()
+            })
+            .sum::<u64>();
+        assert_eq!(stripes_size, file_size, "{path}");
+    }
+}
+
+#[tokio::test]
+pub async fn async_matches_sync_test() {
+    for path in [
+        "alltypes.snappy.orc",
+        "nested_struct.orc",
+        "demo-11-zlib.orc",
+    ] {
+        let path = basic_path(path);
+        let expected = new_arrow_reader_root(&path)
+            .collect::<Result<Vec<_>, _>>()
+            .unwrap();
+        let actual = new_arrow_stream_reader_root(&path)
+            .await
+            .try_collect::<Vec<_>>()
+            .await
+            .unwrap();
+        assert_eq!(expected, actual, "{path}");
+    }
+}
+
+#[test]
+pub fn map_as_key_value_lists_test() {
+    let path = basic_path("nested_map.orc");
+    let expected = new_arrow_reader(&path, &["map"])
+        .collect::<Result<Vec<_>, _>>()
+        .unwrap();
+    let expected = expected[0].column(0).as_map();
+
+    let data_type = DataType::Struct(
+        vec![
+            Field::new_list("keys", Field::new("item", DataType::Utf8, false), true),
+            Field::new_list("values", Field::new("item", DataType::Int32, true), true),
+        ]
+        .into(),
+    );
+    let batches = new_arrow_reader_with_schema(&path, "map", data_type)
+        .collect::<Result<Vec<_>, _>>()
+        .unwrap();
+    let actual = batches[0].column(0).as_struct();
+    let keys = actual.column(0).as_list::<i32>();
+    let values = actual.column(1).as_list::<i32>();
+
+    assert_eq!(expected.len(), actual.len());
+    assert_eq!(expected.nulls(), actual.nulls());
+    for index in 0..expected.len() {
+        if expected.is_null(index) {
+            continue;
+        }
+        let entries = expected.value(index);
+        assert_eq!(entries.column(0), &keys.value(index));
+        assert_eq!(entries.column(1), &values.value(index));
+    }
+}
+
+#[test]
+pub fn map_values_first_test() {
+    let path = basic_path("nested_map.orc");
+    let expected = new_arrow_reader(&path, &["map"])
+        .collect::<Result<Vec<_>, _>>()
+        .unwrap();
+    let expected = expected[0].column(0).as_map();
+
+    let entries = Field::new(
+        "entries",
+        DataType::Struct(Fields::from(vec![
+            Field::new("value", DataType::Int32, true),
+            Field::new("key", DataType::Utf8, false),
+        ])),
+        false,
+    );
+    let data_type = DataType::Map(Arc::new(entries), false);
+    let batches = new_arrow_reader_with_schema(&path, "map", data_type)
+        .collect::<Result<Vec<_>, _>>()
+        .unwrap();
+    let actual = batches[0].column(0).as_map();
+    assert_eq!(expected.value_offsets(), actual.value_offsets());
+    assert_eq!(expected.nulls(), actual.nulls());
+    assert_eq!(
+        expected.keys(),
+        actual.entries().column_by_name("key").unwrap()
+    );
+    assert_eq!(
+        expected.values(),
+        actual.entries().column_by_name("value").unwrap()
+    );
+
+    // Same for key and value lists
+    let data_type = DataType::Struct(
+        vec![
+            Field::new_list("values", Field::new("item", DataType::Int32, true), true),
+            Field::new_list("keys", Field::new("item", DataType::Utf8, false), true),
+        ]
+        .into(),
+    );
+    let batches = new_arrow_reader_with_schema(&path, "map", data_type)
+        .collect::<Result<Vec<_>, _>>()
+        .unwrap();
+    let actual = batches[0].column(0).as_struct();
+    let keys = actual.column_by_name("keys").unwrap().as_list::<i32>();
+    let values = actual.column_by_name("values").unwrap().as_list::<i32>();
+    assert_eq!(expected.value_offsets(), keys.value_offsets());
+    assert_eq!(expected.keys(), keys.values());
+    assert_eq!(expected.values(), values.values());
+}
+
+#[test]
+pub fn sorted_map_test() {
+    let path = basic_path("nested_map.orc");
+    let reader = new_arrow_reader(&path, &["map"]);
+    let DataType::Map(entries, false) = reader.schema().field(0).data_type().clone() else {
+        panic!("expected unsorted map");
+    };
+    let expected = reader.collect::<Result<Vec<_>, _>>().unwrap();
+
+    let data_type = DataType::Map(entries, true);
+    let batches = new_arrow_reader_with_schema(&path, "map", data_type.clone())
+        .collect::<Result<Vec<_>, _>>()
+        .unwrap();
+    let actual = batches[0].column(0);
+    assert_eq!(&data_type, actual.data_type());
+    assert_eq!(
+        expected[0].column(0).as_map().entries(),
+        actual.as_map().entries()
+    );
+}
+
+#[test]
+pub fn raw_footer_test() {
+    let path = basic_path("demo-12-zlib.orc");
+    let mut f = File::open(path).expect("no file found");
+    let file_metadata = read_metadata(&mut f).unwrap();
+    let footer = file_metadata.raw_footer();
+    assert_eq!(Some(10_000), footer.row_index_stride);
+    assert_eq!(1_920_800, footer.number_of_rows());
+    let postscript = file_metadata.raw_postscript();
+    assert_eq!(Some("ORC"), postscript.magic.as_deref());
+    assert_eq!(
+        file_metadata.compression_block_size(),
+        postscript.compression_block_size
+    );
+}
+
+#[test]
+pub fn multi_file_reader_test() {
+    let paths = [
+        basic_path("alltypes.none.orc"),
+        basic_path("alltypes.zstd.orc"),
+    ];
+    let files = paths.iter().map(|path| File::open(path).unwrap());
+    let builder = MultiFileReaderBuilder::try_new(files).unwrap();
+    assert_eq!(22, builder.total_row_count());
+    let root_data_type = read_metadata(&mut File::open(&paths[0]).unwrap())
+        .unwrap()
+        .root_data_type()
+        .clone();
+    let projection = ProjectionMask::named_roots(&root_data_type, &["int32", "utf8"]);
+    let reader = builder
+        .with_projection(projection)
+        .with_batch_size(4)
+        .build();
+    let schema = reader.schema();
+    let actual = reader.collect::<Result<Vec<_>, _>>().unwrap();
+    assert!(actual.iter().all(|batch| batch.schema() == schema));
+    assert_eq!(22, actual.iter().map(RecordBatch::num_rows).sum::<usize>());
+
+    let expected = paths
+        .iter()
+        .flat_map(|path| new_arrow_reader(path, &["int32", "utf8"]))
+        .collect::<Result<Vec<_>, _>>()
+        .unwrap();
+    assert_eq!(
+        pretty::pretty_format_batches(&expected)
+            .unwrap()
+            .to_string(),
+        pretty::pretty_format_batches(&actual).unwrap().to_string()
+    );
+
+    let files = [basic_path("alltypes.none.orc"), basic_path("test.orc")]
+        .map(|path| File::open(path).unwrap());
+    let err = MultiFileReaderBuilder::try_new(files).err().unwrap();
+    assert!(
+        matches!(err, OrcError::IncompatibleSchema { file: 1, .. }),
+        "{err}"
+    );
+}
+
+#[test]
+pub fn projected_schema_test() {
+    let path = basic_path("demo-11-zlib.orc");
+    // Root columns are projected in file order; _col1 is dictionary encoded
+    let reader = new_arrow_reader(&path, &["_col4", "_col1"]);
+    let schema = reader.schema();
+    let expected = Schema::new(vec![
+        Field::new("_col1", DataType::Utf8, true),
+        Field::new("_col4", DataType::Int32, true),
+    ]);
+    assert_eq!(&expected, schema.as_ref());
+
+    let batch = reader.take(1).next().unwrap().unwrap();
+    assert_eq!(schema, batch.schema());
+
+    // Dictionary encoded strings are only kept as dictionaries if requested
+    let dictionary = DataType::Dictionary(Box::new(DataType::UInt64), Box::new(DataType::Utf8));
+    let schema = Arc::new(Schema::new(vec![Field::new(
+        "dict",
+        dictionary.clone(),
+        true,
+    )]));
+    let f = File::open(basic_path("string_dict.orc")).expect("no file found");
+    let reader = ArrowReaderBuilder::try_new(f)
+        .unwrap()
+        .with_schema(schema.clone())
+        .build();
+    assert_eq!(schema, reader.schema());
+    let batch = reader.take(1).next().unwrap().unwrap();
+    assert_eq!(schema, batch.schema());
+}
+
+#[test]
+pub fn dictionary_decoded_once_test() {
+    let path = basic_path("string_dict.orc");
+    let dictionary = DataType::Dictionary(Box::new(DataType::UInt64), Box::new(DataType::Utf8));
+    let schema = Arc::new(Schema::new(vec![Field::new("dict", dictionary, true)]));
+    let batches = ArrowReaderBuilder::try_new(File::open(&path).unwrap())
+        .unwrap()
+        .with_schema(schema)
+        .with_batch_size(10)
+        .build()
+        .collect::<Result<Vec<_>, _>>()
+        .unwrap();
+    assert_eq!(7, batches.len());
+
+    // The single stripe's dictionary is decoded once and shared by every batch
+    let values = batches
+        .iter()
+        .map(|batch| batch.column(0).as_any_dictionary().values().clone())
+        .collect::<Vec<_>>();
+    for other in &values[1..] {
+        assert_eq!(
+            values[0].to_data().buffers()[1].as_ptr(),
+            other.to_data().buffers()[1].as_ptr()
+        );
+    }
+
+    // Same strings as when decoding the whole stripe in one batch
+    let expected = ArrowReaderBuilder::try_new(File::open(&path).unwrap())
+        .unwrap()
+        .build()
+        .collect::<Result<Vec<_>, _>>()
+        .unwrap();
+    let actual = batches
+        .iter()
+        .map(|batch| arrow::compute::cast(batch.column(0), &DataType::Utf8).unwrap())
+        .collect::<Vec<_>>();
+    let actual =
+        arrow::compute::concat(&actual.iter().map(AsRef::as_ref).collect::<Vec<_>>()).unwrap();
+    assert_eq!(expected[0].column(0), &actual);
+}
+
+#[test]
+pub fn column_reader_test() {
+    let path = basic_path("demo-12-zlib.orc");
+    let sum = |arrays: &[ArrayRef]| {
+        arrays
+            .iter()
+            .map(|array| arrow::compute::sum(array.as_primitive::<Int32Type>()).unwrap() as i64)
+            .sum::<i64>()
+    };
+
+    let arrays = ArrowReaderBuilder::try_new(File::open(&path).unwrap())
+        .unwrap()
+        .build_column_reader("_col0")
+        .unwrap()
+        .collect::<Result<Vec<_>, _>>()
+        .unwrap();
+    let batches = new_arrow_reader_root(&path)
+        .collect::<Result<Vec<_>, _>>()
+        .unwrap();
+    let columns = batches
+        .iter()
+        .map(|batch| batch.column(0).clone())
+        .collect::<Vec<_>>();
+    assert_eq!(
+        1_920_800,
+        arrays.iter().map(|array| array.len()).sum::<usize>()
+    );
+    assert_eq!(sum(&columns), sum(&arrays));
+
+    // Fields of structs can be read on their own
+    let path = basic_path("nested_struct.orc");
+    let arrays = ArrowReaderBuilder::try_new(File::open(&path).unwrap())
+        .unwrap()
+        .build_column_reader("nest.a")
+        .unwrap()
+        .collect::<Result<Vec<_>, _>>()
+        .unwrap();
+    let batches = new_arrow_reader_root(&path)
+        .collect::<Result<Vec<_>, _>>()
+        .unwrap();
+    let expected = batches[0]
+        .column(0)
+        .as_struct()
+        .column_by_name("a")
+        .unwrap();
+    assert_eq!(expected, &arrays[0]);
+
+    let err = ArrowReaderBuilder::try_new(File::open(&path).unwrap())
+        .unwrap()
+        .build_column_reader("nest.missing")
+        .err()
+        .unwrap();
+    assert!(err.to_string().contains("No column at path"), "{err}");
+}
+
+#[test]
+pub fn hive_acid_unwrap_test() {
+    let file = synthetic::hive_acid_file(200);
+    let reader = ArrowReaderBuilder::try_new(file.clone())
+        .unwrap()
+        .with_hive_acid_unwrap(true)
+        .build();
+    let expected_schema = Schema::new(vec![
+        Field::new("x", DataType::Int32, true),
+        Field::new("y", DataType::Int64, true),
+        Field::new("z", DataType::Utf8, true),
+    ]);
+    assert_eq!(&expected_schema, reader.schema().as_ref());
+    let batches = reader.collect::<Result<Vec<_>, _>>().unwrap();
+    assert_eq!(&expected_schema, batches[0].schema().as_ref());
+    assert_eq!(200, batches[0].num_rows());
+    let y = batches[0].column(1).as_primitive::<Int64Type>();
+    assert_eq!((0..200).collect::<Vec<_>>(), y.values().to_vec());
+    let z = batches[0].column(2).as_string::<i32>();
+    assert_eq!("7", z.value(17));
+
+    // Dictionary columns apply to the user columns
+    let reader = ArrowReaderBuilder::try_new(file.clone())
+        .unwrap()
+        .with_hive_acid_unwrap(true)
+        .with_dictionary_columns(&["z"])
+        .build();
+    let dictionary_type =
+        DataType::Dictionary(Box::new(DataType::UInt64), Box::new(DataType::Utf8));
+    assert_eq!(&dictionary_type, reader.schema().field(2).data_type());
+    let batches = reader.collect::<Result<Vec<_>, _>>().unwrap();
+    let z = arrow::compute::cast(batches[0].column(2), &DataType::Utf8).unwrap();
+    assert_eq!("7", z.as_string::<i32>().value(17));
+
+    // Renames apply to the user columns
+    let reader = ArrowReaderBuilder::try_new(file.clone())
+        .unwrap()
+        .with_hive_acid_unwrap(true)
+        .with_column_rename(HashMap::from([("z".to_owned(), "digit".to_owned())]))
+        .build();
+    assert_eq!("digit", reader.schema().field(2).name());
+    let batches = reader.collect::<Result<Vec<_>, _>>().unwrap();
+    assert_eq!("digit", batches[0].schema().field(2).name());
+    assert_eq!("y", batches[0].schema().field(1).name());
+    assert_eq!("7", batches[0].column(2).as_string::<i32>().value(17));
+
+    // The ACID columns are kept when not unwrapping
+    let reader = ArrowReaderBuilder::try_new(file).unwrap().build();
+    assert_eq!(6, reader.schema().fields().len());
+
+    // Other files are read as usual
+    let reader = ArrowReaderBuilder::try_new(synthetic::int_file(100, 0))
+        .unwrap()
+        .with_hive_acid_unwrap(true)
+        .build();
+    assert_eq!("a", reader.schema().field(0).name());
+}
+
+#[tokio::test]
+pub async fn async_hive_acid_unwrap_test() {
+    let file = synthetic::hive_acid_file(200);
+    let expected = ArrowReaderBuilder::try_new(file.clone())
+        .unwrap()
+        .with_hive_acid_unwrap(true)
+        .with_column_rename(HashMap::from([("z".to_owned(), "digit".to_owned())]))
+        .with_output_order(&["digit"])
+        .read_all()
+        .unwrap();
+    let reader = RecordingAsyncReader {
+        file,
+        ranges: Arc::default(),
+    };
+    let reader = ArrowReaderBuilder::try_new_async(reader)
+        .await
+        .unwrap()
+        .with_hive_acid_unwrap(true)
+        .with_column_rename(HashMap::from([("z".to_owned(), "digit".to_owned())]))
+        .with_output_order(&["digit"])
+        .build_async();
+    assert_eq!(expected[0].schema(), reader.schema());
+    assert_eq!(3, reader.schema().fields().len());
+    let actual = reader.try_collect::<Vec<_>>().await.unwrap();
+    assert_eq!(expected, actual);
+}
+
+#[test]
+pub fn readahead_test() {
+    let path = basic_path("demo-11-zlib.orc");
+    let read = |readahead: Option<usize>, offset: usize| {
+        let builder = ArrowReaderBuilder::try_new(File::open(&path).unwrap()).unwrap();
+        let projection =
+            ProjectionMask::named_roots(builder.file_metadata().root_data_type(), &["_col0"]);
+        let builder = builder
+            .with_projection(projection)
+            .with_offset(offset)
+            .with_limit(200_000);
+        let batches: Result<Vec<_>, _> = match readahead {
+            Some(readahead) => builder.with_readahead(readahead).build().collect(),
+            None => builder.build().collect(),
+        };
+        batches
+            .unwrap()
+            .iter()
+            .map(|batch| batch.column(0).clone())
+            .collect::<Vec<_>>()
+    };
+
+    // Reading 40 stripes of about 5000 rows, from the first one or from the middle
+    for offset in [0, 1_000_000] {
+        let expected = read(None, offset);
+        for readahead in [0, 1, 4] {
+            let actual = read(Some(readahead), offset);
+            assert_eq!(expected, actual, "readahead of {readahead} from {offset}");
+        }
+    }
+}
+
+#[test]
+pub fn struct_fields_by_name_test() {
+    let file = synthetic::wide_struct_file(5);
+    // Requested out of order, leaving out w and y
+    let fields = vec![
+        Field::new("z", DataType::Int32, true),
+        Field::new("x", DataType::Int32, true),
+    ];
+    let batches = read_synthetic_as(file.clone(), DataType::Struct(fields.into())).unwrap();
+    let a = batches[0].column(0).as_struct();
+    let values_of = |a: &arrow::array::StructArray, name: &str| {
+        a.column_by_name(name)
+            .unwrap()
+            .as_primitive::<Int32Type>()
+            .values()
+            .to_vec()
+    };
+    assert_eq!(vec![300, 301, 302, 303, 304], values_of(a, "z"));
+    assert_eq!(vec![100, 101, 102, 103, 104], values_of(a, "x"));
+
+    // Fields missing from the ORC struct can't be matched
+    let fields = vec![
+        Field::new("x", DataType::Int32, true),
+        Field::new("missing", DataType::Int32, true),
+    ];
+    let err = read_synthetic_as(file.clone(), DataType::Struct(fields.into())).unwrap_err();
+    assert!(err.to_string().contains("Mismatched schema"), "{err}");
+
+    // Fields are only matched by position if no name matches
+    let names = ["z", "y", "x", "v"];
+    let fields = names.map(|name| Field::new(name, DataType::Int32, true));
+    let err =
+        read_synthetic_as(file.clone(), DataType::Struct(fields.to_vec().into())).unwrap_err();
+    assert!(err.to_string().contains("Mismatched schema"), "{err}");
+    let names = ["a", "b", "c", "d"];
+    let fields = names.map(|name| Field::new(name, DataType::Int32, true));
+    let batches = read_synthetic_as(file, DataType::Struct(fields.to_vec().into())).unwrap();
+    let a = batches[0].column(0).as_struct();
+    assert_eq!(vec![0, 1, 2, 3, 4], values_of(a, "a"));
+    assert_eq!(vec![300, 301, 302, 303, 304], values_of(a, "d"));
+}
+
+#[test]
+pub fn into_reader_test() {
+    let path = basic_path("test.orc");
+    let mut reader = new_arrow_reader(&path, &["int_delta"]);
+    let expected = reader.by_ref().collect::<Result<Vec<_>, _>>().unwrap();
+
+    // The file can be read again once given back
+    let file = reader.into_reader();
+    let builder = ArrowReaderBuilder::try_new(file).unwrap();
+    let projection =
+        ProjectionMask::named_roots(builder.file_metadata().root_data_type(), &["int_delta"]);
+    let batches = builder
+        .with_projection(projection)
+        .build()
+        .collect::<Result<Vec<_>, _>>()
+        .unwrap();
+    assert_eq!(expected, batches);
+}
+
+#[test]
+pub fn read_all_test() {
+    let path = basic_path("demo-12-zlib.orc");
+    let builder = |path: &str| {
+        let f = File::open(path).unwrap();
+        let builder = ArrowReaderBuilder::try_new(f).unwrap();
+        let projection =
+            ProjectionMask::named_roots(builder.file_metadata().root_data_type(), &["_col1"]);
+        builder
+            .with_projection(projection)
+            .with_batch_size(1000)
+            .with_offset(500)
+            .with_limit(2500)
+    };
+
+    let expected = builder(&path)
+        .build()
+        .collect::<Result<Vec<_>, _>>()
+        .unwrap();
+    let batches = builder(&path).read_all().unwrap();
+    assert_eq!(expected, batches);
+    let rows = batches.iter().map(RecordBatch::num_rows).sum::<usize>();
+    assert_eq!(2500, rows);
+    assert_eq!(1, batches[0].num_columns());
+
+    let batches = builder(&path).with_limit(0).read_all().unwrap();
+    assert!(batches.is_empty());
+}
+
+#[test]
+pub fn decode_threads_test() {
+    for path in [
+        "demo-12-zlib.orc",
+        "test.orc",
+        "alltypes.zstd.orc",
+        "nested_struct.orc",
+    ] {
+        let builder = || {
+            let f = File::open(basic_path(path)).unwrap();
+            ArrowReaderBuilder::try_new(f)
+                .unwrap()
+                .with_batch_size(1000)
+                .with_limit(50_000)
+        };
+        let expected = builder().read_all().unwrap();
+        // More threads than columns too
+        for decode_threads in [2, 4, 64] {
+            let batches = builder()
+                .with_decode_threads(decode_threads)
+                .read_all()
+                .unwrap();
+            assert_eq!(expected, batches, "{path} with {decode_threads} threads");
+        }
+    }
+}
+
+#[tokio::test]
+pub async fn async_decode_threads_test() {
+    let path = basic_path("demo-12-zlib.orc");
+    let expected = ArrowReaderBuilder::try_new(File::open(&path).unwrap())
+        .unwrap()
+        .with_limit(50_000)
+        .read_all()
+        .unwrap();
+    let f = tokio::fs::File::open(&path).await.unwrap();
+    let reader = ArrowReaderBuilder::try_new_async(f)
+        .await
+        .unwrap()
+        .with_limit(50_000)
+        .with_decode_threads(4)
+        .build_async();
+    let batches = reader.try_collect::<Vec<_>>().await.unwrap();
+    assert_eq!(expected, batches);
+}
+
+/// Decodes columns with the default decoder, recording the threads decoding them.
+struct ThreadRecordingDecoder {
+    inner: Box<dyn ArrayBatchDecoder>,
+    threads: Arc<Mutex<HashSet<std::thread::ThreadId>>>,
+}
+
+impl ArrayBatchDecoder for ThreadRecordingDecoder {
+    fn next_batch(
+        &mut self,
+        batch_size: usize,
+        parent_present: Option<&[bool]>,
+    ) -> datafusion_orc::error::Result<ArrayRef> {
+        self.threads
+            .lock()
+            .unwrap()
+            .insert(std::thread::current().id());
+        self.inner.next_batch(batch_size, parent_present)
+    }
+}
+
+fn thread_recording_builder<R>(
+    builder: ArrowReaderBuilder<R>,
+    threads: &Arc<Mutex<HashSet<std::thread::ThreadId>>>,
+) -> ArrowReaderBuilder<R> {
+    let threads = threads.clone();
+    builder
+        .with_batch_size(100)
+        .with_decode_threads(3)
+        .with_decoder_factory(Arc::new(move |column, field, stripe| {
+            let inner = array_decoder_factory(column, field, stripe, StringValidation::Strict)?;
+            let threads = threads.clone();
+            Ok(Some(Box::new(ThreadRecordingDecoder { inner, threads })))
+        }))
+}
+
+#[test]
+pub fn decode_threads_reused_test() {
+    let f = File::open(basic_path("demo-12-zlib.orc")).unwrap();
+    let threads = Arc::new(Mutex::new(HashSet::new()));
+    let batches = thread_recording_builder(ArrowReaderBuilder::try_new(f).unwrap(), &threads)
+        .with_limit(10_000)
+        .read_all()
+        .unwrap();
+    assert_eq!(100, batches.len());
+    // The same threads decode all the batches, this one included
+    let threads = threads.lock().unwrap();
+    assert!(threads.len() <= 3, "{threads:?}");
+    assert!(threads.contains(&std::thread::current().id()));
+}
+
+#[tokio::test]
+pub async fn async_decode_threads_reused_test() {
+    let f = tokio::fs::File::open(basic_path("demo-12-zlib.orc"))
+        .await
+        .unwrap();
+    let builder = ArrowReaderBuilder::try_new_async(f).await.unwrap();
+    let threads = Arc::new(Mutex::new(HashSet::new()));
+    let reader = thread_recording_builder(builder, &threads)
+        .with_limit(10_000)
+        .build_async();
+    let batches = reader.try_collect::<Vec<_>>().await.unwrap();
+    assert_eq!(100, batches.len());
+    // Batches are decoded off the task polling the stream
+    let threads = threads.lock().unwrap();
+    assert!(threads.len() <= 3, "{threads:?}");
+    assert!(!threads.contains(&std::thread::current().id()));
+}
+
+#[test]
+pub fn output_order_test() {
+    let path = basic_path("alltypes.snappy.orc");
+    let builder = || {
+        let f = File::open(&path).unwrap();
+        let builder = ArrowReaderBuilder::try_new(f).unwrap();
+        let root_data_type = builder.file_metadata().root_data_type();
+        let projection = ProjectionMask::named_roots(root_data_type, &["boolean", "int32", "utf8"]);
+        builder.with_projection(projection)
+    };
+    let expected = builder().read_all().unwrap();
+    let column_names = |schema: &Schema| {
+        schema
+            .fields()
+            .iter()
+            .map(|field| field.name().clone())
+            .collect::<Vec<_>>()
+    };
+    assert_eq!(
+        vec!["boolean", "int32", "utf8"],
+        column_names(&expected[0].schema())
+    );
+
+    let reader = builder()
+        .with_output_order(&["utf8", "boolean", "int32"])
+        .build();
+    assert_eq!(
+        vec!["utf8", "boolean", "int32"],
+        column_names(&reader.schema())
+    );
+    let batches = reader.collect::<Result<Vec<_>, _>>().unwrap();
+    let reordered = expected[0].project(&[2, 0, 1]).unwrap();
+    assert_eq!(reordered, batches[0]);
+
+    // Unknown names fail the read
+    let mut reader = builder().with_output_order(&["missing", "utf8"]).build();
+    let err = reader.next().unwrap().unwrap_err().to_string();
+    assert!(
+        err.contains("Column missing of the output order is not in the output schema"),
+        "unexpected error: {err}"
+    );
+    assert!(reader.next().is_none());
+}
+
+#[tokio::test]
+pub async fn async_output_order_test() {
+    let path = basic_path("alltypes.snappy.orc");
+    let expected = ArrowReaderBuilder::try_new(File::open(&path).unwrap())
+        .unwrap()
+        .read_all()
+        .unwrap();
+    let f = tokio::fs::File::open(&path).await.unwrap();
+    let reader = ArrowReaderBuilder::try_new_async(f)
+        .await
+        .unwrap()
+        .with_output_order(&["utf8", "boolean"])
+        .build_async();
+    let utf8 = expected[0].schema().index_of("utf8").unwrap();
+    let boolean = expected[0].schema().index_of("boolean").unwrap();
+    let columns = [utf8, boolean]
+        .into_iter()
+        .chain((0..expected[0].num_columns()).filter(|&index| index != utf8 && index != boolean))
+        .collect::<Vec<_>>();
+    let reordered = expected[0].project(&columns).unwrap();
+    assert_eq!(reordered.schema(), reader.schema());
+    let batches = reader.try_collect::<Vec<_>>().await.unwrap();
+    assert_eq!(reordered, batches[0]);
+
+    // Unknown names fail the stream
+    let f = tokio::fs::File::open(&path).await.unwrap();
+    let results = ArrowReaderBuilder::try_new_async(f)
+        .await
+        .unwrap()
+        .with_output_order(&["missing"])
+        .build_async()
+        .collect::<Vec<_>>()
+        .await;
+    assert_eq!(1, results.len());
+    let err = results[0].as_ref().unwrap_err().to_string();
+    assert!(err.contains("Column missing"), "unexpected error: {err}");
+}
+
+#[test]
+pub fn clone_builder_test() {
+    let file = Bytes::from(std::fs::read(basic_path("alltypes.snappy.orc")).unwrap());
+    let builder = ArrowReaderBuilder::try_new(file).unwrap();
+    let projection =
+        ProjectionMask::named_roots(builder.file_metadata().root_data_type(), &["int32", "utf8"]);
+    let schema = Arc::new(Schema::new(vec![
+        Field::new("int32", DataType::Int64, true),
+        Field::new("utf8", DataType::LargeUtf8, true),
+    ]));
+    let builder = builder
+        .with_projection(projection)
+        .with_schema(schema.clone())
+        .with_batch_size(4)
+        .with_offset(1)
+        .with_limit(8)
+        .with_string_validation(StringValidation::Lossy);
+
+    let first = builder.clone().read_all().unwrap();
+    let second = builder.read_all().unwrap();
+    assert_eq!(first, second);
+    assert_eq!(8, first.iter().map(RecordBatch::num_rows).sum::<usize>());
+    assert_eq!(&DataType::Int64, first[0].schema().field(0).data_type());
+    assert_eq!(&DataType::LargeUtf8, first[0].schema().field(1).data_type());
+}
+
+/// Checks batches of [`synthetic::root_present_file`], with every other row null.
+fn check_root_present_batches(batches: Vec<RecordBatch>) {
+    let expected = (0..200)
+        .map(|row| (row % 2 == 0).then_some(row / 2))
+        .collect::<Vec<_>>();
+    let batch = arrow::compute::concat_batches(&batches[0].schema(), &batches).unwrap();
+    let a = batch.column(0).as_primitive::<Int32Type>();
+    assert_eq!(expected, a.iter().collect::<Vec<_>>());
+    let b = batch.column(1).as_struct();
+    assert_eq!(100, b.null_count());
+    let c = b.column(0).as_primitive::<Int32Type>();
+    assert_eq!(expected, c.iter().collect::<Vec<_>>());
+}
+
+#[test]
+pub fn root_present_test() {
+    let batches = ArrowReaderBuilder::try_new(synthetic::root_present_file())
+        .unwrap()
+        .with_batch_size(64)
+        .build()
+        .collect::<Result<Vec<_>, _>>()
+        .unwrap();
+    check_root_present_batches(batches);
+}
+
+#[tokio::test]
+pub async fn async_root_present_test() {
+    let file = std::io::Cursor::new(synthetic::root_present_file().to_vec());
+    let batches = ArrowReaderBuilder::try_new_async(file)
+        .await
+        .unwrap()
+        .with_batch_size(64)
+        .build_async()
+        .try_collect::<Vec<_>>()
+        .await
+        .unwrap();
+    check_root_present_batches(batches);
+}
+
+#[test]
+pub fn gzip_wrapped_chunks_test() {
+    let file = synthetic::gzip_wrapped_file();
+    let builder = ArrowReaderBuilder::try_new(file).unwrap();
+    assert!(builder.file_metadata().compression().is_some());
+    let batches = builder.read_all().unwrap();
+    let values = batches[0].column(0).as_primitive::<Int32Type>();
+    assert_eq!((0..100).collect::<Vec<_>>(), values.values().to_vec());
+}
+
+/// Chunks of files written with a larger `orc.compress.size` than the default
+/// 256 KiB decompress to up to the block size given in the postscript.
+#[test]
+pub fn large_compression_block_size_test() {
+    // 800,000 bytes of string data in a single chunk
+    let file = synthetic::large_chunk_file(8, Some(1024 * 1024));
+    let batches = ArrowReaderBuilder::try_new(file)
+        .unwrap()
+        .read_all()
+        .unwrap();
+    let values = batches[0].column(0).as_string::<i32>();
+    assert_eq!(8, values.len());
+    assert!(values
+        .iter()
+        .all(|value| value == Some("x".repeat(100_000).as_str())));
+
+    let file = synthetic::large_chunk_file(8, None);
+    let err = ArrowReaderBuilder::try_new(file)
+        .unwrap()
+        .read_all()
+        .unwrap_err();
+    assert!(
+        err.to_string()
+            .contains("exceeds compression block size 262144"),
+        "{err}"
+    );
+}
+
+#[test]
+pub fn missing_column_encoding_test() {
+    let file = synthetic::missing_encoding_file();
+    let builder = ArrowReaderBuilder::try_new(file.clone()).unwrap();
+    let projection = ProjectionMask::named_roots(builder.file_metadata().root_data_type(), &["a"]);
+    let batches = builder.with_projection(projection).read_all().unwrap();
+    assert_eq!(100, batches[0].num_rows());
+
+    let err = ArrowReaderBuilder::try_new(file)
+        .unwrap()
+        .read_all()
+        .unwrap_err();
+    assert!(
+        err.to_string().contains(
+            "Corrupt stripe footer: no encoding for column 2 (in column 'b'), only 2 encodings"
+        ),
+        "unexpected error: {err}"
+    );
+}
+
+#[test]
+pub fn stripe_filter_test() {
+    let read = |offset: usize| {
+        let batches = ArrowReaderBuilder::try_new(synthetic::int_stripes_file(5))
+            .unwrap()
+            .with_stripe_filter(Arc::new(|_, index| index % 2 == 0))
+            .with_offset(offset)
+            .read_all()
+            .unwrap();
+        batches
+            .iter()
+            .flat_map(|batch| {
+                batch
+                    .column(0)
+                    .as_primitive::<Int32Type>()
+                    .values()
+                    .to_vec()
+            })
+            .collect::<Vec<_>>()
+    };
+
+    let even_stripes = (0..100).chain(200..300).chain(400..500);
+    assert_eq!(even_stripes.collect::<Vec<_>>(), read(0));
+    // The offset counts the rows of the skipped stripes
+    assert_eq!((250..300).chain(400..500).collect::<Vec<_>>(), read(250));
+    assert_eq!((200..300).chain(400..500).collect::<Vec<_>>(), read(150));
+
+    // Filtering on the metadata, here skipping the first stripe, which starts
+    // right after the "ORC" header
+    let batches = ArrowReaderBuilder::try_new(synthetic::empty_stripe_file())
+        .unwrap()
+        .with_stripe_filter(Arc::new(|info, _| info.offset() > 3))
+        .read_all()
+        .unwrap();
+    assert_eq!(
+        100,
+        batches.iter().map(|batch| batch.num_rows()).sum::<usize>()
+    );
+}
+
+#[tokio::test]
+pub async fn async_stripe_filter_test() {
+    let file = std::io::Cursor::new(synthetic::int_stripes_file(5));
+    let batches = ArrowReaderBuilder::try_new_async(file)
+        .await
+        .unwrap()
+        .with_stripe_filter(Arc::new(|_, index| index % 2 == 0))
+        .build_async()
+        .try_collect::<Vec<_>>()
+        .await
+        .unwrap();
+    let values = batches
+        .iter()
+        .flat_map(|batch| {
+            batch
+                .column(0)
+                .as_primitive::<Int32Type>()
+                .values()
+                .to_vec()
+        })
+        .collect::<Vec<_>>();
+    let even_stripes = (0..100).chain(200..300).chain(400..500);
+    assert_eq!(even_stripes.collect::<Vec<_>>(), values);
+}
+
+#[cfg(feature = "datafusion")]
+#[test]
+pub fn null_check_stripe_pruning_test() {
+    use datafusion::logical_expr::Expr;
+    use datafusion::prelude::col;
+    use datafusion_orc::datafusion::stripe_may_match;
+
+    let file = synthetic::nullable_int_stripes_file();
+    // Indices of the stripes read and number of null and non-null values
+    let read = |predicate: Expr| {
+        let builder = ArrowReaderBuilder::try_new(file.clone()).unwrap();
+        let file_metadata = builder.file_metadata().clone();
+        let read_stripes = Arc::new(Mutex::new(vec![]));
+        let recorded_stripes = read_stripes.clone();
+        let batches = builder
+            .with_stripe_filter(Arc::new(move |info, index| {
+                let may_match = stripe_may_match(&file_metadata, info, &predicate);
+                if may_match {
+                    recorded_stripes.lock().unwrap().push(index);
+                }
+                may_match
+            }))
+            .read_all()
+            .unwrap();
+        let nulls = batches
+            .iter()
+            .map(|batch| batch.column(0).null_count())
+            .sum::<usize>();
+        let rows = batches.iter().map(|batch| batch.num_rows()).sum::<usize>();
+        let read_stripes = read_stripes.lock().unwrap().clone();
+        (read_stripes, nulls, rows - nulls)
+    };
+
+    // The first stripe has no nulls
+    assert_eq!((vec![1, 2, 3], 152, 148), read(col("a").is_null()));
+    // The third stripe has no values, the last one doesn't record how many
+    assert_eq!((vec![0, 1, 3], 52, 248), read(col("a").is_not_null()));
+}
+
+#[test]
+pub fn coalesce_batches_test() {
+    let row_counts = |batches: &[RecordBatch]| {
+        batches
+            .iter()
+            .map(RecordBatch::num_rows)
+            .collect::<Vec<_>>()
+    };
+    // Stripes of 100, 0 and 100 rows
+    let read = |coalesce_batches: bool| {
+        ArrowReaderBuilder::try_new(synthetic::empty_stripe_file())
+            .unwrap()
+            .with_batch_size(30)
+            .with_offset(5)
+            .with_coalesce_batches(coalesce_batches)
+            .read_all()
+            .unwrap()
+    };
+    let batches = read(false);
+    assert_eq!(vec![25, 30, 30, 10, 30, 30, 30, 10], row_counts(&batches));
+    let expected = arrow::compute::concat_batches(&batches[0].schema(), &batches).unwrap();
+    let coalesced = read(true);
+    assert_eq!(vec![30, 30, 30, 30, 30, 30, 15], row_counts(&coalesced));
+    let actual = arrow::compute::concat_batches(&coalesced[0].schema(), &coalesced).unwrap();
+    assert_eq!(expected, actual);
+
+    // Rows buffered before seeking are dropped
+    let mut reader = ArrowReaderBuilder::try_new(synthetic::empty_stripe_file())
+        .unwrap()
+        .with_batch_size(30)
+        .with_offset(5)
+        .with_coalesce_batches(true)
+        .build();
+    reader.next().unwrap().unwrap();
+    reader.seek_to_row(150);
+    let batch = reader.next().unwrap().unwrap();
+    let values = batch
+        .column(0)
+        .as_primitive::<Int32Type>()
+        .values()
+        .to_vec();
+    let expected_values = expected.column(0).as_primitive::<Int32Type>();
+    assert_eq!(expected_values.values()[145..175].to_vec(), values);
+
+    // Batches of a single row rather than empty ones
+    let batches = ArrowReaderBuilder::try_new(synthetic::int_file(100, 0))
+        .unwrap()
+        .with_batch_size(0)
+        .with_coalesce_batches(true)
+        .read_all()
+        .unwrap();
+    assert_eq!(vec![1; 100], row_counts(&batches));
+
+    // Row groups of 100 rows, smaller than the batch size
+    let batches = ArrowReaderBuilder::try_new(synthetic::int_file(1000, 100))
+        .unwrap()
+        .with_batch_size(300)
+        .with_row_group_aligned_batches(true)
+        .with_coalesce_batches(true)
+        .read_all()
+        .unwrap();
+    assert_eq!(vec![300, 300, 300, 100], row_counts(&batches));
+    let values = batches
+        .iter()
+        .flat_map(|batch| {
+            batch
+                .column(0)
+                .as_primitive::<Int32Type>()
+                .values()
+                .to_vec()
+        })
+        .collect::<Vec<_>>();
+    assert_eq!((0..1000).collect::<Vec<_>>(), values);
+}
+
+#[tokio::test]
+pub async fn async_coalesce_batches_test() {
+    let file = std::io::Cursor::new(synthetic::empty_stripe_file());
+    let reader = ArrowReaderBuilder::try_new_async(file)
+        .await
+        .unwrap()
+        .with_batch_size(30)
+        .with_coalesce_batches(true)
+        .build_async();
+    let batches = reader.try_collect::<Vec<_>>().await.unwrap();
+    assert_eq!(
+        vec![30, 30, 30, 30, 30, 30, 20],
+        batches
+            .iter()
+            .map(RecordBatch::num_rows)
+            .collect::<Vec<_>>()
+    );
+}
+
+#[test]
+pub fn large_footer_test() {
+    let file = synthetic::wide_file(5000);
+    let observer = Arc::new(RecordingObserver::default());
+    let mut reader = ObservedReader::new(file.clone(), observer.clone());
+    let metadata = read_metadata(&mut reader).unwrap();
+    assert_eq!(5000, metadata.root_data_type().children().len());
+    // The footer doesn't fit in the initial tail read, so the rest is read after
+    let ranges = observer.ranges.lock().unwrap().clone();
+    assert_eq!(2, ranges.len(), "{ranges:?}");
+    let (start, length) = ranges[0];
+    assert_eq!(file.len() as u64, start + length);
+    assert_eq!(start, ranges[1].0 + ranges[1].1);
+
+    // Truncated before the end of the footer
+    let err = read_metadata(&mut file.slice(file.len() - 20_000..)).unwrap_err();
+    assert!(
+        err.to_string()
+            .contains("File too small for given footer and metadata lengths"),
+        "{err}"
+    );
+
+    let batches = ArrowReaderBuilder::try_new(file)
+        .unwrap()
+        .read_all()
+        .unwrap();
+    assert_eq!(5000, batches[0].num_columns());
+    let values = batches[0].column(4999).as_primitive::<Int32Type>();
+    assert_eq!((0..100).collect::<Vec<_>>(), values.values().to_vec());
+}
+
+#[test]
+pub fn empty_stripe_test() {
+    let file = synthetic::empty_stripe_file();
+    let builder = ArrowReaderBuilder::try_new(file).unwrap();
+    assert_eq!(
+        vec![100, 0, 100],
+        builder
+            .file_metadata()
+            .stripe_metadatas()
+            .iter()
+            .map(|stripe| stripe.number_of_rows())
+            .collect::<Vec<_>>()
+    );
+    let batches = builder.read_all().unwrap();
+    assert_eq!(
+        vec![100, 100],
+        batches
+            .iter()
+            .map(RecordBatch::num_rows)
+            .collect::<Vec<_>>()
+    );
+    let expected = [
+        "+---+---+-----+--------+",
+        "| a | b | c   | d      |",
+        "+---+---+-----+--------+",
+        "| 0 | x | [0] | {e: 0} |",
+        "| 1 | x | [1] | {e: 1} |",
+        "+---+---+-----+--------+",
+    ];
+    assert_batches_eq(&[batches[1].slice(0, 2)], &expected);
+
+    // Starting within the empty stripe
+    let batches = ArrowReaderBuilder::try_new(synthetic::empty_stripe_file())
+        .unwrap()
+        .with_offset(100)
+        .read_all()
+        .unwrap();
+    assert_eq!(
+        vec![100],
+        batches
+            .iter()
+            .map(RecordBatch::num_rows)
+            .collect::<Vec<_>>()
+    );
+}
+
+#[tokio::test]
+pub async fn async_empty_stripe_test() {
+    let file = std::io::Cursor::new(synthetic::empty_stripe_file());
+    let reader = ArrowReaderBuilder::try_new_async(file)
+        .await
+        .unwrap()
+        .build_async();
+    let batches = reader.try_collect::<Vec<_>>().await.unwrap();
+    assert_eq!(
+        vec![100, 100],
+        batches
+            .iter()
+            .map(RecordBatch::num_rows)
+            .collect::<Vec<_>>()
+    );
+}
+
+#[test]
+pub fn corrupt_schema_test() {
+    let struct_type = |subtypes: Vec<u32>| Type {
+        kind: Some(TypeKind::Struct as i32),
+        field_names: (0..subtypes.len()).map(|i| format!("f{i}")).collect(),
+        subtypes,
+        ..Default::default()
+    };
+    let int_type = Type {
+        kind: Some(TypeKind::Int as i32),
+        ..Default::default()
+    };
+    let list_type = |child| Type {
+        kind: Some(TypeKind::List as i32),
+        subtypes: vec![child],
+        ..Default::default()
+    };
+
+    let cases = [
+        (
+            vec![struct_type(vec![1, 5]), int_type.clone()],
+            "Type 0 references subtype 5, but there are only 2 types",
+        ),
+        (
+            vec![struct_type(vec![1]), list_type(1)],
+            "Type 1 references subtype 1, which doesn't come after it",
+        ),
+        (
+            vec![struct_type(vec![1]), struct_type(vec![2]), list_type(0)],
+            "Type 2 references subtype 0, which doesn't come after it",
+        ),
+        (vec![int_type], "Root type must be a struct, found Int"),
+    ];
+    for (types, expected) in cases {
+        let err = RootDataType::from_proto(&types).unwrap_err();
+        assert!(matches!(err, OrcError::CorruptSchema { .. }), "{err}");
+        assert_eq!(format!("Corrupt schema: {expected}"), err.to_string());
+    }
+}
+
+#[test]
+pub fn send_sync_test() {
+    fn assert_send<T: Send>() {}
+    fn assert_sync<T: Sync>() {}
+
+    assert_send::<Box<dyn ArrayBatchDecoder>>();
+    assert_send::<NaiveStripeDecoder>();
+    assert_send::<PrimitiveVisitorDecoder>();
+    assert_send::<ArrowReader<File>>();
+    assert_send::<ColumnReader<File>>();
+    assert_send::<ArrowReaderBuilder<File>>();
+    assert_sync::<ArrowReaderBuilder<File>>();
+    assert_send::<ArrowStreamReader<tokio::fs::File>>();
+    assert_send::<MultiFileReader<File>>();
+    assert_send::<Stripe>();
+    assert_sync::<Stripe>();
+    assert_send::<FileMetadata>();
+    assert_sync::<FileMetadata>();
+    assert_send::<OrcError>();
+    assert_sync::<OrcError>();
+}
+
 // From https://github.com/apache/arrow-rs/blob/7705acad845e8b2a366a08640f7acb4033ed7049/arrow-flight/src/sql/metadata/mod.rs#L67-L75
 pub fn assert_batches_eq(batches: &[RecordBatch], expected_lines: &[&str]) {
     let formatted = pretty::pretty_format_batches(batches).unwrap().to_string();
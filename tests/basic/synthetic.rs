@@ -0,0 +1,1264 @@
+//! Small ORC files built in memory, for layouts and types none of the fixtures have.
+
+use bytes::Bytes;
+use datafusion_orc::bloom_filter::BloomFilter;
+use datafusion_orc::proto::{
+    self, column_encoding, r#type, stream, ColumnEncoding, Footer, Metadata, PostScript,
+    StripeFooter, StripeInformation, StripeStatistics,
+};
+use num::{BigInt, Signed};
+use prost::Message;
+
+fn write_varint(buffer: &mut Vec<u8>, mut value: u64) {
+    while value >= 0x80 {
+        buffer.push(value as u8 | 0x80);
+        value >>= 7;
+    }
+    buffer.push(value as u8);
+}
+
+/// Uncompressed file with a single stripe holding a non-null int column `a`
+/// with values `0..num_rows`, in runs of 100 rows.
+pub fn int_file(num_rows: u64, row_index_stride: u32) -> Bytes {
+    int_file_with_present(num_rows, row_index_stride, None)
+}
+
+/// Same as [`int_file`], with the given (encoded) present stream for column `a`.
+pub fn int_file_with_present(
+    num_rows: u64,
+    row_index_stride: u32,
+    present: Option<&[u8]>,
+) -> Bytes {
+    let mut streams = vec![];
+    if let Some(present) = present {
+        streams.push((stream::Kind::Present, present.to_vec()));
+    }
+    streams.push((stream::Kind::Data, int_data(num_rows)));
+    single_column_file(int_type(), num_rows, streams, row_index_stride)
+}
+
+/// Same as [`int_file`], with a row index recording the minimum and maximum of
+/// column `a` in each row group.
+pub fn int_file_with_row_index(num_rows: u64, row_index_stride: u32) -> Bytes {
+    let stride = row_index_stride as u64;
+    let row_index = proto::RowIndex {
+        entry: (0..num_rows)
+            .step_by(stride as usize)
+            .map(|start| {
+                let end = (start + stride).min(num_rows);
+                proto::RowIndexEntry {
+                    positions: vec![],
+                    statistics: Some(proto::ColumnStatistics {
+                        number_of_values: Some(end - start),
+                        int_statistics: Some(proto::IntegerStatistics {
+                            minimum: Some(start as i64),
+                            maximum: Some(end as i64 - 1),
+                            sum: None,
+                        }),
+                        ..Default::default()
+                    }),
+                }
+            })
+            .collect(),
+    };
+    let streams = vec![
+        (stream::Kind::RowIndex, row_index.encode_to_vec()),
+        (stream::Kind::Data, int_data(num_rows)),
+    ];
+    single_column_file(int_type(), num_rows, streams, row_index_stride)
+}
+
+/// Same as [`int_file_with_row_index`], with the row index also recording where
+/// each row group starts in the data stream, so row groups can be read on their own.
+pub fn int_file_with_row_positions(num_rows: u64, row_index_stride: u32) -> Bytes {
+    let stride = row_index_stride as u64;
+    let row_index = proto::RowIndex {
+        entry: (0..num_rows)
+            .step_by(stride as usize)
+            .map(|start| {
+                let end = (start + stride).min(num_rows);
+                // The runs before that of the first row, then the rows before it in its run
+                let run_offset = int_data(start / 100 * 100).len() as u64;
+                proto::RowIndexEntry {
+                    positions: vec![run_offset, start % 100],
+                    statistics: Some(proto::ColumnStatistics {
+                        number_of_values: Some(end - start),
+                        int_statistics: Some(proto::IntegerStatistics {
+                            minimum: Some(start as i64),
+                            maximum: Some(end as i64 - 1),
+                            sum: None,
+                        }),
+                        ..Default::default()
+                    }),
+                }
+            })
+            .collect(),
+    };
+    let streams = vec![
+        (stream::Kind::RowIndex, row_index.encode_to_vec()),
+        (stream::Kind::Data, int_data(num_rows)),
+    ];
+    single_column_file(int_type(), num_rows, streams, row_index_stride)
+}
+
+/// Same as [`int_file_with_present`], with stripe statistics recording
+/// `has_null` for column `a`.
+pub fn int_file_with_has_null(num_rows: u64, present: &[u8], has_null: Option<bool>) -> Bytes {
+    let streams = vec![
+        (1, stream::Kind::Present, present.to_vec()),
+        (1, stream::Kind::Data, int_data(num_rows)),
+    ];
+    let column_statistics = vec![
+        proto::ColumnStatistics::default(),
+        proto::ColumnStatistics {
+            number_of_values: Some(num_rows),
+            has_null,
+            ..Default::default()
+        },
+    ];
+    file_with_columns(
+        root_with_field_a(vec![int_type()]),
+        num_rows,
+        streams,
+        0,
+        column_statistics,
+    )
+}
+
+/// Unsigned integers as a single RLE v1 run of literals.
+fn rle_v1_literals(values: &[u64]) -> Vec<u8> {
+    assert!(!values.is_empty() && values.len() <= 128);
+    let mut buffer = vec![values.len().wrapping_neg() as u8];
+    for &value in values {
+        write_varint(&mut buffer, value);
+    }
+    buffer
+}
+
+fn int_type() -> proto::Type {
+    proto::Type {
+        kind: Some(r#type::Kind::Int as i32),
+        ..Default::default()
+    }
+}
+
+/// Values `0..num_rows` as RLE v1 runs of 100 values.
+fn int_data(num_rows: u64) -> Vec<u8> {
+    assert_eq!(0, num_rows % 100);
+
+    // RLE v1 runs: length - 3, delta of 1, zigzag encoded base
+    let mut data = vec![];
+    for base in (0..num_rows).step_by(100) {
+        data.extend_from_slice(&[97, 1]);
+        write_varint(&mut data, base << 1);
+    }
+    data
+}
+
+/// Uncompressed file with a single stripe holding a decimal column `a`, with
+/// the given unscaled values and their scales.
+pub fn decimal_file(values: &[Option<(BigInt, i32)>]) -> Bytes {
+    decimal_file_with_truncated_stream(values, None)
+}
+
+/// Same as [`decimal_file`], without the last value of the given stream (data or
+/// secondary) if any.
+pub fn decimal_file_with_truncated_stream(
+    values: &[Option<(BigInt, i32)>],
+    truncated: Option<stream::Kind>,
+) -> Bytes {
+    assert!(values.len() <= 128);
+    let num_rows = values.len() as u64;
+
+    // Byte RLE literals of the present bits
+    let present_bits = values
+        .chunks(8)
+        .map(|chunk| {
+            chunk
+                .iter()
+                .enumerate()
+                .filter(|(_, value)| value.is_some())
+                .fold(0u8, |byte, (index, _)| byte | (0x80 >> index))
+        })
+        .collect::<Vec<_>>();
+    let mut present = vec![present_bits.len().wrapping_neg() as u8];
+    present.extend(present_bits);
+
+    let values = values.iter().flatten().collect::<Vec<_>>();
+    let stream_values = |kind| match truncated {
+        Some(truncated) if truncated == kind => &values[..values.len() - 1],
+        _ => &values[..],
+    };
+    // Zigzag encoded varints of unbounded length
+    let mut data = vec![];
+    for (value, _) in stream_values(stream::Kind::Data) {
+        let mut zigzag = if value.is_negative() {
+            (-value << 1) - 1
+        } else {
+            value << 1
+        };
+        loop {
+            let byte = (&zigzag & BigInt::from(0x7F)).try_into().unwrap();
+            zigzag >>= 7;
+            if zigzag == BigInt::from(0) {
+                data.push(byte);
+                break;
+            }
+            data.push(byte | 0x80);
+        }
+    }
+    // RLE v1 literals of the zigzag encoded scales
+    let scale_values = stream_values(stream::Kind::Secondary);
+    let mut scales = vec![scale_values.len().wrapping_neg() as u8];
+    for (_, scale) in scale_values {
+        write_varint(&mut scales, ((scale << 1) ^ (scale >> 31)) as u32 as u64);
+    }
+
+    let streams = vec![
+        (stream::Kind::Present, present),
+        (stream::Kind::Data, data),
+        (stream::Kind::Secondary, scales),
+    ];
+    let column_type = proto::Type {
+        kind: Some(r#type::Kind::Decimal as i32),
+        precision: Some(38),
+        scale: Some(2),
+        ..Default::default()
+    };
+    single_column_file(column_type, num_rows, streams, 0)
+}
+
+/// Uncompressed file with a single stripe holding a direct encoded string column
+/// `a`, with the given (unchecked) lengths and data.
+pub fn string_file(lengths: &[u64], data: &[u8]) -> Bytes {
+    let column_type = proto::Type {
+        kind: Some(r#type::Kind::String as i32),
+        ..Default::default()
+    };
+    let streams = vec![
+        (stream::Kind::Length, rle_v1_literals(lengths)),
+        (stream::Kind::Data, data.to_vec()),
+    ];
+    single_column_file(column_type, lengths.len() as u64, streams, 0)
+}
+
+/// File with a string column `a`, a binary column `b` and a struct column `s` of a
+/// string field `x`, each holding the values "ab" and "c", with stripe statistics
+/// claiming their values add up to `total_length` bytes.
+pub fn strings_file_with_total_length(total_length: i64) -> Bytes {
+    let string_type = proto::Type {
+        kind: Some(r#type::Kind::String as i32),
+        ..Default::default()
+    };
+    let types = vec![
+        proto::Type {
+            kind: Some(r#type::Kind::Struct as i32),
+            subtypes: vec![1, 2, 3],
+            field_names: vec!["a".to_owned(), "b".to_owned(), "s".to_owned()],
+            ..Default::default()
+        },
+        string_type.clone(),
+        proto::Type {
+            kind: Some(r#type::Kind::Binary as i32),
+            ..Default::default()
+        },
+        proto::Type {
+            kind: Some(r#type::Kind::Struct as i32),
+            subtypes: vec![4],
+            field_names: vec!["x".to_owned()],
+            ..Default::default()
+        },
+        string_type,
+    ];
+    let streams = [1, 2, 4]
+        .into_iter()
+        .flat_map(|column| {
+            [
+                (column, stream::Kind::Length, rle_v1_literals(&[2, 1])),
+                (column, stream::Kind::Data, b"abc".to_vec()),
+            ]
+        })
+        .collect();
+    let string_statistics = proto::ColumnStatistics {
+        string_statistics: Some(proto::StringStatistics {
+            minimum: Some("ab".to_owned()),
+            maximum: Some("c".to_owned()),
+            sum: Some(total_length),
+            ..Default::default()
+        }),
+        ..Default::default()
+    };
+    let column_statistics = vec![
+        proto::ColumnStatistics::default(),
+        string_statistics.clone(),
+        proto::ColumnStatistics {
+            binary_statistics: Some(proto::BinaryStatistics {
+                sum: Some(total_length),
+            }),
+            ..Default::default()
+        },
+        proto::ColumnStatistics::default(),
+        string_statistics,
+    ];
+    file_with_columns(types, 2, streams, 0, column_statistics)
+}
+
+/// Uncompressed file with a single stripe holding a float or double column `a`
+/// (as given by `kind`), with `num_rows` values stored as the little-endian `data`.
+pub fn float_file(kind: r#type::Kind, num_rows: u64, data: Vec<u8>) -> Bytes {
+    let column_type = proto::Type {
+        kind: Some(kind as i32),
+        ..Default::default()
+    };
+    single_column_file(column_type, num_rows, vec![(stream::Kind::Data, data)], 0)
+}
+
+/// Uncompressed file with a single stripe holding a timestamp column `a` of the
+/// given kind, with values the given seconds after 2015-01-01 (the ORC base).
+pub fn timestamp_file(kind: r#type::Kind, seconds: &[i64]) -> Bytes {
+    let column_type = proto::Type {
+        kind: Some(kind as i32),
+        ..Default::default()
+    };
+    let zigzag = seconds
+        .iter()
+        .map(|&value| ((value << 1) ^ (value >> 63)) as u64)
+        .collect::<Vec<_>>();
+    let streams = vec![
+        (stream::Kind::Data, rle_v1_literals(&zigzag)),
+        // No nanoseconds
+        (
+            stream::Kind::Secondary,
+            rle_v1_literals(&vec![0; seconds.len()]),
+        ),
+    ];
+    single_column_file(column_type, seconds.len() as u64, streams, 0)
+}
+
+/// Uncompressed file with a single stripe holding a binary column `a` with the
+/// given values, and a bloom filter of them in a single row group.
+pub fn binary_file_with_bloom_filter(values: &[&[u8]]) -> Bytes {
+    binary_file_with_bloom_filters(values, 0)
+}
+
+/// Same as [`binary_file_with_bloom_filter`], preceded by `empty_filters` row
+/// groups whose bloom filters have no bits.
+pub fn binary_file_with_bloom_filters(values: &[&[u8]], empty_filters: usize) -> Bytes {
+    let mut filter = BloomFilter::new(16, 3);
+    for value in values {
+        filter.add_bytes(value);
+    }
+    let empty_filter = proto::BloomFilter {
+        num_hash_functions: Some(filter.num_hash_functions()),
+        bitset: vec![],
+        utf8bitset: None,
+    };
+    let mut bloom_filters = vec![empty_filter; empty_filters];
+    bloom_filters.push(proto::BloomFilter {
+        num_hash_functions: Some(filter.num_hash_functions()),
+        bitset: filter.bitset().to_vec(),
+        utf8bitset: None,
+    });
+    let bloom_filter = proto::BloomFilterIndex {
+        bloom_filter: bloom_filters,
+    };
+
+    let column_type = proto::Type {
+        kind: Some(r#type::Kind::Binary as i32),
+        ..Default::default()
+    };
+    let lengths = values
+        .iter()
+        .map(|value| value.len() as u64)
+        .collect::<Vec<_>>();
+    let streams = vec![
+        (stream::Kind::BloomFilter, bloom_filter.encode_to_vec()),
+        (stream::Kind::Length, rle_v1_literals(&lengths)),
+        (stream::Kind::Data, values.concat()),
+    ];
+    single_column_file(column_type, values.len() as u64, streams, 0)
+}
+
+/// Uncompressed file with a single stripe laid out as a Hive ACID table: the ACID
+/// columns, then a `row` struct with user columns `x`, `y` and `z`. All int and
+/// long columns hold `0..num_rows`, and the string `z` holds the last digit of the
+/// row number.
+pub fn hive_acid_file(num_rows: u64) -> Bytes {
+    let long_type = || proto::Type {
+        kind: Some(r#type::Kind::Long as i32),
+        ..Default::default()
+    };
+    let types = vec![
+        proto::Type {
+            kind: Some(r#type::Kind::Struct as i32),
+            subtypes: (1..=6).collect(),
+            field_names: [
+                "operation",
+                "originalTransaction",
+                "bucket",
+                "rowId",
+                "currentTransaction",
+                "row",
+            ]
+            .map(str::to_owned)
+            .to_vec(),
+            ..Default::default()
+        },
+        int_type(),
+        long_type(),
+        int_type(),
+        long_type(),
+        long_type(),
+        proto::Type {
+            kind: Some(r#type::Kind::Struct as i32),
+            subtypes: vec![7, 8, 9],
+            field_names: ["x", "y", "z"].map(str::to_owned).to_vec(),
+            ..Default::default()
+        },
+        int_type(),
+        long_type(),
+        proto::Type {
+            kind: Some(r#type::Kind::String as i32),
+            ..Default::default()
+        },
+    ];
+    let mut streams: Vec<_> = [1, 2, 3, 4, 5, 7, 8]
+        .into_iter()
+        .map(|column| (column, stream::Kind::Data, int_data(num_rows)))
+        .collect();
+    // RLE v1 runs of 100 lengths of 1
+    let lengths = (0..num_rows / 100).flat_map(|_| [97, 0, 1]).collect();
+    let digits = (0..num_rows).map(|row| b'0' + (row % 10) as u8).collect();
+    streams.push((9, stream::Kind::Length, lengths));
+    streams.push((9, stream::Kind::Data, digits));
+    file_with_columns(types, num_rows, streams, 0, vec![])
+}
+
+/// Uncompressed file of version 0.11, as written by Hive 0.11 before RLE v2, with
+/// a single stripe of 3 rows of each primitive type, all using RLE v1. Its columns
+/// are a dictionary encoded string, and direct encoded ones otherwise, including
+/// a decimal without precision nor scale, as those were only added in Hive 0.13.
+pub fn v0_file() -> Bytes {
+    let kinds = [
+        ("boolean", r#type::Kind::Boolean),
+        ("tinyint", r#type::Kind::Byte),
+        ("smallint", r#type::Kind::Short),
+        ("int", r#type::Kind::Int),
+        ("bigint", r#type::Kind::Long),
+        ("float", r#type::Kind::Float),
+        ("double", r#type::Kind::Double),
+        ("string", r#type::Kind::String),
+        ("dictionary", r#type::Kind::String),
+        ("binary", r#type::Kind::Binary),
+        ("timestamp", r#type::Kind::Timestamp),
+        ("date", r#type::Kind::Date),
+        ("decimal", r#type::Kind::Decimal),
+    ];
+    let root = proto::Type {
+        kind: Some(r#type::Kind::Struct as i32),
+        subtypes: (1..=kinds.len() as u32).collect(),
+        field_names: kinds.iter().map(|(name, _)| name.to_string()).collect(),
+        ..Default::default()
+    };
+    let types = std::iter::once(root)
+        .chain(kinds.iter().map(|&(_, kind)| proto::Type {
+            kind: Some(kind as i32),
+            ..Default::default()
+        }))
+        .collect::<Vec<_>>();
+
+    let direct = ColumnEncoding {
+        kind: Some(column_encoding::Kind::Direct as i32),
+        ..Default::default()
+    };
+    let mut encodings = vec![direct; types.len()];
+    encodings[9] = ColumnEncoding {
+        kind: Some(column_encoding::Kind::Dictionary as i32),
+        dictionary_size: Some(2),
+        ..Default::default()
+    };
+
+    let signed = |values: &[i64]| {
+        let zigzag = values
+            .iter()
+            .map(|&value| ((value << 1) ^ (value >> 63)) as u64)
+            .collect::<Vec<_>>();
+        rle_v1_literals(&zigzag)
+    };
+    let floats = [1.5f32, 0.0, -2.25]
+        .iter()
+        .flat_map(|value| value.to_le_bytes())
+        .collect();
+    let doubles = [1.5f64, 0.0, -2.25]
+        .iter()
+        .flat_map(|value| value.to_le_bytes())
+        .collect();
+    let mut decimals = vec![];
+    // Zigzag encoded 12345, -1 and 7
+    write_varint(&mut decimals, 24690);
+    write_varint(&mut decimals, 1);
+    write_varint(&mut decimals, 14);
+    let streams = vec![
+        // Byte RLE literals of true, false, true
+        (1, stream::Kind::Data, vec![0xFF, 0b1010_0000]),
+        // Byte RLE literals of -1, 0, 127
+        (2, stream::Kind::Data, vec![0xFD, 0xFF, 0x00, 0x7F]),
+        (3, stream::Kind::Data, signed(&[-2, 0, 300])),
+        (4, stream::Kind::Data, signed(&[-3, 0, 70000])),
+        (5, stream::Kind::Data, signed(&[-4, 0, 1 << 40])),
+        (6, stream::Kind::Data, floats),
+        (7, stream::Kind::Data, doubles),
+        (8, stream::Kind::Length, rle_v1_literals(&[1, 0, 3])),
+        (8, stream::Kind::Data, b"accc".to_vec()),
+        (9, stream::Kind::Data, rle_v1_literals(&[0, 1, 0])),
+        (9, stream::Kind::DictionaryData, b"xy".to_vec()),
+        (9, stream::Kind::Length, rle_v1_literals(&[1, 1])),
+        (10, stream::Kind::Length, rle_v1_literals(&[2, 0, 1])),
+        (10, stream::Kind::Data, b"\x00\xFF\x01".to_vec()),
+        // Seconds since 2015-01-01, and nanoseconds of 0, 5 * 10^8 and 0 (with
+        // the count of trailing zeros minus one in the 3 low bits)
+        (11, stream::Kind::Data, signed(&[0, 1, -1])),
+        (
+            11,
+            stream::Kind::Secondary,
+            rle_v1_literals(&[0, (5 << 3) | 7, 0]),
+        ),
+        // Days since 1970-01-01
+        (12, stream::Kind::Data, signed(&[0, 16436, -1])),
+        (13, stream::Kind::Data, decimals),
+        (13, stream::Kind::Secondary, signed(&[2, 0, 10])),
+    ];
+    let layout = FileLayout {
+        row_index_stride: 0,
+        column_statistics: vec![],
+        version: vec![0, 11],
+        gzip_chunks: false,
+        compression_block_size: None,
+    };
+    file_with_encodings(types, encodings, 3, streams, layout)
+}
+
+/// Uncompressed file with a single stripe holding an int column `a` with values
+/// `0..num_rows` and a struct column `b` with a union field `c` of a single int
+/// variant, whose streams are all empty.
+pub fn int_and_union_struct_file(num_rows: u64) -> Bytes {
+    let types = vec![
+        proto::Type {
+            kind: Some(r#type::Kind::Struct as i32),
+            subtypes: vec![1, 2],
+            field_names: vec!["a".to_owned(), "b".to_owned()],
+            ..Default::default()
+        },
+        int_type(),
+        proto::Type {
+            kind: Some(r#type::Kind::Struct as i32),
+            subtypes: vec![3],
+            field_names: vec!["c".to_owned()],
+            ..Default::default()
+        },
+        proto::Type {
+            kind: Some(r#type::Kind::Union as i32),
+            subtypes: vec![4],
+            ..Default::default()
+        },
+        int_type(),
+    ];
+    let streams = vec![
+        (1, stream::Kind::Data, int_data(num_rows)),
+        (3, stream::Kind::Data, vec![]),
+        (4, stream::Kind::Data, vec![]),
+    ];
+    file_with_columns(types, num_rows, streams, 0, vec![])
+}
+
+/// Uncompressed file with a single stripe of 200 rows, every other one null
+/// according to the present stream of the root struct. The rows hold an int
+/// column `a` and a struct column `b` with an int field `c`, both int columns
+/// holding `0..100` in the non-null rows.
+pub fn root_present_file() -> Bytes {
+    let types = vec![
+        proto::Type {
+            kind: Some(r#type::Kind::Struct as i32),
+            subtypes: vec![1, 2],
+            field_names: vec!["a".to_owned(), "b".to_owned()],
+            ..Default::default()
+        },
+        int_type(),
+        proto::Type {
+            kind: Some(r#type::Kind::Struct as i32),
+            subtypes: vec![3],
+            field_names: vec!["c".to_owned()],
+            ..Default::default()
+        },
+        int_type(),
+    ];
+    let streams = vec![
+        // Byte RLE run of 25 bytes: 25 - 3, 0b10101010
+        (0, stream::Kind::Present, vec![22, 0xAA]),
+        (1, stream::Kind::Data, int_data(100)),
+        (3, stream::Kind::Data, int_data(100)),
+    ];
+    file_with_columns(types, 200, streams, 0, vec![])
+}
+
+/// Uncompressed file with a single stripe holding an int column `a` and a struct
+/// column `b` with an int field `c`, both int types having the given attributes.
+/// The int columns hold `0..num_rows`.
+pub fn attributed_file(num_rows: u64, attributes: &[(&str, &str)]) -> Bytes {
+    let attributed_int_type = || proto::Type {
+        attributes: attributes
+            .iter()
+            .map(|&(key, value)| proto::StringPair {
+                key: Some(key.to_owned()),
+                value: Some(value.to_owned()),
+            })
+            .collect(),
+        ..int_type()
+    };
+    let types = vec![
+        proto::Type {
+            kind: Some(r#type::Kind::Struct as i32),
+            subtypes: vec![1, 2],
+            field_names: vec!["a".to_owned(), "b".to_owned()],
+            ..Default::default()
+        },
+        attributed_int_type(),
+        proto::Type {
+            kind: Some(r#type::Kind::Struct as i32),
+            subtypes: vec![3],
+            field_names: vec!["c".to_owned()],
+            ..Default::default()
+        },
+        attributed_int_type(),
+    ];
+    let streams = vec![
+        (1, stream::Kind::Data, int_data(num_rows)),
+        (3, stream::Kind::Data, int_data(num_rows)),
+    ];
+    file_with_columns(types, num_rows, streams, 0, vec![])
+}
+
+/// Uncompressed file with a single stripe holding a struct column `a` with the
+/// int fields `w`, `x`, `y` and `z`. Row `i` holds `i`, `100 + i`, `200 + i` and
+/// `300 + i` in those fields.
+pub fn wide_struct_file(num_rows: u64) -> Bytes {
+    let types = vec![
+        proto::Type {
+            kind: Some(r#type::Kind::Struct as i32),
+            subtypes: vec![2, 3, 4, 5],
+            field_names: ["w", "x", "y", "z"].map(str::to_owned).to_vec(),
+            ..Default::default()
+        },
+        int_type(),
+        int_type(),
+        int_type(),
+        int_type(),
+    ];
+    let streams = (0..4)
+        .map(|field| {
+            // Zigzag encoded
+            let values = (0..num_rows)
+                .map(|row| (field * 100 + row) << 1)
+                .collect::<Vec<_>>();
+            (
+                field as u32 + 2,
+                stream::Kind::Data,
+                rle_v1_literals(&values),
+            )
+        })
+        .collect();
+    file_with_columns(root_with_field_a(types), num_rows, streams, 0, vec![])
+}
+
+/// Uncompressed file with a single stripe of `num_rows` rows holding a list of
+/// ints column `a`, with the given (unchecked) lengths and no list elements.
+pub fn list_file(num_rows: u64, lengths: &[u64]) -> Bytes {
+    let types = vec![
+        proto::Type {
+            kind: Some(r#type::Kind::List as i32),
+            subtypes: vec![2],
+            ..Default::default()
+        },
+        proto::Type {
+            kind: Some(r#type::Kind::Int as i32),
+            ..Default::default()
+        },
+    ];
+    let streams = vec![
+        (1, stream::Kind::Length, rle_v1_literals(lengths)),
+        (2, stream::Kind::Data, vec![]),
+    ];
+    file_with_columns(root_with_field_a(types), num_rows, streams, 0, vec![])
+}
+
+/// Same as [`list_file`], with list elements given by their encoded present
+/// stream and their zigzag encoded values.
+pub fn list_file_with_elements(
+    num_rows: u64,
+    lengths: &[u64],
+    present: &[u8],
+    values: &[u64],
+) -> Bytes {
+    let types = vec![
+        proto::Type {
+            kind: Some(r#type::Kind::List as i32),
+            subtypes: vec![2],
+            ..Default::default()
+        },
+        int_type(),
+    ];
+    let streams = vec![
+        (1, stream::Kind::Length, rle_v1_literals(lengths)),
+        (2, stream::Kind::Present, present.to_vec()),
+        (2, stream::Kind::Data, rle_v1_literals(values)),
+    ];
+    file_with_columns(root_with_field_a(types), num_rows, streams, 0, vec![])
+}
+
+/// Uncompressed file with a single stripe of `num_rows` rows holding a column
+/// `a` of lists nested as many times as there are `levels`, of ints with the
+/// given values. Each level has the encoded present stream and the lengths of
+/// its non-null lists.
+pub fn nested_list_file(num_rows: u64, levels: &[(&[u8], &[u64])], values: &[i64]) -> Bytes {
+    let depth = levels.len() as u32;
+    let types = (1..=depth)
+        .map(|column| proto::Type {
+            kind: Some(r#type::Kind::List as i32),
+            subtypes: vec![column + 1],
+            ..Default::default()
+        })
+        .chain(std::iter::once(int_type()))
+        .collect();
+    let mut streams = vec![];
+    for (column, (present, lengths)) in (1..).zip(levels) {
+        streams.push((column, stream::Kind::Present, present.to_vec()));
+        streams.push((column, stream::Kind::Length, rle_v1_literals(lengths)));
+    }
+    // Zigzag encoded
+    let values = values
+        .iter()
+        .map(|&value| ((value << 1) ^ (value >> 63)) as u64)
+        .collect::<Vec<_>>();
+    streams.push((depth + 1, stream::Kind::Data, rle_v1_literals(&values)));
+    file_with_columns(root_with_field_a(types), num_rows, streams, 0, vec![])
+}
+
+/// Uncompressed file with a single stripe holding a `list<struct<a:int,b:string>>`
+/// column `a`, with the rows `[{1, "x"}, null]`, null, `[]`, `[{2, "yz"}]`, null
+/// and `[{3, ""}, {4, "w"}]`.
+pub fn list_of_structs_file() -> Bytes {
+    let types = vec![
+        proto::Type {
+            kind: Some(r#type::Kind::List as i32),
+            subtypes: vec![2],
+            ..Default::default()
+        },
+        proto::Type {
+            kind: Some(r#type::Kind::Struct as i32),
+            subtypes: vec![3, 4],
+            field_names: vec!["a".to_owned(), "b".to_owned()],
+            ..Default::default()
+        },
+        int_type(),
+        proto::Type {
+            kind: Some(r#type::Kind::String as i32),
+            ..Default::default()
+        },
+    ];
+    let streams = vec![
+        // Byte RLE literal of the bits 101101
+        (1, stream::Kind::Present, vec![0xFF, 0b1011_0100]),
+        // Null lists have no length
+        (1, stream::Kind::Length, rle_v1_literals(&[2, 0, 1, 2])),
+        // Bits 10111 for the 5 list elements
+        (2, stream::Kind::Present, vec![0xFF, 0b1011_1000]),
+        // Null structs have no field values, zigzag encoded
+        (3, stream::Kind::Data, rle_v1_literals(&[2, 4, 6, 8])),
+        (4, stream::Kind::Length, rle_v1_literals(&[1, 2, 0, 1])),
+        (4, stream::Kind::Data, b"xyzw".to_vec()),
+    ];
+    file_with_columns(root_with_field_a(types), 6, streams, 0, vec![])
+}
+
+/// Uncompressed file with a single stripe holding int columns `a` and `b`, with
+/// `0..100` as values, whose stripe footer lacks the encoding of `b`.
+pub fn missing_encoding_file() -> Bytes {
+    let root = proto::Type {
+        kind: Some(r#type::Kind::Struct as i32),
+        subtypes: vec![1, 2],
+        field_names: vec!["a".to_owned(), "b".to_owned()],
+        ..Default::default()
+    };
+    let types = vec![root, int_type(), int_type()];
+    let encodings = vec![
+        ColumnEncoding {
+            kind: Some(column_encoding::Kind::Direct as i32),
+            ..Default::default()
+        };
+        2
+    ];
+    let streams = vec![
+        (1, stream::Kind::Data, int_data(100)),
+        (2, stream::Kind::Data, int_data(100)),
+    ];
+    let layout = FileLayout {
+        row_index_stride: 0,
+        column_statistics: vec![],
+        version: vec![],
+        gzip_chunks: false,
+        compression_block_size: None,
+    };
+    file_with_encodings(types, encodings, 100, streams, layout)
+}
+
+/// Zlib compressed file with a single stripe holding a non-null int column `a`
+/// with values `0..100`, whose chunks are gzip wrapped like some writers do.
+pub fn gzip_wrapped_file() -> Bytes {
+    let types = root_with_field_a(vec![int_type()]);
+    let encodings = vec![
+        ColumnEncoding {
+            kind: Some(column_encoding::Kind::Direct as i32),
+            ..Default::default()
+        };
+        types.len()
+    ];
+    let streams = vec![(1, stream::Kind::Data, int_data(100))];
+    let layout = FileLayout {
+        row_index_stride: 0,
+        column_statistics: vec![],
+        version: vec![],
+        gzip_chunks: true,
+        compression_block_size: None,
+    };
+    file_with_encodings(types, encodings, 100, streams, layout)
+}
+
+/// Zlib compressed file with a single stripe holding a string column `a` of
+/// `num_rows` values of 100,000 `x`s each, as a single gzip wrapped chunk per
+/// stream and the given compression block size in the postscript.
+pub fn large_chunk_file(num_rows: u64, compression_block_size: Option<u64>) -> Bytes {
+    let types = root_with_field_a(vec![proto::Type {
+        kind: Some(r#type::Kind::String as i32),
+        ..Default::default()
+    }]);
+    let encodings = vec![
+        ColumnEncoding {
+            kind: Some(column_encoding::Kind::Direct as i32),
+            ..Default::default()
+        };
+        types.len()
+    ];
+    let streams = vec![
+        (
+            1,
+            stream::Kind::Length,
+            rle_v1_literals(&vec![100_000; num_rows as usize]),
+        ),
+        (
+            1,
+            stream::Kind::Data,
+            vec![b'x'; 100_000 * num_rows as usize],
+        ),
+    ];
+    let layout = FileLayout {
+        row_index_stride: 0,
+        column_statistics: vec![],
+        version: vec![],
+        gzip_chunks: true,
+        compression_block_size,
+    };
+    file_with_encodings(types, encodings, num_rows, streams, layout)
+}
+
+/// Uncompressed file with a single stripe holding `num_columns` int columns
+/// `c0`, `c1`... each with values `0..100`, for a footer as large as needed.
+pub fn wide_file(num_columns: u32) -> Bytes {
+    let root = proto::Type {
+        kind: Some(r#type::Kind::Struct as i32),
+        subtypes: (1..=num_columns).collect(),
+        field_names: (0..num_columns).map(|i| format!("c{i}")).collect(),
+        ..Default::default()
+    };
+    let types = std::iter::once(root)
+        .chain((0..num_columns).map(|_| int_type()))
+        .collect();
+    let streams = (1..=num_columns)
+        .map(|column| (column, stream::Kind::Data, int_data(100)))
+        .collect();
+    file_with_columns(types, 100, streams, 0, vec![])
+}
+
+/// Uncompressed file with three stripes holding an int column `a`, a string
+/// column `b`, a `list<int>` column `c` and a `struct<e:int>` column `d`. The
+/// first and last stripes have 100 rows, with `0..100` as ints, `"x"` as
+/// strings and single element lists, while the middle one has no rows nor
+/// streams.
+pub fn empty_stripe_file() -> Bytes {
+    let types = vec![
+        proto::Type {
+            kind: Some(r#type::Kind::Struct as i32),
+            subtypes: vec![1, 2, 3, 5],
+            field_names: ["a", "b", "c", "d"].map(String::from).to_vec(),
+            ..Default::default()
+        },
+        int_type(),
+        proto::Type {
+            kind: Some(r#type::Kind::String as i32),
+            ..Default::default()
+        },
+        proto::Type {
+            kind: Some(r#type::Kind::List as i32),
+            subtypes: vec![4],
+            ..Default::default()
+        },
+        int_type(),
+        proto::Type {
+            kind: Some(r#type::Kind::Struct as i32),
+            subtypes: vec![6],
+            field_names: vec!["e".to_owned()],
+            ..Default::default()
+        },
+        int_type(),
+    ];
+    // RLE v1 run of 100 ones
+    let ones = vec![97, 0, 1];
+    let streams = vec![
+        (1, stream::Kind::Data, int_data(100)),
+        (2, stream::Kind::Length, ones.clone()),
+        (2, stream::Kind::Data, vec![b'x'; 100]),
+        (3, stream::Kind::Length, ones),
+        (4, stream::Kind::Data, int_data(100)),
+        (6, stream::Kind::Data, int_data(100)),
+    ];
+    let stripes = vec![(100, streams.clone()), (0, vec![]), (100, streams)];
+    multi_stripe_file(types, stripes)
+}
+
+/// Uncompressed file with `num_stripes` stripes of 100 rows holding a non-null
+/// int column `a`, with values `0..100 * num_stripes` in order.
+pub fn int_stripes_file(num_stripes: u64) -> Bytes {
+    let stripes = (0..num_stripes)
+        .map(|stripe| {
+            // RLE v1 run of 100 values with a delta of 1, from a zigzag encoded base
+            let mut data = vec![97, 1];
+            write_varint(&mut data, (stripe * 100) << 1);
+            (100, vec![(1, stream::Kind::Data, data)])
+        })
+        .collect();
+    multi_stripe_file(root_with_field_a(vec![int_type()]), stripes)
+}
+
+/// Uncompressed file with four stripes of 100 rows holding an int column `a`,
+/// whose statistics record:
+/// - no nulls, in the first stripe holding `0..100`
+/// - 48 values, in the second stripe holding `0..48` then nulls
+/// - no values, in the third stripe holding only nulls
+/// - neither the number of values nor whether there are nulls, in the last
+///   stripe holding `0..100`
+#[cfg(feature = "datafusion")]
+pub fn nullable_int_stripes_file() -> Bytes {
+    let stats = |number_of_values, has_null| StripeStatistics {
+        col_stats: vec![
+            proto::ColumnStatistics::default(),
+            proto::ColumnStatistics {
+                number_of_values,
+                has_null,
+                ..Default::default()
+            },
+        ],
+    };
+    let stripes = vec![
+        (100, vec![(1, stream::Kind::Data, int_data(100))]),
+        (
+            100,
+            vec![
+                // Byte RLE runs of 6 0xff bytes then 7 zero bytes
+                (1, stream::Kind::Present, vec![3, 0xFF, 4, 0x00]),
+                // RLE v1 run of 48 values with a delta of 1 from 0
+                (1, stream::Kind::Data, vec![45, 1, 0]),
+            ],
+        ),
+        (
+            100,
+            vec![
+                (1, stream::Kind::Present, vec![10, 0x00]),
+                (1, stream::Kind::Data, vec![]),
+            ],
+        ),
+        (100, vec![(1, stream::Kind::Data, int_data(100))]),
+    ];
+    let stripe_stats = vec![
+        stats(Some(100), Some(false)),
+        stats(Some(48), Some(true)),
+        stats(Some(0), Some(true)),
+        stats(None, None),
+    ];
+    multi_stripe_file_with_statistics(root_with_field_a(vec![int_type()]), stripes, stripe_stats)
+}
+
+/// Stream of a column, as its column id, kind and content.
+type ColumnStream = (u32, stream::Kind, Vec<u8>);
+
+/// Uncompressed file with a stripe of each of the given row counts and streams,
+/// all columns being directly encoded.
+fn multi_stripe_file(types: Vec<proto::Type>, stripes: Vec<(u64, Vec<ColumnStream>)>) -> Bytes {
+    let stripe_stats = vec![StripeStatistics::default(); stripes.len()];
+    multi_stripe_file_with_statistics(types, stripes, stripe_stats)
+}
+
+/// Same as [`multi_stripe_file`], with the given statistics of each stripe.
+fn multi_stripe_file_with_statistics(
+    types: Vec<proto::Type>,
+    stripes: Vec<(u64, Vec<ColumnStream>)>,
+    stripe_stats: Vec<StripeStatistics>,
+) -> Bytes {
+    let mut file = b"ORC".to_vec();
+    let header_length = file.len() as u64;
+    let mut stripe_infos = vec![];
+    for (num_rows, streams) in stripes {
+        let stripe_offset = file.len() as u64;
+        let streams = streams
+            .into_iter()
+            .map(|(column, kind, stream)| {
+                file.extend_from_slice(&stream);
+                proto::Stream {
+                    kind: Some(kind as i32),
+                    column: Some(column),
+                    length: Some(stream.len() as u64),
+                }
+            })
+            .collect();
+        let data_length = file.len() as u64 - stripe_offset;
+        let stripe_footer = StripeFooter {
+            streams,
+            columns: vec![
+                ColumnEncoding {
+                    kind: Some(column_encoding::Kind::Direct as i32),
+                    ..Default::default()
+                };
+                types.len()
+            ],
+            ..Default::default()
+        }
+        .encode_to_vec();
+        file.extend_from_slice(&stripe_footer);
+        stripe_infos.push(StripeInformation {
+            offset: Some(stripe_offset),
+            index_length: Some(0),
+            data_length: Some(data_length),
+            footer_length: Some(stripe_footer.len() as u64),
+            number_of_rows: Some(num_rows),
+            ..Default::default()
+        });
+    }
+
+    let metadata = Metadata { stripe_stats }.encode_to_vec();
+    let footer = Footer {
+        header_length: Some(header_length),
+        content_length: Some(file.len() as u64 - header_length),
+        number_of_rows: Some(stripe_infos.iter().map(|info| info.number_of_rows()).sum()),
+        stripes: stripe_infos,
+        types,
+        row_index_stride: Some(0),
+        ..Default::default()
+    }
+    .encode_to_vec();
+    file.extend_from_slice(&metadata);
+    file.extend_from_slice(&footer);
+
+    let postscript = PostScript {
+        footer_length: Some(footer.len() as u64),
+        compression: Some(proto::CompressionKind::None as i32),
+        metadata_length: Some(metadata.len() as u64),
+        magic: Some("ORC".to_owned()),
+        ..Default::default()
+    }
+    .encode_to_vec();
+    file.extend_from_slice(&postscript);
+    file.push(postscript.len() as u8);
+    file.into()
+}
+
+fn single_column_file(
+    column_type: proto::Type,
+    num_rows: u64,
+    streams: Vec<(stream::Kind, Vec<u8>)>,
+    row_index_stride: u32,
+) -> Bytes {
+    let streams = streams
+        .into_iter()
+        .map(|(kind, stream)| (1, kind, stream))
+        .collect();
+    file_with_columns(
+        root_with_field_a(vec![column_type]),
+        num_rows,
+        streams,
+        row_index_stride,
+        vec![],
+    )
+}
+
+/// Types of a root struct with a single field `a`, of the first of `types` (the
+/// others being its descendants).
+fn root_with_field_a(types: Vec<proto::Type>) -> Vec<proto::Type> {
+    let root = proto::Type {
+        kind: Some(r#type::Kind::Struct as i32),
+        subtypes: vec![1],
+        field_names: vec!["a".to_owned()],
+        ..Default::default()
+    };
+    std::iter::once(root).chain(types).collect()
+}
+
+/// File with a single stripe of the given types, starting with the root struct,
+/// and the given streams and stripe statistics per column.
+fn file_with_columns(
+    types: Vec<proto::Type>,
+    num_rows: u64,
+    streams: Vec<(u32, stream::Kind, Vec<u8>)>,
+    row_index_stride: u32,
+    column_statistics: Vec<proto::ColumnStatistics>,
+) -> Bytes {
+    let encodings = vec![
+        ColumnEncoding {
+            kind: Some(column_encoding::Kind::Direct as i32),
+            ..Default::default()
+        };
+        types.len()
+    ];
+    let layout = FileLayout {
+        row_index_stride,
+        column_statistics,
+        version: vec![],
+        gzip_chunks: false,
+        compression_block_size: None,
+    };
+    file_with_encodings(types, encodings, num_rows, streams, layout)
+}
+
+/// Parts of a file which most synthetic files leave empty.
+struct FileLayout {
+    row_index_stride: u32,
+    column_statistics: Vec<proto::ColumnStatistics>,
+    /// Version of the file format, as `[major, minor]`
+    version: Vec<u32>,
+    /// Compress the file as zlib, with each stream and section as a single chunk
+    /// wrapped in gzip (as some writers do) rather than raw deflate
+    gzip_chunks: bool,
+    /// Compression block size recorded in the postscript, `None` for the default
+    compression_block_size: Option<u64>,
+}
+
+/// Single gzip compressed chunk holding `bytes`, or nothing if empty.
+fn gzip_chunk(bytes: &[u8]) -> Vec<u8> {
+    use std::io::Write;
+
+    if bytes.is_empty() {
+        return vec![];
+    }
+    let mut encoder = flate2::write::GzEncoder::new(vec![], flate2::Compression::default());
+    encoder.write_all(bytes).unwrap();
+    let compressed = encoder.finish().unwrap();
+    // Chunk header: length shifted left, with a low bit of 0 as it is compressed
+    let header = (compressed.len() as u32) << 1;
+    let mut chunk = header.to_le_bytes()[..3].to_vec();
+    chunk.extend(compressed);
+    chunk
+}
+
+/// Same as [`file_with_columns`], with the given encoding of each column.
+fn file_with_encodings(
+    types: Vec<proto::Type>,
+    encodings: Vec<ColumnEncoding>,
+    num_rows: u64,
+    streams: Vec<(u32, stream::Kind, Vec<u8>)>,
+    layout: FileLayout,
+) -> Bytes {
+    let FileLayout {
+        row_index_stride,
+        column_statistics,
+        version,
+        gzip_chunks,
+        compression_block_size,
+    } = layout;
+    let compress = |bytes: Vec<u8>| {
+        if gzip_chunks {
+            gzip_chunk(&bytes)
+        } else {
+            bytes
+        }
+    };
+    let mut file = b"ORC".to_vec();
+    let stripe_offset = file.len() as u64;
+    let streams = streams
+        .into_iter()
+        .map(|(column, kind, stream)| {
+            let stream = compress(stream);
+            file.extend_from_slice(&stream);
+            proto::Stream {
+                kind: Some(kind as i32),
+                column: Some(column),
+                length: Some(stream.len() as u64),
+            }
+        })
+        .collect();
+    let data_length = file.len() as u64 - stripe_offset;
+
+    let stripe_footer = compress(
+        StripeFooter {
+            streams,
+            columns: encodings,
+            ..Default::default()
+        }
+        .encode_to_vec(),
+    );
+    file.extend_from_slice(&stripe_footer);
+
+    let metadata = compress(
+        Metadata {
+            stripe_stats: vec![StripeStatistics {
+                col_stats: column_statistics,
+            }],
+        }
+        .encode_to_vec(),
+    );
+    let footer = compress(
+        Footer {
+            header_length: Some(stripe_offset),
+            content_length: Some(file.len() as u64 - stripe_offset),
+            stripes: vec![StripeInformation {
+                offset: Some(stripe_offset),
+                index_length: Some(0),
+                data_length: Some(data_length),
+                footer_length: Some(stripe_footer.len() as u64),
+                number_of_rows: Some(num_rows),
+                ..Default::default()
+            }],
+            types,
+            number_of_rows: Some(num_rows),
+            row_index_stride: Some(row_index_stride),
+            ..Default::default()
+        }
+        .encode_to_vec(),
+    );
+    file.extend_from_slice(&metadata);
+    file.extend_from_slice(&footer);
+
+    let postscript = PostScript {
+        footer_length: Some(footer.len() as u64),
+        compression: Some(if gzip_chunks {
+            proto::CompressionKind::Zlib as i32
+        } else {
+            proto::CompressionKind::None as i32
+        }),
+        metadata_length: Some(metadata.len() as u64),
+        compression_block_size,
+        magic: Some("ORC".to_owned()),
+        version,
+        ..Default::default()
+    }
+    .encode_to_vec();
+    file.extend_from_slice(&postscript);
+    file.push(postscript.len() as u8);
+    file.into()
+}